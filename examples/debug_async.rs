@@ -0,0 +1,68 @@
+use _tuicore::async_input::AsyncRawInputReader;
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+use std::io::{self, Stdout, Write};
+use std::time::{Duration, Instant};
+
+const FLUSH_TIMEOUT: Duration = Duration::from_millis(35);
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> io::Result<()> {
+    enable_raw_mode()?;
+    let result = run().await;
+    disable_raw_mode()?;
+    result
+}
+
+async fn run() -> io::Result<()> {
+    let mut out = io::stdout();
+    write!(out, "Reading raw input asynchronously. Ctrl-C to exit.\r\n")?;
+    out.flush()?;
+
+    let mut reader = AsyncRawInputReader::new(FLUSH_TIMEOUT)?;
+    let result = read_events(&mut reader, &mut out).await;
+    let throughput = reader.throughput();
+    write!(
+        out,
+        "Read {} bytes as {} events.\r\n",
+        throughput.total_bytes(),
+        throughput.total_events()
+    )?;
+    result
+}
+
+async fn read_events(reader: &mut AsyncRawInputReader, out: &mut Stdout) -> io::Result<()> {
+    loop {
+        let event = match tokio::time::timeout(IDLE_TIMEOUT, reader.next_event()).await {
+            Ok(event) => event?,
+            Err(_elapsed) => {
+                write!(out, "No input for {}s.\r\n", IDLE_TIMEOUT.as_secs())?;
+                return Ok(());
+            }
+        };
+
+        let Some(event) = event else {
+            write!(out, "Input closed.\r\n")?;
+            return Ok(());
+        };
+
+        let hex = event
+            .bytes
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect::<Vec<_>>()
+            .join(" ");
+        write!(
+            out,
+            "{:<24} spread {:.1}ms  {}\r\n",
+            hex,
+            event.spread().as_secs_f64() * 1000.0,
+            reader.throughput().rates(Instant::now())
+        )?;
+        out.flush()?;
+
+        if event.bytes == [0x03] {
+            return Ok(());
+        }
+    }
+}
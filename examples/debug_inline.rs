@@ -1,33 +1,58 @@
+#[cfg(unix)]
+use _tuicore::channel_input::{ChannelInputReader, InputMessage};
+#[cfg(unix)]
+use _tuicore::clock::Clock;
+#[cfg(unix)]
+use _tuicore::coalesce::{Coalesced, RepeatCoalescer};
+use _tuicore::export::{write_csv, write_json_lines, ExportedEvent};
+use _tuicore::interpret::{
+    escape_bytes, format_modifiers, hex_string, interpret_bytes, parse_event_stream, GuessInfo,
+    InputEventInfo,
+};
+#[cfg(unix)]
+use _tuicore::probe::{color_depth, ColorDepth};
+#[cfg(unix)]
+use _tuicore::raw_input::{ByteSource, RawInputReader, PASTE_END, PASTE_START};
+use _tuicore::raw_input::{FlushTimeouts, RawEvent};
+use _tuicore::session::{SessionRecorder, SessionReplay};
+#[cfg(unix)]
+use _tuicore::theme::{self, ThemeWatcher};
+#[cfg(unix)]
+use _tuicore::throughput::{Rates, Throughput};
 use _tuicore::TuiApp;
+#[cfg(unix)]
+use _tuicore::{AlternateScreenBackend, NonTtyBehavior, MAX_INLINE_HEIGHT};
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use crossterm::event::{KeyCode, KeyModifiers};
 #[cfg(not(unix))]
 use eyre::eyre;
-use eyre::Result;
-#[cfg(unix)]
-use libc;
-#[cfg(unix)]
-use nix::errno::Errno;
-#[cfg(unix)]
-use nix::poll::{poll, PollFd, PollFlags, PollTimeout};
+use eyre::{Result, WrapErr};
+use owo_colors::OwoColorize;
 #[cfg(unix)]
 use ratatui::{
-    layout::Constraint,
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    prelude::Widget,
     style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Block, BorderType, Borders, Cell, Row, Table},
-    prelude::Widget,
 };
+use std::fs;
+#[cfg(unix)]
+use std::fs::File;
+#[cfg(unix)]
+use std::io::BufRead;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
 #[cfg(unix)]
-use std::collections::VecDeque;
-use std::io::{self, ErrorKind, Read};
+use std::process::{Command, Stdio};
 #[cfg(unix)]
-use std::os::fd::{AsFd, AsRawFd};
+use std::sync::mpsc;
 use std::time::Duration;
 use std::time::Instant;
 #[cfg(unix)]
-use terminal_colorsaurus::{theme_mode, ThemeMode, QueryOptions};
+use terminal_colorsaurus::{theme_mode, QueryOptions, ThemeMode};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -36,29 +61,429 @@ struct Args {
     #[arg(short, long, default_value_t = 30)]
     timeout: u64,
 
-    /// Maximum number of inputs before exiting
+    /// Maximum number of inputs before exiting; coalesced key repeats count once
     #[arg(short, long, default_value_t = 10)]
     max_inputs: usize,
 
+    /// Table rows to show, defaulting to --max-inputs; older events scroll out of view and
+    /// back with PageUp/PageDown
+    #[arg(long = "rows", value_name = "N")]
+    rows: Option<usize>,
+
+    /// Show every key auto-repeat as its own row instead of one counted row
+    #[arg(long = "no-coalesce")]
+    no_coalesce: bool,
+
+    /// Milliseconds between identical events under which they count as one repeated key
+    #[arg(
+        long = "repeat-window-ms",
+        default_value_t = 100,
+        value_parser = clap::value_parser!(u64).range(1..=1000)
+    )]
+    repeat_window_ms: u64,
+
+    /// Frames drawn per second while capturing; input is gathered between frames
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u32).range(1..=120))]
+    fps: u32,
+
     /// Render rounded borders around the event table
     #[arg(long = "table-borders", default_value_t = true)]
     table_borders: bool,
+
+    /// Leave out the Time column, each event's arrival in seconds since the capture started
+    #[arg(long = "hide-time")]
+    hide_time: bool,
+
+    /// Milliseconds to wait before reporting a lone ESC as the Escape key
+    #[arg(
+        long = "esc-timeout-ms",
+        default_value_t = 10,
+        value_parser = clap::value_parser!(u64).range(1..=1000)
+    )]
+    esc_timeout_ms: u64,
+
+    /// Milliseconds to wait before flushing a partially received multi-byte sequence
+    #[arg(
+        long = "sequence-timeout-ms",
+        default_value_t = 150,
+        value_parser = clap::value_parser!(u64).range(1..=1000)
+    )]
+    sequence_timeout_ms: u64,
+
+    /// Use one flush timeout (in milliseconds) for both lone ESC and partial sequences
+    #[arg(
+        long = "flush-timeout-ms",
+        value_parser = clap::value_parser!(u64).range(1..=1000)
+    )]
+    flush_timeout_ms: Option<u64>,
+
+    /// Analyze raw bytes read from FILE instead of capturing live input
+    #[arg(long = "input", value_name = "FILE", conflicts_with = "input_hex")]
+    input: Option<PathBuf>,
+
+    /// Analyze hex-encoded bytes (e.g. "1b 5b 41") instead of capturing live input
+    #[arg(long = "input-hex", value_name = "HEX")]
+    input_hex: Option<String>,
+
+    /// Replay a recorded session file instead of capturing live input
+    #[arg(long = "replay", value_name = "FILE")]
+    replay: Option<PathBuf>,
+
+    /// Record every raw input byte with timestamps to a session file
+    #[arg(long = "record", value_name = "FILE", conflicts_with = "replay")]
+    record: Option<PathBuf>,
+
+    /// Also decode every event with crossterm and flag rows where the two disagree
+    #[arg(long = "compare-crossterm")]
+    compare_crossterm: bool,
+
+    /// Internal: run as the crossterm decoder child of --compare-crossterm
+    #[arg(long = "crossterm-decoder", hide = true)]
+    crossterm_decoder: bool,
+
+    /// How to read live input: poll() with the flush timeouts above, or the tty's VMIN/VTIME
+    /// inter-byte timer for serial lines where poll() is unreliable (exiting then waits for a key)
+    #[arg(long = "reader", value_enum, default_value_t = ReaderKind::Poll, conflicts_with = "replay")]
+    reader: ReaderKind,
+
+    /// Playback speed multiplier applied to recorded delays during replay
+    #[arg(long = "speed", default_value_t = 1.0, value_parser = parse_speed, requires = "replay")]
+    speed: f64,
+
+    /// Also print how long each phase of setting up and restoring the terminal took
+    #[arg(short, long)]
+    verbose: bool,
+
+    /// Also quit when q is pressed
+    #[arg(long = "quit-on-q", conflicts_with = "no_exit_keys")]
+    quit_on_q: bool,
+
+    /// Capture Ctrl+C like any other key instead of quitting when it is pressed twice
+    #[arg(long = "no-exit-keys")]
+    no_exit_keys: bool,
+
+    /// Function key (1-12) that pauses and resumes capture; input while paused is discarded
+    #[arg(
+        long = "pause-key",
+        default_value_t = 2,
+        value_parser = clap::value_parser!(u8).range(1..=12)
+    )]
+    pause_key: u8,
+
+    /// Record PageUp/PageDown and Shift+Up/Down like other keys instead of scrolling with them
+    #[arg(long = "capture-scroll-keys")]
+    capture_scroll_keys: bool,
+
+    /// How to write out the captured events once the capture ends; JSON and CSV go to stdout,
+    /// with the TUI on stderr, unless --output names a file
+    #[arg(long = "format", value_enum, default_value_t = OutputFormat::Table)]
+    format: OutputFormat,
+
+    /// Write the events to FILE once the terminal is restored, creating its directory; `-`
+    /// writes them to stdout in place of the final table, with the TUI on stderr, so they can
+    /// be piped
+    #[arg(long = "output", value_name = "FILE")]
+    output: Option<PathBuf>,
+
+    /// Overwrite the --output file if it already exists
+    #[arg(long = "force", requires = "output")]
+    force: bool,
 }
 
-#[derive(Debug, Clone)]
-struct InputEventInfo {
-    hex_string: String,
-    escaped_string: String,
-    guess: GuessInfo,
+/// How captured events are written out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// The event table; live captures also print a summary
+    Table,
+    /// JSON Lines, one object per event (see `_tuicore::export`)
+    Json,
+    /// CSV with a header row, with the same fields as JSON
+    Csv,
+}
+
+/// Backend used to read live input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ReaderKind {
+    Poll,
+    Vtime,
+}
+
+fn parse_speed(value: &str) -> std::result::Result<f64, String> {
+    let speed: f64 = value
+        .parse()
+        .map_err(|_| format!("`{value}` is not a number"))?;
+    if speed.is_finite() && speed > 0.0 {
+        Ok(speed)
+    } else {
+        Err("speed must be a positive number".to_string())
+    }
+}
+
+impl Args {
+    #[cfg(unix)]
+    fn coalescer(&self) -> RepeatCoalescer {
+        if self.no_coalesce {
+            RepeatCoalescer::disabled()
+        } else {
+            RepeatCoalescer::new(Duration::from_millis(self.repeat_window_ms))
+        }
+    }
+
+    #[cfg(unix)]
+    fn quit_keys(&self) -> QuitKeys {
+        QuitKeys {
+            enabled: !self.no_exit_keys,
+            quit_on_q: self.quit_on_q,
+            last_ctrl_c: None,
+        }
+    }
+
+    #[cfg(unix)]
+    fn capture_pause(&self) -> CapturePause {
+        CapturePause {
+            key: self.pause_key,
+            paused_since: None,
+            paused_total: Duration::ZERO,
+        }
+    }
+
+    #[cfg(unix)]
+    fn history_scroll(&self) -> HistoryScroll {
+        HistoryScroll {
+            enabled: !self.capture_scroll_keys,
+            rows: self.table_rows(),
+            top: None,
+        }
+    }
+
+    /// The `--output` file, or `None` for stdout.
+    fn output_file(&self) -> Option<&Path> {
+        self.output
+            .as_deref()
+            .filter(|path| *path != Path::new("-"))
+    }
+
+    /// Whether the export goes to stdout instead of the final table: with `--output -`, or a
+    /// structured `--format` and no `--output`. The TUI then draws on stderr (see
+    /// [`Args::tui_stream`]), so stdout carries nothing but the export and can be piped.
+    #[cfg(unix)]
+    fn exports_to_stdout(&self) -> bool {
+        match &self.output {
+            Some(path) => path == Path::new("-"),
+            None => self.format != OutputFormat::Table,
+        }
+    }
+
+    /// The stream the TUI draws on: stdout's inline viewport, or stderr's alternate screen
+    /// while the export takes stdout.
+    #[cfg(unix)]
+    fn tui_stream(&self) -> AlternateScreenBackend {
+        if self.exports_to_stdout() {
+            AlternateScreenBackend::Stderr
+        } else {
+            AlternateScreenBackend::Stdout
+        }
+    }
+
+    /// Rows the table asks for, before the terminal's height clamps the viewport.
+    fn table_rows(&self) -> usize {
+        self.rows.unwrap_or(self.max_inputs).max(1)
+    }
+
+    /// Viewport rows around the table: the title, pending line and header, plus borders.
+    fn table_chrome(&self) -> u16 {
+        if self.table_borders {
+            5
+        } else {
+            3
+        }
+    }
+
+    fn frame_interval(&self) -> Duration {
+        Duration::from_secs(1) / self.fps
+    }
+
+    fn flush_timeouts(&self) -> FlushTimeouts {
+        match self.flush_timeout_ms {
+            Some(ms) => Duration::from_millis(ms).into(),
+            None => FlushTimeouts {
+                esc_timeout: Duration::from_millis(self.esc_timeout_ms),
+                sequence_timeout: Duration::from_millis(self.sequence_timeout_ms),
+            },
+        }
+    }
+}
+
+/// Where offline analysis reads its bytes from.
+enum OfflineSource {
+    File(PathBuf),
+    Hex(String),
+    Stdin,
+}
+
+impl OfflineSource {
+    fn from_args(args: &Args) -> Option<Self> {
+        if let Some(path) = &args.input {
+            Some(Self::File(path.clone()))
+        } else if let Some(hex) = &args.input_hex {
+            Some(Self::Hex(hex.clone()))
+        } else if !io::stdin().is_terminal() {
+            Some(Self::Stdin)
+        } else {
+            None
+        }
+    }
+
+    /// Read the whole source into memory.
+    fn read(self) -> Result<Vec<u8>> {
+        match self {
+            Self::File(path) => fs::read(&path)
+                .wrap_err_with(|| format!("Failed to read input file {}", path.display())),
+            Self::Hex(text) => decode_hex(&text),
+            Self::Stdin => {
+                let mut bytes = Vec::new();
+                io::stdin()
+                    .read_to_end(&mut bytes)
+                    .wrap_err("Failed to read offline input")?;
+                Ok(bytes)
+            }
+        }
+    }
 }
 
+/// A table row: an interpreted event plus what the live view tracks about it.
 #[derive(Debug, Clone)]
-struct GuessInfo {
-    key: String,
-    modifiers: String,
-    description: String,
-    _code: String,
-    _kind: String,
+struct EventRow {
+    event: InputEventInfo,
+    first_byte_at: Option<Instant>,
+    last_byte_at: Option<Instant>,
+    crossterm: Option<CrosstermDecode>,
+    /// How many times in a row this event arrived; above one for coalesced key repeats.
+    repeats: usize,
+    /// Set on the row a bracketed paste streams into.
+    paste: Option<PasteProgress>,
+    /// Time since the previous event's first byte; `None` for the first event.
+    gap: Option<Duration>,
+    /// When the latest event folded into this row arrived: its own first byte, or that of its
+    /// last repeat or paste chunk.
+    last_arrival: Option<Instant>,
+    /// What the reader had delivered once the latest event folded into this row arrived.
+    received: Option<Received>,
+}
+
+/// Bytes and events the reader has delivered, counted as the capture loop takes them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Received {
+    bytes: u64,
+    events: u64,
+}
+
+#[cfg(unix)]
+impl Received {
+    fn count(&mut self, event: &RawEvent) {
+        self.bytes += event.bytes.len() as u64;
+        self.events += 1;
+    }
+
+    /// Stamp the running totals on the last row, where the event just counted landed.
+    fn stamp(&self, events: &mut [EventRow]) {
+        if let Some(last) = events.last_mut() {
+            last.received = Some(*self);
+        }
+    }
+}
+
+/// Whether the NO_COLOR and CLICOLOR conventions ask for no color, with `var` looking up the
+/// environment: `CLICOLOR_FORCE` other than `0` keeps colors on, otherwise a non-empty
+/// `NO_COLOR` or `CLICOLOR=0` turns them off.
+fn colors_disabled(var: impl Fn(&str) -> Option<String>) -> bool {
+    if var("CLICOLOR_FORCE").is_some_and(|value| !value.is_empty() && value != "0") {
+        return false;
+    }
+    var("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || var("CLICOLOR").is_some_and(|value| value == "0")
+}
+
+fn env_colors_disabled() -> bool {
+    colors_disabled(|key| std::env::var(key).ok())
+}
+
+/// Channel levels of the 6×6×6 cube in the 256-color palette.
+#[cfg(unix)]
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+#[cfg(unix)]
+fn distance(a: [u8; 3], b: [u8; 3]) -> u32 {
+    a.iter()
+        .zip(b)
+        .map(|(&a, b)| u32::from(a.abs_diff(b)).pow(2))
+        .sum()
+}
+
+/// The named color with `rgb`'s hue: a gray for washed-out colors, otherwise the hue of the
+/// channels in the upper half of its range, bright when the strongest channel is.
+#[cfg(unix)]
+fn named_color([r, g, b]: [u8; 3]) -> Color {
+    let (min, max) = (r.min(g).min(b), r.max(g).max(b));
+    if max - min < 32 {
+        return match max {
+            0..=63 => Color::Black,
+            64..=159 => Color::DarkGray,
+            160..=223 => Color::Gray,
+            _ => Color::White,
+        };
+    }
+    let mid = min + (max - min) / 2;
+    let bright = max > 191;
+    match (r > mid, g > mid, b > mid, bright) {
+        (true, false, false, false) => Color::Red,
+        (true, false, false, true) => Color::LightRed,
+        (false, true, false, false) => Color::Green,
+        (false, true, false, true) => Color::LightGreen,
+        (true, true, false, false) => Color::Yellow,
+        (true, true, false, true) => Color::LightYellow,
+        (false, false, true, false) => Color::Blue,
+        (false, false, true, true) => Color::LightBlue,
+        (true, false, true, false) => Color::Magenta,
+        (true, false, true, true) => Color::LightMagenta,
+        (false, true, true, false) => Color::Cyan,
+        (false, true, true, true) => Color::LightCyan,
+        // Every channel can't sit above the midpoint of a range that wide, nor all below it.
+        (_, _, _, false) => Color::DarkGray,
+        (_, _, _, true) => Color::Gray,
+    }
+}
+
+/// The closest color `depth` can show to `color`; only RGB colors change.
+#[cfg(unix)]
+fn downgrade(color: Color, depth: ColorDepth) -> Color {
+    let Color::Rgb(r, g, b) = color else {
+        return color;
+    };
+    let rgb = [r, g, b];
+    match depth {
+        ColorDepth::TrueColor => color,
+        ColorDepth::Ansi256 => {
+            let level = |channel: u8| {
+                (0..6)
+                    .min_by_key(|&i| CUBE_LEVELS[i].abs_diff(channel))
+                    .unwrap()
+            };
+            let cube = rgb.map(level);
+            let cube_rgb = cube.map(|i| CUBE_LEVELS[i]);
+            // The 24 grays run from 8 to 238 in steps of 10.
+            let average = rgb.iter().map(|&c| u32::from(c)).sum::<u32>() / 3;
+            let gray = (average.saturating_sub(3) / 10).min(23) as u8;
+            let gray_rgb = [8 + gray * 10; 3];
+            if distance(rgb, gray_rgb) < distance(rgb, cube_rgb) {
+                Color::Indexed(232 + gray)
+            } else {
+                Color::Indexed(16 + 36 * cube[0] as u8 + 6 * cube[1] as u8 + cube[2] as u8)
+            }
+        }
+        ColorDepth::Ansi16 => named_color(rgb),
+        ColorDepth::Monochrome => Color::Reset,
+    }
 }
 
 #[cfg(unix)]
@@ -82,57 +507,173 @@ struct AppPalette {
     info_fg: Color,
     row_even_bg: Color,
     row_odd_bg: Color,
+    mismatch_bg: Color,
+    /// Gaps between events under [`QUICK_GAP`].
+    gap_quick: Color,
+    /// Gaps under [`SLOW_GAP`].
+    gap_moderate: Color,
+    gap_slow: Color,
 }
 
 #[cfg(unix)]
 impl AppPalette {
-    fn detect() -> Self {
-        match theme_mode(QueryOptions::default()) {
-            Ok(ThemeMode::Light) => Self {
-                block_background: Color::Rgb(247, 247, 250),
-                table_background: Color::Rgb(247, 247, 250),
-                border: Color::Rgb(190, 198, 216),
-                title_primary: Color::Rgb(55, 60, 92),
-                title_accent: Color::Rgb(103, 140, 220),
-                title_muted: Color::Rgb(120, 128, 156),
-                status_primary: Color::Rgb(54, 112, 186),
-                status_secondary: Color::Rgb(118, 132, 156),
-                divider: Color::Rgb(188, 194, 208),
-                header_fg: Color::Rgb(58, 62, 94),
-                header_bg: Color::Rgb(228, 231, 241),
-                hex_fg: Color::Rgb(163, 103, 24),
-                escape_fg: Color::Rgb(71, 134, 182),
-                key_fg: Color::Rgb(63, 136, 74),
-                modifiers_fg: Color::Rgb(143, 92, 170),
-                info_fg: Color::Rgb(60, 64, 88),
-                row_even_bg: Color::Rgb(235, 238, 246),
-                row_odd_bg: Color::Rgb(244, 244, 250),
-            },
-            Ok(ThemeMode::Dark) | Err(_) => Self {
-                block_background: Color::Rgb(22, 24, 32),
-                table_background: Color::Rgb(22, 24, 32),
-                border: Color::Rgb(82, 86, 105),
-                title_primary: Color::Rgb(233, 226, 248),
-                title_accent: Color::Rgb(137, 220, 235),
-                title_muted: Color::Rgb(150, 155, 170),
-                status_primary: Color::Rgb(244, 208, 149),
-                status_secondary: Color::Rgb(158, 167, 188),
-                divider: Color::Rgb(90, 96, 120),
-                header_fg: Color::Rgb(244, 235, 208),
-                header_bg: Color::Rgb(40, 42, 54),
-                hex_fg: Color::Rgb(247, 208, 96),
-                escape_fg: Color::Rgb(124, 209, 226),
-                key_fg: Color::Rgb(143, 220, 155),
-                modifiers_fg: Color::Rgb(218, 163, 241),
-                info_fg: Color::Rgb(220, 222, 233),
-                row_even_bg: Color::Rgb(28, 30, 40),
-                row_odd_bg: Color::Rgb(24, 26, 35),
-            },
+    /// The theme the terminal reports, or `None` if it doesn't answer.
+    ///
+    /// Only safe while nothing else reads the tty, as during a replay.
+    fn query_theme() -> Option<ThemeMode> {
+        theme_mode(QueryOptions::default()).ok()
+    }
+
+    /// The theme the terminal reports, asked on `out`, the stream the TUI draws on, and
+    /// answered through the reader that owns the tty so the replies don't show up as input.
+    fn query_theme_with<S: ByteSource, C: Clock>(
+        reader: &mut RawInputReader<S, C>,
+        out: &mut impl Write,
+    ) -> Option<ThemeMode> {
+        theme::query_theme(reader, out, QueryOptions::default().timeout)
+            .inspect_err(|e| tracing::warn!("Theme query failed: {}", e))
+            .ok()
+            .flatten()
+    }
+
+    /// The palette for `theme`, dark if it is unknown, or plain if colors are disabled, cut
+    /// down to the colors the terminal advertises.
+    fn for_theme(theme: Option<ThemeMode>) -> Self {
+        if env_colors_disabled() {
+            return Self::plain();
+        }
+        let palette = match theme {
+            Some(ThemeMode::Light) => Self::light(),
+            Some(ThemeMode::Dark) | None => Self::dark(),
+        };
+        palette.downgraded(color_depth())
+    }
+
+    /// Every color replaced by the closest one `depth` can show, so terminals without 24-bit
+    /// color never get RGB sequences.
+    fn downgraded(self, depth: ColorDepth) -> Self {
+        let down = |color| downgrade(color, depth);
+        Self {
+            block_background: down(self.block_background),
+            table_background: down(self.table_background),
+            border: down(self.border),
+            title_primary: down(self.title_primary),
+            title_accent: down(self.title_accent),
+            title_muted: down(self.title_muted),
+            status_primary: down(self.status_primary),
+            status_secondary: down(self.status_secondary),
+            divider: down(self.divider),
+            header_fg: down(self.header_fg),
+            header_bg: down(self.header_bg),
+            hex_fg: down(self.hex_fg),
+            escape_fg: down(self.escape_fg),
+            key_fg: down(self.key_fg),
+            modifiers_fg: down(self.modifiers_fg),
+            info_fg: down(self.info_fg),
+            row_even_bg: down(self.row_even_bg),
+            row_odd_bg: down(self.row_odd_bg),
+            mismatch_bg: down(self.mismatch_bg),
+            gap_quick: down(self.gap_quick),
+            gap_moderate: down(self.gap_moderate),
+            gap_slow: down(self.gap_slow),
+        }
+    }
+
+    fn light() -> Self {
+        Self {
+            block_background: Color::Rgb(247, 247, 250),
+            table_background: Color::Rgb(247, 247, 250),
+            border: Color::Rgb(190, 198, 216),
+            title_primary: Color::Rgb(55, 60, 92),
+            title_accent: Color::Rgb(103, 140, 220),
+            title_muted: Color::Rgb(120, 128, 156),
+            status_primary: Color::Rgb(54, 112, 186),
+            status_secondary: Color::Rgb(118, 132, 156),
+            divider: Color::Rgb(188, 194, 208),
+            header_fg: Color::Rgb(58, 62, 94),
+            header_bg: Color::Rgb(228, 231, 241),
+            hex_fg: Color::Rgb(163, 103, 24),
+            escape_fg: Color::Rgb(71, 134, 182),
+            key_fg: Color::Rgb(63, 136, 74),
+            modifiers_fg: Color::Rgb(143, 92, 170),
+            info_fg: Color::Rgb(60, 64, 88),
+            row_even_bg: Color::Rgb(235, 238, 246),
+            row_odd_bg: Color::Rgb(244, 244, 250),
+            mismatch_bg: Color::Rgb(250, 222, 222),
+            gap_quick: Color::Rgb(52, 130, 64),
+            gap_moderate: Color::Rgb(166, 124, 0),
+            gap_slow: Color::Rgb(196, 60, 60),
+        }
+    }
+
+    fn dark() -> Self {
+        Self {
+            block_background: Color::Rgb(22, 24, 32),
+            table_background: Color::Rgb(22, 24, 32),
+            border: Color::Rgb(82, 86, 105),
+            title_primary: Color::Rgb(233, 226, 248),
+            title_accent: Color::Rgb(137, 220, 235),
+            title_muted: Color::Rgb(150, 155, 170),
+            status_primary: Color::Rgb(244, 208, 149),
+            status_secondary: Color::Rgb(158, 167, 188),
+            divider: Color::Rgb(90, 96, 120),
+            header_fg: Color::Rgb(244, 235, 208),
+            header_bg: Color::Rgb(40, 42, 54),
+            hex_fg: Color::Rgb(247, 208, 96),
+            escape_fg: Color::Rgb(124, 209, 226),
+            key_fg: Color::Rgb(143, 220, 155),
+            modifiers_fg: Color::Rgb(218, 163, 241),
+            info_fg: Color::Rgb(220, 222, 233),
+            row_even_bg: Color::Rgb(28, 30, 40),
+            row_odd_bg: Color::Rgb(24, 26, 35),
+            mismatch_bg: Color::Rgb(74, 32, 38),
+            gap_quick: Color::Rgb(143, 220, 155),
+            gap_moderate: Color::Rgb(240, 214, 120),
+            gap_slow: Color::Rgb(243, 139, 150),
+        }
+    }
+
+    /// The terminal's own colors throughout, for when colors are disabled.
+    fn plain() -> Self {
+        Self {
+            block_background: Color::Reset,
+            table_background: Color::Reset,
+            border: Color::Reset,
+            title_primary: Color::Reset,
+            title_accent: Color::Reset,
+            title_muted: Color::Reset,
+            status_primary: Color::Reset,
+            status_secondary: Color::Reset,
+            divider: Color::Reset,
+            header_fg: Color::Reset,
+            header_bg: Color::Reset,
+            hex_fg: Color::Reset,
+            escape_fg: Color::Reset,
+            key_fg: Color::Reset,
+            modifiers_fg: Color::Reset,
+            info_fg: Color::Reset,
+            row_even_bg: Color::Reset,
+            row_odd_bg: Color::Reset,
+            mismatch_bg: Color::Reset,
+            gap_quick: Color::Reset,
+            gap_moderate: Color::Reset,
+            gap_slow: Color::Reset,
+        }
+    }
+
+    /// Green for a gap between events under [`QUICK_GAP`], yellow under [`SLOW_GAP`], red
+    /// beyond; muted for the first event, which has none.
+    fn gap_color(&self, gap: Option<Duration>) -> Color {
+        match gap {
+            None => self.status_secondary,
+            Some(gap) if gap < QUICK_GAP => self.gap_quick,
+            Some(gap) if gap < SLOW_GAP => self.gap_moderate,
+            Some(_) => self.gap_slow,
         }
     }
 
     fn row_background(&self, index: usize) -> Color {
-        if index % 2 == 0 {
+        if index.is_multiple_of(2) {
             self.row_even_bg
         } else {
             self.row_odd_bg
@@ -140,19 +681,37 @@ impl AppPalette {
     }
 }
 
+/// Gaps between events shorter than this are typical of pastes, repeats and escape sequences
+/// split across reads.
+#[cfg(unix)]
+const QUICK_GAP: Duration = Duration::from_millis(20);
+
+/// Gaps between events from this long on are typical of separate human keypresses.
+#[cfg(unix)]
+const SLOW_GAP: Duration = Duration::from_millis(100);
+
 #[cfg(unix)]
+#[allow(clippy::too_many_arguments)]
 fn build_title_line(
     label: &str,
     input_count: usize,
     max_inputs: usize,
     elapsed: Duration,
     timeout: u64,
+    flush_timeouts: FlushTimeouts,
+    rates: Rates,
+    paused: bool,
     palette: &AppPalette,
 ) -> Line<'static> {
     let elapsed_text = format!("{:.1}s", elapsed.as_secs_f32());
     let timeout_text = format!("{}s", timeout);
+    let flush_text = format!(
+        "ESC {}ms / Seq {}ms",
+        flush_timeouts.esc_timeout.as_millis(),
+        flush_timeouts.sequence_timeout.as_millis()
+    );
 
-    Line::from(vec![
+    let mut spans = vec![
         Span::styled("◈ ", Style::default().fg(palette.title_accent)),
         Span::styled(
             label.to_string(),
@@ -188,26 +747,183 @@ fn build_title_line(
             format!(" / {}", timeout_text),
             Style::default().fg(palette.status_secondary),
         ),
-    ])
+        Span::raw("   "),
+        Span::styled("Flush", Style::default().fg(palette.title_muted)),
+        Span::raw(" "),
+        Span::styled(flush_text, Style::default().fg(palette.status_secondary)),
+        Span::raw("   "),
+        Span::styled("Rate", Style::default().fg(palette.title_muted)),
+        Span::raw(" "),
+        Span::styled(
+            rates.to_string(),
+            Style::default().fg(palette.status_secondary),
+        ),
+    ];
+
+    if paused {
+        spans.push(Span::raw("   "));
+        spans.push(Span::styled(
+            " PAUSED ",
+            Style::default()
+                .fg(palette.block_background)
+                .bg(palette.title_accent)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+
+    Line::from(spans)
+}
+
+/// Rates averaged over the whole session, for the final summary.
+#[cfg(unix)]
+fn average_rates(throughput: &Throughput, elapsed: Duration) -> Rates {
+    let secs = elapsed.as_secs_f64().max(1.0);
+    Rates {
+        bytes_per_sec: (throughput.total_bytes() as f64 / secs).round() as u64,
+        events_per_sec: (throughput.total_events() as f64 / secs).round() as u64,
+    }
 }
 
+/// Most pending bytes shown before the preview is truncated.
+#[cfg(unix)]
+const PENDING_PREVIEW_BYTES: usize = 16;
+
+#[cfg(unix)]
+fn build_pending_line(pending: &[u8], palette: &AppPalette) -> Line<'static> {
+    let label = Span::styled("pending: ", Style::default().fg(palette.title_muted));
+    if pending.is_empty() {
+        return Line::from(vec![
+            label,
+            Span::styled("—", Style::default().fg(palette.status_secondary)),
+        ]);
+    }
+
+    let mut preview = pending
+        .iter()
+        .take(PENDING_PREVIEW_BYTES)
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if pending.len() > PENDING_PREVIEW_BYTES {
+        preview.push_str(&format!(" … ({} bytes)", pending.len()));
+    }
 
+    Line::from(vec![
+        label,
+        Span::styled(
+            preview,
+            Style::default()
+                .fg(palette.hex_fg)
+                .add_modifier(Modifier::BOLD),
+        ),
+    ])
+}
 
 #[cfg(unix)]
-fn build_header_row(palette: &AppPalette) -> Row<'static> {
+fn build_header_row(palette: &AppPalette, time: bool, compare: bool) -> Row<'static> {
     let header_style = Style::default()
         .fg(palette.header_fg)
         .bg(palette.header_bg)
         .add_modifier(Modifier::BOLD);
 
-    Row::new(vec![
+    let mut cells = Vec::new();
+    if time {
+        cells.push(Cell::from("Time"));
+    }
+    cells.extend([
+        Cell::from("Δ"),
         Cell::from("Hex"),
         Cell::from("Esc"),
         Cell::from("Key"),
         Cell::from("Mods"),
-        Cell::from("Info"),
-    ])
-    .style(header_style)
+        Cell::from("Spread"),
+    ]);
+    if compare {
+        cells.push(Cell::from("Crossterm"));
+    }
+    cells.push(Cell::from("Info"));
+    Row::new(cells).style(header_style)
+}
+
+#[cfg(unix)]
+fn column_widths(time: bool, compare: bool) -> Vec<Constraint> {
+    let mut widths = Vec::new();
+    if time {
+        widths.push(Constraint::Length(9));
+    }
+    widths.extend([
+        Constraint::Length(9),
+        Constraint::Length(18),
+        Constraint::Length(20),
+        Constraint::Length(12),
+        Constraint::Length(14),
+        Constraint::Length(8),
+    ]);
+    if compare {
+        widths.push(Constraint::Length(22));
+    }
+    widths.push(Constraint::Min(10));
+    widths
+}
+
+/// One frame of the event table.
+#[cfg(unix)]
+struct EventsView<'a> {
+    title: Line<'static>,
+    /// Bytes read toward an unfinished sequence, shown above the table; `None` leaves the row
+    /// out.
+    pending: Option<&'a [u8]>,
+    events: &'a [EventRow],
+    palette: &'a AppPalette,
+    borders: bool,
+    /// When the capture started, for the Time column; `None` leaves the column out.
+    started: Option<Instant>,
+    compare: bool,
+}
+
+#[cfg(unix)]
+fn render_events(view: &EventsView<'_>, area: Rect, buf: &mut Buffer) {
+    let palette = view.palette;
+    let block = Block::default()
+        .title(view.title.clone())
+        .style(Style::default().bg(palette.block_background));
+
+    let block = if view.borders {
+        block
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(palette.border))
+    } else {
+        block
+    };
+
+    let inner_area = block.inner(area);
+    block.render(area, buf);
+
+    let table_area = match view.pending {
+        Some(pending) => {
+            let [pending_area, table_area] =
+                Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(inner_area);
+            build_pending_line(pending, palette).render(pending_area, buf);
+            table_area
+        }
+        None => inner_area,
+    };
+
+    let events_rows: Vec<Row> = view
+        .events
+        .iter()
+        .enumerate()
+        .map(|(idx, info)| format_event_info(info, palette, idx, view.started, view.compare))
+        .collect();
+
+    let time = view.started.is_some();
+    let events_table = Table::new(events_rows, column_widths(time, view.compare))
+        .header(build_header_row(palette, time, view.compare))
+        .column_spacing(1)
+        .style(Style::default().bg(palette.table_background));
+
+    Widget::render(&events_table, table_area, buf);
 }
 
 fn main() -> eyre::Result<()> {
@@ -215,7 +931,15 @@ fn main() -> eyre::Result<()> {
 
     let args = Args::parse();
 
-    let result = run(args);
+    #[cfg(unix)]
+    if args.crossterm_decoder {
+        return Ok(run_crossterm_decoder()?);
+    }
+
+    let result = match OfflineSource::from_args(&args) {
+        Some(source) => run_offline(source, &args),
+        None => run(args),
+    };
 
     if let Err(ref e) = result {
         tracing::error!("Application error: {}", e);
@@ -227,27 +951,100 @@ fn main() -> eyre::Result<()> {
 
 #[cfg(unix)]
 fn run(args: Args) -> Result<()> {
-    const DRAW_TIMEOUT: Duration = Duration::from_millis(100);
-    const FLUSH_TIMEOUT: Duration = Duration::from_millis(35);
-
-    let border_offset: u16 = if args.table_borders { 2 } else { 0 };
-    let height = args.max_inputs as u16 + 2 + border_offset; // extra space for header and borders
-    let mut tui_app = TuiApp::builder("controlsequencedebugger")
-        .inline(height)
-        .build();
+    // A taller viewport would only be clamped to the terminal anyway.
+    let height = (args.table_rows() as u16)
+        .saturating_add(args.table_chrome())
+        .min(MAX_INLINE_HEIGHT);
+    // Pastes then arrive framed by markers, so they stream into one row instead of many keys.
+    // Frames are synchronized so the table doesn't tear while events stream in.
+    let builder = TuiApp::builder("controlsequencedebugger")
+        .bracketed_paste(true)
+        .synchronized_output(true)
+        .theme_notifications(true);
+    // An inline viewport needs stdout, so with the export there the TUI takes over stderr's
+    // alternate screen instead, and can't fall back to the pipe it would corrupt.
+    let mut tui_app = match args.tui_stream() {
+        AlternateScreenBackend::Stderr => builder
+            .alternate_screen_backend(AlternateScreenBackend::Stderr)
+            .non_tty_behavior(NonTtyBehavior::Error)
+            .build(),
+        AlternateScreenBackend::Stdout => builder.inline(height).build(),
+    };
+    // Checked up front, rather than losing the whole session to a file that won't be written.
+    if let Some(path) = args.output_file() {
+        if !args.force && path.exists() {
+            return Err(output_exists(path));
+        }
+    }
     let mut terminal = tui_app.init()?;
 
-    let mut events: Vec<InputEventInfo> = Vec::new();
+    let mut events: Vec<EventRow> = Vec::new();
     let mut input_count = 0usize;
+    let mut coalescer = args.coalescer();
+    let mut input_closed = false;
+    let mut shutdown = None;
+    let mut quit_keys = args.quit_keys();
+    let mut quit = false;
 
     let timeout_duration = Duration::from_secs(args.timeout);
+    let frame_interval = args.frame_interval();
     let start_time = Instant::now();
-    let palette = AppPalette::detect();
+    let mut next_frame = start_time + frame_interval;
+    let flush_timeouts = args.flush_timeouts();
+
+    let mut tui_out: Box<dyn Write> = match tui_app.output_stream() {
+        AlternateScreenBackend::Stdout => Box::new(io::stdout()),
+        AlternateScreenBackend::Stderr => Box::new(io::stderr()),
+    };
+    let queried_theme;
+    let input = match &args.replay {
+        Some(path) => {
+            queried_theme = AppPalette::query_theme();
+            ChannelInputReader::spawn_source(
+                SessionReplay::open(path, flush_timeouts, args.speed)?,
+                frame_interval,
+            )?
+        }
+        None if args.reader == ReaderKind::Vtime => {
+            let mut live = RawInputReader::with_vtime(io::stdin())?;
+            queried_theme = AppPalette::query_theme_with(&mut live, &mut tui_out);
+            if let Some(path) = &args.record {
+                live.record_to(SessionRecorder::create(path)?);
+            }
+            ChannelInputReader::spawn_source(live, frame_interval)?
+        }
+        None => {
+            let mut live = RawInputReader::new(flush_timeouts)?;
+            live.watch_resize()?;
+            live.watch_shutdown()?;
+            queried_theme = AppPalette::query_theme_with(&mut live, &mut tui_out);
+            if let Some(path) = &args.record {
+                live.record_to(SessionRecorder::create(path)?);
+            }
+            ChannelInputReader::spawn(live, frame_interval)?
+        }
+    };
+    // Follows the terminal's dark/light switches, where it reports them.
+    let mut theme = ThemeWatcher::new(queried_theme);
+    let mut palette = AppPalette::for_theme(theme.current());
 
-    let mut reader = RawInputReader::new(FLUSH_TIMEOUT)?;
+    let compare = args.compare_crossterm;
+    let mut comparer = if compare {
+        Some(CrosstermComparer::spawn()?)
+    } else {
+        None
+    };
+
+    let mut pause = args.capture_pause();
+    let mut scroll = args.history_scroll();
+    let mut received = Received::default();
+    // A terminal shorter than the table clamps the viewport, leaving fewer rows to show.
+    scroll.fit(terminal.get_frame().area().height, args.table_chrome());
 
     loop {
-        if start_time.elapsed() >= timeout_duration {
+        // Let an in-flight sequence complete or flush rather than cutting it off at the deadline.
+        let active = pause.active_time(start_time, Instant::now());
+        if active >= timeout_duration && input.pending_bytes().is_empty() {
             break;
         }
 
@@ -255,126 +1052,201 @@ fn run(args: Args) -> Result<()> {
             break;
         }
 
-        if let Some(bytes) = reader.poll_next(DRAW_TIMEOUT)? {
-            process_event_bytes(bytes, &mut events, &mut input_count);
+        if input_closed {
+            break;
+        }
 
-            while let Some(extra) = reader.poll_next(Duration::ZERO)? {
-                process_event_bytes(extra, &mut events, &mut input_count);
-                if input_count >= args.max_inputs {
-                    break;
+        // Gather messages until the next frame is due, so frames land on a fixed cadence.
+        let frame_at = next_frame;
+        while input_count < args.max_inputs && !input_closed && shutdown.is_none() && !quit {
+            let remaining = frame_at.saturating_duration_since(Instant::now());
+            let Ok(current) = input.recv_timeout(remaining) else {
+                break;
+            };
+            match current {
+                InputMessage::Event(event) => {
+                    if let Some(mode) = theme.feed(&event.bytes) {
+                        palette = AppPalette::for_theme(Some(mode));
+                    }
+                    received.count(&event);
+                    // Checked first, so a quit chord still ends a paused capture.
+                    quit = quit_keys.is_quit(&event);
+                    if !quit && intercept_control(&event, &mut scroll, &mut pause, events.len()) {
+                        continue;
+                    }
+                    process_event(event, &mut coalescer, comparer.as_mut(), &mut events)?;
+                    received.stamp(&mut events);
+                    if quit {
+                        if let Some(last) = events.last_mut() {
+                            last.mark_quit();
+                        }
+                    }
+                    // Repeats share a row, so a held key never uses up --max-inputs.
+                    input_count = coalescer.entries();
                 }
+                InputMessage::Resize => {
+                    terminal.autoresize()?;
+                    scroll.fit(terminal.get_frame().area().height, args.table_chrome());
+                    events.push(EventRow::resize()?);
+                    coalescer.reset();
+                }
+                InputMessage::Shutdown(signal) => shutdown = Some(signal),
+                InputMessage::Tick => {}
+                InputMessage::Eof => input_closed = true,
+                InputMessage::Error(err) => return Err(err.into()),
             }
         }
+        // Frames missed while busy are skipped rather than drawn back to back.
+        while next_frame <= Instant::now() {
+            next_frame += frame_interval;
+        }
 
-        terminal.draw(|f| {
-            let size = f.area();
-            let title_line = build_title_line(
-                "Events",
-                input_count,
-                args.max_inputs,
-                start_time.elapsed(),
-                args.timeout,
-                &palette,
-            );
-
-            let block = Block::default()
-                .title(title_line)
-                .style(Style::default().bg(palette.block_background));
-
-            let block = if args.table_borders {
-                block
-                    .borders(Borders::ALL)
-                    .border_type(BorderType::Rounded)
-                    .border_style(Style::default().fg(palette.border))
-            } else {
-                block
-            };
+        if shutdown.is_some() || quit {
+            break;
+        }
 
-            let inner_area = block.inner(size);
-            f.render_widget(block, size);
-
-            let header = build_header_row(&palette);
-
-            let widths = [
-                Constraint::Length(18),
-                Constraint::Length(20),
-                Constraint::Length(12),
-                Constraint::Length(14),
-                Constraint::Length(12),
-                Constraint::Length(12),
-                Constraint::Min(10),
-            ];
-
-            let events_rows: Vec<Row> = events
-                .iter()
-                .take(args.max_inputs)
-                .enumerate()
-                .map(|(idx, info)| format_event_info(info, &palette, idx))
-                .collect();
-
-            let events_table = Table::new(events_rows, widths)
-                .header(header)
-                .column_spacing(1)
-                .style(Style::default().bg(palette.table_background));
-
-            Widget::render(&events_table, inner_area, f.buffer_mut());
+        let pending = input.pending_bytes();
+        let window = scroll.window(events.len());
+        let mut title = build_title_line(
+            "Events",
+            input_count,
+            args.max_inputs,
+            // The scheduled frame time, so the countdown advances in even steps.
+            pause.active_time(start_time, frame_at),
+            args.timeout,
+            flush_timeouts,
+            input.throughput().rates(frame_at),
+            pause.is_paused(),
+            &palette,
+        );
+        if window.len() < events.len() {
+            title.push_span(Span::raw("   "));
+            title.push_span(Span::styled(
+                scroll.label(events.len()),
+                Style::default().fg(palette.status_secondary),
+            ));
+        }
+        let view = EventsView {
+            title,
+            pending: Some(&pending),
+            events: &events[window],
+            palette: &palette,
+            borders: args.table_borders,
+            started: (!args.hide_time).then_some(start_time),
+            compare,
+        };
+        tui_app.draw_synced(&mut terminal, |f| {
+            render_events(&view, f.area(), f.buffer_mut())
         })?;
     }
 
+    let latency = input.latency();
+    let throughput = input.throughput();
+    let elapsed = start_time.elapsed();
+    let active = pause.active_time(start_time, Instant::now());
+    // Dropping the reader joins its thread, which flushes any in-progress session recording.
+    drop(input);
     tui_app.restore()?;
 
-    terminal.insert_before(height, |f| {
-        let size = f.area();
-        let title_line = build_title_line(
+    // Only now that the terminal is restored, so no escape output can end up in the export.
+    // A failed file is reported after the summary, which still has the events.
+    let infos: Vec<InputEventInfo> = events.iter().map(|row| row.event.clone()).collect();
+    let exported: Vec<ExportedEvent> = events.iter().map(|row| row.export(start_time)).collect();
+    let export = match args.output_file() {
+        Some(path) => export_to_file(path, args.force, args.format, &infos, &exported),
+        // The export is all that goes to stdout, so it can be piped.
+        None if args.exports_to_stdout() => {
+            write_export(
+                &mut io::stdout().lock(),
+                args.format,
+                &infos,
+                &exported,
+                false,
+            )?;
+            if let Some(signal) = shutdown {
+                tui_app.shutdown()?;
+                std::process::exit(128 + signal);
+            }
+            return Ok(());
+        }
+        None => Ok(()),
+    };
+
+    let view = EventsView {
+        title: build_title_line(
             "Final Events",
             input_count,
             args.max_inputs,
-            start_time.elapsed(),
+            active,
             args.timeout,
+            flush_timeouts,
+            average_rates(&throughput, elapsed),
+            false,
             &palette,
-        );
-
-        let block = Block::default()
-            .title(title_line)
-            .style(Style::default().bg(palette.block_background));
-
-        let block = if args.table_borders {
-            block
-                .borders(Borders::ALL)
-                .border_type(BorderType::Rounded)
-                .border_style(Style::default().fg(palette.border))
-        } else {
-            block
-        };
-
-        let inner_area = block.inner(*size);
-        block.render(*size, f);
+        ),
+        pending: None,
+        events: &events,
+        palette: &palette,
+        borders: args.table_borders,
+        started: (!args.hide_time).then_some(start_time),
+        compare,
+    };
+    // Tall enough for every event, including those scrolled out of the live table.
+    let lines = events.len().max(args.table_rows()) + usize::from(args.table_chrome());
+    tui_app.insert_lines_above(&mut terminal, lines, |f| render_events(&view, f.area, f))?;
 
-        let header = build_header_row(&palette);
+    if input_closed {
+        println!("Input closed.");
+    }
+    if quit {
+        println!("Quit by key.");
+    }
 
-        let widths = [
-            Constraint::Length(18),
-            Constraint::Length(20),
-            Constraint::Length(14),
-            Constraint::Length(12),
-            Constraint::Min(10),
-        ];
+    println!("Latency, wakeup to frame: {}", latency.wakeup_to_frame);
+    println!(
+        "Latency, first byte to emit: {}",
+        latency.first_byte_to_emit
+    );
+    println!(
+        "Throughput: {} bytes, {} events ({} on average).",
+        throughput.total_bytes(),
+        throughput.total_events(),
+        average_rates(&throughput, elapsed)
+    );
+    if args.verbose {
+        println!("Terminal setup and restore: {}.", tui_app.timings());
+    }
 
-        let events_rows: Vec<Row> = events
+    if compare {
+        let compared = events
             .iter()
-            .enumerate()
-            .map(|(idx, info)| format_event_info(info, &palette, idx))
-            .collect();
+            .filter(|info| info.crossterm.is_some())
+            .count();
+        let mismatches = events
+            .iter()
+            .filter(|info| {
+                info.crossterm
+                    .as_ref()
+                    .is_some_and(|decode| !decode.matches)
+            })
+            .count();
+        println!(
+            "Crossterm disagreed on {} of {} events.",
+            mismatches, compared
+        );
+    }
 
-        let events_table = Table::new(events_rows, widths)
-            .header(header)
-            .column_spacing(1)
-            .style(Style::default().bg(palette.table_background));
+    if let Some(signal) = shutdown {
+        tracing::info!("Exiting on signal {}", signal);
+        if let Err(err) = &export {
+            eprintln!("Error: {err:?}");
+        }
+        // Exiting skips destructors, so flush the log first.
+        tui_app.shutdown()?;
+        std::process::exit(128 + signal);
+    }
 
-        Widget::render(&events_table, inner_area, f);
-    })?;
-
-    Ok(())
+    export
 }
 
 #[cfg(not(unix))]
@@ -384,744 +1256,1516 @@ fn run(_args: Args) -> Result<()> {
     ))
 }
 
+fn run_offline(source: OfflineSource, args: &Args) -> Result<()> {
+    let events = parse_event_stream(&source.read()?);
+    let exported: Vec<ExportedEvent> = events
+        .iter()
+        .map(|info| ExportedEvent::new(info, None))
+        .collect();
+    match args.output_file() {
+        Some(path) => export_to_file(path, args.force, args.format, &events, &exported)?,
+        None => {
+            let stdout = io::stdout();
+            let styled = stdout.is_terminal() && !env_colors_disabled();
+            write_export(&mut stdout.lock(), args.format, &events, &exported, styled)?;
+        }
+    }
+    Ok(())
+}
+
+/// Write the export to a new file at `path`, creating its directory; an existing file is only
+/// replaced with `force`.
+fn export_to_file(
+    path: &Path,
+    force: bool,
+    format: OutputFormat,
+    events: &[InputEventInfo],
+    exported: &[ExportedEvent],
+) -> Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)
+            .wrap_err_with(|| format!("Failed to create directory {}", dir.display()))?;
+    }
+    let mut options = File::options();
+    if force {
+        options.write(true).create(true).truncate(true);
+    } else {
+        options.write(true).create_new(true);
+    }
+    let file = options.open(path).map_err(|err| match err.kind() {
+        io::ErrorKind::AlreadyExists => output_exists(path),
+        _ => eyre::Report::new(err).wrap_err(format!("Failed to create {}", path.display())),
+    })?;
+    let mut out = io::BufWriter::new(file);
+    // Flushed here, since dropping the writer would swallow a failed write.
+    write_export(&mut out, format, events, exported, false)
+        .and_then(|()| out.flush())
+        .wrap_err_with(|| format!("Failed to write events to {}", path.display()))
+}
+
+fn output_exists(path: &Path) -> eyre::Report {
+    eyre::eyre!(
+        "{} already exists; pass --force to overwrite it",
+        path.display()
+    )
+}
+
+/// Write `events` in `format`; `exported` holds the same events with their timestamps.
+fn write_export(
+    out: &mut impl Write,
+    format: OutputFormat,
+    events: &[InputEventInfo],
+    exported: &[ExportedEvent],
+    styled: bool,
+) -> io::Result<()> {
+    match format {
+        OutputFormat::Table => write_event_table(out, events, styled),
+        OutputFormat::Json => write_json_lines(out, exported),
+        OutputFormat::Csv => write_csv(out, exported),
+    }
+}
+
+fn decode_hex(text: &str) -> Result<Vec<u8>> {
+    let digits: String = text
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .map(|token| {
+            token
+                .trim_start_matches("0x")
+                .trim_start_matches("0X")
+                .trim_start_matches("\\x")
+        })
+        .collect();
+
+    if !digits.is_ascii() {
+        return Err(eyre::eyre!("Hex input contains non-ASCII characters"));
+    }
+    if !digits.len().is_multiple_of(2) {
+        return Err(eyre::eyre!("Hex input has an odd number of digits"));
+    }
+
+    (0..digits.len())
+        .step_by(2)
+        .map(|idx| {
+            u8::from_str_radix(&digits[idx..idx + 2], 16)
+                .wrap_err_with(|| format!("Invalid hex byte {:?}", &digits[idx..idx + 2]))
+        })
+        .collect()
+}
+
+fn write_event_table(
+    out: &mut impl Write,
+    events: &[InputEventInfo],
+    styled: bool,
+) -> io::Result<()> {
+    let header = ["Hex", "Esc", "Key", "Mods", "Info"];
+    let rows: Vec<[String; 5]> = events
+        .iter()
+        .map(|info| {
+            [
+                info.hex_string.clone(),
+                info.escaped_string.clone(),
+                info.guess.key.clone(),
+                info.guess.modifiers.clone(),
+                info.guess.description.clone(),
+            ]
+        })
+        .collect();
+
+    if !styled {
+        writeln!(out, "{}", header.join("\t"))?;
+        for row in &rows {
+            writeln!(out, "{}", row.join("\t"))?;
+        }
+        return out.flush();
+    }
+
+    let mut widths = header.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let header_line = header
+        .iter()
+        .zip(widths)
+        .map(|(cell, width)| format!("{:<width$}", cell))
+        .collect::<Vec<_>>()
+        .join(" ");
+    writeln!(out, "{}", header_line.bold())?;
+
+    for [hex, escaped, key, modifiers, info] in &rows {
+        writeln!(
+            out,
+            "{} {} {} {} {}",
+            format!("{:<w$}", hex, w = widths[0]).yellow().bold(),
+            format!("{:<w$}", escaped, w = widths[1]).cyan(),
+            format!("{:<w$}", key, w = widths[2]).green().bold(),
+            format!("{:<w$}", modifiers, w = widths[3]).magenta(),
+            info,
+        )?;
+    }
+    out.flush()
+}
+
+/// How soon a second Ctrl+C has to follow the first to quit.
+#[cfg(unix)]
+const QUIT_CHORD_WINDOW: Duration = Duration::from_secs(1);
+
+/// Ctrl+C as a control byte, or as the kitty keyboard protocol encodes it.
 #[cfg(unix)]
-fn process_event_bytes(bytes: Vec<u8>, events: &mut Vec<InputEventInfo>, count: &mut usize) {
-    if bytes.is_empty() {
-        return;
+fn is_ctrl_c(bytes: &[u8]) -> bool {
+    bytes == [0x03] || bytes == b"\x1b[99;5u"
+}
+
+/// Spots the keys that end a capture early: Ctrl+C twice within [`QUIT_CHORD_WINDOW`], and q
+/// with `--quit-on-q`.
+#[cfg(unix)]
+#[derive(Debug, Clone)]
+struct QuitKeys {
+    enabled: bool,
+    quit_on_q: bool,
+    last_ctrl_c: Option<Instant>,
+}
+
+#[cfg(unix)]
+impl QuitKeys {
+    /// Whether `event` completes a quit chord. Pasted text is captured input, never a chord.
+    fn is_quit(&mut self, event: &RawEvent) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        if event.paste {
+            self.last_ctrl_c = None;
+            return false;
+        }
+        if self.quit_on_q && event.bytes == b"q" {
+            return true;
+        }
+        if !is_ctrl_c(&event.bytes) {
+            self.last_ctrl_c = None;
+            return false;
+        }
+        let at = event.first_byte_at;
+        match self.last_ctrl_c.replace(at) {
+            Some(first) => at.saturating_duration_since(first) <= QUIT_CHORD_WINDOW,
+            None => false,
+        }
+    }
+}
+
+/// Which rows of the history the table shows: the newest ones, following new events, until
+/// PageUp/PageDown or Shift+Up/Down pin it elsewhere.
+#[cfg(unix)]
+#[derive(Debug, Clone)]
+struct HistoryScroll {
+    enabled: bool,
+    /// Rows the table has room for.
+    rows: usize,
+    /// The first row shown, or `None` to follow the newest rows.
+    top: Option<usize>,
+}
+
+#[cfg(unix)]
+impl HistoryScroll {
+    /// How many rows `event` scrolls by, if it is a scroll key: a page for PageUp/PageDown
+    /// and one row for Shift+Up/Down, negative towards older rows.
+    fn step(&self, event: &RawEvent) -> Option<isize> {
+        if !self.enabled {
+            return None;
+        }
+        let key = interpret_bytes(&event.bytes)?;
+        let page = self.rows.max(1) as isize;
+        match (key.code, key.modifiers) {
+            (KeyCode::PageUp, KeyModifiers::NONE) => Some(-page),
+            (KeyCode::PageDown, KeyModifiers::NONE) => Some(page),
+            (KeyCode::Up, KeyModifiers::SHIFT) => Some(-1),
+            (KeyCode::Down, KeyModifiers::SHIFT) => Some(1),
+            _ => None,
+        }
+    }
+
+    /// Move the view by `step` rows within a history of `len`, following new rows again once
+    /// it reaches the bottom.
+    fn scroll_by(&mut self, step: isize, len: usize) {
+        let bottom = len.saturating_sub(self.rows);
+        let top = self.top.unwrap_or(bottom).min(bottom);
+        let top = top.saturating_add_signed(step).min(bottom);
+        self.top = (top < bottom).then_some(top);
+    }
+
+    /// The rows of a history of `len` to show.
+    fn window(&self, len: usize) -> std::ops::Range<usize> {
+        let bottom = len.saturating_sub(self.rows);
+        let top = self.top.unwrap_or(bottom).min(bottom);
+        top..len.min(top + self.rows)
     }
-    let info = InputEventInfo::from_bytes(bytes);
-    events.push(info);
-    *count += 1;
+
+    /// Size the window to a viewport `height` rows tall, of which `chrome` aren't table rows.
+    fn fit(&mut self, height: u16, chrome: u16) {
+        self.rows = usize::from(height.saturating_sub(chrome)).max(1);
+    }
+
+    /// Where the view is, e.g. `rows 12–26 of 143`.
+    fn label(&self, len: usize) -> String {
+        let window = self.window(len);
+        format!("rows {}–{} of {}", window.start + 1, window.end, len)
+    }
+}
+
+/// Handle the scroll and pause keys, which are control input and never recorded as captured
+/// events; `false` lets `event` through. `history` is how many rows the table has.
+#[cfg(unix)]
+fn intercept_control(
+    event: &RawEvent,
+    scroll: &mut HistoryScroll,
+    pause: &mut CapturePause,
+    history: usize,
+) -> bool {
+    if let Some(step) = scroll.step(event) {
+        scroll.scroll_by(step, history);
+        return true;
+    }
+    pause.intercepts(event)
+}
+
+/// Pausing capture with a function key: while paused, events other than a quit chord are
+/// dropped before they reach the table or count toward `--max-inputs`, and the timeout stops
+/// running.
+#[cfg(unix)]
+#[derive(Debug, Clone)]
+struct CapturePause {
+    /// The function key number, F1 to F12.
+    key: u8,
+    paused_since: Option<Instant>,
+    paused_total: Duration,
+}
+
+#[cfg(unix)]
+impl CapturePause {
+    /// Whether `event` is the pause key, in any of its encodings and without modifiers.
+    fn is_key(&self, event: &RawEvent) -> bool {
+        interpret_bytes(&event.bytes).is_some_and(|key| {
+            key.code == KeyCode::F(self.key) && key.modifiers == KeyModifiers::NONE
+        })
+    }
+
+    /// Toggle on the pause key and swallow everything while paused; `false` lets `event`
+    /// through to be recorded.
+    fn intercepts(&mut self, event: &RawEvent) -> bool {
+        if self.is_key(event) {
+            match self.paused_since.take() {
+                Some(since) => {
+                    self.paused_total += event.first_byte_at.saturating_duration_since(since);
+                }
+                None => self.paused_since = Some(event.first_byte_at),
+            }
+            return true;
+        }
+        self.is_paused()
+    }
+
+    fn is_paused(&self) -> bool {
+        self.paused_since.is_some()
+    }
+
+    /// Time since `start` up to `now`, leaving out every pause.
+    fn active_time(&self, start: Instant, now: Instant) -> Duration {
+        let current = self
+            .paused_since
+            .map_or(Duration::ZERO, |since| now.saturating_duration_since(since));
+        now.saturating_duration_since(start)
+            .saturating_sub(self.paused_total + current)
+    }
+}
+
+#[cfg(unix)]
+fn process_event(
+    event: RawEvent,
+    coalescer: &mut RepeatCoalescer,
+    comparer: Option<&mut CrosstermComparer>,
+    events: &mut Vec<EventRow>,
+) -> Result<()> {
+    if event.bytes.is_empty() {
+        return Ok(());
+    }
+    // Paste content and its end marker fold into the paste's row without keeping the payload.
+    let open_paste = events
+        .iter_mut()
+        .rev()
+        .find(|info| info.paste.as_ref().is_some_and(|paste| !paste.done));
+    if let Some(info) = open_paste {
+        if event.paste || event.bytes == PASTE_END {
+            info.extend_paste(&event);
+            coalescer.reset();
+            return Ok(());
+        }
+    }
+    let gap = gap_since_last(events, &event);
+    if event.bytes == PASTE_START {
+        coalescer.push(&event);
+        events.push(EventRow {
+            gap,
+            ..EventRow::paste_start(&event)
+        });
+        return Ok(());
+    }
+    if let Coalesced::Repeat(repeats) = coalescer.push(&event) {
+        if let Some(last) = events.last_mut() {
+            last.repeats = repeats;
+            last.last_arrival = Some(event.first_byte_at);
+            return Ok(());
+        }
+    }
+    let crossterm_lines = comparer.map(|c| c.decode(&event.bytes)).transpose()?;
+    let mut row = EventRow::from_raw_event(&event);
+    row.crossterm =
+        crossterm_lines.map(|lines| CrosstermDecode::from_lines(&lines, &row.event.guess));
+    row.gap = gap;
+    events.push(row);
+    Ok(())
+}
+
+/// Time from the latest captured event's first byte to `event`'s, counting repeats and paste
+/// chunks folded into a row; resize rows don't count.
+#[cfg(unix)]
+fn gap_since_last(events: &[EventRow], event: &RawEvent) -> Option<Duration> {
+    let previous = events.iter().rev().find_map(|row| row.last_arrival)?;
+    Some(event.first_byte_at.saturating_duration_since(previous))
 }
 
 #[cfg(unix)]
 fn format_event_info(
-    info: &InputEventInfo,
+    info: &EventRow,
     palette: &AppPalette,
     row_index: usize,
+    started: Option<Instant>,
+    compare: bool,
 ) -> Row<'static> {
-    let description = if info.guess.description.is_empty() {
+    let description = if info.event.guess.description.is_empty() {
         String::new()
     } else {
-        info.guess.description.clone()
+        info.event.guess.description.clone()
     };
 
-    let row_bg = palette.row_background(row_index);
+    let mismatch = info
+        .crossterm
+        .as_ref()
+        .is_some_and(|decode| !decode.matches);
+    let row_bg = if mismatch {
+        palette.mismatch_bg
+    } else {
+        palette.row_background(row_index)
+    };
     let row_style = Style::default().bg(row_bg);
 
-    Row::new(vec![
-        Cell::from(info.hex_string.clone()).style(
+    let mut cells = Vec::new();
+    if let Some(start) = started {
+        cells.push(
+            Cell::from(info.time_text(start))
+                .style(Style::default().fg(palette.status_secondary).bg(row_bg)),
+        );
+    }
+    cells.extend([
+        Cell::from(info.gap_text())
+            .style(Style::default().fg(palette.gap_color(info.gap)).bg(row_bg)),
+        Cell::from(info.event.hex_string.clone()).style(
             Style::default()
                 .fg(palette.hex_fg)
                 .bg(row_bg)
                 .add_modifier(Modifier::BOLD),
         ),
-        Cell::from(info.escaped_string.clone())
+        Cell::from(info.event.escaped_string.clone())
             .style(Style::default().fg(palette.escape_fg).bg(row_bg)),
-        Cell::from(info.guess.key.clone()).style(
+        Cell::from(info.key_text()).style(
             Style::default()
                 .fg(palette.key_fg)
                 .bg(row_bg)
                 .add_modifier(Modifier::BOLD),
         ),
-        Cell::from(info.guess.modifiers.clone())
+        Cell::from(info.event.guess.modifiers.clone())
             .style(Style::default().fg(palette.modifiers_fg).bg(row_bg)),
-        Cell::from(description).style(Style::default().fg(palette.info_fg).bg(row_bg)),
-    ])
-    .style(row_style)
+        Cell::from(info.spread_text()).style(Style::default().fg(palette.info_fg).bg(row_bg)),
+    ]);
+    if compare {
+        let crossterm = info
+            .crossterm
+            .as_ref()
+            .map(|decode| decode.display.clone())
+            .unwrap_or_default();
+        cells.push(Cell::from(crossterm).style(Style::default().fg(palette.key_fg).bg(row_bg)));
+    }
+    cells.push(Cell::from(description).style(Style::default().fg(palette.info_fg).bg(row_bg)));
+    Row::new(cells).style(row_style)
 }
 
-impl InputEventInfo {
-    fn from_bytes(raw_bytes: Vec<u8>) -> Self {
-        let guess = GuessInfo::from_bytes(&raw_bytes);
-        let hex_string = raw_bytes
-            .iter()
-            .map(|b| format!("{:02X}", b))
-            .collect::<Vec<_>>()
-            .join(" ");
-        let escaped_string = escape_bytes(&raw_bytes);
+impl EventRow {
+    /// Synthetic row recording a terminal resize alongside the captured input.
+    #[cfg(unix)]
+    fn resize() -> io::Result<Self> {
+        let (cols, rows) = crossterm::terminal::size()?;
+        let mut row = Self::new(InputEventInfo::from_bytes(Vec::new()));
+        row.event.guess = GuessInfo {
+            key: "Resize".to_string(),
+            modifiers: "None".to_string(),
+            description: format!("Resize to {}x{}", cols, rows),
+            code: "Resize".to_string(),
+            kind: "Resize".to_string(),
+        };
+        Ok(row)
+    }
+
+    fn new(event: InputEventInfo) -> Self {
         Self {
-            hex_string,
-            escaped_string,
-            guess,
+            event,
+            first_byte_at: None,
+            last_byte_at: None,
+            crossterm: None,
+            repeats: 1,
+            paste: None,
+            gap: None,
+            last_arrival: None,
+            received: None,
         }
     }
-}
 
-impl GuessInfo {
-    fn from_bytes(bytes: &[u8]) -> Self {
-        match interpret_bytes(bytes) {
-            Some(interp) => GuessInfo {
-                key: interp.key_display,
-                modifiers: format_modifiers(interp.modifiers),
-                description: interp.description,
-                _code: format!("{:?}", interp.code),
-                _kind: "Press".to_string(),
-            },
-            None => GuessInfo {
-                key: "Unknown".to_string(),
-                modifiers: "None".to_string(),
-                description: String::new(),
-                _code: "Unknown".to_string(),
-                _kind: "Unknown".to_string(),
-            },
+    fn from_raw_event(event: &RawEvent) -> Self {
+        Self {
+            first_byte_at: Some(event.first_byte_at),
+            last_byte_at: Some(event.last_byte_at),
+            last_arrival: Some(event.first_byte_at),
+            ..Self::new(InputEventInfo::from_raw_event(event))
         }
     }
-}
 
-struct KeyInterpretation {
-    key_display: String,
-    code: KeyCode,
-    modifiers: KeyModifiers,
-    description: String,
-}
-
-fn interpret_bytes(bytes: &[u8]) -> Option<KeyInterpretation> {
-    if bytes.is_empty() {
-        return None;
+    /// This row for export, timestamped relative to `start`.
+    #[cfg(unix)]
+    fn export(&self, start: Instant) -> ExportedEvent {
+        let timestamp = self
+            .first_byte_at
+            .map(|at| at.saturating_duration_since(start));
+        ExportedEvent {
+            gap_ms: self.gap.map(|gap| gap.as_secs_f64() * 1000.0),
+            repeats: self.repeats,
+            total_bytes: self.received.map(|received| received.bytes),
+            total_events: self.received.map(|received| received.events),
+            ..ExportedEvent::new(&self.event, timestamp)
+        }
     }
 
-    interpret_csi_sequence(bytes)
-        .or_else(|| interpret_ss3_sequence(bytes))
-        .or_else(|| interpret_alt_sequence(bytes))
-        .or_else(|| interpret_single_byte(bytes))
-        .or_else(|| interpret_utf8_char(bytes))
-}
+    /// Note that this row's key ended the capture.
+    #[cfg(unix)]
+    fn mark_quit(&mut self) {
+        let description = &mut self.event.guess.description;
+        if !description.is_empty() {
+            description.push(' ');
+        }
+        description.push_str("(quit)");
+    }
 
-fn interpret_single_byte(bytes: &[u8]) -> Option<KeyInterpretation> {
-    if bytes.len() != 1 {
-        return None;
-    }
-    let byte = bytes[0];
-    let description;
-    let (code, key_display, modifiers) = match byte {
-        0x00 => (KeyCode::Null, "Null".to_string(), KeyModifiers::empty()),
-        b'\r' | b'\n' => (KeyCode::Enter, "Enter".to_string(), KeyModifiers::empty()),
-        b'\t' => (KeyCode::Tab, "Tab".to_string(), KeyModifiers::empty()),
-        0x7F => (
-            KeyCode::Backspace,
-            "Backspace".to_string(),
-            KeyModifiers::empty(),
-        ),
-        0x08 => (
-            KeyCode::Backspace,
-            "Backspace".to_string(),
-            KeyModifiers::CONTROL,
-        ),
-        0x1B => (KeyCode::Esc, "Esc".to_string(), KeyModifiers::empty()),
-        0x01..=0x1A => {
-            let ch = (byte + 0x60) as char;
-            (
-                KeyCode::Char(ch),
-                format!("'{}'", ch),
-                KeyModifiers::CONTROL,
-            )
+    /// The guessed key, with a repeat count when auto-repeats were folded into this row.
+    fn key_text(&self) -> String {
+        if self.repeats > 1 {
+            format!("{} ×{}", self.event.guess.key, self.repeats)
+        } else {
+            self.event.guess.key.clone()
         }
-        0x1C..=0x1F => {
-            let ch = (byte + 0x60) as char;
-            (
-                KeyCode::Char(ch),
-                format!("'{}'", ch),
-                KeyModifiers::CONTROL,
-            )
+    }
+
+    /// The gap since the previous event in milliseconds; `—` for the first event.
+    #[cfg(unix)]
+    fn gap_text(&self) -> String {
+        match self.gap {
+            Some(gap) => format!("{:.1}ms", gap.as_secs_f64() * 1000.0),
+            None => "—".to_string(),
         }
-        0x20..=0x7E => {
-            let ch = byte as char;
-            (
-                KeyCode::Char(ch),
-                format!("'{}'", ch),
-                KeyModifiers::empty(),
-            )
+    }
+
+    /// Seconds from `start` to the first byte, to the millisecond; `—` when unknown.
+    #[cfg(unix)]
+    fn time_text(&self, start: Instant) -> String {
+        match self.first_byte_at {
+            Some(at) => format!("{:.3}", at.saturating_duration_since(start).as_secs_f64()),
+            None => "—".to_string(),
         }
-        _ => return None,
-    };
+    }
 
-    description = match code {
-        KeyCode::Backspace if modifiers.contains(KeyModifiers::CONTROL) => {
-            "Backspace (Ctrl+H)".to_string()
+    /// Time between the first and last byte, blank when unknown or delivered in one read.
+    fn spread_text(&self) -> String {
+        match (self.first_byte_at, self.last_byte_at) {
+            (Some(first), Some(last)) if last > first => {
+                format!("{:.1}ms", (last - first).as_secs_f64() * 1000.0)
+            }
+            _ => String::new(),
         }
-        KeyCode::Char(_) if modifiers.contains(KeyModifiers::CONTROL) => {
-            "Control-modified character".to_string()
+    }
+
+    /// Row that a bracketed paste streams into until its end marker arrives.
+    #[cfg(unix)]
+    fn paste_start(event: &RawEvent) -> Self {
+        let mut row = Self {
+            paste: Some(PasteProgress::default()),
+            ..Self::from_raw_event(event)
+        };
+        row.refresh_paste();
+        row
+    }
+
+    /// Count a paste chunk, or close the paste on its end marker.
+    #[cfg(unix)]
+    fn extend_paste(&mut self, event: &RawEvent) {
+        let Some(paste) = self.paste.as_mut() else {
+            return;
+        };
+        if event.paste {
+            paste.total += event.bytes.len();
+            let room = PASTE_PREVIEW_BYTES.saturating_sub(paste.preview.len());
+            paste.preview.extend(event.bytes.iter().take(room));
+        } else {
+            paste.done = true;
         }
-        KeyCode::Enter => "Carriage return".to_string(),
-        KeyCode::Tab => "Horizontal tab".to_string(),
-        KeyCode::Esc => "Escape".to_string(),
-        KeyCode::Backspace => "Backspace".to_string(),
-        KeyCode::Null => "NULL".to_string(),
-        KeyCode::Char(_) => "Printable character".to_string(),
-        _ => String::new(),
-    };
+        self.last_byte_at = Some(event.last_byte_at);
+        self.last_arrival = Some(event.first_byte_at);
+        self.refresh_paste();
+    }
 
-    Some(KeyInterpretation {
-        key_display,
-        code,
-        modifiers,
-        description,
-    })
+    #[cfg(unix)]
+    fn refresh_paste(&mut self) {
+        let Some(paste) = &self.paste else {
+            return;
+        };
+        let info = &mut self.event;
+        info.hex_string = hex_string(&paste.preview);
+        info.escaped_string = escape_bytes(&paste.preview);
+        info.guess.modifiers = "None".to_string();
+        info.guess.description = if paste.done {
+            format!("Paste: {}", format_size(paste.total))
+        } else {
+            format!("Paste in progress: {}…", format_size(paste.total))
+        };
+    }
 }
 
-fn interpret_utf8_char(bytes: &[u8]) -> Option<KeyInterpretation> {
-    let width = utf8_char_width(*bytes.first()?);
-    if width != bytes.len() {
-        return None;
-    }
-    let text = std::str::from_utf8(bytes).ok()?;
-    let mut chars = text.chars();
-    let ch = chars.next()?;
-    if chars.next().is_some() {
-        return None;
-    }
-    Some(KeyInterpretation {
-        key_display: format!("'{}'", ch),
-        code: KeyCode::Char(ch),
-        modifiers: KeyModifiers::empty(),
-        description: "UTF-8 character".to_string(),
-    })
+/// How many bytes of a paste are kept for display; the rest are only counted.
+const PASTE_PREVIEW_BYTES: usize = 32;
+
+/// Running totals for a bracketed paste shown as one row.
+#[derive(Debug, Clone, Default)]
+struct PasteProgress {
+    total: usize,
+    preview: Vec<u8>,
+    done: bool,
 }
 
-fn interpret_alt_sequence(bytes: &[u8]) -> Option<KeyInterpretation> {
-    if bytes.len() < 2 || bytes[0] != 0x1B {
-        return None;
-    }
-    let seq = &bytes[1..];
-    let text = std::str::from_utf8(seq).ok()?;
-    if text.is_empty() {
-        return None;
+/// Human-readable byte count, e.g. `3.2 MB`.
+#[cfg(unix)]
+fn format_size(bytes: usize) -> String {
+    const UNITS: [&str; 3] = ["KB", "MB", "GB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
     }
-    let mut chars = text.chars();
-    let ch = chars.next()?;
-    if chars.next().is_some() {
-        return None;
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
     }
+    format!("{:.1} {}", size, UNITS[unit])
+}
 
-    Some(KeyInterpretation {
-        key_display: format!("'{}'", ch),
-        code: KeyCode::Char(ch),
-        modifiers: KeyModifiers::ALT,
-        description: "Alt-modified character".to_string(),
-    })
+/// What crossterm's parser made of one raw event, for `--compare-crossterm`.
+#[derive(Debug, Clone)]
+struct CrosstermDecode {
+    display: String,
+    matches: bool,
 }
 
-fn interpret_ss3_sequence(bytes: &[u8]) -> Option<KeyInterpretation> {
-    if bytes.len() != 3 || bytes[0] != 0x1B || bytes[1] != b'O' {
-        return None;
+impl CrosstermDecode {
+    /// Compare the decoder child's output lines against our own guess for the same bytes.
+    fn from_lines(lines: &[String], guess: &GuessInfo) -> Self {
+        let decoded: Vec<(String, Option<(String, String)>)> = lines
+            .iter()
+            .map(
+                |line| match line.split('\t').collect::<Vec<_>>().as_slice() {
+                    ["key", code, bits] => {
+                        let modifiers = bits
+                            .parse()
+                            .map(KeyModifiers::from_bits_truncate)
+                            .unwrap_or(KeyModifiers::NONE);
+                        let modifiers = format_modifiers(modifiers);
+                        let display = if modifiers == "None" {
+                            code.to_string()
+                        } else {
+                            format!("{} {}", modifiers, code)
+                        };
+                        (display, Some((code.to_string(), modifiers)))
+                    }
+                    _ => (line.trim_start_matches("other\t").to_string(), None),
+                },
+            )
+            .collect();
+
+        let matches = match decoded.as_slice() {
+            [(_, Some((code, modifiers)))] => *code == guess.code && *modifiers == guess.modifiers,
+            _ => false,
+        };
+        let display = if decoded.is_empty() {
+            "(nothing)".to_string()
+        } else {
+            decoded
+                .into_iter()
+                .map(|(display, _)| display)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        Self { display, matches }
     }
-    let final_byte = bytes[2] as char;
-    let (code, key_display, description) = match final_byte {
-        'P' => (
-            KeyCode::F(1),
-            "F1".to_string(),
-            "SS3 function key".to_string(),
-        ),
-        'Q' => (
-            KeyCode::F(2),
-            "F2".to_string(),
-            "SS3 function key".to_string(),
-        ),
-        'R' => (
-            KeyCode::F(3),
-            "F3".to_string(),
-            "SS3 function key".to_string(),
-        ),
-        'S' => (
-            KeyCode::F(4),
-            "F4".to_string(),
-            "SS3 function key".to_string(),
-        ),
-        'A' => (KeyCode::Up, "Up".to_string(), "SS3 arrow key".to_string()),
-        'B' => (
-            KeyCode::Down,
-            "Down".to_string(),
-            "SS3 arrow key".to_string(),
-        ),
-        'C' => (
-            KeyCode::Right,
-            "Right".to_string(),
-            "SS3 arrow key".to_string(),
-        ),
-        'D' => (
-            KeyCode::Left,
-            "Left".to_string(),
-            "SS3 arrow key".to_string(),
-        ),
-        'H' => (
-            KeyCode::Home,
-            "Home".to_string(),
-            "SS3 home key".to_string(),
-        ),
-        'F' => (KeyCode::End, "End".to_string(), "SS3 end key".to_string()),
-        _ => return None,
-    };
+}
 
-    Some(KeyInterpretation {
-        key_display,
-        code,
-        modifiers: KeyModifiers::empty(),
-        description,
-    })
+/// Feeds raw events to a child process running crossterm's `event::read` on a PTY.
+#[cfg(unix)]
+struct CrosstermComparer {
+    child: std::process::Child,
+    master: File,
+    lines: mpsc::Receiver<String>,
 }
 
-fn interpret_csi_sequence(bytes: &[u8]) -> Option<KeyInterpretation> {
-    let (final_byte, params) = parse_csi(bytes)?;
-    match final_byte {
-        'A' => Some(build_arrow_guess("Up", KeyCode::Up, &params)),
-        'B' => Some(build_arrow_guess("Down", KeyCode::Down, &params)),
-        'C' => Some(build_arrow_guess("Right", KeyCode::Right, &params)),
-        'D' => Some(build_arrow_guess("Left", KeyCode::Left, &params)),
-        'F' => Some(build_arrow_guess("End", KeyCode::End, &params)),
-        'H' => Some(build_arrow_guess("Home", KeyCode::Home, &params)),
-        'Z' => {
-            let modifiers = KeyModifiers::SHIFT;
-            Some(KeyInterpretation {
-                key_display: "BackTab".to_string(),
-                code: KeyCode::BackTab,
-                modifiers,
-                description: "CSI BackTab sequence".to_string(),
-            })
+#[cfg(unix)]
+impl CrosstermComparer {
+    /// How long to wait for crossterm to report anything for an event.
+    const FIRST_LINE_TIMEOUT: Duration = Duration::from_millis(100);
+    /// How long to keep collecting once crossterm has started reporting.
+    const EXTRA_LINE_TIMEOUT: Duration = Duration::from_millis(5);
+
+    fn spawn() -> Result<Self> {
+        let pty = nix::pty::openpty(None, None).wrap_err("Failed to open a PTY for crossterm")?;
+        let slave = File::from(pty.slave);
+        let mut child = Command::new(std::env::current_exe()?)
+            .arg("--crossterm-decoder")
+            .stdin(slave.try_clone()?)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .wrap_err("Failed to start the crossterm decoder")?;
+        drop(slave);
+
+        let stdout = child.stdout.take().expect("child stdout is piped");
+        let (sender, lines) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in io::BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        // The child reports once its PTY is in raw mode, so bytes reach crossterm untouched.
+        match lines.recv_timeout(Duration::from_secs(2)) {
+            Ok(line) if line == "ready" => {}
+            _ => {
+                let _ = child.kill();
+                return Err(eyre::eyre!("Crossterm decoder did not start"));
+            }
         }
-        '~' => interpret_csi_tilde(&params),
-        _ => None,
+
+        Ok(Self {
+            child,
+            master: File::from(pty.master),
+            lines,
+        })
     }
-}
 
-fn build_arrow_guess(name: &str, code: KeyCode, params: &[u16]) -> KeyInterpretation {
-    let (_, modifiers) = split_params_and_modifiers(params);
-    KeyInterpretation {
-        key_display: name.to_string(),
-        code,
-        modifiers,
-        description: "CSI arrow/navigation sequence".to_string(),
+    fn decode(&mut self, bytes: &[u8]) -> Result<Vec<String>> {
+        self.master.write_all(bytes)?;
+        let mut decoded = Vec::new();
+        let mut timeout = Self::FIRST_LINE_TIMEOUT;
+        while let Ok(line) = self.lines.recv_timeout(timeout) {
+            decoded.push(line);
+            timeout = Self::EXTRA_LINE_TIMEOUT;
+        }
+        Ok(decoded)
     }
 }
 
-fn interpret_csi_tilde(params: &[u16]) -> Option<KeyInterpretation> {
-    let (base, modifiers) = split_params_and_modifiers(params);
-    let key_id = base.first().copied()?;
-    let (code, key_display, description) = match key_id {
-        1 | 7 => (
-            KeyCode::Home,
-            "Home".to_string(),
-            "CSI ~ (Home)".to_string(),
-        ),
-        2 => (
-            KeyCode::Insert,
-            "Insert".to_string(),
-            "CSI ~ (Insert)".to_string(),
-        ),
-        3 => (
-            KeyCode::Delete,
-            "Delete".to_string(),
-            "CSI ~ (Delete)".to_string(),
-        ),
-        4 | 8 => (KeyCode::End, "End".to_string(), "CSI ~ (End)".to_string()),
-        5 => (
-            KeyCode::PageUp,
-            "PageUp".to_string(),
-            "CSI ~ (PageUp)".to_string(),
-        ),
-        6 => (
-            KeyCode::PageDown,
-            "PageDown".to_string(),
-            "CSI ~ (PageDown)".to_string(),
-        ),
-        11 => (
-            KeyCode::F(1),
-            "F1".to_string(),
-            "CSI ~ function key".to_string(),
-        ),
-        12 => (
-            KeyCode::F(2),
-            "F2".to_string(),
-            "CSI ~ function key".to_string(),
-        ),
-        13 => (
-            KeyCode::F(3),
-            "F3".to_string(),
-            "CSI ~ function key".to_string(),
-        ),
-        14 => (
-            KeyCode::F(4),
-            "F4".to_string(),
-            "CSI ~ function key".to_string(),
-        ),
-        15 => (
-            KeyCode::F(5),
-            "F5".to_string(),
-            "CSI ~ function key".to_string(),
-        ),
-        17 => (
-            KeyCode::F(6),
-            "F6".to_string(),
-            "CSI ~ function key".to_string(),
-        ),
-        18 => (
-            KeyCode::F(7),
-            "F7".to_string(),
-            "CSI ~ function key".to_string(),
-        ),
-        19 => (
-            KeyCode::F(8),
-            "F8".to_string(),
-            "CSI ~ function key".to_string(),
-        ),
-        20 => (
-            KeyCode::F(9),
-            "F9".to_string(),
-            "CSI ~ function key".to_string(),
-        ),
-        21 => (
-            KeyCode::F(10),
-            "F10".to_string(),
-            "CSI ~ function key".to_string(),
-        ),
-        23 => (
-            KeyCode::F(11),
-            "F11".to_string(),
-            "CSI ~ function key".to_string(),
-        ),
-        24 => (
-            KeyCode::F(12),
-            "F12".to_string(),
-            "CSI ~ function key".to_string(),
-        ),
-        _ => return None,
-    };
+#[cfg(unix)]
+impl Drop for CrosstermComparer {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
 
-    Some(KeyInterpretation {
-        key_display,
-        code,
-        modifiers,
-        description,
-    })
+/// Child side of `--compare-crossterm`: print every event crossterm reads from stdin.
+#[cfg(unix)]
+fn run_crossterm_decoder() -> io::Result<()> {
+    crossterm::terminal::enable_raw_mode()?;
+    let mut out = io::stdout().lock();
+    writeln!(out, "ready")?;
+    out.flush()?;
+
+    while let Ok(event) = crossterm::event::read() {
+        match event {
+            crossterm::event::Event::Key(key) => {
+                writeln!(out, "key\t{:?}\t{}", key.code, key.modifiers.bits())?
+            }
+            other => writeln!(out, "other\t{:?}", other)?,
+        }
+        out.flush()?;
+    }
+    Ok(())
 }
 
-fn parse_csi(bytes: &[u8]) -> Option<(char, Vec<u16>)> {
-    if bytes.len() < 3 || bytes[0] != 0x1B || bytes[1] != b'[' {
-        return None;
+/// The integration tests' PTY helpers, for running the debugger on a terminal.
+#[cfg(all(test, unix))]
+#[path = "../tests/support/mod.rs"]
+mod support;
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::support;
+    #[cfg(feature = "test-backend")]
+    use ratatui::backend::TestBackend;
+    #[cfg(feature = "test-backend")]
+    use ratatui::Terminal;
+
+    #[cfg(feature = "test-backend")]
+    fn app_terminal() -> (TuiApp, Terminal<TestBackend>) {
+        let mut app = TuiApp::builder("controlsequencedebugger-test")
+            .use_disk_logs(false)
+            .build();
+        let terminal = app.init_test(120, 8).unwrap();
+        (app, terminal)
     }
-    let final_byte = *bytes.last()?;
-    if !(0x40..=0x7E).contains(&final_byte) {
-        return None;
+
+    fn raw_event(bytes: &[u8], at: Instant) -> RawEvent {
+        RawEvent {
+            bytes: bytes.to_vec(),
+            first_byte_at: at,
+            last_byte_at: at,
+            oversized: false,
+            paste: false,
+        }
     }
-    let mut params_bytes = &bytes[2..bytes.len() - 1];
-    while let Some(b'?') = params_bytes.first() {
-        params_bytes = &params_bytes[1..];
+
+    fn quit_keys(args: &[&str]) -> QuitKeys {
+        Args::parse_from([&["debug_inline"], args].concat()).quit_keys()
     }
 
-    if params_bytes.is_empty() {
-        return Some((final_byte as char, Vec::new()));
+    #[test]
+    fn ctrl_c_twice_within_a_second_quits() {
+        let start = Instant::now();
+        let mut keys = quit_keys(&[]);
+
+        assert!(!keys.is_quit(&raw_event(b"\x03", start)));
+        assert!(keys.is_quit(&raw_event(b"\x03", start + Duration::from_millis(900))));
+        assert!(!keys.is_quit(&raw_event(b"q", start)));
     }
 
-    let params_str = std::str::from_utf8(params_bytes).ok()?;
-    let mut params = Vec::new();
-    for part in params_str.split(';') {
-        if part.is_empty() {
-            continue;
-        }
-        if let Ok(value) = part.parse::<u16>() {
-            params.push(value);
-        } else {
-            return None;
-        }
+    #[test]
+    fn slow_or_interrupted_ctrl_c_does_not_quit() {
+        let start = Instant::now();
+        let mut keys = quit_keys(&[]);
+
+        assert!(!keys.is_quit(&raw_event(b"\x03", start)));
+        assert!(!keys.is_quit(&raw_event(b"\x03", start + Duration::from_millis(1500))));
+        assert!(!keys.is_quit(&raw_event(b"a", start + Duration::from_millis(1600))));
+        assert!(!keys.is_quit(&raw_event(
+            b"\x1b[99;5u",
+            start + Duration::from_millis(1700)
+        )));
+        assert!(keys.is_quit(&raw_event(
+            b"\x1b[99;5u",
+            start + Duration::from_millis(1800)
+        )));
     }
 
-    Some((final_byte as char, params))
-}
+    #[test]
+    fn pasted_text_never_quits() {
+        let now = Instant::now();
+        let pasted = |bytes: &[u8]| RawEvent {
+            paste: true,
+            ..raw_event(bytes, now)
+        };
+        let mut keys = quit_keys(&["--quit-on-q"]);
+        assert!(!keys.is_quit(&pasted(b"q")));
 
-fn split_params_and_modifiers(params: &[u16]) -> (Vec<u16>, KeyModifiers) {
-    if params.len() <= 1 {
-        return (params.to_vec(), KeyModifiers::empty());
+        assert!(!keys.is_quit(&pasted(b"\x03")));
+        assert!(!keys.is_quit(&raw_event(b"\x03", now)));
+        assert!(keys.is_quit(&raw_event(b"\x03", now)));
     }
-    let (base, modifier_part) = params.split_at(params.len() - 1);
-    let modifiers = decode_modifier_code(modifier_part[0]);
-    (base.to_vec(), modifiers)
-}
 
-fn decode_modifier_code(value: u16) -> KeyModifiers {
-    match value {
-        2 => KeyModifiers::SHIFT,
-        3 => KeyModifiers::ALT,
-        4 => KeyModifiers::SHIFT | KeyModifiers::ALT,
-        5 => KeyModifiers::CONTROL,
-        6 => KeyModifiers::SHIFT | KeyModifiers::CONTROL,
-        7 => KeyModifiers::ALT | KeyModifiers::CONTROL,
-        8 => KeyModifiers::SHIFT | KeyModifiers::ALT | KeyModifiers::CONTROL,
-        _ => KeyModifiers::empty(),
+    #[test]
+    fn quit_on_q_and_no_exit_keys() {
+        let now = Instant::now();
+        assert!(quit_keys(&["--quit-on-q"]).is_quit(&raw_event(b"q", now)));
+
+        let mut keys = quit_keys(&["--no-exit-keys"]);
+        assert!(!keys.is_quit(&raw_event(b"\x03", now)));
+        assert!(!keys.is_quit(&raw_event(b"\x03", now)));
     }
-}
 
-fn format_modifiers(modifiers: KeyModifiers) -> String {
-    if modifiers.is_empty() {
-        "None".to_string()
-    } else {
-        format!("{:?}", modifiers)
+    /// The capture loop's state between frames.
+    struct Capture {
+        quit_keys: QuitKeys,
+        quit: bool,
+        pause: CapturePause,
+        scroll: HistoryScroll,
+        events: Vec<EventRow>,
+        received: Received,
     }
-}
 
-fn escape_bytes(bytes: &[u8]) -> String {
-    let mut output = String::new();
-    let mut i = 0;
-    while i < bytes.len() {
-        let b = bytes[i];
-        match b {
-            b'\x1B' => {
-                output.push_str("\\x1B");
-                i += 1;
-            }
-            b'\n' => {
-                output.push_str("\\n");
-                i += 1;
+    impl Capture {
+        fn new(args: &[&str]) -> Self {
+            let args = Args::parse_from([&["debug_inline"], args].concat());
+            Self {
+                quit_keys: args.quit_keys(),
+                quit: false,
+                pause: args.capture_pause(),
+                scroll: args.history_scroll(),
+                events: Vec::new(),
+                received: Received::default(),
             }
-            b'\r' => {
-                output.push_str("\\r");
-                i += 1;
-            }
-            b'\t' => {
-                output.push_str("\\t");
-                i += 1;
-            }
-            0x20..=0x7E => {
-                output.push(b as char);
-                i += 1;
-            }
-            _ => {
-                let width = utf8_char_width(b);
-                if width > 1 && i + width <= bytes.len() {
-                    if let Ok(slice) = std::str::from_utf8(&bytes[i..i + width]) {
-                        output.push_str(slice);
-                        i += width;
-                        continue;
-                    }
-                }
-                output.push_str(&format!("\\x{:02X}", b));
-                i += 1;
+        }
+
+        /// Run `bytes` through the control keys and the table's accounting as the loop does.
+        fn feed(&mut self, bytes: &[u8], at: Instant) {
+            let event = raw_event(bytes, at);
+            let history = self.events.len();
+            self.received.count(&event);
+            self.quit = self.quit_keys.is_quit(&event);
+            if self.quit || !intercept_control(&event, &mut self.scroll, &mut self.pause, history) {
+                let mut coalescer = RepeatCoalescer::disabled();
+                process_event(event, &mut coalescer, None, &mut self.events).unwrap();
+                self.received.stamp(&mut self.events);
             }
         }
     }
-    output
-}
 
-fn utf8_char_width(first_byte: u8) -> usize {
-    if first_byte < 0x80 {
-        1
-    } else if first_byte >> 5 == 0b110 {
-        2
-    } else if first_byte >> 4 == 0b1110 {
-        3
-    } else if first_byte >> 3 == 0b11110 {
-        4
-    } else {
-        1
+    #[test]
+    fn paused_input_is_discarded_not_replayed() {
+        let start = Instant::now();
+        let ms = |millis| start + Duration::from_millis(millis);
+        let mut capture = Capture::new(&[]);
+
+        capture.feed(b"a", ms(0));
+        capture.feed(b"\x1bOQ", ms(100));
+        assert!(capture.pause.is_paused());
+        capture.feed(b"b", ms(200));
+        capture.feed(b"\x1b[A", ms(300));
+        capture.feed(b"\x1b[12~", ms(400));
+        assert!(!capture.pause.is_paused());
+        capture.feed(b"c", ms(500));
+
+        let keys: Vec<_> = capture
+            .events
+            .iter()
+            .map(|row| row.event.bytes.clone())
+            .collect();
+        assert_eq!(keys, vec![b"a".to_vec(), b"c".to_vec()]);
     }
-}
 
-fn try_extract_event(buffer: &[u8]) -> Option<usize> {
-    if buffer.is_empty() {
-        return None;
+    #[test]
+    fn rows_export_the_totals_received_so_far() {
+        let start = Instant::now();
+        let ms = |millis| start + Duration::from_millis(millis);
+        let mut capture = Capture::new(&[]);
+
+        capture.feed(b"a", ms(0));
+        // The pause key toggled twice counts, though it never gets a row.
+        capture.feed(b"\x1bOQ", ms(100));
+        capture.feed(b"\x1bOQ", ms(200));
+        capture.feed(b"\x1b[A", ms(300));
+
+        let totals: Vec<_> = capture
+            .events
+            .iter()
+            .map(|row| {
+                let exported = row.export(start);
+                (exported.total_bytes, exported.total_events)
+            })
+            .collect();
+        assert_eq!(totals, vec![(Some(1), Some(1)), (Some(10), Some(4))]);
     }
-    let first = buffer[0];
 
-    if first == 0x1B {
-        if buffer.len() >= 2 {
-            match buffer[1] {
-                b'[' => return csi_sequence_length(buffer),
-                b'O' => {
-                    if buffer.len() >= 3 {
-                        return Some(3);
-                    }
-                }
-                _ => {
-                    let width = utf8_char_width(buffer[1]);
-                    if buffer.len() >= 1 + width {
-                        return Some(1 + width);
-                    }
-                }
-            }
+    #[test]
+    fn rows_record_the_gap_since_the_previous_event() {
+        let start = Instant::now();
+        let ms = |millis| start + Duration::from_millis(millis);
+        let mut coalescer = RepeatCoalescer::disabled();
+        let mut events = Vec::new();
+
+        for (bytes, at) in [
+            (&b"a"[..], ms(0)),
+            (b"b", ms(12)),
+            (b"c", ms(62)),
+            (b"d", ms(562)),
+        ] {
+            process_event(raw_event(bytes, at), &mut coalescer, None, &mut events).unwrap();
         }
-        return None;
+
+        let gaps: Vec<String> = events.iter().map(EventRow::gap_text).collect();
+        assert_eq!(gaps, ["—", "12.0ms", "50.0ms", "500.0ms"]);
+        assert_eq!(events[0].export(start).gap_ms, None);
+        assert_eq!(events[3].export(start).gap_ms, Some(500.0));
+
+        let palette = AppPalette::dark();
+        let colors: Vec<Color> = events
+            .iter()
+            .map(|row| palette.gap_color(row.gap))
+            .collect();
+        assert_eq!(
+            colors,
+            [
+                palette.status_secondary,
+                palette.gap_quick,
+                palette.gap_moderate,
+                palette.gap_slow
+            ]
+        );
+    }
+
+    #[test]
+    fn quit_chords_end_a_paused_capture() {
+        let start = Instant::now();
+        let ms = |millis| start + Duration::from_millis(millis);
+        let mut capture = Capture::new(&["--quit-on-q"]);
+
+        capture.feed(b"\x1bOQ", ms(0));
+        capture.feed(b"x", ms(100));
+        capture.feed(b"q", ms(200));
+        assert!(capture.pause.is_paused());
+        assert!(capture.quit);
+
+        let mut capture = Capture::new(&[]);
+        capture.feed(b"\x1bOQ", ms(0));
+        capture.feed(b"\x03", ms(100));
+        assert!(!capture.quit);
+        capture.feed(b"\x03", ms(200));
+        assert!(capture.quit);
     }
 
-    if first >= 0x80 {
-        let width = utf8_char_width(first);
-        if buffer.len() >= width {
-            return Some(width);
+    #[test]
+    fn the_gap_after_held_key_counts_from_its_last_repeat() {
+        let start = Instant::now();
+        let ms = |millis| start + Duration::from_millis(millis);
+        let mut coalescer = Args::parse_from(["debug_inline"]).coalescer();
+        let mut events = Vec::new();
+
+        // Held for two seconds, auto-repeating every 30 ms.
+        for at in (0..=2000).step_by(30) {
+            process_event(raw_event(b"j", ms(at)), &mut coalescer, None, &mut events).unwrap();
         }
-        return None;
+        process_event(raw_event(b"k", ms(2010)), &mut coalescer, None, &mut events).unwrap();
+
+        assert_eq!(events.len(), 2);
+        assert!(events[0].repeats > 1);
+        assert_eq!(events[1].gap, Some(Duration::from_millis(30)));
+        assert_eq!(events[1].export(start).gap_ms, Some(30.0));
     }
 
-    Some(1)
-}
+    #[test]
+    fn the_timeout_clock_stops_while_paused() {
+        let start = Instant::now();
+        let ms = |millis| start + Duration::from_millis(millis);
+        let mut capture = Capture::new(&[]);
+
+        capture.feed(b"\x1bOQ", ms(100));
+        assert_eq!(
+            capture.pause.active_time(start, ms(1100)),
+            Duration::from_millis(100)
+        );
+        capture.feed(b"\x1bOQ", ms(1100));
+        assert_eq!(
+            capture.pause.active_time(start, ms(1500)),
+            Duration::from_millis(500)
+        );
+    }
+
+    #[test]
+    fn only_the_configured_unmodified_key_pauses() {
+        let now = Instant::now();
+        let mut pause = Args::parse_from(["debug_inline", "--pause-key", "5"]).capture_pause();
 
-fn csi_sequence_length(buffer: &[u8]) -> Option<usize> {
-    if buffer.len() < 3 {
-        return None;
+        assert!(!pause.intercepts(&raw_event(b"\x1bOQ", now)));
+        assert!(!pause.intercepts(&raw_event(b"\x1b[15;2~", now)));
+        assert!(pause.intercepts(&raw_event(b"\x1b[15~", now)));
+        assert!(pause.is_paused());
     }
-    for (idx, byte) in buffer[2..].iter().enumerate() {
-        if (0x40..=0x7E).contains(byte) {
-            return Some(idx + 3);
+
+    fn scroll(rows: usize) -> HistoryScroll {
+        let max_inputs = rows.to_string();
+        Args::parse_from(["debug_inline", "--max-inputs", &max_inputs]).history_scroll()
+    }
+
+    #[test]
+    fn history_follows_new_rows_until_scrolled_up() {
+        let mut history = scroll(5);
+        assert_eq!(history.window(3), 0..3);
+        assert_eq!(history.window(12), 7..12);
+
+        history.scroll_by(-1, 12);
+        assert_eq!(history.window(12), 6..11);
+        // Pinned: new rows don't move the view.
+        assert_eq!(history.window(20), 6..11);
+        assert_eq!(history.label(20), "rows 7–11 of 20");
+
+        history.scroll_by(-100, 20);
+        assert_eq!(history.window(20), 0..5);
+        history.scroll_by(100, 20);
+        assert_eq!(history.window(25), 20..25);
+    }
+
+    #[cfg(feature = "test-backend")]
+    #[test]
+    fn scrolling_reaches_events_a_short_viewport_cut_off() {
+        let (_app, mut terminal) = app_terminal();
+        let palette = AppPalette::dark();
+        let start = Instant::now();
+        let mut capture = Capture::new(&["--max-inputs", "10"]);
+        // Eight terminal rows clamp the table asking for ten to three.
+        capture.scroll.fit(terminal.get_frame().area().height, 5);
+        for (idx, key) in (b'a'..=b'j').enumerate() {
+            capture.feed(&[key], start + Duration::from_millis(idx as u64 * 100));
         }
+        assert_eq!(capture.scroll.window(10), 7..10);
+
+        capture.feed(b"\x1b[5~", start + Duration::from_secs(2));
+        capture.feed(b"\x1b[1;2A", start + Duration::from_secs(3));
+
+        assert_eq!(capture.events.len(), 10);
+        let window = capture.scroll.window(capture.events.len());
+        assert_eq!(window, 3..6);
+        let view = view("Events", None, &capture.events[window], &palette);
+        terminal
+            .draw(|f| render_events(&view, f.area(), f.buffer_mut()))
+            .unwrap();
+        let screen = screen(&terminal);
+        assert!(screen.contains("Printable character"), "{screen}");
+        assert!(
+            screen.contains(" 64 ") && screen.contains(" 66 "),
+            "{screen}"
+        );
+        assert!(!screen.contains(" 6A "), "{screen}");
     }
-    None
-}
 
-#[cfg(unix)]
-struct RawInputReader {
-    stdin: io::Stdin,
-    fd: libc::c_int,
-    buffer: Vec<u8>,
-    ready: VecDeque<Vec<u8>>,
-    last_byte_at: Option<Instant>,
-    flush_timeout: Duration,
-}
+    #[test]
+    fn rows_default_to_max_inputs() {
+        let args = Args::parse_from(["debug_inline", "--max-inputs", "40"]);
+        assert_eq!(args.history_scroll().rows, 40);
+        let args = Args::parse_from(["debug_inline", "--max-inputs", "40", "--rows", "8"]);
+        assert_eq!(args.history_scroll().rows, 8);
+        assert_eq!(args.table_chrome(), 5);
+    }
 
-#[cfg(unix)]
-impl RawInputReader {
-    fn new(flush_timeout: Duration) -> io::Result<Self> {
-        let stdin = io::stdin();
-        let fd = stdin.as_raw_fd();
-        Ok(Self {
-            stdin,
-            fd,
-            buffer: Vec::new(),
-            ready: VecDeque::new(),
-            last_byte_at: None,
-            flush_timeout,
-        })
+    #[test]
+    fn scroll_keys_are_control_input_unless_captured() {
+        let now = Instant::now();
+        let history = scroll(5);
+        assert_eq!(history.step(&raw_event(b"\x1b[5~", now)), Some(-5));
+        assert_eq!(history.step(&raw_event(b"\x1b[6~", now)), Some(5));
+        assert_eq!(history.step(&raw_event(b"\x1b[1;2A", now)), Some(-1));
+        assert_eq!(history.step(&raw_event(b"\x1b[1;2B", now)), Some(1));
+        assert_eq!(history.step(&raw_event(b"\x1b[A", now)), None);
+        assert_eq!(history.step(&raw_event(b"\x1b[5;5~", now)), None);
+
+        let captured = Args::parse_from(["debug_inline", "--capture-scroll-keys"]).history_scroll();
+        assert_eq!(captured.step(&raw_event(b"\x1b[5~", now)), None);
     }
 
-    fn poll_next(&mut self, timeout: Duration) -> io::Result<Option<Vec<u8>>> {
-        if let Some(event) = self.ready.pop_front() {
-            return Ok(Some(event));
-        }
-
-        if !self.buffer.is_empty() && self.should_flush_pending() {
-            let event = self.buffer.drain(..).collect();
-            return Ok(Some(event));
-        }
-
-        let effective_timeout = self.effective_timeout(timeout);
-        let mut fds = [PollFd::new(self.stdin.as_fd(), PollFlags::POLLIN)];
-        let poll_timeout_ms = duration_to_poll_timeout(effective_timeout);
-        let poll_timeout = PollTimeout::try_from(poll_timeout_ms).unwrap_or(PollTimeout::MAX);
-        let res = loop {
-            match poll(&mut fds, poll_timeout) {
-                Ok(res) => break res,
-                Err(Errno::EINTR) => {
-                    tracing::warn!(
-                        target: "raw_input_reader",
-                        fd = self.fd,
-                        timeout_ms = poll_timeout_ms,
-                        buffer_len = self.buffer.len(),
-                        last_byte_age_ms = self
-                            .last_byte_at
-                            .map(|instant| instant.elapsed().as_millis() as i64)
-                            .unwrap_or(-1),
-                        "poll interrupted, retrying"
-                    );
-                    continue;
-                }
-                Err(errno) => {
-                    let errno_value = errno as i32;
-                    let io_err: io::Error = errno.into();
-                    tracing::error!(
-                        target: "raw_input_reader",
-                        fd = self.fd,
-                        timeout_ms = poll_timeout_ms,
-                        buffer_len = self.buffer.len(),
-                        last_byte_age_ms = self
-                            .last_byte_at
-                            .map(|instant| instant.elapsed().as_millis() as i64)
-                            .unwrap_or(-1),
-                        errno = errno_value,
-                        kind = ?io_err.kind(),
-                        "poll failed"
-                    );
-                    return Err(io_err);
-                }
-            }
+    #[test]
+    fn offline_json_export_parses_back_into_the_events() {
+        let path = std::env::temp_dir().join(format!("debug-inline-{}.jsonl", std::process::id()));
+        let output = path.to_str().unwrap();
+        let args = Args::parse_from([
+            "debug_inline",
+            "--input-hex",
+            "61 1b 5b 41 2c 22 c3 a9",
+            "--format",
+            "json",
+            "--output",
+            output,
+        ]);
+
+        run_offline(OfflineSource::from_args(&args).unwrap(), &args).unwrap();
+
+        let text = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let parsed: Vec<ExportedEvent> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let expected: Vec<ExportedEvent> = parse_event_stream(b"a\x1b[A,\"\xc3\xa9")
+            .iter()
+            .map(|info| ExportedEvent::new(info, None))
+            .collect();
+        assert_eq!(parsed, expected);
+        assert_eq!(parsed[1].key, "Up");
+    }
+
+    #[test]
+    fn offline_csv_export_parses_back_into_the_events() {
+        let path = std::env::temp_dir().join(format!("debug-inline-{}.csv", std::process::id()));
+        let output = path.to_str().unwrap();
+        let args = Args::parse_from([
+            "debug_inline",
+            "--input-hex",
+            "2c 3b 1b 5b 31 3b 35 41 c3 a9",
+            "--format",
+            "csv",
+            "--output",
+            output,
+        ]);
+
+        run_offline(OfflineSource::from_args(&args).unwrap(), &args).unwrap();
+
+        let text = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let parsed: Vec<ExportedEvent> = csv::Reader::from_reader(text.as_slice())
+            .deserialize()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let expected: Vec<ExportedEvent> = parse_event_stream(b",;\x1b[1;5A\xc3\xa9")
+            .iter()
+            .map(|info| ExportedEvent::new(info, None))
+            .collect();
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn offline_export_creates_directories_and_keeps_existing_files() {
+        let dir = std::env::temp_dir().join(format!("debug-inline-out-{}", std::process::id()));
+        let path = dir.join("nested").join("events.txt");
+        let output = path.to_str().unwrap();
+        let run_with = |extra: &[&str]| {
+            let mut argv = vec!["debug_inline", "--input-hex", "61", "--output", output];
+            argv.extend_from_slice(extra);
+            let args = Args::parse_from(argv);
+            run_offline(OfflineSource::from_args(&args).unwrap(), &args)
         };
 
-        if res == 0 {
-            if !self.buffer.is_empty() && self.should_flush_pending() {
-                let event = self.buffer.drain(..).collect();
-                return Ok(Some(event));
-            }
-            return Ok(None);
-        }
-
-        if let Some(revents) = fds[0].revents() {
-            if revents.contains(PollFlags::POLLIN) {
-                let mut byte = [0u8; 1];
-                let mut stdin_lock = self.stdin.lock();
-                loop {
-                    match stdin_lock.read(&mut byte) {
-                        Ok(0) => break,
-                        Ok(_) => {
-                            self.push_byte(byte[0]);
-                            if let Some(event) = self.ready.pop_front() {
-                                drop(stdin_lock);
-                                return Ok(Some(event));
-                            }
-                        }
-                        Err(err) if err.kind() == ErrorKind::Interrupted => continue,
-                        Err(err) if err.kind() == ErrorKind::WouldBlock => break,
-                        Err(err) => return Err(err),
-                    }
-                }
-            }
-        }
+        run_with(&[]).unwrap();
+        let table = fs::read_to_string(&path).unwrap();
+        let refused = run_with(&["--format", "json"]).unwrap_err();
+        let kept = fs::read_to_string(&path).unwrap();
+        run_with(&["--format", "json", "--force"]).unwrap();
+        let replaced = fs::read_to_string(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(table.contains("61"), "{table}");
+        assert!(refused.to_string().contains("--force"), "{refused}");
+        assert_eq!(kept, table);
+        assert!(replaced.starts_with('{'), "{replaced}");
+    }
 
-        if let Some(event) = self.ready.pop_front() {
-            return Ok(Some(event));
-        }
+    #[test]
+    fn exports_to_stdout_move_the_tui_to_stderr() {
+        let args = Args::parse_from(["debug_inline", "--output", "-"]);
+        assert_eq!(args.output_file(), None);
+        assert!(args.exports_to_stdout());
+        assert_eq!(args.tui_stream(), AlternateScreenBackend::Stderr);
+
+        let args = Args::parse_from(["debug_inline", "--format", "csv"]);
+        assert!(args.exports_to_stdout());
+        assert_eq!(args.tui_stream(), AlternateScreenBackend::Stderr);
+
+        let args = Args::parse_from(["debug_inline", "--output", "events.csv"]);
+        assert_eq!(args.output_file(), Some(Path::new("events.csv")));
+        assert!(!args.exports_to_stdout());
+        assert_eq!(args.tui_stream(), AlternateScreenBackend::Stdout);
+        let args = Args::parse_from(["debug_inline"]);
+        assert!(!args.exports_to_stdout());
+        assert_eq!(args.tui_stream(), AlternateScreenBackend::Stdout);
+    }
 
-        if !self.buffer.is_empty() && self.should_flush_pending() {
-            let event = self.buffer.drain(..).collect();
-            return Ok(Some(event));
-        }
+    #[test]
+    fn live_rows_export_their_timestamp_and_repeats() {
+        let start = Instant::now();
+        let at = start + Duration::from_millis(250);
+        let mut row = EventRow::from_raw_event(&raw_event(b"j", at));
+        row.repeats = 3;
+
+        let exported = row.export(start);
 
-        Ok(None)
+        assert_eq!(exported.timestamp_ms, Some(250.0));
+        assert_eq!(exported.repeats, 3);
+        assert_eq!(exported.escaped, "j");
     }
 
-    fn push_byte(&mut self, byte: u8) {
-        self.buffer.push(byte);
-        self.last_byte_at = Some(Instant::now());
-        while let Some(len) = try_extract_event(&self.buffer) {
-            let event = self.buffer.drain(..len).collect::<Vec<u8>>();
-            self.ready.push_back(event);
-        }
+    #[test]
+    fn quit_rows_are_noted() {
+        let mut row = up_arrow();
+        row.mark_quit();
+        assert!(row.event.guess.description.ends_with(" (quit)"));
+
+        let mut row = EventRow::new(InputEventInfo::from_bytes(Vec::new()));
+        row.mark_quit();
+        assert_eq!(row.event.guess.description, "(quit)");
+    }
+
+    fn up_arrow() -> EventRow {
+        let now = Instant::now();
+        EventRow::from_raw_event(&RawEvent {
+            bytes: b"\x1b[A".to_vec(),
+            first_byte_at: now,
+            last_byte_at: now,
+            oversized: false,
+            paste: false,
+        })
     }
 
-    fn should_flush_pending(&self) -> bool {
-        self.last_byte_at
-            .map(|instant| instant.elapsed() >= self.flush_timeout)
-            .unwrap_or(false)
+    #[cfg(feature = "test-backend")]
+    fn screen(terminal: &Terminal<TestBackend>) -> String {
+        terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect()
     }
 
-    fn effective_timeout(&self, requested: Duration) -> Duration {
-        if self.buffer.is_empty() {
-            return requested;
+    #[cfg(feature = "test-backend")]
+    fn view<'a>(
+        label: &str,
+        pending: Option<&'a [u8]>,
+        events: &'a [EventRow],
+        palette: &'a AppPalette,
+    ) -> EventsView<'a> {
+        EventsView {
+            title: build_title_line(
+                label,
+                events.len(),
+                10,
+                Duration::from_secs(1),
+                30,
+                FlushTimeouts::default(),
+                Rates {
+                    bytes_per_sec: 0,
+                    events_per_sec: 0,
+                },
+                false,
+                palette,
+            ),
+            pending,
+            events,
+            palette,
+            borders: true,
+            started: None,
+            compare: false,
         }
+    }
 
-        if let Some(last) = self.last_byte_at {
-            let elapsed = last.elapsed();
-            if elapsed >= self.flush_timeout {
-                Duration::ZERO
-            } else {
-                requested.min(self.flush_timeout - elapsed)
+    #[cfg(feature = "test-backend")]
+    #[test]
+    fn live_frame_shows_pending_bytes_above_the_events() {
+        let (_app, mut terminal) = app_terminal();
+        let palette = AppPalette::dark();
+        let events = [up_arrow()];
+        let view = view("Events", Some(b"\x1b["), &events, &palette);
+
+        terminal
+            .draw(|f| render_events(&view, f.area(), f.buffer_mut()))
+            .unwrap();
+
+        let screen = screen(&terminal);
+        assert!(screen.contains("Events"));
+        assert!(screen.contains("pending: 1B 5B"));
+        assert!(screen.contains("Hex"));
+        assert!(screen.contains(&events[0].key_text()));
+    }
+
+    #[cfg(feature = "test-backend")]
+    #[test]
+    fn final_frame_leaves_out_the_pending_row() {
+        let (_app, mut terminal) = app_terminal();
+        let palette = AppPalette::light();
+        let events = [up_arrow(), up_arrow()];
+        let view = view("Final Events", None, &events, &palette);
+
+        terminal
+            .draw(|f| render_events(&view, f.area(), f.buffer_mut()))
+            .unwrap();
+
+        let screen = screen(&terminal);
+        assert!(screen.contains("Final Events"));
+        assert!(!screen.contains("pending:"));
+        assert_eq!(screen.matches("1B 5B 41").count(), 2);
+    }
+
+    #[cfg(feature = "test-backend")]
+    #[test]
+    fn time_column_shows_seconds_since_the_capture_started() {
+        let (_app, mut terminal) = app_terminal();
+        let palette = AppPalette::dark();
+        let start = Instant::now();
+        let mut events = [
+            up_arrow(),
+            EventRow::new(InputEventInfo::from_bytes(Vec::new())),
+        ];
+        events[0].first_byte_at = Some(start + Duration::from_millis(1234));
+        let view = EventsView {
+            started: Some(start),
+            ..view("Final Events", None, &events, &palette)
+        };
+
+        terminal
+            .draw(|f| render_events(&view, f.area(), f.buffer_mut()))
+            .unwrap();
+
+        let screen = screen(&terminal);
+        assert!(screen.contains("Time"));
+        assert!(screen.contains("1.234"));
+        assert!(screen.contains("—"));
+        assert_eq!(events[0].time_text(start + Duration::from_secs(2)), "0.000");
+    }
+
+    #[cfg(feature = "test-backend")]
+    #[test]
+    fn time_column_can_be_hidden() {
+        let (_app, mut terminal) = app_terminal();
+        let palette = AppPalette::dark();
+        let events = [up_arrow()];
+        let view = view("Events", None, &events, &palette);
+
+        terminal
+            .draw(|f| render_events(&view, f.area(), f.buffer_mut()))
+            .unwrap();
+
+        assert!(!screen(&terminal).contains("Time"));
+        assert!(Args::parse_from(["debug_inline", "--hide-time"]).hide_time);
+    }
+
+    fn disabled(vars: &[(&str, &str)]) -> bool {
+        colors_disabled(|key| {
+            vars.iter()
+                .find(|(name, _)| *name == key)
+                .map(|(_, value)| value.to_string())
+        })
+    }
+
+    #[test]
+    fn colors_follow_no_color_and_clicolor() {
+        assert!(!disabled(&[]));
+        assert!(disabled(&[("NO_COLOR", "1")]));
+        assert!(!disabled(&[("NO_COLOR", "")]));
+        assert!(disabled(&[("CLICOLOR", "0")]));
+        assert!(!disabled(&[("CLICOLOR", "1")]));
+        assert!(!disabled(&[("NO_COLOR", "1"), ("CLICOLOR_FORCE", "1")]));
+        assert!(disabled(&[("NO_COLOR", "1"), ("CLICOLOR_FORCE", "0")]));
+    }
+
+    #[test]
+    fn downgrades_rgb_to_what_the_terminal_shows() {
+        let teal = Color::Rgb(124, 209, 226);
+        assert_eq!(downgrade(teal, ColorDepth::TrueColor), teal);
+        assert_eq!(downgrade(teal, ColorDepth::Ansi16), Color::LightCyan);
+        assert_eq!(downgrade(teal, ColorDepth::Monochrome), Color::Reset);
+        // The cube's (2, 4, 4) beats any gray.
+        assert_eq!(
+            downgrade(teal, ColorDepth::Ansi256),
+            Color::Indexed(16 + 72 + 24 + 4)
+        );
+        assert_eq!(
+            downgrade(Color::Rgb(22, 24, 32), ColorDepth::Ansi256),
+            Color::Indexed(234)
+        );
+        assert_eq!(
+            downgrade(Color::Rgb(63, 136, 74), ColorDepth::Ansi16),
+            Color::Green
+        );
+        assert_eq!(
+            downgrade(Color::Rgb(247, 247, 250), ColorDepth::Ansi16),
+            Color::White
+        );
+        assert_eq!(
+            downgrade(Color::Rgb(22, 24, 32), ColorDepth::Ansi16),
+            Color::Black
+        );
+        assert_eq!(downgrade(Color::Cyan, ColorDepth::Ansi16), Color::Cyan);
+    }
+
+    #[cfg(feature = "test-backend")]
+    #[test]
+    fn palettes_without_truecolor_draw_no_rgb() {
+        let (_app, mut terminal) = app_terminal();
+        let events = [up_arrow()];
+        for depth in [ColorDepth::Ansi256, ColorDepth::Ansi16] {
+            for palette in [AppPalette::dark(), AppPalette::light()] {
+                let palette = palette.downgraded(depth);
+                let view = view("Events", None, &events, &palette);
+                terminal
+                    .draw(|f| render_events(&view, f.area(), f.buffer_mut()))
+                    .unwrap();
+
+                let buffer = terminal.backend().buffer();
+                assert!(!buffer
+                    .content()
+                    .iter()
+                    .any(|cell| matches!(cell.fg, Color::Rgb(..))
+                        || matches!(cell.bg, Color::Rgb(..))));
             }
-        } else {
-            requested
         }
     }
-}
 
-#[cfg(unix)]
-fn duration_to_poll_timeout(duration: Duration) -> libc::c_int {
-    if duration == Duration::ZERO {
-        return 0;
+    #[cfg(feature = "test-backend")]
+    #[test]
+    fn plain_palette_draws_no_colors() {
+        let (_app, mut terminal) = app_terminal();
+        let palette = AppPalette::plain();
+        let events = [up_arrow()];
+        let view = view("Events", None, &events, &palette);
+
+        terminal
+            .draw(|f| render_events(&view, f.area(), f.buffer_mut()))
+            .unwrap();
+
+        let buffer = terminal.backend().buffer();
+        assert!(buffer
+            .content()
+            .iter()
+            .all(|cell| cell.fg == Color::Reset && cell.bg == Color::Reset));
     }
 
-    let millis = duration.as_millis().min(i32::MAX as u128);
-    millis as libc::c_int
+    #[test]
+    fn json_to_a_piped_stdout_child() {
+        if !support::is_child("tests::json_to_a_piped_stdout_child") {
+            return;
+        }
+        run(Args::parse_from([
+            "debug_inline",
+            "--format",
+            "json",
+            "--max-inputs",
+            "1",
+        ]))
+        .unwrap();
+    }
+
+    #[test]
+    fn json_to_a_piped_stdout_keeps_the_tui_out_of_the_pipe() {
+        let mut child =
+            support::PtyChild::spawn_with_piped_stdout("tests::json_to_a_piped_stdout_child");
+        child.wait_for(b"Hex");
+        child.write(b"a");
+        assert!(child.wait().success());
+        let stdout = child.read_stdout();
+
+        // The TUI drew on stderr's alternate screen, which is the PTY here.
+        assert!(support::contains(child.output(), b"\x1b[?1049h"));
+        assert!(
+            !stdout.contains(&0x1b),
+            "{:?}",
+            String::from_utf8_lossy(&stdout)
+        );
+        // The test harness shares the pipe, and starts the export's line with the test's name.
+        let exported: Vec<ExportedEvent> = String::from_utf8(stdout)
+            .unwrap()
+            .lines()
+            .filter_map(|line| serde_json::from_str(&line[line.find('{')?..]).ok())
+            .collect();
+        assert_eq!(exported.len(), 1);
+        assert_eq!(exported[0].hex, "61");
+    }
 }
@@ -1,8 +1,25 @@
-use nix::poll::{poll, PollFd, PollFlags, PollTimeout};
+use _tuicore::raw_input::{FlushTimeouts, InputSource, PollResult, RawEvent, RawInputReader};
+use _tuicore::throughput::Throughput;
+use clap::Parser;
 use nix::sys::termios::{self, Termios};
 use std::collections::VecDeque;
-use std::io::{self, Read, Write};
+use std::io::{self, Write};
 use std::os::fd::{AsRawFd, BorrowedFd};
+use std::time::{Duration, Instant};
+
+/// Shown on the status line pinned above the tokens, followed by the current rates.
+const STATUS: &str = "Reading TTY in raw mode. Ctrl-C to exit.";
+
+/// Most pending bytes shown before the preview is truncated.
+const PENDING_PREVIEW_BYTES: usize = 16;
+
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Status line redraws per second; input is gathered and printed between them
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u32).range(1..=120))]
+    fps: u32,
+}
 
 struct RawMode<'a> {
     fd: BorrowedFd<'a>,
@@ -26,10 +43,11 @@ impl<'a> Drop for RawMode<'a> {
 }
 
 fn to_io(err: nix::Error) -> io::Error {
-    io::Error::new(io::ErrorKind::Other, err)
+    io::Error::other(err)
 }
 
 #[derive(Debug)]
+#[allow(clippy::upper_case_acronyms)]
 enum Token {
     Byte(u8),
     Ctrl(&'static str),
@@ -49,12 +67,17 @@ enum Token {
 }
 
 fn main() -> io::Result<()> {
-    let stdin = io::stdin();
-    let mut input = stdin.lock();
+    let args = Args::parse();
     let stdout = io::stdout();
     let mut out = stdout.lock();
+    let mut reader = RawInputReader::new(FlushTimeouts::default())?;
 
-    writeln!(out, "Reading TTY in raw mode. Ctrl-C to exit.")?;
+    // Clear the screen and keep the status and pending lines out of the region the tokens
+    // scroll in. From here on the screen is put back on every way out, errors included.
+    let screen = ScreenGuard;
+    write!(out, "\x1b[2J\x1b[H")?;
+    print_status(&mut out, &reader)?;
+    write!(out, "\x1b[3r\x1b[3;1H")?;
 
     // Put TTY in raw mode (only if stdin is a TTY).
     let stdin_fd = unsafe { BorrowedFd::borrow_raw(io::stdin().as_raw_fd()) };
@@ -67,47 +90,125 @@ fn main() -> io::Result<()> {
     // Optionally enable bracketed paste + mouse reporting (comment out if not needed).
     enable_capabilities(&mut out, true, true)?; // (paste, mouse)
 
-    let mut pfd = [PollFd::new(stdin_fd, PollFlags::POLLIN)];
-    let mut buf = [0u8; 4096];
-    let mut q: VecDeque<u8> = VecDeque::new();
+    let result = capture(&mut out, &mut reader, args.fps);
+    drop(screen);
+    print_totals(&mut out, reader.throughput())?;
+    result
+}
+
+/// Print tokens as they arrive until Ctrl-C or the input closes, redrawing the status line
+/// `fps` times a second.
+fn capture(out: &mut impl Write, reader: &mut RawInputReader, fps: u32) -> io::Result<()> {
+    // When the previous event arrived, for the gap printed before each token.
+    let mut last_event_at: Option<Instant> = None;
+    let frame_interval = Duration::from_secs(1) / fps;
+    let mut next_frame = Instant::now() + frame_interval;
 
     out.flush()?;
 
     loop {
-        match poll(&mut pfd, PollTimeout::NONE) {
-            Ok(n) if n > 0 => {
-                match input.read(&mut buf) {
-                    Ok(0) => break, // EOF
-                    Ok(n) => {
-                        q.extend(&buf[..n]);
-                        while let Some(tok) = parse_next(&mut q) {
-                            print_token(&mut out, &tok)?;
-                            out.flush()?;
-                            if matches!(tok, Token::Ctrl("C")) {
-                                // Ctrl-C
-                                cleanup_capabilities(&mut out)?;
-                                writeln!(out, "\n^C")?;
-                                return Ok(());
-                            }
-                        }
-                    }
-                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
-                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => continue,
-                    Err(e) => return Err(e),
-                }
+        // Gather events until the next frame is due, so the status line redraws on a fixed
+        // cadence and its rates fall back to zero while nothing arrives.
+        let mut events = reader.poll_until(next_frame)?;
+        // Whatever ended the frame: input closing, or an event that landed right at its end.
+        let closed = match reader.poll_next(Duration::ZERO)? {
+            PollResult::Event(event) => {
+                events.push(event);
+                false
             }
-            Ok(_) => continue,
-            Err(err) => {
-                if err == nix::errno::Errno::EINTR {
-                    continue;
-                }
-                return Err(to_io(err));
+            PollResult::Eof => true,
+            PollResult::Idle | PollResult::Pending { .. } => false,
+            // Only reported once watched for, and this reader watches for neither.
+            PollResult::Resize | PollResult::Shutdown(_) => false,
+        };
+        for event in &events {
+            if print_event(out, event, &mut last_event_at)? {
+                writeln!(out, "\n^C")?;
+                return Ok(());
             }
         }
+        // Frames missed while busy are skipped rather than drawn back to back.
+        while next_frame <= Instant::now() {
+            next_frame += frame_interval;
+        }
+
+        print_status(out, reader)?;
+        out.flush()?;
+        if closed {
+            writeln!(out, "\r\nInput closed.")?;
+            return Ok(());
+        }
+    }
+}
+
+/// Print `event`'s tokens, each after the gap since the previous event, returning whether one
+/// was Ctrl-C.
+fn print_event(
+    out: &mut impl Write,
+    event: &RawEvent,
+    last_event_at: &mut Option<Instant>,
+) -> io::Result<bool> {
+    // Tokens from one event arrived together, so all but the first show 0.
+    let at = event.last_byte_at;
+    for tok in tokens(&event.bytes) {
+        print_gap(
+            out,
+            last_event_at.map(|last| at.saturating_duration_since(last)),
+        )?;
+        *last_event_at = Some(at);
+        print_token(out, &tok)?;
+        if matches!(tok, Token::Ctrl("C")) {
+            return Ok(true);
+        }
     }
+    Ok(false)
+}
+
+/// Redraw the status line with the current rates and, under it, the bytes held back as a
+/// partial sequence, leaving the cursor where it was.
+fn print_status(out: &mut impl Write, reader: &RawInputReader) -> io::Result<()> {
+    let rates = reader.throughput().rates(Instant::now());
+    let pending = pending_preview(reader.pending_bytes());
+    write!(
+        out,
+        "\x1b7\x1b[1;1H\x1b[2K{STATUS}  {rates}\x1b[2;1H\x1b[2Kpending: {pending}\x1b8"
+    )
+}
 
-    cleanup_capabilities(&mut out)?;
-    Ok(())
+/// Pending bytes in hex, such as `1B 5B 31`, or `—` when nothing is buffered.
+fn pending_preview(pending: &[u8]) -> String {
+    if pending.is_empty() {
+        return "—".to_string();
+    }
+    let mut preview = pending
+        .iter()
+        .take(PENDING_PREVIEW_BYTES)
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if pending.len() > PENDING_PREVIEW_BYTES {
+        preview.push_str(&format!(" … ({} bytes)", pending.len()));
+    }
+    preview
+}
+
+/// Everything read over the session, for the summary after the last token.
+fn print_totals(out: &mut impl Write, throughput: &Throughput) -> io::Result<()> {
+    write!(
+        out,
+        "\r\nRead {} bytes as {} events.\r\n",
+        throughput.total_bytes(),
+        throughput.total_events()
+    )?;
+    out.flush()
+}
+
+/// The gap since the previous token in milliseconds, or `—` before the first token.
+fn print_gap(out: &mut impl Write, gap: Option<Duration>) -> io::Result<()> {
+    match gap {
+        Some(gap) => write!(out, "[Δ{:.1}ms]", gap.as_secs_f64() * 1000.0),
+        None => write!(out, "[Δ—]"),
+    }
 }
 
 fn print_token(out: &mut impl Write, t: &Token) -> io::Result<()> {
@@ -140,10 +241,27 @@ fn print_token(out: &mut impl Write, t: &Token) -> io::Result<()> {
     }
 }
 
+/// Split one event's bytes into tokens.
+///
+/// The reader only hands over an unfinished sequence once its flush timeout passes, so its ESC
+/// stands alone and the rest shows byte by byte.
+fn tokens(bytes: &[u8]) -> Vec<Token> {
+    let mut q: VecDeque<u8> = bytes.iter().copied().collect();
+    let mut tokens = Vec::new();
+    while !q.is_empty() {
+        let tok = parse_next(&mut q).unwrap_or_else(|| {
+            q.pop_front();
+            Token::Esc
+        });
+        tokens.push(tok);
+    }
+    tokens
+}
+
 fn parse_next(q: &mut VecDeque<u8>) -> Option<Token> {
     let b = *q.front()?;
-    // Control bytes and DEL
-    if b <= 0x1F || b == 0x7F {
+    // Control bytes and DEL; ESC begins the sequences below
+    if (b <= 0x1F && b != 0x1B) || b == 0x7F {
         q.pop_front();
         return Some(match b {
             0x00 => Token::Ctrl("@"),
@@ -158,7 +276,6 @@ fn parse_next(q: &mut VecDeque<u8>) -> Option<Token> {
             0x09 => Token::Key("TAB"),
             0x0A => Token::Key("LF"),
             0x0D => Token::Key("CR"),
-            0x1B => Token::Esc, // ESC begins sequences too, but return bare ESC if it’s alone so far
             0x7F => Token::Key("DEL"),
             _ => Token::Ctrl("?"),
         });
@@ -175,32 +292,19 @@ fn parse_next(q: &mut VecDeque<u8>) -> Option<Token> {
         match it.next() {
             Some(b'[') => {
                 // CSI: ESC [ ... final
-                // Final byte: 0x40..0x7E. Gather until we have a final.
-                if let Some((seq, final_ready)) = gather_ansi(q, b'[', true) {
-                    if !final_ready {
-                        return None;
-                    }
-                    if let Some(tok) = map_csi(&seq) {
-                        return Some(tok);
-                    }
-                    return Some(Token::CSI(seq));
-                } else {
-                    return None;
+                let seq = gather_ansi(q)?;
+                if let Some(tok) = map_csi(&seq) {
+                    return Some(tok);
                 }
+                return Some(Token::CSI(seq));
             }
             Some(b'O') => {
                 // SS3: ESC O <final>
-                if let Some((seq, final_ready)) = gather_ansi(q, b'O', false) {
-                    if !final_ready {
-                        return None;
-                    }
-                    if let Some(tok) = map_ss3(&seq) {
-                        return Some(tok);
-                    }
-                    return Some(Token::SS3(seq));
-                } else {
-                    return None;
+                let seq = gather_ansi(q)?;
+                if let Some(tok) = map_ss3(&seq) {
+                    return Some(tok);
                 }
+                return Some(Token::SS3(seq));
             }
             _ => {
                 // ESC followed by something else (e.g., ESC alone then byte)
@@ -215,39 +319,12 @@ fn parse_next(q: &mut VecDeque<u8>) -> Option<Token> {
     Some(Token::Byte(b))
 }
 
-/// Gather an ANSI sequence after ESC <lead> …
-/// Returns (string_without_ESC, has_final).
-fn gather_ansi(q: &mut VecDeque<u8>, lead: u8, _has_params: bool) -> Option<(String, bool)> {
-    // Ensure prefix is present: ESC lead
-    if q.len() < 2 {
-        return None;
-    }
-    let esc = q.pop_front().unwrap();
-    let l = q.pop_front().unwrap();
-    debug_assert_eq!(esc, 0x1B);
-    debug_assert_eq!(l, lead);
-
-    let mut tmp: Vec<u8> = vec![lead];
-    // Consume until final 0x40..0x7E or give up if buffer ends
-    while let Some(&c) = q.front() {
-        tmp.push(c);
-        q.pop_front();
-        if (0x40..=0x7E).contains(&c) {
-            let s = String::from_utf8_lossy(&tmp).into_owned();
-            return Some((s, true));
-        }
-        // Protective cap
-        if tmp.len() > 64 {
-            let s = String::from_utf8_lossy(&tmp).into_owned();
-            return Some((s, false));
-        }
-    }
-    // Not enough yet; put back what we consumed (except the ESC+lead) for next time
-    // (We *don’t* push back here—simplify: treat as incomplete; caller will return None.)
-    // But since we already popped, simplest is to stash in a side buffer; for brevity we just signal incomplete.
-    // In practice, make a ring buffer snapshot before consuming.
-    // Here, we return the partial (no final) and signal incomplete.
-    Some((String::from_utf8_lossy(&tmp).into_owned(), false))
+/// Take the ANSI sequence ESC <lead> … <final> off the front of `q`, returning it without the
+/// ESC, or `None` while its final byte (0x40..0x7E) has yet to arrive.
+fn gather_ansi(q: &mut VecDeque<u8>) -> Option<String> {
+    let len = q.iter().skip(2).position(|c| (0x40..=0x7E).contains(c))? + 3;
+    let seq: Vec<u8> = q.drain(..len).skip(1).collect();
+    Some(String::from_utf8_lossy(&seq).into_owned())
 }
 
 fn map_csi(s: &str) -> Option<Token> {
@@ -309,7 +386,7 @@ fn map_csi(s: &str) -> Option<Token> {
 
         // xterm SGR mouse: CSI <btn;col;row M/m
         if let Some(rest) = rest.strip_prefix('<') {
-            let mut parts = rest.split(|c| c == ';' || c == 'M' || c == 'm');
+            let mut parts = rest.split([';', 'M', 'm']);
             if let (Some(btn), Some(x), Some(y)) = (parts.next(), parts.next(), parts.next()) {
                 let press = rest.contains('M'); // release uses 'm'
                 if let (Ok(b), Ok(cx), Ok(cy)) = (btn.parse::<i32>(), x.parse(), y.parse()) {
@@ -357,8 +434,15 @@ fn enable_capabilities(out: &mut impl Write, paste: bool, mouse: bool) -> io::Re
     out.flush()
 }
 
-fn cleanup_capabilities(out: &mut impl Write) -> io::Result<()> {
-    // Turn off features we turned on
-    write!(out, "\x1b[?2004l\x1b[?1000l")?;
-    out.flush()
+/// Undoes the screen setup in `main` when dropped, however the capture ends.
+struct ScreenGuard;
+
+impl Drop for ScreenGuard {
+    fn drop(&mut self) {
+        // Turn off features we turned on, and give the pinned lines' rows back to the scroll
+        // region (resetting it homes the cursor, so it is saved around that).
+        let mut out = io::stdout();
+        let _ = write!(out, "\x1b[?2004l\x1b[?1000l\x1b[?1006l\x1b7\x1b[r\x1b8");
+        let _ = out.flush();
+    }
 }
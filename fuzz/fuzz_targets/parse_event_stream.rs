@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// Any crash found here gets its input saved under tests/fixtures/parse as a regression case.
+fuzz_target!(|data: &[u8]| {
+    let events = _tuicore::interpret::parse_event_stream(data);
+    let rejoined: Vec<u8> = events.into_iter().flat_map(|info| info.bytes).collect();
+    assert_eq!(rejoined, data);
+});
@@ -0,0 +1,111 @@
+use crate::raw_input::{FlushTimeouts, Framer, RawEvent};
+use crate::throughput::Throughput;
+use std::fs::File;
+use std::io::{self, Read};
+use std::os::fd::{AsFd, AsRawFd, OwnedFd};
+use std::time::Instant;
+use tokio::io::unix::AsyncFd;
+
+/// Async counterpart of [`RawInputReader`](crate::raw_input::RawInputReader) for tokio apps.
+///
+/// Framing and flush-timeout behavior match the blocking reader. The fd is switched to
+/// non-blocking mode for the reader's lifetime and its original flags are restored on drop.
+pub struct AsyncRawInputReader {
+    fd: AsyncFd<File>,
+    framer: Framer,
+    closed: bool,
+    throughput: Throughput,
+    original_flags: libc::c_int,
+}
+
+impl AsyncRawInputReader {
+    /// Read from a duplicate of stdin, flushing partial sequences after `flush_timeout`.
+    pub fn new(flush_timeout: impl Into<FlushTimeouts>) -> io::Result<Self> {
+        let fd = io::stdin().as_fd().try_clone_to_owned()?;
+        Self::with_fd(fd, flush_timeout)
+    }
+
+    /// Read from `fd`; must be called from within a tokio runtime.
+    pub fn with_fd(fd: OwnedFd, flush_timeout: impl Into<FlushTimeouts>) -> io::Result<Self> {
+        let raw = fd.as_raw_fd();
+        let original_flags = unsafe { libc::fcntl(raw, libc::F_GETFL) };
+        if original_flags == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        if unsafe { libc::fcntl(raw, libc::F_SETFL, original_flags | libc::O_NONBLOCK) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self {
+            fd: AsyncFd::new(File::from(fd))?,
+            framer: Framer::new(flush_timeout),
+            closed: false,
+            throughput: Throughput::default(),
+            original_flags,
+        })
+    }
+
+    /// Bytes held back as an incomplete sequence, without consuming them.
+    pub fn pending_bytes(&self) -> &[u8] {
+        self.framer.pending()
+    }
+
+    /// Bytes read and events returned so far, with their recent rates.
+    pub fn throughput(&self) -> &Throughput {
+        &self.throughput
+    }
+
+    /// Wait for the next framed event, returning `None` once the input is closed and drained.
+    pub async fn next_event(&mut self) -> io::Result<Option<RawEvent>> {
+        let event = self.next_framed().await?;
+        if event.is_some() {
+            self.throughput.record_events(Instant::now(), 1);
+        }
+        Ok(event)
+    }
+
+    async fn next_framed(&mut self) -> io::Result<Option<RawEvent>> {
+        let mut buf = [0u8; 1024];
+        loop {
+            if let Some(event) = self.framer.next_pending(Instant::now()) {
+                return Ok(Some(event));
+            }
+            if self.closed {
+                return Ok(self.framer.drain_closed());
+            }
+
+            let deadline = self.framer.flush_deadline();
+            tokio::select! {
+                guard = self.fd.readable_mut() => {
+                    let mut guard = guard?;
+                    match guard.try_io(|inner| inner.get_mut().read(&mut buf)) {
+                        Ok(Ok(0)) => self.closed = true,
+                        Ok(Ok(n)) => {
+                            let now = Instant::now();
+                            self.throughput.record_bytes(now, n);
+                            self.framer.push_bytes(&buf[..n], now);
+                        }
+                        Ok(Err(err)) if err.kind() == io::ErrorKind::Interrupted => {}
+                        // A PTY whose other side closed reports EIO rather than EOF.
+                        Ok(Err(err)) if err.raw_os_error() == Some(libc::EIO) => self.closed = true,
+                        Ok(Err(err)) => return Err(err),
+                        Err(_would_block) => {}
+                    }
+                }
+                _ = sleep_until(deadline), if deadline.is_some() => {}
+            }
+        }
+    }
+}
+
+impl Drop for AsyncRawInputReader {
+    fn drop(&mut self) {
+        unsafe { libc::fcntl(self.fd.as_raw_fd(), libc::F_SETFL, self.original_flags) };
+    }
+}
+
+async fn sleep_until(deadline: Option<Instant>) {
+    if let Some(deadline) = deadline {
+        tokio::time::sleep_until(deadline.into()).await;
+    }
+}
@@ -0,0 +1,254 @@
+use crate::latency::InputLatency;
+use crate::raw_input::{FdSource, InputSource, PollResult, RawEvent, RawInputReader, Waker};
+use crate::throughput::Throughput;
+use std::io::{self, Read};
+use std::os::fd::AsFd;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// How many undelivered messages the reader thread may queue before it blocks.
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// A notification from the background reader thread.
+#[derive(Debug)]
+pub enum InputMessage {
+    /// A complete (or flushed partial) event.
+    Event(RawEvent),
+    /// The terminal was resized.
+    Resize,
+    /// A termination signal arrived; the UI should restore the terminal and exit.
+    Shutdown(libc::c_int),
+    /// No input arrived for a whole tick interval.
+    Tick,
+    /// The input closed and every buffered byte has been delivered; no more messages follow.
+    Eof,
+    /// Reading failed; no more messages follow.
+    Error(io::Error),
+}
+
+/// Runs an [`InputSource`] on its own thread and delivers framed events over a bounded channel.
+///
+/// The thread keeps polling while the UI is busy, so flush timeouts fire on time regardless of
+/// how often the UI checks in. Dropping the handle stops and joins the thread.
+pub struct ChannelInputReader {
+    receiver: Option<Receiver<InputMessage>>,
+    stop: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+    pending: Arc<Mutex<Vec<u8>>>,
+    latency: Arc<Mutex<InputLatency>>,
+    throughput: Arc<Mutex<Throughput>>,
+    waker: Option<Waker>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ChannelInputReader {
+    /// Move `reader` onto a background thread, sending a tick after each idle `tick_interval`.
+    ///
+    /// Dropping the handle wakes the reader's poll immediately.
+    pub fn spawn<F>(
+        mut reader: RawInputReader<FdSource<F>>,
+        tick_interval: Duration,
+    ) -> io::Result<Self>
+    where
+        F: AsFd + Read + Send + 'static,
+    {
+        let waker = reader.waker()?;
+        let mut handle = Self::spawn_source(reader, tick_interval)?;
+        handle.waker = Some(waker);
+        Ok(handle)
+    }
+
+    /// Move any `source` onto a background thread, sending a tick after each idle `tick_interval`.
+    ///
+    /// Without a waker, dropping the handle waits for the source's current poll to return.
+    pub fn spawn_source<S>(source: S, tick_interval: Duration) -> io::Result<Self>
+    where
+        S: InputSource + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::sync_channel(CHANNEL_CAPACITY);
+        let stop = Arc::new(AtomicBool::new(false));
+        let paused = Arc::new(AtomicBool::new(false));
+        let pending = Arc::new(Mutex::new(Vec::new()));
+        let latency = Arc::new(Mutex::new(InputLatency::default()));
+        let throughput = Arc::new(Mutex::new(Throughput::default()));
+        let control = Control {
+            stop: Arc::clone(&stop),
+            paused: Arc::clone(&paused),
+            pending: Arc::clone(&pending),
+            latency: Arc::clone(&latency),
+            throughput: Arc::clone(&throughput),
+        };
+        let thread = thread::Builder::new()
+            .name("raw-input-reader".to_string())
+            .spawn(move || read_loop(source, tick_interval, sender, control))?;
+
+        Ok(Self {
+            receiver: Some(receiver),
+            stop,
+            paused,
+            pending,
+            latency,
+            throughput,
+            waker: None,
+            thread: Some(thread),
+        })
+    }
+
+    /// Stop the thread consuming input; see [`InputSource::set_paused`]. As with
+    /// [`RawInputReader::pause`], nothing typed can resume it.
+    pub fn pause(&self) {
+        self.set_paused(true);
+    }
+
+    /// Let the thread consume input again, starting with whatever queued up while paused.
+    pub fn resume(&self) {
+        self.set_paused(false);
+    }
+
+    /// Whether input is currently paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// A snapshot of the bytes the thread is holding back as an incomplete sequence.
+    pub fn pending_bytes(&self) -> Vec<u8> {
+        self.pending.lock().unwrap().clone()
+    }
+
+    /// A snapshot of the source's latency statistics, updated after each batch of events.
+    pub fn latency(&self) -> InputLatency {
+        self.latency.lock().unwrap().clone()
+    }
+
+    /// A snapshot of the source's byte and event counters, updated after each batch of events.
+    pub fn throughput(&self) -> Throughput {
+        self.throughput.lock().unwrap().clone()
+    }
+
+    fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+        self.wake();
+    }
+
+    fn wake(&self) {
+        if let Some(waker) = &self.waker {
+            if let Err(e) = waker.wake() {
+                tracing::warn!("Failed to wake input thread: {}", e);
+            }
+        }
+    }
+
+    /// Wait up to `timeout` for the next message.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<InputMessage, RecvTimeoutError> {
+        self.receiver().recv_timeout(timeout)
+    }
+
+    /// Take the next message if one is already queued.
+    pub fn try_recv(&self) -> Result<InputMessage, TryRecvError> {
+        self.receiver().try_recv()
+    }
+
+    fn receiver(&self) -> &Receiver<InputMessage> {
+        self.receiver
+            .as_ref()
+            .expect("receiver is only taken on drop")
+    }
+}
+
+impl Drop for ChannelInputReader {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.wake();
+        // Unblocks a thread stuck sending into a full channel.
+        drop(self.receiver.take());
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                tracing::error!("Input thread panicked");
+            }
+        }
+    }
+}
+
+/// Flags the handle uses to steer the reader thread.
+struct Control {
+    stop: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+    pending: Arc<Mutex<Vec<u8>>>,
+    latency: Arc<Mutex<InputLatency>>,
+    throughput: Arc<Mutex<Throughput>>,
+}
+
+fn read_loop<S: InputSource>(
+    mut source: S,
+    tick_interval: Duration,
+    sender: SyncSender<InputMessage>,
+    control: Control,
+) {
+    // Idle polls also happen when bytes arrive without completing an event, so tick on elapsed
+    // time rather than on every idle result.
+    let mut last_sent = Instant::now();
+    let mut paused = false;
+    let mut batch = Vec::new();
+    while !control.stop.load(Ordering::Relaxed) {
+        let want_paused = control.paused.load(Ordering::Relaxed);
+        if want_paused != paused {
+            source.set_paused(want_paused);
+            paused = want_paused;
+        }
+        let timeout = tick_interval.saturating_sub(last_sent.elapsed());
+        let result = source.poll_batch(timeout, &mut batch);
+        {
+            let mut pending = control.pending.lock().unwrap();
+            if pending.as_slice() != source.pending_bytes() {
+                *pending = source.pending_bytes().to_vec();
+            }
+        }
+        if !batch.is_empty() {
+            if let Some(latency) = source.latency() {
+                *control.latency.lock().unwrap() = latency.clone();
+            }
+            if let Some(throughput) = source.throughput() {
+                *control.throughput.lock().unwrap() = throughput.clone();
+            }
+            for event in batch.drain(..) {
+                if sender.send(InputMessage::Event(event)).is_err() {
+                    return;
+                }
+            }
+            last_sent = Instant::now();
+        }
+        let message = match result {
+            Ok(PollResult::Event(event)) => InputMessage::Event(event),
+            Ok(PollResult::Resize) => InputMessage::Resize,
+            Ok(PollResult::Shutdown(signal)) => InputMessage::Shutdown(signal),
+            Ok(PollResult::Idle | PollResult::Pending { .. })
+                if last_sent.elapsed() < tick_interval =>
+            {
+                continue
+            }
+            Ok(PollResult::Idle | PollResult::Pending { .. }) => {
+                last_sent = Instant::now();
+                // Ticks are advisory; skip them rather than block behind a slow consumer.
+                match sender.try_send(InputMessage::Tick) {
+                    Err(mpsc::TrySendError::Disconnected(_)) => break,
+                    Ok(()) | Err(mpsc::TrySendError::Full(_)) => continue,
+                }
+            }
+            Ok(PollResult::Eof) => {
+                let _ = sender.send(InputMessage::Eof);
+                break;
+            }
+            Err(err) => {
+                let _ = sender.send(InputMessage::Error(err));
+                break;
+            }
+        };
+        if sender.send(message).is_err() {
+            break;
+        }
+        last_sent = Instant::now();
+    }
+}
@@ -0,0 +1,85 @@
+use crate::raw_input::RawEvent;
+use std::time::{Duration, Instant};
+
+/// Default gap under which an identical event counts as key auto-repeat.
+pub const DEFAULT_REPEAT_WINDOW: Duration = Duration::from_millis(100);
+
+/// Whether an event starts a new entry or repeats the previous one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Coalesced {
+    New,
+    /// Folded into the previous entry, which has now been seen this many times in a row.
+    Repeat(usize),
+}
+
+/// Folds key auto-repeat into counted entries.
+///
+/// An event repeats the previous one when its bytes are identical and its first byte arrives
+/// within the window of the previous event's last byte. Repeats do not create entries, so
+/// [`entries`](Self::entries) counts distinct rows and a row limit is never used up by one held key.
+#[derive(Debug, Clone)]
+pub struct RepeatCoalescer {
+    window: Option<Duration>,
+    last: Option<(Vec<u8>, Instant)>,
+    run: usize,
+    entries: usize,
+    events: usize,
+}
+
+impl RepeatCoalescer {
+    /// Coalesce identical events that arrive within `window` of each other.
+    pub fn new(window: Duration) -> Self {
+        Self::with_window(Some(window))
+    }
+
+    /// Give every event its own entry.
+    pub fn disabled() -> Self {
+        Self::with_window(None)
+    }
+
+    fn with_window(window: Option<Duration>) -> Self {
+        Self {
+            window,
+            last: None,
+            run: 0,
+            entries: 0,
+            events: 0,
+        }
+    }
+
+    /// Classify `event` against the one before it.
+    pub fn push(&mut self, event: &RawEvent) -> Coalesced {
+        self.events += 1;
+        let repeat = match (&self.last, self.window) {
+            (Some((bytes, last_byte_at)), Some(window)) => {
+                *bytes == event.bytes
+                    && event.first_byte_at.saturating_duration_since(*last_byte_at) <= window
+            }
+            _ => false,
+        };
+        self.last = Some((event.bytes.clone(), event.last_byte_at));
+        if repeat {
+            self.run += 1;
+            Coalesced::Repeat(self.run)
+        } else {
+            self.run = 1;
+            self.entries += 1;
+            Coalesced::New
+        }
+    }
+
+    /// End the current run, e.g. after a row that isn't an event was shown in between.
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+
+    /// Distinct entries so far; repeats are not counted.
+    pub fn entries(&self) -> usize {
+        self.entries
+    }
+
+    /// Every event pushed so far, repeats included.
+    pub fn events(&self) -> usize {
+        self.events
+    }
+}
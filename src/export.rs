@@ -0,0 +1,89 @@
+use crate::interpret::InputEventInfo;
+use serde::{Deserialize, Serialize};
+use std::io::{self, Write};
+use std::time::Duration;
+
+/// Version of the [`ExportedEvent`] layout, bumped whenever a field changes meaning or is removed.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// One captured event as exported: what the debugger's table shows, plus when it arrived.
+///
+/// Every format writes these fields, in this order: JSON Lines as object keys and CSV as
+/// columns.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedEvent {
+    /// Always [`SCHEMA_VERSION`] when written by this version.
+    pub schema: u32,
+    /// The raw bytes as space-separated hex, e.g. `1B 5B 41`.
+    pub hex: String,
+    /// The raw bytes with control characters escaped, e.g. `\x1B[A`.
+    pub escaped: String,
+    /// Milliseconds from the start of the capture to the event's first byte; `None` when the
+    /// bytes weren't captured live.
+    pub timestamp_ms: Option<f64>,
+    /// Milliseconds since the previous event's first byte; `None` for the first event, or when
+    /// the bytes weren't captured live.
+    #[serde(default)]
+    pub gap_ms: Option<f64>,
+    pub key: String,
+    pub code: String,
+    pub modifiers: String,
+    pub kind: String,
+    pub description: String,
+    /// How many auto-repeats of the key this one event stands for.
+    pub repeats: usize,
+    /// Bytes of input received up to and including this event, its repeats and paste chunks;
+    /// `None` when the bytes weren't captured live.
+    #[serde(default)]
+    pub total_bytes: Option<u64>,
+    /// Events received up to and including this one, counting each repeat and paste chunk and
+    /// any control key in between; `None` when the bytes weren't captured live.
+    #[serde(default)]
+    pub total_events: Option<u64>,
+}
+
+impl ExportedEvent {
+    /// Export `info`, which arrived `timestamp` into the capture if known.
+    pub fn new(info: &InputEventInfo, timestamp: Option<Duration>) -> Self {
+        Self {
+            schema: SCHEMA_VERSION,
+            hex: info.hex_string.clone(),
+            escaped: info.escaped_string.clone(),
+            timestamp_ms: timestamp.map(|timestamp| timestamp.as_secs_f64() * 1000.0),
+            gap_ms: None,
+            key: info.guess.key.clone(),
+            code: info.guess.code.clone(),
+            modifiers: info.guess.modifiers.clone(),
+            kind: info.guess.kind.clone(),
+            description: info.guess.description.clone(),
+            repeats: 1,
+            total_bytes: None,
+            total_events: None,
+        }
+    }
+}
+
+/// Write `events` as JSON Lines: one object per event, each on its own line.
+pub fn write_json_lines<'a>(
+    out: &mut impl Write,
+    events: impl IntoIterator<Item = &'a ExportedEvent>,
+) -> io::Result<()> {
+    for event in events {
+        serde_json::to_writer(&mut *out, event)?;
+        out.write_all(b"\n")?;
+    }
+    out.flush()
+}
+
+/// Write `events` as CSV with a header row, quoting fields that contain commas, quotes or line
+/// breaks. A missing timestamp, gap or total is an empty field.
+pub fn write_csv<'a>(
+    out: &mut impl Write,
+    events: impl IntoIterator<Item = &'a ExportedEvent>,
+) -> io::Result<()> {
+    let mut writer = csv::Writer::from_writer(out);
+    for event in events {
+        writer.serialize(event)?;
+    }
+    writer.flush()
+}
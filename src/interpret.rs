@@ -0,0 +1,565 @@
+use crate::raw_input::{utf8_char_width, FlushTimeouts, Framer, RawEvent, PASTE_END, PASTE_START};
+use crate::theme::{parse_theme_report, ThemeReport};
+use crossterm::event::{KeyCode, KeyModifiers};
+use std::time::Instant;
+
+/// One framed event, formatted for display together with a guess at the key it encodes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputEventInfo {
+    pub bytes: Vec<u8>,
+    pub hex_string: String,
+    pub escaped_string: String,
+    pub guess: GuessInfo,
+}
+
+/// The key (or other input) an event most likely encodes, as display strings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GuessInfo {
+    pub key: String,
+    pub modifiers: String,
+    pub description: String,
+    pub code: String,
+    pub kind: String,
+}
+
+/// Frame and interpret a complete byte stream, as if it had all arrived in one read and then
+/// the input closed.
+///
+/// Runs the live reader's framing, including bracketed paste chunking and the flush of a
+/// trailing partial sequence, without an fd or timeouts. Never panics, whatever the input.
+pub fn parse_event_stream(bytes: &[u8]) -> Vec<InputEventInfo> {
+    let mut framer = Framer::new(FlushTimeouts::default());
+    framer.push_bytes(bytes, Instant::now());
+    std::iter::from_fn(|| framer.drain_closed())
+        .map(|event| InputEventInfo::from_raw_event(&event))
+        .collect()
+}
+
+impl InputEventInfo {
+    /// Interpret `bytes` as a single key.
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self {
+            hex_string: hex_string(&bytes),
+            escaped_string: escape_bytes(&bytes),
+            guess: GuessInfo::from_bytes(&bytes),
+            bytes,
+        }
+    }
+
+    /// Interpret a framed event, labelling cut-off sequences and paste chunks as such.
+    pub fn from_raw_event(event: &RawEvent) -> Self {
+        let mut info = Self::from_bytes(event.bytes.clone());
+        if event.oversized {
+            info.guess.key = "Garbage".to_string();
+            info.guess.description = "Oversized/unterminated sequence".to_string();
+        } else if event.paste {
+            info.guess = GuessInfo {
+                key: "Paste".to_string(),
+                modifiers: "None".to_string(),
+                description: format!("Pasted text ({} bytes)", event.bytes.len()),
+                code: "Paste".to_string(),
+                kind: "Paste".to_string(),
+            };
+        }
+        info
+    }
+}
+
+impl GuessInfo {
+    /// Guess the key `bytes` encode, or `Unknown`.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        if bytes == PASTE_START || bytes == PASTE_END {
+            let start = bytes == PASTE_START;
+            return GuessInfo {
+                key: "Paste".to_string(),
+                modifiers: "None".to_string(),
+                description: if start { "Paste start" } else { "Paste end" }.to_string(),
+                code: "Paste".to_string(),
+                kind: "Paste".to_string(),
+            };
+        }
+        if let Some(report) = parse_theme_report(bytes) {
+            let description = match report {
+                ThemeReport::Changed(mode) => format!("Theme changed → {mode:?}"),
+                ThemeReport::Unrecognized(params) => format!("Theme notification ({params})"),
+            };
+            return GuessInfo {
+                key: "Theme".to_string(),
+                modifiers: "None".to_string(),
+                description,
+                code: "Theme".to_string(),
+                kind: "Report".to_string(),
+            };
+        }
+        match interpret_bytes(bytes) {
+            Some(interp) => GuessInfo {
+                key: interp.key_display,
+                modifiers: format_modifiers(interp.modifiers),
+                description: interp.description,
+                code: format!("{:?}", interp.code),
+                kind: "Press".to_string(),
+            },
+            None => GuessInfo {
+                key: "Unknown".to_string(),
+                modifiers: "None".to_string(),
+                description: String::new(),
+                code: "Unknown".to_string(),
+                kind: "Unknown".to_string(),
+            },
+        }
+    }
+}
+
+/// A decoded key press.
+pub struct KeyInterpretation {
+    pub key_display: String,
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+    pub description: String,
+}
+
+/// Decode `bytes` as one key press, if they form a sequence this knows.
+pub fn interpret_bytes(bytes: &[u8]) -> Option<KeyInterpretation> {
+    if bytes.is_empty() {
+        return None;
+    }
+
+    interpret_csi_sequence(bytes)
+        .or_else(|| interpret_ss3_sequence(bytes))
+        .or_else(|| interpret_alt_sequence(bytes))
+        .or_else(|| interpret_single_byte(bytes))
+        .or_else(|| interpret_utf8_char(bytes))
+}
+
+fn interpret_single_byte(bytes: &[u8]) -> Option<KeyInterpretation> {
+    if bytes.len() != 1 {
+        return None;
+    }
+    let byte = bytes[0];
+    let (code, key_display, modifiers) = match byte {
+        0x00 => (KeyCode::Null, "Null".to_string(), KeyModifiers::empty()),
+        b'\r' | b'\n' => (KeyCode::Enter, "Enter".to_string(), KeyModifiers::empty()),
+        b'\t' => (KeyCode::Tab, "Tab".to_string(), KeyModifiers::empty()),
+        0x7F => (
+            KeyCode::Backspace,
+            "Backspace".to_string(),
+            KeyModifiers::empty(),
+        ),
+        0x08 => (
+            KeyCode::Backspace,
+            "Backspace".to_string(),
+            KeyModifiers::CONTROL,
+        ),
+        0x1B => (KeyCode::Esc, "Esc".to_string(), KeyModifiers::empty()),
+        0x01..=0x1A => {
+            let ch = (byte + 0x60) as char;
+            (
+                KeyCode::Char(ch),
+                format!("'{}'", ch),
+                KeyModifiers::CONTROL,
+            )
+        }
+        0x1C..=0x1F => {
+            let ch = (byte + 0x60) as char;
+            (
+                KeyCode::Char(ch),
+                format!("'{}'", ch),
+                KeyModifiers::CONTROL,
+            )
+        }
+        0x20..=0x7E => {
+            let ch = byte as char;
+            (
+                KeyCode::Char(ch),
+                format!("'{}'", ch),
+                KeyModifiers::empty(),
+            )
+        }
+        _ => return None,
+    };
+
+    let description = match code {
+        KeyCode::Backspace if modifiers.contains(KeyModifiers::CONTROL) => {
+            "Backspace (Ctrl+H)".to_string()
+        }
+        KeyCode::Char(_) if modifiers.contains(KeyModifiers::CONTROL) => {
+            "Control-modified character".to_string()
+        }
+        KeyCode::Enter => "Carriage return".to_string(),
+        KeyCode::Tab => "Horizontal tab".to_string(),
+        KeyCode::Esc => "Escape".to_string(),
+        KeyCode::Backspace => "Backspace".to_string(),
+        KeyCode::Null => "NULL".to_string(),
+        KeyCode::Char(_) => "Printable character".to_string(),
+        _ => String::new(),
+    };
+
+    Some(KeyInterpretation {
+        key_display,
+        code,
+        modifiers,
+        description,
+    })
+}
+
+fn interpret_utf8_char(bytes: &[u8]) -> Option<KeyInterpretation> {
+    let width = utf8_char_width(*bytes.first()?);
+    if width != bytes.len() {
+        return None;
+    }
+    let text = std::str::from_utf8(bytes).ok()?;
+    let mut chars = text.chars();
+    let ch = chars.next()?;
+    if chars.next().is_some() {
+        return None;
+    }
+    Some(KeyInterpretation {
+        key_display: char_display(ch),
+        code: KeyCode::Char(ch),
+        modifiers: KeyModifiers::empty(),
+        description: "UTF-8 character".to_string(),
+    })
+}
+
+fn interpret_alt_sequence(bytes: &[u8]) -> Option<KeyInterpretation> {
+    let (&0x1B, seq) = bytes.split_first()? else {
+        return None;
+    };
+    // ESC followed by any single key, control keys included, is that key with Alt held.
+    let key = interpret_single_byte(seq).or_else(|| interpret_utf8_char(seq))?;
+    let description = match key.code {
+        KeyCode::Char(_) if key.modifiers.is_empty() => "Alt-modified character".to_string(),
+        _ => format!("Alt-modified key ({})", key.description),
+    };
+    Some(KeyInterpretation {
+        modifiers: key.modifiers | KeyModifiers::ALT,
+        description,
+        ..key
+    })
+}
+
+fn interpret_ss3_sequence(bytes: &[u8]) -> Option<KeyInterpretation> {
+    if bytes.len() != 3 || bytes[0] != 0x1B || bytes[1] != b'O' {
+        return None;
+    }
+    let final_byte = bytes[2] as char;
+    let (code, key_display, description) = match final_byte {
+        'P' => (
+            KeyCode::F(1),
+            "F1".to_string(),
+            "SS3 function key".to_string(),
+        ),
+        'Q' => (
+            KeyCode::F(2),
+            "F2".to_string(),
+            "SS3 function key".to_string(),
+        ),
+        'R' => (
+            KeyCode::F(3),
+            "F3".to_string(),
+            "SS3 function key".to_string(),
+        ),
+        'S' => (
+            KeyCode::F(4),
+            "F4".to_string(),
+            "SS3 function key".to_string(),
+        ),
+        'A' => (KeyCode::Up, "Up".to_string(), "SS3 arrow key".to_string()),
+        'B' => (
+            KeyCode::Down,
+            "Down".to_string(),
+            "SS3 arrow key".to_string(),
+        ),
+        'C' => (
+            KeyCode::Right,
+            "Right".to_string(),
+            "SS3 arrow key".to_string(),
+        ),
+        'D' => (
+            KeyCode::Left,
+            "Left".to_string(),
+            "SS3 arrow key".to_string(),
+        ),
+        'H' => (
+            KeyCode::Home,
+            "Home".to_string(),
+            "SS3 home key".to_string(),
+        ),
+        'F' => (KeyCode::End, "End".to_string(), "SS3 end key".to_string()),
+        _ => return None,
+    };
+
+    Some(KeyInterpretation {
+        key_display,
+        code,
+        modifiers: KeyModifiers::empty(),
+        description,
+    })
+}
+
+fn interpret_csi_sequence(bytes: &[u8]) -> Option<KeyInterpretation> {
+    let (final_byte, params) = parse_csi(bytes)?;
+    match final_byte {
+        'A' => Some(build_arrow_guess("Up", KeyCode::Up, &params)),
+        'B' => Some(build_arrow_guess("Down", KeyCode::Down, &params)),
+        'C' => Some(build_arrow_guess("Right", KeyCode::Right, &params)),
+        'D' => Some(build_arrow_guess("Left", KeyCode::Left, &params)),
+        'F' => Some(build_arrow_guess("End", KeyCode::End, &params)),
+        'H' => Some(build_arrow_guess("Home", KeyCode::Home, &params)),
+        'Z' => {
+            let modifiers = KeyModifiers::SHIFT;
+            Some(KeyInterpretation {
+                key_display: "BackTab".to_string(),
+                code: KeyCode::BackTab,
+                modifiers,
+                description: "CSI BackTab sequence".to_string(),
+            })
+        }
+        '~' => interpret_csi_tilde(&params),
+        _ => None,
+    }
+}
+
+fn build_arrow_guess(name: &str, code: KeyCode, params: &[u16]) -> KeyInterpretation {
+    let (_, modifiers) = split_params_and_modifiers(params);
+    KeyInterpretation {
+        key_display: name.to_string(),
+        code,
+        modifiers,
+        description: "CSI arrow/navigation sequence".to_string(),
+    }
+}
+
+fn interpret_csi_tilde(params: &[u16]) -> Option<KeyInterpretation> {
+    let (base, modifiers) = split_params_and_modifiers(params);
+    let key_id = base.first().copied()?;
+    let (code, key_display, description) = match key_id {
+        1 | 7 => (
+            KeyCode::Home,
+            "Home".to_string(),
+            "CSI ~ (Home)".to_string(),
+        ),
+        2 => (
+            KeyCode::Insert,
+            "Insert".to_string(),
+            "CSI ~ (Insert)".to_string(),
+        ),
+        3 => (
+            KeyCode::Delete,
+            "Delete".to_string(),
+            "CSI ~ (Delete)".to_string(),
+        ),
+        4 | 8 => (KeyCode::End, "End".to_string(), "CSI ~ (End)".to_string()),
+        5 => (
+            KeyCode::PageUp,
+            "PageUp".to_string(),
+            "CSI ~ (PageUp)".to_string(),
+        ),
+        6 => (
+            KeyCode::PageDown,
+            "PageDown".to_string(),
+            "CSI ~ (PageDown)".to_string(),
+        ),
+        11 => (
+            KeyCode::F(1),
+            "F1".to_string(),
+            "CSI ~ function key".to_string(),
+        ),
+        12 => (
+            KeyCode::F(2),
+            "F2".to_string(),
+            "CSI ~ function key".to_string(),
+        ),
+        13 => (
+            KeyCode::F(3),
+            "F3".to_string(),
+            "CSI ~ function key".to_string(),
+        ),
+        14 => (
+            KeyCode::F(4),
+            "F4".to_string(),
+            "CSI ~ function key".to_string(),
+        ),
+        15 => (
+            KeyCode::F(5),
+            "F5".to_string(),
+            "CSI ~ function key".to_string(),
+        ),
+        17 => (
+            KeyCode::F(6),
+            "F6".to_string(),
+            "CSI ~ function key".to_string(),
+        ),
+        18 => (
+            KeyCode::F(7),
+            "F7".to_string(),
+            "CSI ~ function key".to_string(),
+        ),
+        19 => (
+            KeyCode::F(8),
+            "F8".to_string(),
+            "CSI ~ function key".to_string(),
+        ),
+        20 => (
+            KeyCode::F(9),
+            "F9".to_string(),
+            "CSI ~ function key".to_string(),
+        ),
+        21 => (
+            KeyCode::F(10),
+            "F10".to_string(),
+            "CSI ~ function key".to_string(),
+        ),
+        23 => (
+            KeyCode::F(11),
+            "F11".to_string(),
+            "CSI ~ function key".to_string(),
+        ),
+        24 => (
+            KeyCode::F(12),
+            "F12".to_string(),
+            "CSI ~ function key".to_string(),
+        ),
+        _ => return None,
+    };
+
+    Some(KeyInterpretation {
+        key_display,
+        code,
+        modifiers,
+        description,
+    })
+}
+
+fn parse_csi(bytes: &[u8]) -> Option<(char, Vec<u16>)> {
+    if bytes.len() < 3 || bytes[0] != 0x1B || bytes[1] != b'[' {
+        return None;
+    }
+    let final_byte = *bytes.last()?;
+    if !(0x40..=0x7E).contains(&final_byte) {
+        return None;
+    }
+    let mut params_bytes = &bytes[2..bytes.len() - 1];
+    while let Some(b'?') = params_bytes.first() {
+        params_bytes = &params_bytes[1..];
+    }
+
+    if params_bytes.is_empty() {
+        return Some((final_byte as char, Vec::new()));
+    }
+
+    let mut params = Vec::new();
+    for part in params_bytes.split(|&b| b == b';') {
+        if part.is_empty() {
+            continue;
+        }
+        if !part.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        // Out-of-range values saturate instead of overflowing or rejecting the whole sequence.
+        let value = part.iter().fold(0u16, |value, digit| {
+            value
+                .saturating_mul(10)
+                .saturating_add(u16::from(digit - b'0'))
+        });
+        params.push(value);
+    }
+
+    Some((final_byte as char, params))
+}
+
+fn split_params_and_modifiers(params: &[u16]) -> (Vec<u16>, KeyModifiers) {
+    if params.len() <= 1 {
+        return (params.to_vec(), KeyModifiers::empty());
+    }
+    let (base, modifier_part) = params.split_at(params.len() - 1);
+    let modifiers = decode_modifier_code(modifier_part[0]);
+    (base.to_vec(), modifiers)
+}
+
+fn decode_modifier_code(value: u16) -> KeyModifiers {
+    match value {
+        2 => KeyModifiers::SHIFT,
+        3 => KeyModifiers::ALT,
+        4 => KeyModifiers::SHIFT | KeyModifiers::ALT,
+        5 => KeyModifiers::CONTROL,
+        6 => KeyModifiers::SHIFT | KeyModifiers::CONTROL,
+        7 => KeyModifiers::ALT | KeyModifiers::CONTROL,
+        8 => KeyModifiers::SHIFT | KeyModifiers::ALT | KeyModifiers::CONTROL,
+        _ => KeyModifiers::empty(),
+    }
+}
+
+/// Modifier flags for display, `None` when empty.
+pub fn format_modifiers(modifiers: KeyModifiers) -> String {
+    if modifiers.is_empty() {
+        "None".to_string()
+    } else {
+        format!("{:?}", modifiers)
+    }
+}
+
+/// `bytes` with control characters and invalid UTF-8 spelled out as escapes.
+pub fn escape_bytes(bytes: &[u8]) -> String {
+    let mut output = String::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        match b {
+            b'\x1B' => {
+                output.push_str("\\x1B");
+                i += 1;
+            }
+            b'\n' => {
+                output.push_str("\\n");
+                i += 1;
+            }
+            b'\r' => {
+                output.push_str("\\r");
+                i += 1;
+            }
+            b'\t' => {
+                output.push_str("\\t");
+                i += 1;
+            }
+            0x20..=0x7E => {
+                output.push(b as char);
+                i += 1;
+            }
+            _ => {
+                let width = utf8_char_width(b);
+                if width > 1 && i + width <= bytes.len() {
+                    // C1 controls are valid UTF-8 but would be obeyed by the terminal.
+                    let text = std::str::from_utf8(&bytes[i..i + width]).ok();
+                    if let Some(slice) = text.filter(|t| !t.chars().any(char::is_control)) {
+                        output.push_str(slice);
+                        i += width;
+                        continue;
+                    }
+                }
+                output.push_str(&format!("\\x{:02X}", b));
+                i += 1;
+            }
+        }
+    }
+    output
+}
+
+/// A character quoted for display; control characters, C1 ones included, are escaped so they
+/// never reach the terminal raw.
+fn char_display(ch: char) -> String {
+    if ch.is_control() {
+        format!("{:?}", ch)
+    } else {
+        format!("'{}'", ch)
+    }
+}
+
+/// `bytes` as space-separated uppercase hex pairs.
+pub fn hex_string(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,83 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::Duration;
+
+/// How many recent samples the p95 is computed over.
+const P95_WINDOW: usize = 512;
+
+/// Running min/avg/max over every sample, plus a p95 over the most recent ones.
+#[derive(Debug, Clone, Default)]
+pub struct LatencyStats {
+    count: u64,
+    total: Duration,
+    min: Option<Duration>,
+    max: Duration,
+    recent: VecDeque<Duration>,
+}
+
+impl LatencyStats {
+    /// Add one measurement.
+    pub fn record(&mut self, sample: Duration) {
+        self.count += 1;
+        self.total += sample;
+        self.min = Some(self.min.map_or(sample, |min| min.min(sample)));
+        self.max = self.max.max(sample);
+        if self.recent.len() == P95_WINDOW {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(sample);
+    }
+
+    /// Number of samples recorded.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Smallest sample, if any were recorded.
+    pub fn min(&self) -> Option<Duration> {
+        self.min
+    }
+
+    /// Largest sample, if any were recorded.
+    pub fn max(&self) -> Option<Duration> {
+        (self.count > 0).then_some(self.max)
+    }
+
+    /// Mean of every sample, if any were recorded.
+    pub fn avg(&self) -> Option<Duration> {
+        (self.count > 0).then(|| self.total.div_f64(self.count as f64))
+    }
+
+    /// 95th percentile of the most recent samples, if any were recorded.
+    pub fn p95(&self) -> Option<Duration> {
+        if self.recent.is_empty() {
+            return None;
+        }
+        let mut sorted: Vec<Duration> = self.recent.iter().copied().collect();
+        sorted.sort_unstable();
+        let rank = (sorted.len() * 95).div_ceil(100);
+        Some(sorted[rank.saturating_sub(1)])
+    }
+}
+
+impl fmt::Display for LatencyStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.min(), self.avg(), self.p95(), self.max()) {
+            (Some(min), Some(avg), Some(p95), Some(max)) => write!(
+                f,
+                "min {:?} avg {:?} p95 {:?} max {:?} ({} events)",
+                min, avg, p95, max, self.count
+            ),
+            _ => write!(f, "no events"),
+        }
+    }
+}
+
+/// Time the reader itself adds between bytes arriving and events leaving it.
+#[derive(Debug, Clone, Default)]
+pub struct InputLatency {
+    /// From the poll wakeup whose read completed an event to that event being framed.
+    pub wakeup_to_frame: LatencyStats,
+    /// From an event's first byte arriving to the reader returning it, flush timeouts included.
+    pub first_byte_to_emit: LatencyStats,
+}
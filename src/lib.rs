@@ -1,26 +1,69 @@
 #![allow(dead_code)]
 
+#[cfg(all(unix, feature = "tokio"))]
+pub mod async_input;
+#[cfg(unix)]
+pub mod channel_input;
+pub mod clock;
+pub mod coalesce;
+pub mod export;
+pub mod interpret;
+pub mod latency;
+pub mod probe;
+pub mod raw_input;
+pub mod scripted_input;
+pub mod session;
+pub mod theme;
+pub mod throughput;
+#[cfg(unix)]
+pub mod vtime_input;
+
+use color_eyre::config::{HookBuilder, Theme};
 use color_eyre::Result;
 use crossterm::{
-    cursor,
-    event::{DisableMouseCapture, EnableMouseCapture},
-    execute,
+    cursor::{self, SetCursorStyle},
+    event::{
+        DisableBracketedPaste, DisableFocusChange, DisableMouseCapture, EnableBracketedPaste,
+        EnableFocusChange, EnableMouseCapture, KeyboardEnhancementFlags,
+        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
+    execute, queue,
+    style::Print,
     terminal::{
-        disable_raw_mode, enable_raw_mode, size, Clear, ClearType, EnterAlternateScreen,
-        LeaveAlternateScreen,
+        disable_raw_mode, enable_raw_mode, is_raw_mode_enabled, size,
+        supports_keyboard_enhancement, BeginSynchronizedUpdate, Clear, ClearType,
+        EndSynchronizedUpdate, EnterAlternateScreen, LeaveAlternateScreen, SetTitle,
     },
+    Command,
 };
-use eyre::WrapErr;
-use ratatui::{backend::CrosstermBackend, Terminal, TerminalOptions, Viewport};
-use std::io::{self, Write};
-use std::path::PathBuf;
-use tracing_appender::rolling;
-use tracing_subscriber::{self, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
-
-/// Selectable writer that can target stdout or stderr interchangeably.
+use eyre::{bail, WrapErr};
+use probe::{TerminalCapabilities, PROBE_TIMEOUT};
+#[cfg(feature = "test-backend")]
+use ratatui::backend::TestBackend;
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Rect, Size};
+use ratatui::{backend::CrosstermBackend, Frame, Terminal, TerminalOptions, Viewport};
+use std::fmt;
+use std::io::{self, IsTerminal, Write};
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex, Once, PoisonError};
+use std::time::{Duration, Instant};
+use theme::{DISABLE_THEME_NOTIFICATIONS, ENABLE_THEME_NOTIFICATIONS};
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::{
+    self, filter::LevelFilter, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer,
+    Registry,
+};
+
+/// Selectable writer that can target stdout, stderr, or a [`TuiAppBuilder::custom_writer`]
+/// interchangeably.
 pub enum TerminalWriter {
     Stdout(io::Stdout),
     Stderr(io::Stderr),
+    /// Shared by the backend and restore, so both write to the same place.
+    Custom(Arc<Mutex<Box<dyn Write + Send>>>),
 }
 
 impl TerminalWriter {
@@ -38,6 +81,7 @@ impl Write for TerminalWriter {
         match self {
             Self::Stdout(writer) => writer.write(buf),
             Self::Stderr(writer) => writer.write(buf),
+            Self::Custom(writer) => lock_writer(writer).write(buf),
         }
     }
 
@@ -45,12 +89,30 @@ impl Write for TerminalWriter {
         match self {
             Self::Stdout(writer) => writer.flush(),
             Self::Stderr(writer) => writer.flush(),
+            Self::Custom(writer) => lock_writer(writer).flush(),
         }
     }
 }
 
+/// A writer that panicked mid-write is still the only place the TUI can draw.
+fn lock_writer(
+    writer: &Mutex<Box<dyn Write + Send>>,
+) -> std::sync::MutexGuard<'_, Box<dyn Write + Send>> {
+    writer.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// The writer from [`TuiAppBuilder::custom_writer`], handed out as a [`TerminalWriter::Custom`].
+#[derive(Clone)]
+struct CustomWriter(Arc<Mutex<Box<dyn Write + Send>>>);
+
+impl fmt::Debug for CustomWriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CustomWriter")
+    }
+}
+
 /// Selects which stream the alternate screen backend should target.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AlternateScreenBackend {
     Stdout,
     Stderr,
@@ -63,6 +125,67 @@ impl AlternateScreenBackend {
             Self::Stderr => TerminalWriter::stderr(),
         }
     }
+
+    fn is_terminal(self) -> bool {
+        match self {
+            Self::Stdout => io::stdout().is_terminal(),
+            Self::Stderr => io::stderr().is_terminal(),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Stdout => "stdout",
+            Self::Stderr => "stderr",
+        }
+    }
+}
+
+/// What [`TuiApp::init`] does when the stream the TUI would draw on isn't a terminal, e.g.
+/// because stdout is piped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NonTtyBehavior {
+    /// Return an error before touching the terminal.
+    Error,
+    /// Draw on stderr instead, as fzf does; an error if stderr isn't a terminal either, or for
+    /// an inline viewport, which always draws on stdout.
+    FallbackToStderr,
+    /// Draw on the stream anyway.
+    #[default]
+    Proceed,
+}
+
+/// Which private mode switches to the alternate screen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AltScreenStyle {
+    /// `?1049h`: save the cursor and clear the alternate screen on entry, restoring the cursor
+    /// on exit. Supported nearly everywhere.
+    #[default]
+    Standard,
+    /// `?47h`: the original xterm switch. Neither clears nor saves the cursor, so the shell's
+    /// cursor ends up wherever the TUI left it; some terminals ignore it entirely.
+    Mode47,
+    /// `?1047h`: like `?47h`, but the alternate screen is cleared on exit. Not saving the cursor
+    /// has the same caveat, and fewer terminals implement it than `?1049h`.
+    Mode1047,
+}
+
+impl AltScreenStyle {
+    fn enter(self, out: &mut impl Write) -> io::Result<()> {
+        match self {
+            Self::Standard => execute!(out, EnterAlternateScreen),
+            Self::Mode47 => execute!(out, Print("\x1b[?47h")),
+            Self::Mode1047 => execute!(out, Print("\x1b[?1047h")),
+        }
+    }
+
+    fn leave(self, out: &mut impl Write) -> io::Result<()> {
+        match self {
+            Self::Standard => execute!(out, LeaveAlternateScreen),
+            Self::Mode47 => execute!(out, Print("\x1b[?47l")),
+            Self::Mode1047 => execute!(out, Print("\x1b[?1047l")),
+        }
+    }
 }
 
 /// Describes how the TUI consumes terminal real estate.
@@ -81,6 +204,14 @@ impl Default for ViewportMode {
 }
 
 impl ViewportMode {
+    /// The stream the viewport draws on.
+    fn stream(self) -> AlternateScreenBackend {
+        match self {
+            Self::Inline { .. } => AlternateScreenBackend::Stdout,
+            Self::AlternateScreen { backend } => backend,
+        }
+    }
+
     fn is_inline(self) -> bool {
         matches!(self, Self::Inline { .. })
     }
@@ -92,320 +223,2544 @@ impl ViewportMode {
         }
     }
 
+    /// This viewport with an inline height no taller than `rows`.
+    fn fit_to(self, rows: u16) -> Self {
+        match self {
+            Self::Inline { height } => Self::Inline {
+                height: height.min(rows),
+            },
+            Self::AlternateScreen { .. } => self,
+        }
+    }
+
     fn writer(self) -> TerminalWriter {
         match self {
             Self::Inline { .. } => TerminalWriter::stdout(),
             Self::AlternateScreen { backend } => backend.into_writer(),
         }
     }
-}
 
-/// Logger guard
-struct LoggerGuard {
-    _guard: tracing_appender::non_blocking::WorkerGuard,
+    /// The stream the viewport does not draw on, for output that must outlive the TUI.
+    fn report_writer(self) -> TerminalWriter {
+        match self {
+            Self::AlternateScreen {
+                backend: AlternateScreenBackend::Stderr,
+            } => TerminalWriter::stdout(),
+            _ => TerminalWriter::stderr(),
+        }
+    }
 }
 
-/// Initialize logger
-fn init_file_logger(app_name: &str) -> Result<LoggerGuard> {
-    let log_dir = get_log_directory(app_name);
-
-    std::fs::create_dir_all(&log_dir).wrap_err("Failed to create log directory")?;
+/// What restoring the terminal does with the rows an inline viewport drew on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InlineRestorePolicy {
+    /// Clear the viewport's rows and leave the cursor at their top.
+    #[default]
+    ClearViewport,
+    /// Keep the last frame on screen and continue on a fresh line below it.
+    LeaveUntouched,
+    /// Clear only the bottom N rows of the screen, leaving the cursor at their top.
+    /// `ClearBottom(0)` clears nothing and acts like [`InlineRestorePolicy::LeaveUntouched`].
+    ClearBottom(u16),
+    /// Clear the whole screen and home the cursor.
+    ClearAll,
+}
 
-    let log_file = rolling::daily(&log_dir, "logs");
-    let (non_blocking_log_file, guard) = tracing_appender::non_blocking(log_file);
+/// Where restoring leaves the cursor for the shell, once the screen is otherwise restored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CursorRestore {
+    /// Wherever leaving the alternate screen or the [`InlineRestorePolicy`] puts it: below the
+    /// rows an inline viewport keeps, or at the top of those it clears.
+    #[default]
+    BelowViewport,
+    /// Back where it was before init, saved with DECSC. Rows scrolled since then move the
+    /// position along with the screen, not with the text. Not applied over a frame kept by
+    /// [`InlineRestorePolicy::LeaveUntouched`], where the shell would print on top of it.
+    SavedPosition,
+    /// At the start of the screen's last row.
+    Column0LastRow,
+}
 
-    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+/// A terminating signal [`TuiAppBuilder::restore_on_signals`] can restore the terminal for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    /// SIGINT, e.g. Ctrl-C outside raw mode.
+    Interrupt,
+    /// SIGTERM, e.g. from a supervisor.
+    Terminate,
+    /// SIGHUP, when the terminal goes away.
+    Hangup,
+    /// SIGQUIT, e.g. Ctrl-\\ outside raw mode.
+    Quit,
+}
 
-    let file_layer = tracing_subscriber::fmt::layer()
-        .with_writer(non_blocking_log_file)
-        .with_ansi(false)
-        .with_thread_ids(true)
-        .with_thread_names(true)
-        .with_file(true)
-        .with_line_number(true)
-        .with_target(true);
+#[cfg(unix)]
+impl Signal {
+    fn number(self) -> libc::c_int {
+        match self {
+            Self::Interrupt => libc::SIGINT,
+            Self::Terminate => libc::SIGTERM,
+            Self::Hangup => libc::SIGHUP,
+            Self::Quit => libc::SIGQUIT,
+        }
+    }
+}
 
-    tracing_subscriber::registry()
-        .with(filter)
-        .with(file_layer)
-        .try_init()
-        .wrap_err("Failed to initialize tracing subscriber")?;
+/// What [`TuiApp::init`] clears before the first draw.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ClearOnInit {
+    /// Clear the whole alternate screen, or everything from the inline viewport's top down.
+    #[default]
+    Always,
+    /// Emit nothing; the first draw paints every cell of the viewport, blanks included, so it
+    /// can't blend with what was on screen.
+    Never,
+    /// Clear only the inline viewport's own rows; the same as `Always` on the alternate screen.
+    ViewportOnly,
+}
 
-    tracing::debug!("Logger initialized to: {}", log_dir.display());
-    Ok(LoggerGuard { _guard: guard })
+/// What [`TuiApp::init`] does when the terminal is smaller than [`TuiAppBuilder::min_size`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MinSizeBehavior {
+    /// Return an error before touching the terminal.
+    #[default]
+    Fail,
+    /// Ask the user to enlarge the terminal and carry on once they have.
+    Wait,
 }
 
-fn get_log_directory(app_name: &str) -> PathBuf {
-    let env_var = format!("{}_LOG_DIR", app_name.to_ascii_uppercase());
+/// How often [`MinSizeBehavior::Wait`] checks the terminal size again.
+const MIN_SIZE_POLL: std::time::Duration = std::time::Duration::from_millis(100);
 
-    if let Ok(dir) = std::env::var(&env_var) {
-        PathBuf::from(dir)
-    } else if let Some(home) = dirs::home_dir() {
-        home.join(format!(".{}", app_name)).join("logs")
-    } else {
-        PathBuf::from("/tmp").join(app_name)
+/// Formats the message printed after restore when the app exits on an error or panic.
+#[derive(Clone)]
+struct ErrorBanner(Arc<dyn Fn(&str) -> String + Send + Sync>);
+
+impl fmt::Debug for ErrorBanner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ErrorBanner(..)")
     }
 }
 
-fn init_terminal(
-    viewport_mode: ViewportMode,
-    use_panic_terminal_restore: bool,
-    capture_mouse: bool,
-    hide_cursor: bool,
-) -> Result<Terminal<CrosstermBackend<TerminalWriter>>> {
-    tracing::debug!("Initializing terminal");
-
-    enable_raw_mode().wrap_err("Failed to enable raw mode")?;
+/// Formats the report the panic hook prints once the terminal is restored.
+#[derive(Clone)]
+struct PanicReport(Arc<dyn Fn(&std::panic::PanicHookInfo<'_>) -> String + Send + Sync>);
 
-    let mut terminal_output = viewport_mode.writer();
-    if capture_mouse {
-        execute!(terminal_output, EnableMouseCapture).wrap_err("Failed to enable mouse capture")?;
+impl fmt::Debug for PanicReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PanicReport(..)")
     }
+}
 
-    if !viewport_mode.is_inline() {
-        tracing::debug!("Entering alternate screen mode");
-        execute!(terminal_output, EnterAlternateScreen)
-            .wrap_err("Failed to enter alternate screen")?;
-    } else {
-        if let Some(height) = viewport_mode.inline_height() {
-            tracing::debug!("Using inline mode with height: {}", height);
-        }
+impl PanicReport {
+    /// The message and location, plus where the logs are when there is a log file.
+    fn default_for(app_name: &str, log_dir: Option<&Path>) -> Self {
+        let app_name = app_name.to_string();
+        let log_dir = log_dir.map(Path::to_path_buf);
+        Self(Arc::new(move |panic_info| {
+            let mut report = format!("{app_name} panicked");
+            if let Some(location) = panic_info.location() {
+                report.push_str(&format!(" at {location}"));
+            }
+            report.push_str(&format!(":\n  {}", panic_message(panic_info)));
+            if let Some(dir) = &log_dir {
+                report.push_str(&format!("\nLogs are in {}", dir.display()));
+            }
+            report
+        }))
     }
+}
 
-    // Set up panic hook
-    if use_panic_terminal_restore {
-        let panic_viewport = viewport_mode;
-        let hook = std::panic::take_hook();
-        std::panic::set_hook(Box::new(move |panic_info| {
-            // We've already panicked so ignore any err
-            let _ = restore_terminal(capture_mouse, hide_cursor, panic_viewport);
-            hook(panic_info);
-        }));
-    }
+/// What the panic hook installed by [`TuiApp::init`] needs beyond the terminal modes.
+struct PanicHook {
+    lifecycle: Lifecycle,
+    error_banner: Option<ErrorBanner>,
+    report: PanicReport,
+}
 
-    let backend = CrosstermBackend::new(terminal_output);
+/// The active app's [`PanicHook`] and what it restores.
+struct PanicSession {
+    hook: PanicHook,
+    modes: TerminalModes,
+    viewport: ViewportMode,
+    inline_restore: InlineRestorePolicy,
+}
 
-    let viewport = match viewport_mode {
-        ViewportMode::Inline { height } => Viewport::Inline(height),
-        ViewportMode::AlternateScreen { .. } => Viewport::Fullscreen,
-    };
+/// Set by init and cleared by restore, so repeated sessions share one installed panic hook.
+static PANIC_SESSION: Mutex<Option<PanicSession>> = Mutex::new(None);
+static PANIC_HOOK: Once = Once::new();
+static COLOR_EYRE: Once = Once::new();
 
-    let mut terminal = ratatui::Terminal::with_options(backend, TerminalOptions { viewport })
-        .wrap_err("Failed to create terminal")?;
+fn panic_session() -> std::sync::MutexGuard<'static, Option<PanicSession>> {
+    PANIC_SESSION.lock().unwrap_or_else(PoisonError::into_inner)
+}
 
-    terminal.clear().wrap_err("Failed to clear terminal")?;
-    if hide_cursor {
-        terminal.hide_cursor().wrap_err("Failed to hide cursor")?;
+/// Print `text` on its own line(s) to `out`, where the viewport draws.
+fn print_banner(mut out: TerminalWriter, text: &str) -> io::Result<()> {
+    out.write_all(text.as_bytes())?;
+    if !text.ends_with('\n') {
+        out.write_all(b"\n")?;
     }
+    out.flush()
+}
 
-    tracing::debug!("Terminal initialized successfully");
-    Ok(terminal)
+/// The message a panic was raised with, if it was a string.
+fn panic_message(panic_info: &std::panic::PanicHookInfo<'_>) -> String {
+    let payload = panic_info.payload();
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "panic".to_string())
 }
 
-fn restore_terminal(
-    capture_mouse: bool,
-    hide_cursor: bool,
-    viewport_mode: ViewportMode,
-) -> io::Result<()> {
-    tracing::debug!("Restoring terminal");
+/// Install color-eyre's error and panic hooks the first time any app asks to; later inits keep
+/// those hooks, as does a host application that installed its own, with a warning.
+fn install_color_eyre(theme: Option<Theme>) {
+    COLOR_EYRE.call_once(|| try_install_color_eyre(theme));
+}
 
-    if let Err(e) = disable_raw_mode() {
-        tracing::error!("Failed to disable raw mode during restore: {}", e);
+fn try_install_color_eyre(theme: Option<Theme>) {
+    let mut builder = HookBuilder::default();
+    if let Some(theme) = theme {
+        builder = builder.theme(theme);
     }
+    // Both steps only fail when an earlier install already set the same global.
+    let installed = builder
+        .try_into_hooks()
+        .and_then(|(panic_hook, eyre_hook)| {
+            eyre_hook.install()?;
+            panic_hook.install();
+            Ok(())
+        });
+    if let Err(e) = installed {
+        tracing::warn!("Keeping the existing color-eyre hooks: {:#}", e);
+    }
+}
 
-    let mut stdout = io::stdout();
+/// Logger guard, plus where the log files go.
+struct LoggerGuard {
+    _guard: tracing_appender::non_blocking::WorkerGuard,
+    dir: PathBuf,
+    path: PathBuf,
+    filter: String,
+}
 
-    if capture_mouse {
-        if let Err(e) = execute!(stdout, DisableMouseCapture) {
-            tracing::error!("Failed to disable mouse capture during restore: {}", e);
-        }
-    }
+/// A type-erased tracing layer over the base registry.
+pub type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
 
-    if !viewport_mode.is_inline() {
-        execute!(stdout, LeaveAlternateScreen)?;
-    } else {
-        if let Some(height) = viewport_mode.inline_height() {
-            if let Ok((_cols, rows)) = size() {
-                execute!(
-                    stdout,
-                    cursor::MoveTo(0, rows.saturating_sub(height)),
-                    Clear(ClearType::FromCursorDown),
-                )?;
-            }
-        }
-    }
+/// Installs a caller-built subscriber around the crate's log layers.
+pub type InstallSubscriber = dyn Fn(Option<BoxedLayer>) -> Result<()> + Send + Sync;
 
-    if hide_cursor {
-        execute!(stdout, cursor::Show)?;
-    }
-    stdout.flush()?;
+/// How [`TuiApp::init`] sets up the global tracing subscriber.
+#[derive(Clone, Default)]
+pub enum TracingConfig {
+    /// Install a registry with the crate's log layers, unless a global subscriber is already
+    /// set; then log through that one and skip the log file. Use [`TracingConfig::Custom`] to
+    /// add the crate's layers to a subscriber of your own instead.
+    #[default]
+    Default,
+    /// Install nothing and skip the log file, e.g. when the host application owns tracing.
+    Disabled,
+    /// Install the subscriber yourself. The closure gets the file and stderr layers, already
+    /// filtered and combined, to add alongside its own layers, or `None` when both are off.
+    /// An error from the closure fails [`TuiApp::init`].
+    Custom(Arc<InstallSubscriber>),
+}
 
-    tracing::debug!("Terminal restore completed");
-    Ok(())
+impl fmt::Debug for TracingConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Default => f.write_str("Default"),
+            Self::Disabled => f.write_str("Disabled"),
+            Self::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
 }
 
-/// Coordinates color-eyre, logging, and terminal lifecycle for the TUI.
-#[derive(Debug, Clone)]
-pub struct TuiAppBuilder {
-    app_name: String,
-    use_panic_terminal_restore: bool,
-    use_color_eyre: bool,
-    use_disk_logs: bool,
-    capture_mouse: bool,
-    hide_cursor: bool,
-    viewport: ViewportMode,
+/// Formatting and verbosity of the stderr log layer enabled by
+/// [`TuiAppBuilder::log_to_stderr`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StderrLog {
+    /// Color the output with ANSI escapes.
+    pub ansi: bool,
+    /// Prefix each line with the emitting thread's id.
+    pub thread_ids: bool,
+    /// Include the source file and line of each event.
+    pub file_and_line: bool,
+    /// Least severe level written to stderr, independent of `RUST_LOG`.
+    pub level: tracing::Level,
 }
 
-impl Default for TuiAppBuilder {
+impl Default for StderrLog {
     fn default() -> Self {
         Self {
-            app_name: String::new(),
-            use_panic_terminal_restore: true,
-            use_color_eyre: true,
-            use_disk_logs: true,
-            capture_mouse: true,
-            hide_cursor: true,
-            viewport: ViewportMode::default(),
+            ansi: true,
+            thread_ids: false,
+            file_and_line: false,
+            level: tracing::Level::WARN,
         }
     }
 }
 
-impl TuiAppBuilder {
-    pub fn new(app_name: impl Into<String>) -> Self {
-        Self {
-            app_name: app_name.into(),
-            ..Self::default()
-        }
+/// Where the default log directory goes when neither `{APP}_LOG_DIR` nor
+/// [`TuiAppBuilder::log_dir`] says otherwise.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogLocation {
+    /// The platform's convention: `$XDG_STATE_HOME/{app}/logs` (or the data directory without
+    /// one) on Linux, `~/Library/Logs/{app}` on macOS, and `%LOCALAPPDATA%\{app}\logs` on
+    /// Windows.
+    #[default]
+    Platform,
+    /// `~/.{app}/logs`, where logs went before the platform conventions were followed.
+    Dotfile,
+}
+
+/// The environment [`log_directory`] looks in, so it can be resolved against a fake one.
+pub trait LogEnvironment {
+    fn var(&self, key: &str) -> Option<String>;
+    fn home_dir(&self) -> Option<PathBuf>;
+    /// Per-user state directory, where the platform has one.
+    fn state_dir(&self) -> Option<PathBuf>;
+    /// Per-user data directory that isn't synced between machines.
+    fn data_local_dir(&self) -> Option<PathBuf>;
+}
+
+/// The process environment and the [`dirs`] crate's platform directories.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemLogEnvironment;
+
+impl LogEnvironment for SystemLogEnvironment {
+    fn var(&self, key: &str) -> Option<String> {
+        std::env::var(key).ok()
     }
 
-    pub fn app_name(mut self, app_name: impl Into<String>) -> Self {
-        self.app_name = app_name.into();
-        self
+    fn home_dir(&self) -> Option<PathBuf> {
+        dirs::home_dir()
     }
 
-    pub fn capture_mouse(mut self, capture_mouse: bool) -> Self {
-        self.capture_mouse = capture_mouse;
-        self
+    fn state_dir(&self) -> Option<PathBuf> {
+        dirs::state_dir()
     }
 
-    pub fn hide_cursor(mut self, hide_cursor: bool) -> Self {
-        self.hide_cursor = hide_cursor;
-        self
+    fn data_local_dir(&self) -> Option<PathBuf> {
+        dirs::data_local_dir()
     }
+}
 
-    pub fn inline(mut self, height: u16) -> Self {
-        self.viewport = ViewportMode::Inline { height };
-        self
+/// When the log file rolls over to a new one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogRotation {
+    /// A file per day, named `{prefix}.YYYY-MM-DD`.
+    #[default]
+    Daily,
+    /// A file per hour, named `{prefix}.YYYY-MM-DD-HH`.
+    Hourly,
+    /// A single file named `{prefix}`, appended to across runs.
+    Never,
+}
+
+impl LogRotation {
+    fn rotation(self) -> Rotation {
+        match self {
+            Self::Daily => Rotation::DAILY,
+            Self::Hourly => Rotation::HOURLY,
+            Self::Never => Rotation::NEVER,
+        }
     }
+}
 
-    pub fn alternate_screen(mut self) -> Self {
-        self.viewport = ViewportMode::AlternateScreen {
-            backend: AlternateScreenBackend::Stdout,
-        };
-        self
+/// What [`init_logging`] sets up.
+struct LoggingConfig<'a> {
+    app_name: &'a str,
+    use_disk_logs: bool,
+    log_dir: Option<&'a Path>,
+    log_location: LogLocation,
+    log_file_prefix: &'a str,
+    log_rotation: LogRotation,
+    log_keep: Option<usize>,
+    log_filter: &'a str,
+    stderr: Option<StderrLog>,
+    tracing: &'a TracingConfig,
+}
+
+/// Initialize logging, returning the file appender's guard when there is a log file.
+///
+/// An already-installed global subscriber is kept, with a warning, rather than failing.
+fn init_logging(
+    config: &LoggingConfig<'_>,
+    timings: &mut LifecycleTimings,
+) -> Result<Option<LoggerGuard>> {
+    if matches!(config.tracing, TracingConfig::Disabled) {
+        return Ok(None);
+    }
+    if matches!(config.tracing, TracingConfig::Default) && tracing::dispatcher::has_been_set() {
+        tracing::info!(
+            "Logging {} through the existing tracing subscriber; no log file",
+            config.app_name
+        );
+        return Ok(None);
     }
 
-    pub fn alternate_screen_backend(mut self, backend: AlternateScreenBackend) -> Self {
-        self.viewport = ViewportMode::AlternateScreen { backend };
-        self
+    let mut layers: Vec<BoxedLayer> = Vec::new();
+    let mut guard = None;
+    if config.use_disk_logs {
+        let phase = LifecycleTimings::start("log_file");
+        let dir = log_directory(
+            config.app_name,
+            config.log_dir,
+            config.log_location,
+            &SystemLogEnvironment,
+        );
+        std::fs::create_dir_all(&dir)
+            .wrap_err_with(|| format!("Failed to create log directory {}", dir.display()))?;
+        let log_file = RollingFileAppender::builder()
+            .rotation(config.log_rotation.rotation())
+            .filename_prefix(config.log_file_prefix)
+            .build(&dir)
+            .wrap_err("Failed to open log file")?;
+        let path = prune_log_files(
+            &dir,
+            config.log_file_prefix,
+            config.log_rotation,
+            config.log_keep,
+        )
+        .wrap_err("Failed to prune old log files")?;
+        let (non_blocking_log_file, file_guard) = tracing_appender::non_blocking(log_file);
+        let filter = file_log_filter(config.log_filter)?;
+        let directives = filter.to_string();
+        let file_layer = tracing_subscriber::fmt::layer()
+            .with_writer(non_blocking_log_file)
+            .with_ansi(false)
+            .with_thread_ids(true)
+            .with_thread_names(true)
+            .with_file(true)
+            .with_line_number(true)
+            .with_target(true)
+            .with_filter(filter)
+            .boxed();
+        layers.push(file_layer);
+        guard = Some(LoggerGuard {
+            _guard: file_guard,
+            dir,
+            path,
+            filter: directives,
+        });
+        timings.end(phase);
     }
+    if let Some(stderr) = config.stderr {
+        let stderr_layer = tracing_subscriber::fmt::layer()
+            .with_writer(io::stderr)
+            .with_ansi(stderr.ansi)
+            .with_thread_ids(stderr.thread_ids)
+            .with_file(stderr.file_and_line)
+            .with_line_number(stderr.file_and_line)
+            .with_filter(LevelFilter::from_level(stderr.level))
+            .boxed();
+        layers.push(stderr_layer);
+    }
+    let layer = (!layers.is_empty()).then(|| layers.boxed());
 
-    pub fn use_panic_terminal_restore(mut self, use_panic_terminal_restore: bool) -> Self {
-        self.use_panic_terminal_restore = use_panic_terminal_restore;
-        self
+    let phase = LifecycleTimings::start("subscriber");
+    let installed = match config.tracing {
+        TracingConfig::Default => match layer {
+            Some(layer) => tracing_subscriber::registry()
+                .with(layer)
+                .try_init()
+                .wrap_err("Failed to initialize tracing subscriber"),
+            None => Ok(()),
+        },
+        TracingConfig::Custom(install) => {
+            install(layer).wrap_err("The custom tracing subscriber failed to install")
+        }
+        TracingConfig::Disabled => Ok(()),
+    };
+    timings.end(phase);
+    match installed {
+        // Only a race with another default install gets here; its subscriber serves as well.
+        Err(e) if matches!(config.tracing, TracingConfig::Default) => {
+            tracing::warn!("Keeping the existing tracing subscriber: {:#}", e);
+        }
+        installed => installed?,
     }
 
-    pub fn use_color_eyre(mut self, use_color_eyre: bool) -> Self {
-        self.use_color_eyre = use_color_eyre;
-        self
+    if let Some(guard) = &guard {
+        tracing::debug!("Logger initialized to: {}", guard.path.display());
+        tracing::info!("Log filter: {}", guard.filter);
     }
+    Ok(guard)
+}
 
-    pub fn use_disk_logs(mut self, use_disk_logs: bool) -> Self {
-        self.use_disk_logs = use_disk_logs;
-        self
+/// `RUST_LOG` when it is set and valid, otherwise `default`.
+fn file_log_filter(default: &str) -> Result<EnvFilter> {
+    if let Ok(filter) = EnvFilter::try_from_default_env() {
+        return Ok(filter);
     }
+    EnvFilter::try_new(default).wrap_err_with(|| format!("Invalid log filter {default:?}"))
+}
 
-    pub fn build(self) -> TuiApp {
-        let app_name = if self.app_name.is_empty() {
-            env!("CARGO_PKG_NAME").to_string()
-        } else {
-            self.app_name
-        };
+/// Delete all but the newest `keep` of `rotation`'s log files in `dir`, returning the current
+/// one.
+///
+/// Rotated file names end in their date, so they sort oldest first. Files another rotation
+/// left behind have dates of a different length and are kept, as is the one file that never
+/// rotates.
+fn prune_log_files(
+    dir: &Path,
+    prefix: &str,
+    rotation: LogRotation,
+    keep: Option<usize>,
+) -> io::Result<PathBuf> {
+    let date_len = match rotation {
+        LogRotation::Daily => "YYYY-MM-DD".len(),
+        LogRotation::Hourly => "YYYY-MM-DD-HH".len(),
+        LogRotation::Never => return Ok(dir.join(prefix)),
+    };
+    let dated_prefix = format!("{prefix}.");
+    let mut rotated = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let name = entry?.file_name().to_string_lossy().into_owned();
+        if name
+            .strip_prefix(&dated_prefix)
+            .is_some_and(|date| date.len() == date_len)
+        {
+            rotated.push(name);
+        }
+    }
+    rotated.sort();
 
-        TuiApp {
-            logger_guard: None,
-            app_name,
-            use_panic_terminal_restore: self.use_panic_terminal_restore,
-            use_color_eyre: self.use_color_eyre,
-            use_disk_logs: self.use_disk_logs,
-            capture_mouse: self.capture_mouse,
-            hide_cursor: self.hide_cursor,
-            viewport: self.viewport,
+    let current = rotated.pop().unwrap_or_default();
+    if let Some(keep) = keep {
+        let old = rotated.len().saturating_sub(keep.saturating_sub(1));
+        for name in &rotated[..old] {
+            std::fs::remove_file(dir.join(name))?;
         }
     }
+    Ok(dir.join(current))
 }
 
-pub struct TuiApp {
-    logger_guard: Option<LoggerGuard>,
-    app_name: String,
-    use_panic_terminal_restore: bool,
-    use_color_eyre: bool,
-    use_disk_logs: bool,
+/// The [`log_dir_env_var`] variable, then the builder's directory, then the default for
+/// `location`, falling back to the temp directory when `env` has no home for it.
+pub fn log_directory(
+    app_name: &str,
+    configured: Option<&Path>,
+    location: LogLocation,
+    env: &impl LogEnvironment,
+) -> PathBuf {
+    if let Some(dir) = env.var(&log_dir_env_var(app_name)) {
+        return PathBuf::from(dir);
+    }
+    if let Some(dir) = configured {
+        return dir.to_path_buf();
+    }
+
+    let app = path_safe_app_name(app_name);
+    let dir = match location {
+        LogLocation::Dotfile => env
+            .home_dir()
+            .map(|home| home.join(format!(".{}", app)).join("logs")),
+        LogLocation::Platform if cfg!(target_os = "macos") => env
+            .home_dir()
+            .map(|home| home.join("Library").join("Logs").join(&app)),
+        LogLocation::Platform => env
+            .state_dir()
+            .or_else(|| env.data_local_dir())
+            .map(|dir| dir.join(&app).join("logs")),
+    };
+    dir.unwrap_or_else(|| std::env::temp_dir().join(app))
+}
+
+/// The environment variable that overrides `app_name`'s log directory: `{APP}_LOG_DIR`, where
+/// `{APP}` is the name uppercased with every character other than an ASCII letter or digit
+/// replaced by `_`, so "my-cool tool" gives `MY_COOL_TOOL_LOG_DIR`.
+pub fn log_dir_env_var(app_name: &str) -> String {
+    let app: String = app_name
+        .chars()
+        .map(|c| match c {
+            c if c.is_ascii_alphanumeric() => c.to_ascii_uppercase(),
+            _ => '_',
+        })
+        .collect();
+    format!("{}_LOG_DIR", app)
+}
+
+/// `app_name` with every character that can't appear in a file name on some platform replaced,
+/// so it names exactly one directory.
+fn path_safe_app_name(app_name: &str) -> String {
+    if app_name.is_empty() || app_name.chars().all(|c| c == '.') {
+        return "_".repeat(app_name.len().max(1));
+    }
+    app_name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | '<' | '>' | ':' | '"' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect()
+}
+
+/// Terminal modes the TUI turns on at init and restore turns back off.
+#[derive(Debug, Clone)]
+struct TerminalModes {
+    raw_mode: bool,
     capture_mouse: bool,
     hide_cursor: bool,
-    viewport: ViewportMode,
+    bracketed_paste: bool,
+    focus_events: bool,
+    theme_notifications: bool,
+    keyboard_enhancement: Option<KeyboardEnhancementFlags>,
+    title: Option<String>,
+    cursor_style: Option<CursorStyle>,
+    synchronized_output: bool,
+    cursor_restore: CursorRestore,
+    alternate_screen: bool,
+    alt_screen_style: AltScreenStyle,
+    /// ANSI for [`TuiAppBuilder::extra_init_command`] and
+    /// [`TuiAppBuilder::extra_restore_command`].
+    init_commands: Vec<String>,
+    restore_commands: Vec<String>,
+    custom_writer: Option<CustomWriter>,
 }
 
-impl TuiApp {
-    // TODO customization points:
-    //
-    // Terminal Lifecycle
-    //
-    // - Inline mode currently forces stdout. Re-evaluate whether to support directing inline output
-    //   elsewhere without breaking existing guarantees.
-    // - tui_core.rs:128-137 hard-codes clearing the inline viewport on restore; provide options
-    //   for inline mode restore policies such as “leave inline buffer untouched”, “clear bottom N
-    //   lines”, or “always clear everything”
-    // - The user should be able to specify welcome, goodbye, and error banners that are printed
-    //   in those respective situations, either entirely before or entirely after all the remaining
-    //   terminal lifecycle management.
-    pub fn builder(app_name: impl Into<String>) -> TuiAppBuilder {
-        TuiAppBuilder::new(app_name)
+impl TerminalModes {
+    /// Where the TUI draws: the custom writer if there is one, else `viewport_mode`'s stream.
+    fn writer(&self, viewport_mode: ViewportMode) -> TerminalWriter {
+        match &self.custom_writer {
+            Some(CustomWriter(writer)) => TerminalWriter::Custom(writer.clone()),
+            None => viewport_mode.writer(),
+        }
     }
+}
 
-    /// Construct a new application harness with default settings.
-    pub fn new(app_name: impl Into<String>) -> Self {
-        Self::builder(app_name).build()
-    }
+/// The ANSI `command` writes.
+fn ansi(command: &impl Command) -> String {
+    let mut ansi = String::new();
+    // Writing to a String can't fail, and a command that fails to format writes nothing useful.
+    let _ = command.write_ansi(&mut ansi);
+    ansi
+}
 
-    /// Access the application name currently configured for the TUI.
-    pub fn app_name(&self) -> &str {
-        &self.app_name
+/// Cursor shape for the TUI to use while it runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorStyle {
+    BlinkingBlock,
+    SteadyBlock,
+    BlinkingUnderline,
+    SteadyUnderline,
+    BlinkingBar,
+    SteadyBar,
+}
+
+impl CursorStyle {
+    fn command(self) -> SetCursorStyle {
+        match self {
+            Self::BlinkingBlock => SetCursorStyle::BlinkingBlock,
+            Self::SteadyBlock => SetCursorStyle::SteadyBlock,
+            Self::BlinkingUnderline => SetCursorStyle::BlinkingUnderScore,
+            Self::SteadyUnderline => SetCursorStyle::SteadyUnderScore,
+            Self::BlinkingBar => SetCursorStyle::BlinkingBar,
+            Self::SteadyBar => SetCursorStyle::SteadyBar,
+        }
+    }
+}
+
+/// Where a [`TuiApp`] is in its init/restore lifecycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LifecycleState {
+    /// [`TuiApp::init`] has not been called yet.
+    NotStarted,
+    /// The terminal is set up for the TUI and will be restored once.
+    Active,
+    /// The terminal has been restored, or is handed back to the shell while suspended.
+    Restored,
+}
+
+/// How long each phase of the last [`TuiApp::init`] took, followed by those of the restore after
+/// it, in the order they ran. Phases that were skipped are missing, as is an init phase that
+/// failed.
+///
+/// Each phase is also a `lifecycle` tracing span, ending with a debug event for its duration.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LifecycleTimings {
+    phases: Vec<(&'static str, Duration)>,
+}
+
+impl LifecycleTimings {
+    /// How long `phase` took, e.g. `"raw_mode"`, `"alternate_screen"` or `"restore_screen"`.
+    pub fn get(&self, phase: &str) -> Option<Duration> {
+        self.phases
+            .iter()
+            .find(|(name, _)| *name == phase)
+            .map(|&(_, duration)| duration)
+    }
+
+    /// Each phase and how long it took, in the order they ran.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, Duration)> + '_ {
+        self.phases.iter().copied()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.phases.is_empty()
+    }
+
+    /// Start timing `name` inside its span, until [`LifecycleTimings::end`] records it.
+    fn start(name: &'static str) -> Phase {
+        Phase {
+            name,
+            started: Instant::now(),
+            _span: tracing::debug_span!("lifecycle", phase = name).entered(),
+        }
+    }
+
+    fn end(&mut self, phase: Phase) {
+        let elapsed = phase.started.elapsed();
+        tracing::debug!("Phase {} took {:?}", phase.name, elapsed);
+        self.phases.push((phase.name, elapsed));
+    }
+}
+
+impl fmt::Display for LifecycleTimings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (name, duration)) in self.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{} {:?}", name, duration)?;
+        }
+        Ok(())
+    }
+}
+
+/// A phase of [`LifecycleTimings`] being timed.
+struct Phase {
+    name: &'static str,
+    started: Instant,
+    _span: tracing::span::EnteredSpan,
+}
+
+/// A [`LifecycleState`] shared with the panic hook.
+#[derive(Debug, Clone)]
+struct Lifecycle(Arc<AtomicU8>);
+
+impl Lifecycle {
+    fn new(state: LifecycleState) -> Self {
+        Self(Arc::new(AtomicU8::new(state as u8)))
+    }
+
+    /// Whether `other` tracks the same app session.
+    fn is(&self, other: &Lifecycle) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+
+    fn get(&self) -> LifecycleState {
+        match self.0.load(Ordering::SeqCst) {
+            0 => LifecycleState::NotStarted,
+            1 => LifecycleState::Active,
+            _ => LifecycleState::Restored,
+        }
+    }
+
+    fn set(&self, state: LifecycleState) {
+        self.0.store(state as u8, Ordering::SeqCst);
+    }
+
+    /// Move from `Active` to `Restored`, returning whether this call did, i.e. should restore.
+    fn finish(&self) -> bool {
+        let (active, restored) = (LifecycleState::Active as u8, LifecycleState::Restored as u8);
+        self.0
+            .compare_exchange(active, restored, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+    }
+}
+
+/// Pushes the current window title onto the terminal's title stack (xterm `XTWINOPS` 22).
+const PUSH_TITLE: &str = "\x1b[22;0t";
+/// Pops the title saved by [`PUSH_TITLE`] back off the stack.
+const POP_TITLE: &str = "\x1b[23;0t";
+
+/// Set up the terminal with the requested `modes`, also returning the ones actually enabled.
+fn init_terminal(
+    viewport_mode: ViewportMode,
+    inline_restore: InlineRestorePolicy,
+    clear_on_init: ClearOnInit,
+    modes: TerminalModes,
+    panic_hook: Option<PanicHook>,
+    timings: &mut LifecycleTimings,
+) -> Result<(Terminal<CrosstermBackend<TerminalWriter>>, TerminalModes)> {
+    tracing::debug!("Initializing terminal");
+
+    let enabled = enter_terminal_modes(viewport_mode, modes, timings)?;
+    let phase = LifecycleTimings::start("clear");
+    let terminal = new_terminal(viewport_mode, &enabled, clear_on_init);
+    match terminal {
+        Ok(terminal) => {
+            timings.end(phase);
+            install_panic_hook(viewport_mode, inline_restore, &enabled, panic_hook);
+            tracing::debug!("Terminal initialized successfully");
+            Ok((terminal, enabled))
+        }
+        Err(e) => {
+            drop(phase);
+            let _ = leave_terminal_modes(&enabled, viewport_mode, None, timings);
+            Err(e)
+        }
+    }
+}
+
+fn install_panic_hook(
+    viewport_mode: ViewportMode,
+    inline_restore: InlineRestorePolicy,
+    enabled: &TerminalModes,
+    panic_hook: Option<PanicHook>,
+) {
+    let Some(hook) = panic_hook else {
+        return;
+    };
+    *panic_session() = Some(PanicSession {
+        hook,
+        modes: enabled.clone(),
+        viewport: viewport_mode,
+        inline_restore,
+    });
+    // Installed once, in front of whatever hook was there first; each session just swaps the slot.
+    PANIC_HOOK.call_once(|| {
+        let hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |panic_info| {
+            if let Some(session) = panic_session().take() {
+                restore_on_panic(&session, panic_info);
+            }
+            hook(panic_info);
+        }));
+    });
+}
+
+fn restore_on_panic(session: &PanicSession, panic_info: &std::panic::PanicHookInfo<'_>) {
+    let PanicHook {
+        lifecycle,
+        error_banner,
+        report: PanicReport(report),
+    } = &session.hook;
+    if lifecycle.finish() {
+        // We've already panicked so ignore any err
+        let (modes, viewport) = (&session.modes, session.viewport);
+        let timings = &mut LifecycleTimings::default();
+        let _ = restore_terminal(modes, viewport, session.inline_restore, timings);
+        if let Some(ErrorBanner(format)) = error_banner {
+            let out = session.modes.writer(session.viewport);
+            let _ = print_banner(out, &format(&panic_message(panic_info)));
+        }
+        let mut out = session.viewport.report_writer();
+        let _ = writeln!(out, "{}", report(panic_info)).and_then(|()| out.flush());
+    }
+}
+
+/// Clear the panic session if it is the one for `lifecycle`.
+fn end_panic_session(lifecycle: &Lifecycle) {
+    let mut session = panic_session();
+    if session
+        .as_ref()
+        .is_some_and(|s| s.hook.lifecycle.is(lifecycle))
+    {
+        *session = None;
+    }
+}
+
+/// Check the terminal is at least `min_cols` by `min_rows`, failing or waiting per `behavior`.
+fn wait_for_min_size(
+    mut out: TerminalWriter,
+    (min_cols, min_rows): (u16, u16),
+    behavior: MinSizeBehavior,
+) -> Result<()> {
+    let fits = |(cols, rows): (u16, u16)| cols >= min_cols && rows >= min_rows;
+    let mut current = size().wrap_err("Failed to read terminal size")?;
+    if fits(current) {
+        return Ok(());
+    }
+    if behavior == MinSizeBehavior::Fail {
+        bail!(
+            "Terminal is {}x{}, but needs to be at least {}x{}",
+            current.0,
+            current.1,
+            min_cols,
+            min_rows
+        );
+    }
+
+    let mut shown = None;
+    while !fits(current) {
+        if shown != Some(current) {
+            execute!(
+                out,
+                Clear(ClearType::CurrentLine),
+                Print(format!(
+                    "\rPlease enlarge your terminal to at least {}x{} (now {}x{})",
+                    min_cols, min_rows, current.0, current.1
+                )),
+            )?;
+            shown = Some(current);
+        }
+        std::thread::sleep(MIN_SIZE_POLL);
+        current = size().wrap_err("Failed to read terminal size")?;
+    }
+    execute!(out, Clear(ClearType::CurrentLine), Print("\r"))?;
+    Ok(())
+}
+
+/// Turn on raw mode and the requested `modes`, returning the ones actually enabled.
+///
+/// If one fails, those already on are turned off again before returning the error.
+fn enter_terminal_modes(
+    viewport_mode: ViewportMode,
+    modes: TerminalModes,
+    timings: &mut LifecycleTimings,
+) -> Result<TerminalModes> {
+    let mut enabled = TerminalModes {
+        raw_mode: false,
+        capture_mouse: false,
+        hide_cursor: false,
+        bracketed_paste: false,
+        focus_events: false,
+        theme_notifications: false,
+        keyboard_enhancement: None,
+        title: None,
+        cursor_style: None,
+        synchronized_output: false,
+        cursor_restore: CursorRestore::BelowViewport,
+        alternate_screen: false,
+        alt_screen_style: modes.alt_screen_style,
+        init_commands: Vec::new(),
+        restore_commands: Vec::new(),
+        custom_writer: modes.custom_writer.clone(),
+    };
+    match turn_on_terminal_modes(viewport_mode, &modes, &mut enabled, timings) {
+        Ok(()) => Ok(enabled),
+        Err(e) => {
+            let _ = leave_terminal_modes(&enabled, viewport_mode, None, timings);
+            Err(e)
+        }
+    }
+}
+
+/// Turn on each of `modes`, recording it in `enabled` once it is on.
+fn turn_on_terminal_modes(
+    viewport_mode: ViewportMode,
+    modes: &TerminalModes,
+    enabled: &mut TerminalModes,
+    timings: &mut LifecycleTimings,
+) -> Result<()> {
+    if modes.raw_mode {
+        let phase = LifecycleTimings::start("raw_mode");
+        enable_raw_mode().wrap_err("Failed to enable raw mode")?;
+        enabled.raw_mode = true;
+        timings.end(phase);
+    } else if modes.capture_mouse {
+        tracing::debug!("Skipping mouse capture without raw mode");
+    }
+
+    let phase = LifecycleTimings::start("modes");
+    let mut terminal_output = modes.writer(viewport_mode);
+    // Before the alternate screen, so this is the shell's position.
+    if modes.cursor_restore == CursorRestore::SavedPosition {
+        execute!(terminal_output, cursor::SavePosition).wrap_err("Failed to save cursor")?;
+    }
+    enabled.cursor_restore = modes.cursor_restore;
+    if modes.capture_mouse && modes.raw_mode {
+        execute!(terminal_output, EnableMouseCapture).wrap_err("Failed to enable mouse capture")?;
+        enabled.capture_mouse = true;
+    }
+    if modes.bracketed_paste {
+        execute!(terminal_output, EnableBracketedPaste)
+            .wrap_err("Failed to enable bracketed paste")?;
+        enabled.bracketed_paste = true;
+    }
+    if modes.focus_events {
+        execute!(terminal_output, EnableFocusChange).wrap_err("Failed to enable focus events")?;
+        enabled.focus_events = true;
+    }
+    if modes.theme_notifications {
+        execute!(terminal_output, Print(ENABLE_THEME_NOTIFICATIONS))
+            .wrap_err("Failed to enable theme notifications")?;
+        enabled.theme_notifications = true;
+    }
+    if let Some(title) = &modes.title {
+        execute!(terminal_output, Print(PUSH_TITLE), SetTitle(title))
+            .wrap_err("Failed to set terminal title")?;
+        enabled.title = Some(title.clone());
+    }
+    if let Some(style) = modes.cursor_style {
+        execute!(terminal_output, style.command()).wrap_err("Failed to set cursor style")?;
+        enabled.cursor_style = Some(style);
+    }
+    timings.end(phase);
+
+    if modes.alternate_screen {
+        tracing::debug!("Entering alternate screen mode");
+        let phase = LifecycleTimings::start("alternate_screen");
+        modes
+            .alt_screen_style
+            .enter(&mut terminal_output)
+            .wrap_err("Failed to enter alternate screen")?;
+        enabled.alternate_screen = true;
+        timings.end(phase);
+    } else {
+        if let Some(height) = viewport_mode.inline_height() {
+            tracing::debug!("Using inline mode with height: {}", height);
+        }
+    }
+
+    // Pushed after entering the alternate screen, which keeps its own stack of flags.
+    if let Some(flags) = modes.keyboard_enhancement {
+        let phase = LifecycleTimings::start("keyboard_enhancement");
+        enabled.keyboard_enhancement = match supports_keyboard_enhancement() {
+            Ok(true) => {
+                execute!(terminal_output, PushKeyboardEnhancementFlags(flags))
+                    .wrap_err("Failed to push keyboard enhancement flags")?;
+                Some(flags)
+            }
+            Ok(false) => {
+                tracing::info!("Terminal does not support keyboard enhancement");
+                None
+            }
+            Err(e) => {
+                tracing::warn!("Failed to detect keyboard enhancement support: {}", e);
+                None
+            }
+        };
+        timings.end(phase);
+    }
+
+    // Last, so they can build on every mode above. Their undo is recorded first, as a failure
+    // partway through may still have left some of them in effect.
+    enabled.restore_commands = modes.restore_commands.clone();
+    if !modes.init_commands.is_empty() {
+        let phase = LifecycleTimings::start("extra_commands");
+        for command in &modes.init_commands {
+            terminal_output
+                .write_all(command.as_bytes())
+                .wrap_err("Failed to write extra init commands")?;
+        }
+        terminal_output
+            .flush()
+            .wrap_err("Failed to write extra init commands")?;
+        timings.end(phase);
+    }
+    enabled.init_commands = modes.init_commands.clone();
+
+    // Hidden by `new_terminal`; shown again on the way out either way.
+    enabled.hide_cursor = modes.hide_cursor;
+    // Begun per frame by `TuiApp::draw_synced`; ended on the way out either way.
+    enabled.synchronized_output = modes.synchronized_output;
+    Ok(())
+}
+
+/// A ratatui terminal for `viewport_mode` drawing where `modes` say, cleared per `clear` so the
+/// first draw paints everything.
+///
+/// An inline viewport is clamped to the terminal's current height.
+fn new_terminal(
+    viewport_mode: ViewportMode,
+    modes: &TerminalModes,
+    clear: ClearOnInit,
+) -> Result<Terminal<CrosstermBackend<TerminalWriter>>> {
+    let backend = CrosstermBackend::new(modes.writer(viewport_mode));
+
+    let (_cols, rows) = size().wrap_err("Failed to read terminal size")?;
+    let viewport = match viewport_mode.fit_to(rows) {
+        ViewportMode::Inline { height } => Viewport::Inline(height),
+        ViewportMode::AlternateScreen { .. } => Viewport::Fullscreen,
+    };
+
+    let mut terminal = ratatui::Terminal::with_options(backend, TerminalOptions { viewport })
+        .wrap_err("Failed to create terminal")?;
+
+    match (clear, viewport_mode.is_inline()) {
+        (ClearOnInit::Never, _) => invalidate_next_draw(&mut terminal),
+        (ClearOnInit::ViewportOnly, true) => {
+            let area = terminal.get_frame().area();
+            let out = terminal.backend_mut();
+            for y in area.top()..area.bottom() {
+                queue!(out, cursor::MoveTo(0, y), Clear(ClearType::UntilNewLine))?;
+            }
+            out.flush()?;
+        }
+        _ => terminal.clear().wrap_err("Failed to clear terminal")?,
+    }
+    if modes.hide_cursor {
+        terminal.hide_cursor().wrap_err("Failed to hide cursor")?;
+    }
+    Ok(terminal)
+}
+
+/// Make the next draw of `terminal` paint every cell, without writing anything now.
+fn invalidate_next_draw(terminal: &mut Terminal<CrosstermBackend<TerminalWriter>>) {
+    // Swapped to the back buffer, where no drawn cell can match it.
+    for cell in &mut terminal.current_buffer_mut().content {
+        cell.set_symbol("\0");
+    }
+    terminal.swap_buffers();
+}
+
+fn restore_terminal(
+    modes: &TerminalModes,
+    viewport_mode: ViewportMode,
+    inline_restore: InlineRestorePolicy,
+    timings: &mut LifecycleTimings,
+) -> io::Result<()> {
+    tracing::debug!("Restoring terminal");
+    leave_terminal_modes(modes, viewport_mode, Some(inline_restore), timings)?;
+    tracing::debug!("Terminal restore completed");
+    Ok(())
+}
+
+/// Turn off `modes` in the reverse of the order init turned them on, applying `inline_restore`
+/// to an inline viewport that was drawn on.
+///
+/// Every step is attempted even if an earlier one fails, so a failed write can't leave raw mode
+/// on; the first failure is returned.
+fn leave_terminal_modes(
+    modes: &TerminalModes,
+    viewport_mode: ViewportMode,
+    inline_restore: Option<InlineRestorePolicy>,
+    timings: &mut LifecycleTimings,
+) -> io::Result<()> {
+    let mut out = modes.writer(viewport_mode);
+    let mut result = Ok(());
+    let mut check = |step: &str, outcome: io::Result<()>| {
+        if let Err(e) = outcome {
+            tracing::error!("Failed to {} during restore: {}", step, e);
+            if result.is_ok() {
+                result = Err(e);
+            }
+        }
+    };
+
+    // First, while every mode they may depend on is still on.
+    if !modes.restore_commands.is_empty() {
+        let written = modes
+            .restore_commands
+            .iter()
+            .try_for_each(|command| out.write_all(command.as_bytes()))
+            .and_then(|()| out.flush());
+        check("write extra restore commands", written);
+    }
+
+    // A panic mid-frame would otherwise leave the terminal holding back everything that follows.
+    if modes.synchronized_output {
+        check(
+            "end synchronized output",
+            execute!(out, EndSynchronizedUpdate),
+        );
+    }
+
+    if modes.keyboard_enhancement.is_some() {
+        let popped = execute!(out, PopKeyboardEnhancementFlags);
+        check("pop keyboard enhancement flags", popped);
+    }
+
+    let phase = LifecycleTimings::start("restore_screen");
+    if modes.alternate_screen {
+        check(
+            "leave the alternate screen",
+            modes.alt_screen_style.leave(&mut out),
+        );
+    } else if let (Some(policy), Ok((_cols, rows))) = (inline_restore, size()) {
+        // Sized now rather than at init, so a resize since then doesn't misplace the cursor, and
+        // clamped the same way the viewport was.
+        if let Some(height) = viewport_mode.fit_to(rows).inline_height() {
+            check(
+                "clear the inline viewport",
+                restore_inline(&mut out, rows, height, policy),
+            );
+        }
+    }
+    timings.end(phase);
+
+    let phase = LifecycleTimings::start("restore_modes");
+    // Reset even while hidden, so the shell gets its own cursor back.
+    if modes.cursor_style.is_some() {
+        check(
+            "reset the cursor style",
+            execute!(out, SetCursorStyle::DefaultUserShape),
+        );
+    }
+
+    // Terminals without a title stack ignore the pop and keep the cleared title.
+    if modes.title.is_some() {
+        check(
+            "restore the terminal title",
+            execute!(out, SetTitle(""), Print(POP_TITLE)),
+        );
+    }
+
+    if modes.theme_notifications {
+        let disabled = execute!(out, Print(DISABLE_THEME_NOTIFICATIONS));
+        check("disable theme notifications", disabled);
+    }
+
+    if modes.focus_events {
+        check("disable focus events", execute!(out, DisableFocusChange));
+    }
+
+    if modes.bracketed_paste {
+        check(
+            "disable bracketed paste",
+            execute!(out, DisableBracketedPaste),
+        );
+    }
+
+    if modes.capture_mouse {
+        check("disable mouse capture", execute!(out, DisableMouseCapture));
+    }
+
+    check(
+        "park the cursor",
+        park_cursor(&mut out, modes, inline_restore),
+    );
+
+    // Hidden last at init, but shown once the screen is back so it doesn't flash on the way.
+    if modes.hide_cursor {
+        check("show the cursor", execute!(out, cursor::Show));
+    }
+    check("flush the terminal", out.flush());
+    timings.end(phase);
+
+    if modes.raw_mode {
+        let phase = LifecycleTimings::start("restore_raw_mode");
+        check("disable raw mode", disable_raw_mode());
+        timings.end(phase);
+    }
+    result
+}
+
+/// Move the cursor to where [`CursorRestore`] wants it, after the screen is restored.
+fn park_cursor(
+    out: &mut impl Write,
+    modes: &TerminalModes,
+    inline_restore: Option<InlineRestorePolicy>,
+) -> io::Result<()> {
+    match modes.cursor_restore {
+        CursorRestore::BelowViewport => Ok(()),
+        CursorRestore::SavedPosition => {
+            let keeps_frame = !modes.alternate_screen
+                && inline_restore == Some(InlineRestorePolicy::LeaveUntouched);
+            if keeps_frame {
+                return Ok(());
+            }
+            execute!(out, cursor::RestorePosition)
+        }
+        CursorRestore::Column0LastRow => {
+            let (_cols, rows) = size()?;
+            execute!(out, cursor::MoveTo(0, rows.saturating_sub(1)))
+        }
+    }
+}
+
+fn restore_inline(
+    out: &mut impl Write,
+    rows: u16,
+    height: u16,
+    policy: InlineRestorePolicy,
+) -> io::Result<()> {
+    match policy {
+        InlineRestorePolicy::ClearViewport => execute!(
+            out,
+            cursor::MoveTo(0, rows.saturating_sub(height)),
+            Clear(ClearType::FromCursorDown),
+        ),
+        // Clearing no rows leaves them all, so continue below them the same way.
+        InlineRestorePolicy::LeaveUntouched | InlineRestorePolicy::ClearBottom(0) => {
+            execute!(out, cursor::MoveTo(0, rows.saturating_sub(1)))?;
+            out.write_all(b"\r\n")
+        }
+        InlineRestorePolicy::ClearBottom(lines) => execute!(
+            out,
+            cursor::MoveTo(0, rows.saturating_sub(lines)),
+            Clear(ClearType::FromCursorDown),
+        ),
+        InlineRestorePolicy::ClearAll => {
+            execute!(out, Clear(ClearType::All), cursor::MoveTo(0, 0))
+        }
+    }
+}
+
+/// Tallest inline viewport [`TuiAppBuilder::try_build`] accepts; far more rows than any
+/// terminal has, so a larger height is a bug such as an underflowed subtraction.
+pub const MAX_INLINE_HEIGHT: u16 = 1000;
+
+/// A [`TuiAppBuilder`] configuration that can't work, from [`TuiAppBuilder::try_build`] or
+/// [`TuiApp::init`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    /// An inline viewport with no rows to draw on.
+    ZeroInlineHeight,
+    /// An inline viewport taller than [`MAX_INLINE_HEIGHT`].
+    InlineHeightTooLarge(u16),
+    /// Logging to stderr while the TUI draws on stderr.
+    StderrConflict,
+    /// An empty app name, which can't name the default log directory.
+    InvalidAppName(String),
+    /// Keeping no log files, not even the one being written.
+    ZeroLogKeep,
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ZeroInlineHeight => write!(f, "Inline viewport height must be at least 1"),
+            Self::InlineHeightTooLarge(height) => write!(
+                f,
+                "Inline viewport height {} exceeds the maximum of {}",
+                height, MAX_INLINE_HEIGHT
+            ),
+            Self::StderrConflict => write!(f, "Cannot log to stderr while the TUI draws on stderr"),
+            Self::InvalidAppName(name) => {
+                write!(f, "App name {:?} cannot name a log directory", name)
+            }
+            Self::ZeroLogKeep => write!(f, "Log files to keep must be at least 1"),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// Coordinates color-eyre, logging, and terminal lifecycle for the TUI.
+#[derive(Debug, Clone)]
+pub struct TuiAppBuilder {
+    app_name: String,
+    use_panic_terminal_restore: bool,
+    use_color_eyre: bool,
+    color_eyre_theme: Option<Theme>,
+    use_disk_logs: bool,
+    log_dir: Option<PathBuf>,
+    log_location: LogLocation,
+    log_file_prefix: String,
+    log_rotation: LogRotation,
+    log_keep: Option<usize>,
+    log_filter: String,
+    stderr_log: Option<StderrLog>,
+    tracing: TracingConfig,
+    raw_mode: bool,
+    capture_mouse: bool,
+    hide_cursor: bool,
+    bracketed_paste: bool,
+    focus_events: bool,
+    theme_notifications: bool,
+    keyboard_enhancement: Option<KeyboardEnhancementFlags>,
+    title: Option<String>,
+    cursor_style: Option<CursorStyle>,
+    synchronized_output: bool,
+    probe_capabilities: bool,
+    extra_init_commands: Vec<String>,
+    extra_restore_commands: Vec<String>,
+    custom_writer: Option<CustomWriter>,
+    alt_screen_style: AltScreenStyle,
+    handle_suspend: bool,
+    restore_signals: Vec<Signal>,
+    min_size: Option<(u16, u16)>,
+    min_size_behavior: MinSizeBehavior,
+    viewport: ViewportMode,
+    non_tty: NonTtyBehavior,
+    inline_restore: InlineRestorePolicy,
+    cursor_restore: CursorRestore,
+    clear_on_init: ClearOnInit,
+    welcome_banner: Option<String>,
+    goodbye_banner: Option<String>,
+    error_banner: Option<ErrorBanner>,
+    panic_report: Option<PanicReport>,
+}
+
+impl Default for TuiAppBuilder {
+    fn default() -> Self {
+        Self {
+            app_name: String::new(),
+            use_panic_terminal_restore: true,
+            use_color_eyre: true,
+            color_eyre_theme: None,
+            use_disk_logs: true,
+            log_dir: None,
+            log_location: LogLocation::default(),
+            log_file_prefix: "logs".to_string(),
+            log_rotation: LogRotation::default(),
+            log_keep: None,
+            log_filter: "info".to_string(),
+            stderr_log: None,
+            tracing: TracingConfig::default(),
+            raw_mode: true,
+            capture_mouse: true,
+            hide_cursor: true,
+            bracketed_paste: false,
+            focus_events: false,
+            theme_notifications: false,
+            keyboard_enhancement: None,
+            title: None,
+            cursor_style: None,
+            synchronized_output: false,
+            probe_capabilities: false,
+            extra_init_commands: Vec::new(),
+            extra_restore_commands: Vec::new(),
+            custom_writer: None,
+            alt_screen_style: AltScreenStyle::default(),
+            handle_suspend: false,
+            restore_signals: Vec::new(),
+            min_size: None,
+            min_size_behavior: MinSizeBehavior::default(),
+            viewport: ViewportMode::default(),
+            non_tty: NonTtyBehavior::default(),
+            inline_restore: InlineRestorePolicy::default(),
+            cursor_restore: CursorRestore::default(),
+            clear_on_init: ClearOnInit::default(),
+            welcome_banner: None,
+            goodbye_banner: None,
+            error_banner: None,
+            panic_report: None,
+        }
+    }
+}
+
+impl TuiAppBuilder {
+    pub fn new(app_name: impl Into<String>) -> Self {
+        Self {
+            app_name: app_name.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn app_name(mut self, app_name: impl Into<String>) -> Self {
+        self.app_name = app_name.into();
+        self
+    }
+
+    /// Put the terminal in raw mode while the TUI runs; on by default.
+    ///
+    /// Without it, typed input stays line-buffered and keeps reaching the shell, mouse capture
+    /// is skipped, and neither restore nor the panic hook touch the termios settings, so a host
+    /// that set its own are left as they were.
+    pub fn raw_mode(mut self, raw_mode: bool) -> Self {
+        self.raw_mode = raw_mode;
+        self
+    }
+
+    pub fn capture_mouse(mut self, capture_mouse: bool) -> Self {
+        self.capture_mouse = capture_mouse;
+        self
+    }
+
+    pub fn hide_cursor(mut self, hide_cursor: bool) -> Self {
+        self.hide_cursor = hide_cursor;
+        self
+    }
+
+    /// Cursor shape while the TUI runs, reset to the terminal's default on the way out. A hidden
+    /// cursor stays hidden.
+    pub fn cursor_style(mut self, style: CursorStyle) -> Self {
+        self.cursor_style = Some(style);
+        self
+    }
+
+    /// Wrap each frame drawn with [`TuiApp::draw_synced`] in a synchronized update (mode 2026),
+    /// so the terminal shows it all at once instead of tearing. Terminals without the mode ignore
+    /// it.
+    pub fn synchronized_output(mut self, synchronized_output: bool) -> Self {
+        self.synchronized_output = synchronized_output;
+        self
+    }
+
+    /// Write `command` at init once every other mode is on: after raw mode, the alternate screen
+    /// and keyboard enhancement, before the cursor is hidden and the first draw. Commands go out
+    /// in the order they were added, and again on resuming from [`TuiApp::suspend`].
+    pub fn extra_init_command(mut self, command: impl Command) -> Self {
+        self.extra_init_commands.push(ansi(&command));
+        self
+    }
+
+    /// Write `command` on restore before any other mode is turned off, including when restoring
+    /// after a panic or signal. Commands go out in the order they were added.
+    ///
+    /// If init fails before it gets to the init commands, these are not written.
+    pub fn extra_restore_command(mut self, command: impl Command) -> Self {
+        self.extra_restore_commands.push(ansi(&command));
+        self
+    }
+
+    /// Draw on `writer` instead of stdout or stderr, e.g. to tee the output to a file or send it
+    /// over an SSH channel. Restore writes its escape sequences there too, even after a panic or
+    /// signal, and so do the banners; panic reports and [`TuiApp::println_passthrough`] still go
+    /// to the stream the viewport doesn't draw on.
+    ///
+    /// Raw mode, the terminal size and input, mouse events included, still go through the
+    /// controlling tty. [`NonTtyBehavior`] and [`TuiAppBuilder::probe_capabilities`] don't apply.
+    pub fn custom_writer(mut self, writer: Box<dyn Write + Send>) -> Self {
+        self.custom_writer = Some(CustomWriter(Arc::new(Mutex::new(writer))));
+        self
+    }
+
+    /// Ask the terminal what it supports during init, for [`TuiApp::capabilities`]. Needs raw
+    /// mode, and holds up init until the terminal answers or [`PROBE_TIMEOUT`] passes.
+    pub fn probe_capabilities(mut self, probe_capabilities: bool) -> Self {
+        self.probe_capabilities = probe_capabilities;
+        self
+    }
+
+    /// Have the terminal frame pastes in `ESC[200~` / `ESC[201~` while the TUI runs.
+    pub fn bracketed_paste(mut self, bracketed_paste: bool) -> Self {
+        self.bracketed_paste = bracketed_paste;
+        self
+    }
+
+    /// Window title to show while the TUI runs; the previous one is restored on the way out
+    /// where the terminal keeps a title stack, and cleared otherwise.
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Smallest terminal, in columns and rows, the TUI can draw in; checked by
+    /// [`TuiApp::init`] as per [`TuiAppBuilder::min_size_behavior`].
+    pub fn min_size(mut self, cols: u16, rows: u16) -> Self {
+        self.min_size = Some((cols, rows));
+        self
+    }
+
+    /// What happens when the terminal is smaller than [`TuiAppBuilder::min_size`].
+    pub fn min_size_behavior(mut self, behavior: MinSizeBehavior) -> Self {
+        self.min_size_behavior = behavior;
+        self
+    }
+
+    /// Restore the terminal when one of `signals` arrives while the TUI runs, then die of it as
+    /// the default action would, so the exit status still reports the signal.
+    ///
+    /// Once restored, the signals act as if no handler had been installed.
+    pub fn restore_on_signals(mut self, signals: &[Signal]) -> Self {
+        self.restore_signals = signals.to_vec();
+        self
+    }
+
+    /// Catch SIGTSTP while the TUI runs instead of stopping with the terminal still set up; poll
+    /// [`TuiApp::take_suspend_request`] and call [`TuiApp::suspend`] when it fires.
+    ///
+    /// Ctrl-Z does not raise SIGTSTP in raw mode, so apps should also suspend on that key.
+    pub fn handle_suspend(mut self, handle_suspend: bool) -> Self {
+        self.handle_suspend = handle_suspend;
+        self
+    }
+
+    /// Report focus gained and lost (mode 1004) while the TUI runs.
+    pub fn focus_events(mut self, focus_events: bool) -> Self {
+        self.focus_events = focus_events;
+        self
+    }
+
+    /// Have the terminal report switching between dark and light themes (mode 2031) while the
+    /// TUI runs; feed input events to a [`theme::ThemeWatcher`] to follow them. Terminals
+    /// without the mode ignore it.
+    pub fn theme_notifications(mut self, theme_notifications: bool) -> Self {
+        self.theme_notifications = theme_notifications;
+        self
+    }
+
+    /// Kitty keyboard protocol flags to push at init, if the terminal supports them, and pop
+    /// on restore; see [`TuiApp::keyboard_enhancement`] for the outcome.
+    pub fn keyboard_enhancement(mut self, flags: KeyboardEnhancementFlags) -> Self {
+        self.keyboard_enhancement = Some(flags);
+        self
+    }
+
+    pub fn inline(mut self, height: u16) -> Self {
+        self.viewport = ViewportMode::Inline { height };
+        self
+    }
+
+    /// What restoring does with the inline viewport's rows; ignored on the alternate screen.
+    pub fn inline_restore(mut self, policy: InlineRestorePolicy) -> Self {
+        self.inline_restore = policy;
+        self
+    }
+
+    /// Where restoring leaves the cursor, after applying [`TuiAppBuilder::inline_restore`].
+    pub fn cursor_on_restore(mut self, cursor: CursorRestore) -> Self {
+        self.cursor_restore = cursor;
+        self
+    }
+
+    /// What init clears before the first draw; see [`ClearOnInit`].
+    pub fn clear_on_init(mut self, clear_on_init: ClearOnInit) -> Self {
+        self.clear_on_init = clear_on_init;
+        self
+    }
+
+    pub fn alternate_screen(mut self) -> Self {
+        self.viewport = ViewportMode::AlternateScreen {
+            backend: AlternateScreenBackend::Stdout,
+        };
+        self
+    }
+
+    /// What init does when the TUI's stream isn't a terminal; see [`NonTtyBehavior`].
+    pub fn non_tty_behavior(mut self, behavior: NonTtyBehavior) -> Self {
+        self.non_tty = behavior;
+        self
+    }
+
+    /// How to switch to the alternate screen; see [`AltScreenStyle`] for terminal support.
+    pub fn alt_screen_style(mut self, style: AltScreenStyle) -> Self {
+        self.alt_screen_style = style;
+        self
+    }
+
+    pub fn alternate_screen_backend(mut self, backend: AlternateScreenBackend) -> Self {
+        self.viewport = ViewportMode::AlternateScreen { backend };
+        self
+    }
+
+    pub fn use_panic_terminal_restore(mut self, use_panic_terminal_restore: bool) -> Self {
+        self.use_panic_terminal_restore = use_panic_terminal_restore;
+        self
+    }
+
+    pub fn use_color_eyre(mut self, use_color_eyre: bool) -> Self {
+        self.use_color_eyre = use_color_eyre;
+        self
+    }
+
+    /// Colors for color-eyre's error and panic reports; its default theme otherwise.
+    pub fn color_eyre_theme(mut self, theme: Theme) -> Self {
+        self.color_eyre_theme = Some(theme);
+        self
+    }
+
+    pub fn use_disk_logs(mut self, use_disk_logs: bool) -> Self {
+        self.use_disk_logs = use_disk_logs;
+        self
+    }
+
+    /// Where log files go unless `{APP}_LOG_DIR` is set; defaults to a directory chosen by
+    /// [`TuiAppBuilder::log_location`].
+    pub fn log_dir(mut self, log_dir: impl Into<PathBuf>) -> Self {
+        self.log_dir = Some(log_dir.into());
+        self
+    }
+
+    /// Where the default log directory goes; [`LogLocation::Dotfile`] keeps the old
+    /// `~/.{app}/logs`.
+    pub fn log_location(mut self, location: LogLocation) -> Self {
+        self.log_location = location;
+        self
+    }
+
+    /// File name prefix of the log files, before the date suffix; defaults to `logs`.
+    pub fn log_file_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.log_file_prefix = prefix.into();
+        self
+    }
+
+    /// How often the log file rolls over; daily by default.
+    pub fn log_rotation(mut self, rotation: LogRotation) -> Self {
+        self.log_rotation = rotation;
+        self
+    }
+
+    /// Keep only the newest `files` log files, counting the current one, deleting older ones at
+    /// init; all by default. Only files of the current [`LogRotation`] count, and a file that
+    /// never rotates is never deleted. Zero is rejected, since the current file always stays.
+    pub fn log_keep(mut self, files: usize) -> Self {
+        self.log_keep = Some(files);
+        self
+    }
+
+    /// Filter directives for the log file, e.g. `warn,myapp=debug`, used unless `RUST_LOG` is
+    /// set; defaults to `info`.
+    pub fn log_filter(mut self, directives: impl Into<String>) -> Self {
+        self.log_filter = directives.into();
+        self
+    }
+
+    /// Also log to stderr, formatted per [`TuiAppBuilder::stderr_log`]; off by default.
+    ///
+    /// Only useful when stderr is not the terminal the TUI draws on, e.g. redirected to a
+    /// file; [`TuiApp::init`] rejects it alongside an alternate screen on stderr.
+    pub fn log_to_stderr(mut self, log_to_stderr: bool) -> Self {
+        self.stderr_log = log_to_stderr.then(|| self.stderr_log.unwrap_or_default());
+        self
+    }
+
+    /// Format and level of the stderr log layer; implies [`TuiAppBuilder::log_to_stderr`].
+    pub fn stderr_log(mut self, stderr_log: StderrLog) -> Self {
+        self.stderr_log = Some(stderr_log);
+        self
+    }
+
+    /// Text printed before the terminal leaves cooked mode.
+    pub fn welcome_banner(mut self, text: impl Into<String>) -> Self {
+        self.welcome_banner = Some(text.into());
+        self
+    }
+
+    /// Text printed once [`TuiApp::restore`] has put the terminal back.
+    pub fn goodbye_banner(mut self, text: impl Into<String>) -> Self {
+        self.goodbye_banner = Some(text.into());
+        self
+    }
+
+    /// Formats the error (or panic) message printed after restoring on the way out; replaces
+    /// the goodbye banner in that case.
+    pub fn error_banner(mut self, format: impl Fn(&str) -> String + Send + Sync + 'static) -> Self {
+        self.error_banner = Some(ErrorBanner(Arc::new(format)));
+        self
+    }
+
+    /// Formats the report the panic hook prints, after restoring, to the stream the TUI does not
+    /// draw on; by default the message, location and log directory.
+    pub fn panic_report(
+        mut self,
+        format: impl Fn(&std::panic::PanicHookInfo<'_>) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.panic_report = Some(PanicReport(Arc::new(format)));
+        self
+    }
+
+    /// How the global tracing subscriber is set up; see [`TracingConfig`].
+    pub fn tracing(mut self, tracing: TracingConfig) -> Self {
+        self.tracing = tracing;
+        self
+    }
+
+    /// Like [`TuiAppBuilder::build`], but rejects configurations that would fail or misbehave
+    /// at init.
+    ///
+    /// The app name must not be empty unless logs are off or [`TuiAppBuilder::log_dir`] is set;
+    /// any other name has the characters a directory name can't hold replaced, e.g. `my/app`
+    /// logs to `my_app`. Inline heights taller than the terminal are clamped when laid out
+    /// rather than rejected here, since the terminal's size isn't known yet.
+    pub fn try_build(self) -> std::result::Result<TuiApp, BuildError> {
+        let names_log_dir = self.use_disk_logs && self.log_dir.is_none();
+        if names_log_dir && self.app_name.is_empty() {
+            return Err(BuildError::InvalidAppName(self.app_name));
+        }
+        let app = self.build();
+        app.validate()?;
+        Ok(app)
+    }
+
+    pub fn build(self) -> TuiApp {
+        let app_name = if self.app_name.is_empty() {
+            env!("CARGO_PKG_NAME").to_string()
+        } else {
+            self.app_name
+        };
+        let modes = TerminalModes {
+            raw_mode: self.raw_mode,
+            capture_mouse: self.capture_mouse,
+            hide_cursor: self.hide_cursor,
+            bracketed_paste: self.bracketed_paste,
+            focus_events: self.focus_events,
+            theme_notifications: self.theme_notifications,
+            keyboard_enhancement: self.keyboard_enhancement,
+            title: self.title,
+            cursor_style: self.cursor_style,
+            synchronized_output: self.synchronized_output,
+            cursor_restore: self.cursor_restore,
+            alternate_screen: !self.viewport.is_inline(),
+            alt_screen_style: self.alt_screen_style,
+            init_commands: self.extra_init_commands,
+            restore_commands: self.extra_restore_commands,
+            custom_writer: self.custom_writer,
+        };
+        let enabled = TerminalModes {
+            bracketed_paste: false,
+            focus_events: false,
+            theme_notifications: false,
+            keyboard_enhancement: None,
+            title: None,
+            cursor_style: None,
+            synchronized_output: false,
+            cursor_restore: CursorRestore::BelowViewport,
+            alternate_screen: false,
+            init_commands: Vec::new(),
+            restore_commands: Vec::new(),
+            ..modes.clone()
+        };
+
+        TuiApp {
+            logger_guard: None,
+            lifecycle: Lifecycle::new(LifecycleState::NotStarted),
+            handle_suspend: self.handle_suspend,
+            restore_signals: self.restore_signals,
+            #[cfg(unix)]
+            signal_handler: Mutex::new(None),
+            signals_default: Arc::new(AtomicBool::new(true)),
+            signal_defaults_installed: AtomicBool::new(false),
+            min_size: self.min_size,
+            min_size_behavior: self.min_size_behavior,
+            probe_capabilities: self.probe_capabilities,
+            capabilities: None,
+            timings: Mutex::new(LifecycleTimings::default()),
+            suspend_requested: Arc::new(AtomicBool::new(false)),
+            suspend_default: Arc::new(AtomicBool::new(true)),
+            suspend_handlers_installed: AtomicBool::new(false),
+            app_name,
+            use_panic_terminal_restore: self.use_panic_terminal_restore,
+            use_color_eyre: self.use_color_eyre,
+            color_eyre_theme: self.color_eyre_theme,
+            use_disk_logs: self.use_disk_logs,
+            log_dir: self.log_dir,
+            log_location: self.log_location,
+            log_file_prefix: self.log_file_prefix,
+            log_rotation: self.log_rotation,
+            log_keep: self.log_keep,
+            log_filter: self.log_filter,
+            stderr_log: self.stderr_log,
+            tracing: self.tracing,
+            modes,
+            enabled,
+            viewport: self.viewport,
+            non_tty: self.non_tty,
+            inline_restore: self.inline_restore,
+            clear_on_init: self.clear_on_init,
+            welcome_banner: self.welcome_banner,
+            goodbye_banner: self.goodbye_banner,
+            error_banner: self.error_banner,
+            panic_report: self.panic_report,
+        }
+    }
+}
+
+pub struct TuiApp {
+    logger_guard: Option<LoggerGuard>,
+    /// Shared with the panic hook so the terminal is restored at most once, by any path.
+    lifecycle: Lifecycle,
+    handle_suspend: bool,
+    min_size: Option<(u16, u16)>,
+    min_size_behavior: MinSizeBehavior,
+    probe_capabilities: bool,
+    capabilities: Option<TerminalCapabilities>,
+    timings: Mutex<LifecycleTimings>,
+    /// Set by the SIGTSTP handler installed for [`TuiAppBuilder::handle_suspend`].
+    suspend_requested: Arc<AtomicBool>,
+    /// Whether SIGTSTP should stop the process, as it does outside init/restore.
+    suspend_default: Arc<AtomicBool>,
+    suspend_handlers_installed: AtomicBool,
+    restore_signals: Vec<Signal>,
+    /// Closed on restore to stop the thread that restores on [`TuiAppBuilder::restore_on_signals`].
+    #[cfg(unix)]
+    signal_handler: Mutex<Option<signal_hook::iterator::Handle>>,
+    /// Whether the signals should take their default action, as they do outside init/restore.
+    signals_default: Arc<AtomicBool>,
+    signal_defaults_installed: AtomicBool,
+    app_name: String,
+    use_panic_terminal_restore: bool,
+    use_color_eyre: bool,
+    color_eyre_theme: Option<Theme>,
+    use_disk_logs: bool,
+    log_dir: Option<PathBuf>,
+    log_location: LogLocation,
+    log_file_prefix: String,
+    log_rotation: LogRotation,
+    log_keep: Option<usize>,
+    log_filter: String,
+    stderr_log: Option<StderrLog>,
+    tracing: TracingConfig,
+    modes: TerminalModes,
+    enabled: TerminalModes,
+    viewport: ViewportMode,
+    non_tty: NonTtyBehavior,
+    inline_restore: InlineRestorePolicy,
+    clear_on_init: ClearOnInit,
+    welcome_banner: Option<String>,
+    goodbye_banner: Option<String>,
+    error_banner: Option<ErrorBanner>,
+    panic_report: Option<PanicReport>,
+}
+
+impl TuiApp {
+    // TODO customization points:
+    //
+    // Terminal Lifecycle
+    //
+    // - Inline mode currently forces stdout. Re-evaluate whether to support directing inline output
+    //   elsewhere without breaking existing guarantees.
+    pub fn builder(app_name: impl Into<String>) -> TuiAppBuilder {
+        TuiAppBuilder::new(app_name)
+    }
+
+    /// Construct a new application harness with default settings.
+    pub fn new(app_name: impl Into<String>) -> Self {
+        Self::builder(app_name).build()
+    }
+
+    /// Access the application name currently configured for the TUI.
+    pub fn app_name(&self) -> &str {
+        &self.app_name
+    }
+
+    /// Directory the log files are written to, once [`TuiApp::init`] has opened them.
+    pub fn log_dir(&self) -> Option<&Path> {
+        self.logger_guard.as_ref().map(|guard| guard.dir.as_path())
+    }
+
+    /// Filter directives in effect for the log file, once [`TuiApp::init`] has opened it.
+    pub fn log_filter(&self) -> Option<&str> {
+        self.logger_guard
+            .as_ref()
+            .map(|guard| guard.filter.as_str())
+    }
+
+    /// Whether [`TuiApp::init`] turned on focus events, which restoring turns off again.
+    pub fn focus_events(&self) -> bool {
+        self.enabled.focus_events
+    }
+
+    /// Keyboard enhancement flags pushed by [`TuiApp::init`]; `None` if none were requested or
+    /// the terminal does not support them.
+    pub fn keyboard_enhancement(&self) -> Option<KeyboardEnhancementFlags> {
+        self.enabled.keyboard_enhancement
+    }
+
+    /// The environment variable that overrides where this app logs; see [`log_dir_env_var`].
+    pub fn log_dir_env_var(&self) -> String {
+        log_dir_env_var(&self.app_name)
+    }
+
+    /// How long each phase of the last [`TuiApp::init`] and the restore after it took.
+    pub fn timings(&self) -> LifecycleTimings {
+        self.lock_timings().clone()
+    }
+
+    /// What the terminal answered when [`TuiApp::init`] probed it; `None` if
+    /// [`TuiAppBuilder::probe_capabilities`] is off or the terminal didn't answer.
+    pub fn capabilities(&self) -> Option<TerminalCapabilities> {
+        self.capabilities
+    }
+
+    /// The checks [`TuiAppBuilder::try_build`] shares with [`TuiApp::init`].
+    fn validate(&self) -> std::result::Result<(), BuildError> {
+        let stderr_tui = matches!(
+            self.viewport,
+            ViewportMode::AlternateScreen {
+                backend: AlternateScreenBackend::Stderr
+            }
+        );
+        if stderr_tui && self.stderr_log.is_some() {
+            return Err(BuildError::StderrConflict);
+        }
+        match self.viewport.inline_height() {
+            Some(0) => return Err(BuildError::ZeroInlineHeight),
+            Some(height) if height > MAX_INLINE_HEIGHT => {
+                return Err(BuildError::InlineHeightTooLarge(height))
+            }
+            _ => {}
+        }
+        if self.log_keep == Some(0) {
+            return Err(BuildError::ZeroLogKeep);
+        }
+        Ok(())
     }
 
     /// Install diagnostics, start logging, and return a ready-to-draw terminal.
+    ///
+    /// A configuration [`TuiAppBuilder::try_build`] would reject fails with its [`BuildError`].
+    /// Logging and size check failures leave the terminal untouched; a failure while setting the
+    /// terminal up turns off whatever was turned on, leaving the app [`LifecycleState::Restored`].
+    /// An app that is still [`LifecycleState::Active`] must be restored before it can init again.
+    ///
+    /// How long each phase took is kept in [`TuiApp::timings`], even if init fails.
     pub fn init(&mut self) -> Result<Terminal<CrosstermBackend<TerminalWriter>>> {
+        // A second session would turn every mode on again and orphan the first one's restore.
+        if self.lifecycle.get() == LifecycleState::Active {
+            bail!("Cannot init a TUI that is already active; restore it first");
+        }
+        self.lock_timings().phases.clear();
+        let mut timings = LifecycleTimings::default();
+        let terminal = self.init_timed(&mut timings);
+        // Any restore after a failure has already recorded its phases, which come after these.
+        let mut recorded = self.lock_timings();
+        timings.phases.append(&mut recorded.phases);
+        *recorded = timings;
+        terminal
+    }
+
+    fn init_timed(
+        &mut self,
+        timings: &mut LifecycleTimings,
+    ) -> Result<Terminal<CrosstermBackend<TerminalWriter>>> {
+        self.validate()?;
+        self.start_logging(timings)?;
+        self.resolve_output_stream()?;
+
+        // After logging, so a warning about an earlier install reaches the log.
         if self.use_color_eyre {
-            color_eyre::install().expect("Failed to install color-eyre");
+            install_color_eyre(self.color_eyre_theme);
+        }
+
+        if let Some(min_size) = self.min_size {
+            wait_for_min_size(self.writer(), min_size, self.min_size_behavior)?;
+        }
+        self.warn_if_inline_too_tall();
+
+        if let Some(text) = &self.welcome_banner {
+            print_banner(self.writer(), text).wrap_err("Failed to print welcome banner")?;
+        }
+
+        // A failed init turns off whatever it turned on and ends up restored.
+        self.lifecycle = Lifecycle::new(LifecycleState::Active);
+        let panic_hook = self.use_panic_terminal_restore.then(|| PanicHook {
+            lifecycle: self.lifecycle.clone(),
+            error_banner: self.error_banner.clone(),
+            report: self
+                .panic_report
+                .clone()
+                .unwrap_or_else(|| PanicReport::default_for(&self.app_name, self.log_dir())),
+        });
+        let (terminal, enabled) = match init_terminal(
+            self.viewport,
+            self.inline_restore,
+            self.clear_on_init,
+            self.modes.clone(),
+            panic_hook,
+            timings,
+        ) {
+            Ok(initialized) => initialized,
+            Err(e) => {
+                self.lifecycle.finish();
+                return Err(e);
+            }
+        };
+        self.enabled = enabled;
+        self.capabilities = None;
+        if self.probe_capabilities {
+            let phase = LifecycleTimings::start("probe");
+            self.capabilities = self.probe_terminal();
+            timings.end(phase);
+        }
+
+        #[cfg(unix)]
+        if let Err(e) = self.install_signal_handlers() {
+            if self.lifecycle.finish() {
+                let _ = self.restore_terminal();
+            }
+            return Err(e);
+        }
+        Ok(terminal)
+    }
+
+    /// Set up logging like [`TuiApp::init`], but draw on an in-memory [`TestBackend`] of
+    /// `width` by `height` instead of the terminal, so an app's draw code can be tested.
+    ///
+    /// The whole backend is the viewport, even for an inline app. Nothing is written to the
+    /// terminal and neither color-eyre nor the panic hook is installed, so there is nothing to
+    /// restore.
+    #[cfg(feature = "test-backend")]
+    pub fn init_test(&mut self, width: u16, height: u16) -> Result<Terminal<TestBackend>> {
+        self.validate()?;
+        let mut timings = LifecycleTimings::default();
+        self.start_logging(&mut timings)?;
+        *self.lock_timings() = timings;
+        Terminal::new(TestBackend::new(width, height)).wrap_err("Failed to create test terminal")
+    }
+
+    /// Start logging, unless an earlier init already did.
+    fn start_logging(&mut self, timings: &mut LifecycleTimings) -> Result<()> {
+        // An earlier init's logger carries on, rather than being replaced and losing its file.
+        if self.logger_guard.is_none() {
+            self.logger_guard = init_logging(
+                &LoggingConfig {
+                    app_name: &self.app_name,
+                    use_disk_logs: self.use_disk_logs,
+                    log_dir: self.log_dir.as_deref(),
+                    log_location: self.log_location,
+                    log_file_prefix: &self.log_file_prefix,
+                    log_rotation: self.log_rotation,
+                    log_keep: self.log_keep,
+                    log_filter: &self.log_filter,
+                    stderr: self.stderr_log,
+                    tracing: &self.tracing,
+                },
+                timings,
+            )
+            .wrap_err("Failed to initialize logging")?;
+        }
+        Ok(())
+    }
+
+    /// Install the handlers for [`TuiAppBuilder::handle_suspend`] and
+    /// [`TuiAppBuilder::restore_on_signals`].
+    #[cfg(unix)]
+    fn install_signal_handlers(&self) -> Result<()> {
+        if self.handle_suspend {
+            // Installed for good like the restore signals' defaults, since unregistering would
+            // leave SIGTSTP ignored and Ctrl-Z dead once the terminal is handed back.
+            if !self.suspend_handlers_installed.swap(true, Ordering::SeqCst) {
+                let condition = self.suspend_default.clone();
+                signal_hook::flag::register_conditional_default(libc::SIGTSTP, condition)
+                    .wrap_err("Failed to install SIGTSTP handler")?;
+                signal_hook::flag::register(libc::SIGTSTP, self.suspend_requested.clone())
+                    .wrap_err("Failed to install SIGTSTP handler")?;
+            }
+            // The flag is also raised by a SIGTSTP that stopped the process between sessions.
+            self.suspend_requested.store(false, Ordering::SeqCst);
+            self.suspend_default.store(false, Ordering::SeqCst);
+        }
+        self.install_signal_restore()
+    }
+
+    /// Start the thread that restores the terminal on [`TuiAppBuilder::restore_on_signals`].
+    #[cfg(unix)]
+    fn install_signal_restore(&self) -> Result<()> {
+        let mut handler = self
+            .signal_handler
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if self.restore_signals.is_empty() || handler.is_some() {
+            return Ok(());
+        }
+        let numbers: Vec<_> = self.restore_signals.iter().map(|s| s.number()).collect();
+        let mut signals = signal_hook::iterator::Signals::new(&numbers)
+            .wrap_err("Failed to install signal handlers")?;
+        // Unregistering leaves a signal ignored rather than defaulted, so these stay for good and
+        // take the default action whenever the thread below isn't handling the signal.
+        if !self.signal_defaults_installed.swap(true, Ordering::SeqCst) {
+            for &number in &numbers {
+                let condition = self.signals_default.clone();
+                signal_hook::flag::register_conditional_default(number, condition)
+                    .wrap_err("Failed to install signal handlers")?;
+            }
+        }
+        self.signals_default.store(false, Ordering::SeqCst);
+
+        let lifecycle = self.lifecycle.clone();
+        let (modes, viewport, inline_restore) =
+            (self.enabled.clone(), self.viewport, self.inline_restore);
+        *handler = Some(signals.handle());
+        std::thread::spawn(move || {
+            if let Some(signal) = signals.forever().next() {
+                tracing::info!("Restoring terminal on signal {}", signal);
+                if lifecycle.finish() {
+                    let timings = &mut LifecycleTimings::default();
+                    let _ = restore_terminal(&modes, viewport, inline_restore, timings);
+                }
+                let _ = signal_hook::low_level::emulate_default_handler(signal);
+            }
+        });
+        Ok(())
+    }
+
+    /// Run the capability handshake for [`TuiAppBuilder::probe_capabilities`], logging what it
+    /// finds. A probe that can't run or goes unanswered leaves the capabilities unknown.
+    fn probe_terminal(&self) -> Option<TerminalCapabilities> {
+        // Replies would be echoed and held back for a newline without raw mode, and a stream
+        // that isn't a terminal has nothing to reply. A custom writer's replies, if any, don't
+        // come back on the tty.
+        let custom = self.modes.custom_writer.is_some();
+        if !self.enabled.raw_mode || custom || !self.viewport.stream().is_terminal() {
+            tracing::debug!("Skipping the capability probe");
+            return None;
+        }
+        #[cfg(unix)]
+        match probe::probe(&mut self.viewport.writer(), PROBE_TIMEOUT) {
+            Ok(Some(capabilities)) => {
+                tracing::info!("Terminal capabilities: {:?}", capabilities);
+                Some(capabilities)
+            }
+            Ok(None) => {
+                tracing::warn!("Terminal did not answer the capability probe");
+                None
+            }
+            Err(e) => {
+                tracing::warn!("Failed to probe terminal capabilities: {}", e);
+                None
+            }
+        }
+        #[cfg(not(unix))]
+        {
+            tracing::debug!("Capability probing is only supported on unix");
+            None
+        }
+    }
+
+    /// Apply [`NonTtyBehavior`] if the stream the TUI would draw on isn't a terminal.
+    fn resolve_output_stream(&mut self) -> Result<()> {
+        if self.modes.custom_writer.is_some() {
+            tracing::info!("Drawing the TUI on a custom writer");
+            return Ok(());
+        }
+        let stream = self.viewport.stream();
+        if !stream.is_terminal() {
+            match self.non_tty {
+                NonTtyBehavior::Error => {
+                    bail!(
+                        "Cannot draw the TUI on {}, which is not a terminal",
+                        stream.name()
+                    )
+                }
+                NonTtyBehavior::FallbackToStderr => {
+                    if self.viewport.is_inline() {
+                        bail!("stdout is not a terminal, and an inline viewport cannot use stderr");
+                    }
+                    if !AlternateScreenBackend::Stderr.is_terminal() {
+                        bail!("Neither stdout nor stderr is a terminal");
+                    }
+                    tracing::info!("stdout is not a terminal; falling back to stderr");
+                    self.viewport = ViewportMode::AlternateScreen {
+                        backend: AlternateScreenBackend::Stderr,
+                    };
+                    self.validate()?;
+                }
+                NonTtyBehavior::Proceed => {
+                    tracing::warn!("{} is not a terminal; drawing on it anyway", stream.name());
+                }
+            }
+        }
+        tracing::info!("Drawing the TUI on {}", self.viewport.stream().name());
+        Ok(())
+    }
+
+    /// The stream the TUI draws on, including a [`NonTtyBehavior::FallbackToStderr`] switch
+    /// made by [`TuiApp::init`].
+    pub fn output_stream(&self) -> AlternateScreenBackend {
+        self.viewport.stream()
+    }
+
+    /// Where the TUI draws: the custom writer, or the stream from [`TuiApp::output_stream`].
+    fn writer(&self) -> TerminalWriter {
+        self.modes.writer(self.viewport)
+    }
+
+    /// The inline viewport is clamped whenever it's laid out; say so once up front.
+    fn warn_if_inline_too_tall(&self) {
+        let (ViewportMode::Inline { height }, Ok((_cols, rows))) = (self.viewport, size()) else {
+            return;
+        };
+        if height > rows {
+            tracing::warn!(
+                "Inline height {} exceeds the {} terminal rows; clamping",
+                height,
+                rows
+            );
+        }
+    }
+
+    /// Fit `terminal` to `new_size`, e.g. on [`crossterm::event::Event::Resize`], so the next
+    /// draw repaints everything.
+    ///
+    /// An inline viewport is clamped to the new row count and anchored again, after clearing
+    /// the rows it was drawn on.
+    pub fn handle_resize(
+        &mut self,
+        terminal: &mut Terminal<CrosstermBackend<TerminalWriter>>,
+        new_size: Size,
+    ) -> Result<()> {
+        tracing::debug!("Resizing to {}x{}", new_size.width, new_size.height);
+        match self.viewport.fit_to(new_size.height) {
+            ViewportMode::Inline { height } => {
+                let old_top = terminal.get_frame().area().y;
+                let top = old_top.min(new_size.height.saturating_sub(height));
+                let mut out = self.writer();
+                execute!(
+                    out,
+                    cursor::MoveTo(0, top),
+                    Clear(ClearType::FromCursorDown)
+                )
+                .wrap_err("Failed to clear the inline viewport")?;
+                *terminal = new_terminal(self.viewport, &self.enabled, ClearOnInit::Always)?;
+            }
+            ViewportMode::AlternateScreen { .. } => terminal
+                .resize(Rect::new(0, 0, new_size.width, new_size.height))
+                .wrap_err("Failed to resize terminal")?,
+        }
+        Ok(())
+    }
+
+    /// [`Terminal::draw`], bracketed in a synchronized update when
+    /// [`TuiAppBuilder::synchronized_output`] is on and a capability probe didn't rule it out.
+    pub fn draw_synced<F>(
+        &self,
+        terminal: &mut Terminal<CrosstermBackend<TerminalWriter>>,
+        render: F,
+    ) -> io::Result<()>
+    where
+        F: FnOnce(&mut Frame),
+    {
+        let supported = self.capabilities.is_none_or(|c| c.synchronized_output);
+        if !self.enabled.synchronized_output || !supported {
+            return terminal.draw(render).map(drop);
         }
+        queue!(terminal.backend_mut(), BeginSynchronizedUpdate)?;
+        // Ended before the error is returned, so a failed frame doesn't freeze the screen.
+        let drawn = terminal.draw(render).map(drop);
+        execute!(terminal.backend_mut(), EndSynchronizedUpdate)?;
+        drawn
+    }
+
+    /// Draw `lines` rows with `draw` into the scrollback above an inline viewport; rows that
+    /// don't fit on screen scroll straight into the terminal's history.
+    ///
+    /// Does nothing on the alternate screen, which has no scrollback.
+    pub fn insert_lines_above<F>(
+        &self,
+        terminal: &mut Terminal<CrosstermBackend<TerminalWriter>>,
+        lines: usize,
+        draw: F,
+    ) -> Result<()>
+    where
+        F: FnOnce(&mut Buffer),
+    {
+        let height = u16::try_from(lines).unwrap_or(u16::MAX);
+        if usize::from(height) < lines {
+            tracing::warn!(
+                "Inserting only {} of {} lines above the viewport",
+                height,
+                lines
+            );
+        }
+        terminal
+            .insert_before(height, draw)
+            .wrap_err("Failed to insert lines above the viewport")
+    }
 
-        if self.use_disk_logs {
-            self.logger_guard =
-                Some(init_file_logger(self.app_name()).expect("Failed to initialize file logger"));
+    /// Print `text` on its own line(s) to the stream the TUI does not draw on, ending lines with
+    /// `\r\n` while raw mode would otherwise leave them staircased.
+    pub fn println_passthrough(&self, text: &str) -> io::Result<()> {
+        let mut out = self.viewport.report_writer();
+        let text = text.strip_suffix('\n').unwrap_or(text);
+        if is_raw_mode_enabled()? {
+            for line in text.split('\n') {
+                out.write_all(line.strip_suffix('\r').unwrap_or(line).as_bytes())?;
+                out.write_all(b"\r\n")?;
+            }
         } else {
-            self.logger_guard = None;
+            writeln!(out, "{}", text)?;
         }
+        out.flush()
+    }
+
+    /// Where the app is in its init/restore lifecycle.
+    pub fn lifecycle_state(&self) -> LifecycleState {
+        self.lifecycle.get()
+    }
 
-        init_terminal(
+    /// Whether SIGTSTP arrived since the last call; always `false` without
+    /// [`TuiAppBuilder::handle_suspend`].
+    pub fn take_suspend_request(&self) -> bool {
+        self.suspend_requested.swap(false, Ordering::SeqCst)
+    }
+
+    /// Hand the terminal back to the shell and stop the process, as Ctrl-Z would. Once
+    /// continued, set the terminal up again and replace `terminal` with a fresh one, so the
+    /// next draw repaints everything.
+    ///
+    /// The logger and panic hook stay in place throughout.
+    #[cfg(unix)]
+    pub fn suspend(
+        &mut self,
+        terminal: &mut Terminal<CrosstermBackend<TerminalWriter>>,
+    ) -> Result<()> {
+        // Marked restored while stopped, so the panic hook leaves the terminal alone.
+        if !self.lifecycle.finish() {
+            bail!("Cannot suspend a TUI that is not active");
+        }
+        let mut timings = LifecycleTimings::default();
+        let restored = restore_terminal(
+            &self.enabled,
             self.viewport,
-            self.use_panic_terminal_restore,
-            self.capture_mouse,
-            self.hide_cursor,
-        )
+            self.inline_restore,
+            &mut timings,
+        );
+        self.lock_timings().phases.append(&mut timings.phases);
+        restored.wrap_err("Failed to restore terminal before suspending")?;
+        // Ctrl-Z works again should resuming fail and leave the terminal with the shell.
+        self.suspend_default.store(true, Ordering::SeqCst);
+
+        tracing::info!("Suspending");
+        // Stops even with the SIGTSTP handler installed; returns once continued.
+        signal_hook::low_level::emulate_default_handler(libc::SIGTSTP)
+            .wrap_err("Failed to stop the process")?;
+        tracing::info!("Resumed");
+
+        // Set up again as init would, so the timings are this resume's. Until that succeeds the
+        // app stays restored, with `enabled` still the modes it had before suspending.
+        let mut timings = LifecycleTimings::default();
+        let enabled = enter_terminal_modes(self.viewport, self.enabled.clone(), &mut timings)?;
+        let phase = LifecycleTimings::start("clear");
+        match new_terminal(self.viewport, &enabled, self.clear_on_init) {
+            Ok(new) => *terminal = new,
+            Err(e) => {
+                drop(phase);
+                let _ = leave_terminal_modes(&enabled, self.viewport, None, &mut timings);
+                *self.lock_timings() = timings;
+                return Err(e);
+            }
+        }
+        timings.end(phase);
+        *self.lock_timings() = timings;
+        self.enabled = enabled;
+        self.suspend_default
+            .store(!self.handle_suspend, Ordering::SeqCst);
+        self.lifecycle.set(LifecycleState::Active);
+        Ok(())
     }
 
-    /// Restore the terminal to its pre-initialization state.
+    /// Like [`TuiApp::init`], but the terminal is restored when the returned guard drops.
+    pub fn init_guarded(&mut self) -> Result<TuiTerminalGuard<'_>> {
+        let terminal = self.init()?;
+        Ok(TuiTerminalGuard {
+            terminal,
+            app: self,
+        })
+    }
+
+    /// Init, run `f` on the terminal, and restore whether it returns `Ok`, `Err` or panics.
+    ///
+    /// An error from `f` is shown with the error banner and returned as is.
+    pub fn run<F, T>(&mut self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Terminal<CrosstermBackend<TerminalWriter>>) -> Result<T>,
+    {
+        let mut terminal = self.init_guarded()?;
+        let result = f(&mut terminal);
+        match &result {
+            Ok(_) => terminal
+                .app()
+                .restore()
+                .wrap_err("Failed to restore terminal")?,
+            Err(e) => {
+                if let Err(restore_error) = terminal.app().restore_with_error(e) {
+                    tracing::error!("Failed to restore terminal: {}", restore_error);
+                }
+            }
+        }
+        result
+    }
+
+    /// Restore the terminal to its pre-initialization state, then print the goodbye banner.
+    /// [`TuiApp::shutdown`] also flushes the logs first.
+    ///
+    /// Does nothing unless the app is [`LifecycleState::Active`].
     pub fn restore(&self) -> io::Result<()> {
-        restore_terminal(self.capture_mouse, self.hide_cursor, self.viewport)
+        if !self.lifecycle.finish() {
+            return Ok(());
+        }
+        self.restore_terminal()?;
+        if let Some(text) = &self.goodbye_banner {
+            print_banner(self.writer(), text)?;
+        }
+        Ok(())
+    }
+
+    /// Restore the terminal because the app is exiting on `error`, then print the error banner.
+    pub fn restore_with_error(&self, error: &dyn fmt::Display) -> io::Result<()> {
+        if !self.lifecycle.finish() {
+            return Ok(());
+        }
+        self.restore_terminal()?;
+        if let Some(ErrorBanner(format)) = &self.error_banner {
+            print_banner(self.writer(), &format(&error.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Restore the terminal if still active, flush and close the log file, then print the
+    /// goodbye banner, for an app about to exit. Logging is buffered, so without this the last
+    /// lines are lost by [`std::process::exit`] or an abort, which skip destructors.
+    ///
+    /// Unlike [`TuiApp::restore`], the banner waits until the logs are on disk, and is only
+    /// printed if this call restored the terminal. Every step is attempted; the failures are
+    /// reported together.
+    pub fn shutdown(mut self) -> Result<()> {
+        let mut errors = Vec::new();
+        let restored = self.lifecycle.finish();
+        if restored {
+            if let Err(e) = self.restore_terminal() {
+                errors.push(format!("Failed to restore terminal: {}", e));
+            }
+        }
+
+        tracing::debug!("Shutting down");
+        // Blocks until the writer thread has written every buffered line.
+        drop(self.logger_guard.take());
+
+        if let (true, Some(text)) = (restored, &self.goodbye_banner) {
+            if let Err(e) = print_banner(self.writer(), text) {
+                errors.push(format!("Failed to print goodbye banner: {}", e));
+            }
+        }
+        if !errors.is_empty() {
+            bail!("{}", errors.join("; "));
+        }
+        Ok(())
+    }
+
+    fn restore_terminal(&self) -> io::Result<()> {
+        end_panic_session(&self.lifecycle);
+        self.suspend_default.store(true, Ordering::SeqCst);
+        #[cfg(unix)]
+        let handle = self
+            .signal_handler
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        if let Some(handle) = handle {
+            self.signals_default.store(true, Ordering::SeqCst);
+            handle.close();
+        }
+        let mut timings = LifecycleTimings::default();
+        let restored = restore_terminal(
+            &self.enabled,
+            self.viewport,
+            self.inline_restore,
+            &mut timings,
+        );
+        self.lock_timings().phases.append(&mut timings.phases);
+        restored
+    }
+
+    fn lock_timings(&self) -> std::sync::MutexGuard<'_, LifecycleTimings> {
+        self.timings.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Drop for TuiApp {
+    /// Best-effort restore for an app dropped while still active, without the goodbye banner.
+    fn drop(&mut self) {
+        if self.lifecycle.finish() {
+            if let Err(e) = self.restore_terminal() {
+                tracing::error!("Failed to restore terminal on drop: {}", e);
+            }
+        }
+    }
+}
+
+/// The terminal from [`TuiApp::init_guarded`], restored via [`TuiApp::restore`] on drop.
+pub struct TuiTerminalGuard<'a> {
+    terminal: Terminal<CrosstermBackend<TerminalWriter>>,
+    app: &'a TuiApp,
+}
+
+impl TuiTerminalGuard<'_> {
+    /// The app this guard restores, e.g. to restore early with [`TuiApp::restore_with_error`].
+    pub fn app(&self) -> &TuiApp {
+        self.app
+    }
+}
+
+impl Deref for TuiTerminalGuard<'_> {
+    type Target = Terminal<CrosstermBackend<TerminalWriter>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.terminal
+    }
+}
+
+impl DerefMut for TuiTerminalGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.terminal
+    }
+}
+
+impl Drop for TuiTerminalGuard<'_> {
+    fn drop(&mut self) {
+        if let Err(e) = self.app.restore() {
+            tracing::error!("Failed to restore terminal: {}", e);
+        }
     }
 }
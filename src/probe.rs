@@ -0,0 +1,193 @@
+#[cfg(unix)]
+use std::io::{self, Write};
+use std::time::Duration;
+
+/// How long to wait for the terminal to answer [`QUERIES`].
+pub const PROBE_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Queries for each capability, then primary device attributes (DA1). Every terminal answers
+/// DA1, and in order, so its reply marks the end of the replies to come.
+pub const QUERIES: &[u8] = b"\x1b[?u\x1b[?2026$p\x1b[?2004$p\x1b[c";
+
+/// Longest reply kept while waiting for its final byte; anything longer is not one of ours.
+const MAX_REPLY: usize = 64;
+
+/// What the terminal said it supports when probed during init.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TerminalCapabilities {
+    /// Answered the kitty keyboard protocol's flags query.
+    pub kitty_keyboard: bool,
+    /// Recognized synchronized output (mode 2026).
+    pub synchronized_output: bool,
+    /// Recognized bracketed paste (mode 2004).
+    pub bracketed_paste: bool,
+    /// `COLORTERM` advertises 24-bit color, as terminals have no common query for it.
+    pub truecolor: bool,
+}
+
+/// Collects the terminal's replies to [`QUERIES`] a byte at a time.
+///
+/// Bytes outside a CSI reply, such as keys typed during the probe, are dropped.
+#[derive(Debug, Clone, Default)]
+pub struct ProbeReplies {
+    reply: Vec<u8>,
+    capabilities: TerminalCapabilities,
+    complete: bool,
+}
+
+impl ProbeReplies {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take the next byte read from the terminal, returning whether the DA1 reply has now ended
+    /// the probe.
+    pub fn push(&mut self, byte: u8) -> bool {
+        if self.complete {
+            return true;
+        }
+        self.reply.push(byte);
+        match self.reply.as_slice() {
+            [0x1b] | [0x1b, b'['] => {}
+            [0x1b, b'[', .., 0x40..=0x7e] => {
+                self.finish_reply();
+                self.reply.clear();
+            }
+            [0x1b, b'[', ..] if self.reply.len() < MAX_REPLY => {}
+            _ => {
+                self.reply.clear();
+                // An interrupted sequence may be followed straight away by a reply.
+                if byte == 0x1b {
+                    self.reply.push(byte);
+                }
+            }
+        }
+        self.complete
+    }
+
+    /// Whether the DA1 reply has arrived.
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// The capabilities replied so far, with [`TerminalCapabilities::truecolor`] left unset.
+    pub fn capabilities(&self) -> TerminalCapabilities {
+        self.capabilities
+    }
+
+    fn finish_reply(&mut self) {
+        let (&last, params) = self.reply[2..].split_last().unwrap();
+        let Some(params) = params.strip_prefix(b"?") else {
+            return;
+        };
+        match last {
+            b'u' => self.capabilities.kitty_keyboard = true,
+            b'c' => self.complete = true,
+            b'y' => {
+                let Some(report) = params.strip_suffix(b"$") else {
+                    return;
+                };
+                // DECRPM: 1 and 2 are set and reset, 3 is permanently set; 0 and 4 mean the mode
+                // is unknown or can never be turned on.
+                let mut fields = report.split(|&b| b == b';');
+                let (mode, value) = (fields.next(), fields.next());
+                let recognized = matches!(value, Some(b"1" | b"2" | b"3"));
+                match mode {
+                    Some(b"2026") => self.capabilities.synchronized_output = recognized,
+                    Some(b"2004") => self.capabilities.bracketed_paste = recognized,
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Whether `COLORTERM` says the terminal takes 24-bit color.
+pub fn colorterm_truecolor() -> bool {
+    std::env::var("COLORTERM").is_ok_and(|value| value == "truecolor" || value == "24bit")
+}
+
+/// How many colors the terminal can show, from fewest to most.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ColorDepth {
+    /// No color at all, as for `TERM=dumb` or an unset `TERM`.
+    Monochrome,
+    /// The 8 standard colors and their bright variants.
+    Ansi16,
+    /// The xterm 256-color palette.
+    Ansi256,
+    /// 24-bit RGB.
+    TrueColor,
+}
+
+/// The color depth the environment advertises; see [`color_depth_from`].
+pub fn color_depth() -> ColorDepth {
+    color_depth_from(|name| std::env::var(name).ok())
+}
+
+/// The color depth advertised by the variables `var` looks up.
+///
+/// `COLORTERM=truecolor` (or `24bit`) wins, then `TERM`: a `-direct` suffix means 24-bit, a
+/// `256color` one 256 colors, `dumb` or nothing no color, and anything else 16 colors.
+pub fn color_depth_from(var: impl Fn(&str) -> Option<String>) -> ColorDepth {
+    if var("COLORTERM").is_some_and(|value| value == "truecolor" || value == "24bit") {
+        return ColorDepth::TrueColor;
+    }
+    match var("TERM").as_deref() {
+        None | Some("" | "dumb") => ColorDepth::Monochrome,
+        Some(term) if term.ends_with("-direct") => ColorDepth::TrueColor,
+        Some(term) if term.ends_with("256color") => ColorDepth::Ansi256,
+        Some(_) => ColorDepth::Ansi16,
+    }
+}
+
+/// Send [`QUERIES`] to `out` and read the replies from the controlling terminal, which must be
+/// in raw mode so they arrive unechoed and without waiting for a newline.
+///
+/// Reads stop at the end of the DA1 reply, leaving any input after it for the app. Returns
+/// `None` if the terminal doesn't finish replying within `timeout`.
+#[cfg(unix)]
+pub fn probe(out: &mut impl Write, timeout: Duration) -> io::Result<Option<TerminalCapabilities>> {
+    use nix::errno::Errno;
+    use nix::poll::{poll, PollFd, PollFlags, PollTimeout};
+    use std::io::Read;
+    use std::os::fd::AsFd;
+    use std::time::Instant;
+
+    let mut tty = std::fs::File::open("/dev/tty")?;
+    out.write_all(QUERIES)?;
+    out.flush()?;
+
+    let deadline = Instant::now() + timeout;
+    let mut replies = ProbeReplies::new();
+    let mut byte = [0u8];
+    while !replies.is_complete() {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let millis = u16::try_from(remaining.as_millis()).unwrap_or(u16::MAX);
+        if remaining.is_zero() {
+            return Ok(None);
+        }
+        let mut fds = [PollFd::new(tty.as_fd(), PollFlags::POLLIN)];
+        match poll(&mut fds, PollTimeout::from(millis)) {
+            Ok(0) => return Ok(None),
+            Ok(_) => {}
+            Err(Errno::EINTR) => continue,
+            Err(e) => return Err(e.into()),
+        }
+        let revents = fds[0].revents().unwrap_or(PollFlags::empty());
+        // Hung up rather than readable.
+        if !revents.contains(PollFlags::POLLIN) {
+            return Ok(None);
+        }
+        // One byte at a time, so nothing past the DA1 reply is taken from the app.
+        if tty.read(&mut byte)? == 0 {
+            return Ok(None);
+        }
+        replies.push(byte[0]);
+    }
+    Ok(Some(TerminalCapabilities {
+        truecolor: colorterm_truecolor(),
+        ..replies.capabilities()
+    }))
+}
@@ -0,0 +1,1254 @@
+#[cfg(unix)]
+use crate::clock::{Clock, SystemClock};
+use crate::latency::InputLatency;
+#[cfg(unix)]
+use crate::session::SessionRecorder;
+use crate::throughput::Throughput;
+#[cfg(unix)]
+use nix::errno::Errno;
+#[cfg(unix)]
+use nix::poll::{poll, PollFd, PollFlags, PollTimeout};
+use std::collections::VecDeque;
+#[cfg(unix)]
+use std::fs::File;
+use std::io;
+#[cfg(unix)]
+use std::io::{ErrorKind, Read, Write};
+#[cfg(unix)]
+use std::os::fd::{AsFd, AsRawFd};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+#[cfg(unix)]
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// A framed input event together with when its bytes arrived.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawEvent {
+    pub bytes: Vec<u8>,
+    pub first_byte_at: Instant,
+    pub last_byte_at: Instant,
+    /// The bytes hit a buffer cap before forming a complete sequence and were cut off.
+    pub oversized: bool,
+    /// A chunk of bracketed paste content, delivered in pieces of at most [`PASTE_CHUNK`] bytes
+    /// rather than framed as keys.
+    pub paste: bool,
+}
+
+impl RawEvent {
+    /// Time between the first and last byte of the event; non-zero for split sequences.
+    pub fn spread(&self) -> Duration {
+        self.last_byte_at
+            .saturating_duration_since(self.first_byte_at)
+    }
+}
+
+/// How long to wait for more bytes before flushing a partial sequence as-is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlushTimeouts {
+    /// Applied when the buffer holds only a lone ESC, which is usually the Escape key.
+    pub esc_timeout: Duration,
+    /// Applied when a multi-byte sequence is partially buffered, e.g. split over a slow link.
+    pub sequence_timeout: Duration,
+}
+
+impl FlushTimeouts {
+    /// The timeout that applies to `buffer`'s contents.
+    pub fn for_buffer(&self, buffer: &[u8]) -> Duration {
+        if buffer == [0x1B] {
+            self.esc_timeout
+        } else {
+            self.sequence_timeout
+        }
+    }
+}
+
+impl Default for FlushTimeouts {
+    fn default() -> Self {
+        Self {
+            esc_timeout: Duration::from_millis(10),
+            sequence_timeout: Duration::from_millis(150),
+        }
+    }
+}
+
+/// A single timeout used for both lone ESC and partial sequences.
+impl From<Duration> for FlushTimeouts {
+    fn from(timeout: Duration) -> Self {
+        Self {
+            esc_timeout: timeout,
+            sequence_timeout: timeout,
+        }
+    }
+}
+
+/// Outcome of a single [`InputSource::poll_next`] call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PollResult {
+    /// A complete (or flushed partial) event.
+    Event(RawEvent),
+    /// The timeout passed with nothing buffered.
+    Idle,
+    /// No event completed before the timeout, but a partial sequence is buffered, waiting for
+    /// more bytes or its flush timeout.
+    Pending { buffered: usize },
+    /// The terminal was resized (SIGWINCH) while waiting; see [`RawInputReader::watch_resize`].
+    Resize,
+    /// A termination signal arrived; see [`RawInputReader::watch_shutdown`].
+    Shutdown(libc::c_int),
+    /// The input closed and every buffered byte has been delivered.
+    Eof,
+}
+
+impl PollResult {
+    /// The event, if this result carries one.
+    pub fn into_event(self) -> Option<RawEvent> {
+        match self {
+            Self::Event(bytes) => Some(bytes),
+            Self::Idle | Self::Pending { .. } | Self::Resize | Self::Shutdown(_) | Self::Eof => {
+                None
+            }
+        }
+    }
+
+    /// The raw bytes of the event, if this result carries one.
+    pub fn event_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Self::Event(event) => Some(&event.bytes),
+            Self::Idle | Self::Pending { .. } | Self::Resize | Self::Shutdown(_) | Self::Eof => {
+                None
+            }
+        }
+    }
+}
+
+/// Anything that yields framed raw input events the way the live reader does.
+pub trait InputSource {
+    /// Wait up to `timeout` for the next complete event.
+    fn poll_next(&mut self, timeout: Duration) -> io::Result<PollResult>;
+
+    /// Wait up to `timeout` once, then append every event that is ready to `events` in order.
+    ///
+    /// Returns what ended the batch: never [`PollResult::Event`], and [`PollResult::Idle`] or
+    /// [`PollResult::Pending`] when the batch simply ran out of ready events.
+    fn poll_batch(
+        &mut self,
+        timeout: Duration,
+        events: &mut Vec<RawEvent>,
+    ) -> io::Result<PollResult> {
+        let mut result = self.poll_next(timeout)?;
+        while let PollResult::Event(event) = result {
+            events.push(event);
+            result = self.poll_next(Duration::ZERO)?;
+        }
+        Ok(result)
+    }
+
+    /// Stop (or resume) consuming input, leaving unread bytes with the underlying source.
+    ///
+    /// Sources that cannot pause ignore this.
+    fn set_paused(&mut self, _paused: bool) {}
+
+    /// Bytes held back as an incomplete sequence, without consuming them.
+    fn pending_bytes(&self) -> &[u8] {
+        &[]
+    }
+
+    /// Latency statistics, for sources that measure them.
+    fn latency(&self) -> Option<&InputLatency> {
+        None
+    }
+
+    /// Byte and event counters, for sources that keep them.
+    fn throughput(&self) -> Option<&Throughput> {
+        None
+    }
+
+    /// Iterate over events until EOF, the first error, or `deadline` (if any) passes.
+    ///
+    /// Resizes are skipped; use [`InputSource::poll_next`] directly to observe them.
+    fn events(&mut self, deadline: Option<Instant>) -> Events<'_, Self>
+    where
+        Self: Sized,
+    {
+        Events {
+            source: self,
+            deadline,
+            done: false,
+        }
+    }
+}
+
+impl<S: InputSource + ?Sized> InputSource for Box<S> {
+    fn poll_next(&mut self, timeout: Duration) -> io::Result<PollResult> {
+        (**self).poll_next(timeout)
+    }
+
+    fn poll_batch(
+        &mut self,
+        timeout: Duration,
+        events: &mut Vec<RawEvent>,
+    ) -> io::Result<PollResult> {
+        (**self).poll_batch(timeout, events)
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        (**self).set_paused(paused)
+    }
+
+    fn pending_bytes(&self) -> &[u8] {
+        (**self).pending_bytes()
+    }
+
+    fn latency(&self) -> Option<&InputLatency> {
+        (**self).latency()
+    }
+
+    fn throughput(&self) -> Option<&Throughput> {
+        (**self).throughput()
+    }
+}
+
+/// Iterator returned by [`InputSource::events`].
+pub struct Events<'a, S: ?Sized> {
+    source: &'a mut S,
+    deadline: Option<Instant>,
+    done: bool,
+}
+
+impl<S: InputSource + ?Sized> Iterator for Events<'_, S> {
+    type Item = io::Result<RawEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        /// Upper bound on a single wait when there is no deadline.
+        const MAX_WAIT: Duration = Duration::from_secs(1);
+
+        while !self.done {
+            let timeout = match self.deadline {
+                Some(deadline) => deadline.saturating_duration_since(Instant::now()),
+                None => MAX_WAIT,
+            };
+            match self.source.poll_next(timeout) {
+                Ok(PollResult::Event(event)) => return Some(Ok(event)),
+                Ok(PollResult::Resize) => {}
+                Ok(PollResult::Shutdown(_)) => self.done = true,
+                Ok(PollResult::Idle | PollResult::Pending { .. }) => {
+                    self.done = self
+                        .deadline
+                        .is_some_and(|deadline| Instant::now() >= deadline);
+                }
+                Ok(PollResult::Eof) => self.done = true,
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Largest chunk read from the source per poll.
+#[cfg(unix)]
+const READ_CHUNK: usize = 8 * 1024;
+
+/// What a [`ByteSource`] saw while waiting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Readiness {
+    /// A read will not block; it may report EOF.
+    Readable,
+    /// Nothing happened before the timeout (or the wait was woken early).
+    Timeout,
+    /// The terminal was resized.
+    Resize,
+    /// A termination signal arrived.
+    Shutdown(libc::c_int),
+}
+
+/// The raw byte stream under a [`RawInputReader`], split from framing so it can be scripted.
+pub trait ByteSource {
+    /// Wait up to `timeout` for bytes (or a notification) to become available.
+    fn wait_readable(&mut self, timeout: Duration) -> io::Result<Readiness>;
+
+    /// Read available bytes into `buf`, returning `Ok(0)` at EOF.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>;
+
+    /// Stop (or resume) reporting the byte stream as readable; notifications still arrive.
+    fn set_paused(&mut self, _paused: bool) {}
+}
+
+/// The real [`ByteSource`]: a pollable reader plus optional signal and wake-up pipes.
+#[cfg(unix)]
+pub struct FdSource<F> {
+    source: F,
+    fd: libc::c_int,
+    /// Check `FIONREAD` before reading so a stale wakeup can't block on an empty tty.
+    check_available: bool,
+    hung_up: bool,
+    paused: bool,
+    resize_watch: Option<SignalWatch>,
+    shutdown_watches: Vec<SignalWatch>,
+    wake: Option<UnixStream>,
+}
+
+#[cfg(unix)]
+impl<F: AsFd + Read> FdSource<F> {
+    /// Poll and read `source`, which must stay open for the source's lifetime.
+    pub fn new(source: F) -> Self {
+        let fd = source.as_fd().as_raw_fd();
+        Self {
+            source,
+            fd,
+            check_available: unsafe { libc::isatty(fd) } == 1,
+            hung_up: false,
+            paused: false,
+            resize_watch: None,
+            shutdown_watches: Vec::new(),
+            wake: None,
+        }
+    }
+
+    /// The underlying reader.
+    pub fn get_ref(&self) -> &F {
+        &self.source
+    }
+}
+
+#[cfg(unix)]
+impl<F: AsFd + Read> ByteSource for FdSource<F> {
+    fn wait_readable(&mut self, timeout: Duration) -> io::Result<Readiness> {
+        let mut fds = Vec::new();
+        // While paused the source stays out of the poll set so its bytes wait in the kernel.
+        let source_index = (!self.paused).then(|| {
+            fds.push(PollFd::new(self.source.as_fd(), PollFlags::POLLIN));
+            fds.len() - 1
+        });
+        let resize_index = self.resize_watch.as_ref().map(|watch| {
+            fds.push(PollFd::new(watch.read.as_fd(), PollFlags::POLLIN));
+            fds.len() - 1
+        });
+        let wake_index = self.wake.as_ref().map(|wake| {
+            fds.push(PollFd::new(wake.as_fd(), PollFlags::POLLIN));
+            fds.len() - 1
+        });
+        let shutdown_start = fds.len();
+        for watch in &self.shutdown_watches {
+            fds.push(PollFd::new(watch.read.as_fd(), PollFlags::POLLIN));
+        }
+
+        let poll_timeout_ms = duration_to_poll_timeout(timeout);
+        let poll_timeout = PollTimeout::try_from(poll_timeout_ms).unwrap_or(PollTimeout::MAX);
+        let res = loop {
+            match poll(&mut fds, poll_timeout) {
+                Ok(res) => break res,
+                Err(Errno::EINTR) => {
+                    tracing::warn!(
+                        target: "raw_input_reader",
+                        fd = self.fd,
+                        timeout_ms = poll_timeout_ms,
+                        "poll interrupted, retrying"
+                    );
+                    continue;
+                }
+                Err(errno) => {
+                    let errno_value = errno as i32;
+                    let io_err: io::Error = errno.into();
+                    tracing::error!(
+                        target: "raw_input_reader",
+                        fd = self.fd,
+                        timeout_ms = poll_timeout_ms,
+                        errno = errno_value,
+                        kind = ?io_err.kind(),
+                        "poll failed"
+                    );
+                    return Err(io_err);
+                }
+            }
+        };
+
+        if res == 0 {
+            return Ok(Readiness::Timeout);
+        }
+
+        let readable = |index: Option<usize>| {
+            index
+                .and_then(|index| fds[index].revents())
+                .is_some_and(|revents| revents.contains(PollFlags::POLLIN))
+        };
+        let resized = readable(resize_index);
+        let woken = readable(wake_index);
+        let shutdown =
+            (0..self.shutdown_watches.len()).find(|offset| readable(Some(shutdown_start + offset)));
+        let source_revents = source_index
+            .and_then(|index| fds[index].revents())
+            .unwrap_or(PollFlags::empty());
+        drop(fds);
+
+        if woken {
+            if let Some(wake) = self.wake.as_mut() {
+                drain(wake);
+            }
+        }
+
+        if let Some(offset) = shutdown {
+            let watch = &mut self.shutdown_watches[offset];
+            drain(&mut watch.read);
+            tracing::info!(target: "raw_input_reader", signal = watch.signal, "shutdown requested");
+            return Ok(Readiness::Shutdown(watch.signal));
+        }
+
+        if resized {
+            if let Some(watch) = self.resize_watch.as_mut() {
+                drain(&mut watch.read);
+            }
+            return Ok(Readiness::Resize);
+        }
+
+        self.hung_up = source_revents.contains(PollFlags::POLLHUP);
+        // Read on hangup too: a buffered source like stdin may still hold bytes the fd
+        // no longer reports.
+        if source_revents.intersects(PollFlags::POLLIN | PollFlags::POLLHUP) {
+            Ok(Readiness::Readable)
+        } else {
+            Ok(Readiness::Timeout)
+        }
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // The tty is usually blocking and shares its file status flags with stdout, so rather
+        // than setting O_NONBLOCK, only read once the kernel reports bytes waiting. Any read
+        // then returns whatever is there without blocking for more.
+        if self.check_available && !self.hung_up && bytes_available(self.fd) == Some(0) {
+            return Err(ErrorKind::WouldBlock.into());
+        }
+        match self.source.read(buf) {
+            // A PTY whose other side closed reports EIO rather than EOF.
+            Err(err) if self.hung_up && err.raw_os_error() == Some(libc::EIO) => Ok(0),
+            result => result,
+        }
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+}
+
+/// How many bytes can be read from `fd` without blocking, if the fd supports asking.
+#[cfg(unix)]
+fn bytes_available(fd: libc::c_int) -> Option<usize> {
+    let mut available: libc::c_int = 0;
+    let res = unsafe { libc::ioctl(fd, libc::FIONREAD, &mut available) };
+    (res != -1).then_some(available.max(0) as usize)
+}
+
+/// Reads a [`ByteSource`] and frames it into escape-sequence-aware events.
+///
+/// Incomplete sequences are held until they complete or until the applicable [`FlushTimeouts`]
+/// entry passes without new bytes, at which point whatever is buffered is emitted as-is. The
+/// source defaults to stdin but can be any pollable reader, such as `/dev/tty` or one side of a
+/// PTY, or a scripted source driven by a virtual clock in tests.
+#[cfg(unix)]
+pub struct RawInputReader<S = FdSource<io::Stdin>, C = SystemClock> {
+    source: S,
+    clock: C,
+    framer: Framer,
+    recorder: Option<SessionRecorder>,
+    closed: bool,
+    latency: InputLatency,
+    throughput: Throughput,
+    paused: bool,
+    /// A resize or shutdown that ended a [`RawInputReader::poll_until`] early.
+    deferred: Option<PollResult>,
+}
+
+#[cfg(unix)]
+impl RawInputReader {
+    /// Create a reader over stdin that flushes partial sequences after `flush_timeout`.
+    pub fn new(flush_timeout: impl Into<FlushTimeouts>) -> io::Result<Self> {
+        Ok(Self::with_source(io::stdin(), flush_timeout))
+    }
+}
+
+#[cfg(unix)]
+impl RawInputReader<FdSource<File>> {
+    /// Open `path` (for example `/dev/tty` when stdin is redirected) and read from it.
+    pub fn from_path(
+        path: impl AsRef<Path>,
+        flush_timeout: impl Into<FlushTimeouts>,
+    ) -> io::Result<Self> {
+        let file = File::options().read(true).write(true).open(path)?;
+        Ok(Self::with_source(file, flush_timeout))
+    }
+}
+
+#[cfg(unix)]
+impl<F: AsFd + Read> RawInputReader<FdSource<F>> {
+    /// Read from `source`, which must stay open for the reader's lifetime.
+    pub fn with_source(source: F, flush_timeout: impl Into<FlushTimeouts>) -> Self {
+        Self::with_byte_source(FdSource::new(source), SystemClock, flush_timeout)
+    }
+
+    /// Install a SIGWINCH handler and report resizes from `poll_next` as [`PollResult::Resize`].
+    pub fn watch_resize(&mut self) -> io::Result<()> {
+        if self.source.resize_watch.is_none() {
+            self.source.resize_watch = Some(SignalWatch::new(libc::SIGWINCH)?);
+        }
+        Ok(())
+    }
+
+    /// Catch SIGTERM, SIGHUP and SIGINT and report them from `poll_next` as
+    /// [`PollResult::Shutdown`] instead of letting them kill the process mid-raw-mode.
+    pub fn watch_shutdown(&mut self) -> io::Result<()> {
+        if self.source.shutdown_watches.is_empty() {
+            self.source.shutdown_watches = [libc::SIGTERM, libc::SIGHUP, libc::SIGINT]
+                .into_iter()
+                .map(SignalWatch::new)
+                .collect::<io::Result<_>>()?;
+        }
+        Ok(())
+    }
+
+    /// A handle that interrupts a blocked `poll_next` from another thread; the call reports
+    /// [`PollResult::Idle`].
+    pub fn waker(&mut self) -> io::Result<Waker> {
+        let (read, write) = UnixStream::pair()?;
+        read.set_nonblocking(true)?;
+        write.set_nonblocking(true)?;
+        self.source.wake = Some(read);
+        Ok(Waker { write })
+    }
+
+    /// The source this reader polls and reads from.
+    pub fn source(&self) -> &F {
+        self.source.get_ref()
+    }
+}
+
+#[cfg(unix)]
+impl<S: ByteSource, C: Clock> RawInputReader<S, C> {
+    /// Frame bytes from any [`ByteSource`], timing flushes against `clock`.
+    pub fn with_byte_source(source: S, clock: C, flush_timeout: impl Into<FlushTimeouts>) -> Self {
+        Self {
+            source,
+            clock,
+            framer: Framer::new(flush_timeout),
+            recorder: None,
+            closed: false,
+            latency: InputLatency::default(),
+            throughput: Throughput::default(),
+            paused: false,
+            deferred: None,
+        }
+    }
+
+    /// Stop reading input: bytes stay in the kernel buffer and already-framed events are held
+    /// until [`RawInputReader::resume`]. Resizes and shutdown signals are still reported.
+    ///
+    /// A paused reader never sees a key asking it to resume, so resuming has to come from
+    /// elsewhere, such as a timer. To toggle capture from the keyboard, keep reading and drop
+    /// events while paused instead.
+    pub fn pause(&mut self) {
+        self.set_paused(true);
+    }
+
+    /// Pick up reading where [`RawInputReader::pause`] left off.
+    pub fn resume(&mut self) {
+        self.set_paused(false);
+    }
+
+    /// Whether input is currently paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Whether the source reported EOF or hung up.
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Write every byte read from the source to `recorder` as it arrives.
+    pub fn record_to(&mut self, recorder: SessionRecorder) {
+        self.recorder = Some(recorder);
+    }
+
+    /// The current flush timeouts.
+    pub fn flush_timeouts(&self) -> FlushTimeouts {
+        self.framer.timeouts
+    }
+
+    /// Use `flush_timeout` for both lone ESC and partial sequences; applies to the bytes
+    /// already buffered too.
+    pub fn set_flush_timeout(&mut self, flush_timeout: impl Into<FlushTimeouts>) {
+        self.framer.timeouts = flush_timeout.into();
+    }
+
+    /// How long a lone ESC waits before it is reported as the Escape key.
+    pub fn esc_timeout(&self) -> Duration {
+        self.framer.timeouts.esc_timeout
+    }
+
+    /// Change how long a lone ESC waits before it is flushed.
+    pub fn set_esc_timeout(&mut self, esc_timeout: Duration) {
+        self.framer.timeouts.esc_timeout = esc_timeout;
+    }
+
+    /// How long a partially buffered multi-byte sequence waits before it is flushed.
+    pub fn sequence_timeout(&self) -> Duration {
+        self.framer.timeouts.sequence_timeout
+    }
+
+    /// Change how long a partial multi-byte sequence waits before it is flushed.
+    pub fn set_sequence_timeout(&mut self, sequence_timeout: Duration) {
+        self.framer.timeouts.sequence_timeout = sequence_timeout;
+    }
+
+    /// The cap on buffered bytes of one incomplete sequence.
+    pub fn max_pending(&self) -> usize {
+        self.framer.max_pending
+    }
+
+    /// Change the pending-buffer cap; oversized sequences are emitted with
+    /// [`RawEvent::oversized`] set.
+    pub fn set_max_pending(&mut self, max_pending: usize) {
+        self.framer.max_pending = max_pending.max(1);
+    }
+
+    /// Whether `ESC ]` frames as an OSC reply, up to its BEL or ST terminator.
+    pub fn frames_osc(&self) -> bool {
+        self.framer.frame_osc
+    }
+
+    /// Frame `ESC ]` as an OSC reply instead of Alt+], while answers to a query sent outside
+    /// [`RawInputReader::query`] are expected, such as a [`crate::theme::PendingThemeQuery`].
+    pub fn set_frames_osc(&mut self, frames_osc: bool) {
+        self.framer.frame_osc = frames_osc;
+    }
+
+    /// Number of bytes currently held back as an incomplete sequence.
+    pub fn pending_len(&self) -> usize {
+        self.framer.buffer.len()
+    }
+
+    /// Feed bytes through the framer as if they had just been read from the source.
+    pub fn push_bytes(&mut self, bytes: &[u8]) {
+        self.framer.push_bytes(bytes, self.clock.now());
+    }
+
+    /// Take the next framed event without touching the source, flushing a stale partial
+    /// sequence.
+    pub fn next_pending(&mut self) -> Option<RawEvent> {
+        let event = self.framer.next_pending(self.clock.now());
+        self.emitted(event)
+    }
+
+    /// Latency the reader has added to the events it returned so far.
+    pub fn latency(&self) -> &InputLatency {
+        &self.latency
+    }
+
+    /// Bytes read from the source and events returned so far, with their recent rates.
+    pub fn throughput(&self) -> &Throughput {
+        &self.throughput
+    }
+
+    /// Collect every event that completes before `deadline`, flushing partial sequences whose
+    /// timeout falls inside it.
+    ///
+    /// A resize, shutdown signal or EOF ends the wait early; the next
+    /// [`InputSource::poll_next`] reports it.
+    pub fn poll_until(&mut self, deadline: Instant) -> io::Result<Vec<RawEvent>> {
+        let mut events = Vec::new();
+        loop {
+            let remaining = deadline.saturating_duration_since(self.clock.now());
+            match self.poll_batch(remaining, &mut events)? {
+                PollResult::Idle | PollResult::Pending { .. } if self.clock.now() < deadline => {}
+                PollResult::Idle | PollResult::Pending { .. } | PollResult::Eof => {
+                    return Ok(events)
+                }
+                result @ (PollResult::Resize | PollResult::Shutdown(_)) => {
+                    self.deferred = Some(result);
+                    return Ok(events);
+                }
+                PollResult::Event(event) => events.push(event),
+            }
+        }
+    }
+
+    /// Write `request` to `out` and wait up to `timeout` for the first event `matches` accepts,
+    /// returning it out-of-band instead of as input.
+    ///
+    /// Use this for terminal queries (such as OSC 11 for the background color) once the reader
+    /// owns the input: the reply would otherwise be read as a bogus key. Events that arrive
+    /// meanwhile stay queued, in order, for the next poll, as does a resize or shutdown. Input
+    /// is read for the query even while paused, and `ESC ]` frames as an OSC reply rather than
+    /// Alt+]. `None` means no reply came in time, or a shutdown signal cut the wait short.
+    pub fn query(
+        &mut self,
+        out: &mut impl Write,
+        request: &[u8],
+        matches: impl Fn(&[u8]) -> bool,
+        timeout: Duration,
+    ) -> io::Result<Option<Vec<u8>>> {
+        out.write_all(request)?;
+        out.flush()?;
+
+        let deadline = self.clock.now() + timeout;
+        self.source.set_paused(false);
+        let frames_osc = std::mem::replace(&mut self.framer.frame_osc, true);
+        let reply = self.read_reply(&matches, deadline);
+        self.framer.frame_osc = frames_osc;
+        self.source.set_paused(self.paused);
+        reply
+    }
+
+    /// Like [`RawInputReader::query`], but for requests with several replies: collects every
+    /// event `matches` accepts, in order, until one of them also satisfies `last` or `timeout`
+    /// passes.
+    pub fn query_all(
+        &mut self,
+        out: &mut impl Write,
+        request: &[u8],
+        matches: impl Fn(&[u8]) -> bool,
+        last: impl Fn(&[u8]) -> bool,
+        timeout: Duration,
+    ) -> io::Result<Vec<Vec<u8>>> {
+        out.write_all(request)?;
+        out.flush()?;
+
+        let deadline = self.clock.now() + timeout;
+        self.source.set_paused(false);
+        let frames_osc = std::mem::replace(&mut self.framer.frame_osc, true);
+        let mut replies = Vec::new();
+        let result = loop {
+            match self.read_reply(&matches, deadline) {
+                Ok(Some(reply)) => {
+                    let done = last(&reply);
+                    replies.push(reply);
+                    if done {
+                        break Ok(replies);
+                    }
+                }
+                Ok(None) => break Ok(replies),
+                Err(err) => break Err(err),
+            }
+        };
+        self.framer.frame_osc = frames_osc;
+        self.source.set_paused(self.paused);
+        result
+    }
+
+    /// Read until a framed event satisfies `matches` and take it out of the queue.
+    fn read_reply(
+        &mut self,
+        matches: &impl Fn(&[u8]) -> bool,
+        deadline: Instant,
+    ) -> io::Result<Option<Vec<u8>>> {
+        loop {
+            if let Some(idx) = self
+                .framer
+                .ready
+                .iter()
+                .position(|event| matches(&event.bytes))
+            {
+                return Ok(self.framer.ready.remove(idx).map(|event| event.bytes));
+            }
+            let now = self.clock.now();
+            let shutting_down = matches!(self.deferred, Some(PollResult::Shutdown(_)));
+            if self.closed || shutting_down || now >= deadline {
+                return Ok(None);
+            }
+            if let Some(result) = self.read_source(deadline - now)? {
+                // A shutdown outranks a resize still waiting to be reported.
+                if self.deferred.is_none() || matches!(result, PollResult::Shutdown(_)) {
+                    self.deferred = Some(result);
+                }
+            }
+        }
+    }
+
+    /// Wait up to `timeout` for the source, then frame one read's worth of bytes.
+    ///
+    /// Returns a resize or shutdown that interrupted the wait.
+    fn read_source(&mut self, timeout: Duration) -> io::Result<Option<PollResult>> {
+        match self.source.wait_readable(timeout)? {
+            Readiness::Timeout => {}
+            Readiness::Resize => return Ok(Some(PollResult::Resize)),
+            Readiness::Shutdown(signal) => return Ok(Some(PollResult::Shutdown(signal))),
+            Readiness::Readable => {
+                // One read per poll so a trailing partial sequence never blocks the next call.
+                // The buffer matches stdin's own so its internal buffering is bypassed.
+                let mut buf = [0u8; READ_CHUNK];
+                let woke_at = self.clock.now();
+                loop {
+                    match self.source.read(&mut buf) {
+                        Ok(0) => {
+                            self.closed = true;
+                            break;
+                        }
+                        Ok(n) => {
+                            if let Some(recorder) = self.recorder.as_mut() {
+                                recorder.record(&buf[..n])?;
+                            }
+                            let ready_before = self.framer.ready_len();
+                            let read_at = self.clock.now();
+                            self.throughput.record_bytes(read_at, n);
+                            self.framer.push_bytes(&buf[..n], read_at);
+                            let framing = self.clock.now().saturating_duration_since(woke_at);
+                            for _ in ready_before..self.framer.ready_len() {
+                                self.latency.wakeup_to_frame.record(framing);
+                            }
+                            break;
+                        }
+                        Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                        Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                        Err(err) => return Err(err),
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Once the source is closed no more bytes can complete a sequence, so flush immediately.
+    fn drain_closed(&mut self) -> PollResult {
+        let event = self.framer.drain_closed();
+        self.emitted(event)
+            .map_or(PollResult::Eof, PollResult::Event)
+    }
+
+    /// What an idle poll reports: whether a partial sequence is still buffered.
+    fn idle(&self) -> PollResult {
+        match self.framer.pending().len() {
+            0 => PollResult::Idle,
+            buffered => PollResult::Pending { buffered },
+        }
+    }
+
+    fn emitted(&mut self, event: Option<RawEvent>) -> Option<RawEvent> {
+        if let Some(event) = &event {
+            let now = self.clock.now();
+            let waited = now.saturating_duration_since(event.first_byte_at);
+            self.latency.first_byte_to_emit.record(waited);
+            self.throughput.record_events(now, 1);
+        }
+        event
+    }
+}
+
+#[cfg(unix)]
+impl<S: ByteSource, C: Clock> InputSource for RawInputReader<S, C> {
+    fn poll_next(&mut self, timeout: Duration) -> io::Result<PollResult> {
+        if let Some(result) = self.deferred.take() {
+            return Ok(result);
+        }
+
+        if self.paused {
+            return Ok(match self.source.wait_readable(timeout)? {
+                Readiness::Resize => PollResult::Resize,
+                Readiness::Shutdown(signal) => PollResult::Shutdown(signal),
+                Readiness::Readable | Readiness::Timeout => self.idle(),
+            });
+        }
+
+        if let Some(event) = self.next_pending() {
+            return Ok(PollResult::Event(event));
+        }
+
+        if self.closed {
+            return Ok(self.drain_closed());
+        }
+
+        let effective_timeout = self.framer.effective_timeout(timeout, self.clock.now());
+        if let Some(result) = self.read_source(effective_timeout)? {
+            return Ok(result);
+        }
+
+        if self.closed {
+            tracing::debug!(target: "raw_input_reader", "input closed");
+            return Ok(self.drain_closed());
+        }
+
+        Ok(self
+            .next_pending()
+            .map_or_else(|| self.idle(), PollResult::Event))
+    }
+
+    fn poll_batch(
+        &mut self,
+        timeout: Duration,
+        events: &mut Vec<RawEvent>,
+    ) -> io::Result<PollResult> {
+        let mut result = self.poll_next(timeout)?;
+        // Everything after the first event is already framed, so drain without polling again.
+        while let PollResult::Event(event) = result {
+            events.push(event);
+            result = match self.next_pending() {
+                Some(event) => PollResult::Event(event),
+                None if self.closed => self.drain_closed(),
+                None => self.idle(),
+            };
+        }
+        Ok(result)
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        self.source.set_paused(paused);
+    }
+
+    fn pending_bytes(&self) -> &[u8] {
+        self.framer.pending()
+    }
+
+    fn latency(&self) -> Option<&InputLatency> {
+        Some(&self.latency)
+    }
+
+    fn throughput(&self) -> Option<&Throughput> {
+        Some(&self.throughput)
+    }
+}
+
+/// Marker a terminal in bracketed paste mode sends before pasted text.
+pub const PASTE_START: &[u8] = b"\x1b[200~";
+
+/// Marker a terminal in bracketed paste mode sends after pasted text.
+pub const PASTE_END: &[u8] = b"\x1b[201~";
+
+/// Largest chunk of paste content held before it is emitted, so huge pastes stream through.
+pub const PASTE_CHUNK: usize = 64 * 1024;
+
+/// Splits a byte stream into events, holding partial sequences until they complete or go stale.
+#[derive(Debug)]
+pub(crate) struct Framer {
+    buffer: Vec<u8>,
+    ready: VecDeque<RawEvent>,
+    first_byte_at: Option<Instant>,
+    last_byte_at: Option<Instant>,
+    timeouts: FlushTimeouts,
+    max_pending: usize,
+    in_paste: bool,
+    /// Set while a query waits for its reply, so `ESC ]` starts an OSC sequence.
+    frame_osc: bool,
+}
+
+impl Framer {
+    pub(crate) fn new(flush_timeout: impl Into<FlushTimeouts>) -> Self {
+        Self {
+            buffer: Vec::new(),
+            ready: VecDeque::new(),
+            first_byte_at: None,
+            last_byte_at: None,
+            timeouts: flush_timeout.into(),
+            max_pending: DEFAULT_MAX_PENDING,
+            in_paste: false,
+            frame_osc: false,
+        }
+    }
+
+    pub(crate) fn push_bytes(&mut self, bytes: &[u8], now: Instant) {
+        for &byte in bytes {
+            self.push_byte(byte, now);
+        }
+    }
+
+    pub(crate) fn push_byte(&mut self, byte: u8, now: Instant) {
+        if self.buffer.is_empty() {
+            self.first_byte_at = Some(now);
+        }
+        self.buffer.push(byte);
+        self.last_byte_at = Some(now);
+        if self.in_paste {
+            self.frame_paste();
+            return;
+        }
+        while let Some(len) = self.event_length() {
+            let event = self.take_event(len);
+            self.in_paste = event.bytes == PASTE_START;
+            self.ready.push_back(event);
+            if self.in_paste {
+                self.frame_paste();
+                return;
+            }
+        }
+        if is_oversized(&self.buffer, self.max_pending) {
+            let mut event = self.take_event(self.buffer.len());
+            event.oversized = true;
+            self.ready.push_back(event);
+        }
+    }
+
+    fn event_length(&self) -> Option<usize> {
+        if self.frame_osc && self.buffer.starts_with(b"\x1b]") {
+            osc_sequence_length(&self.buffer)
+        } else {
+            try_extract_event(&self.buffer)
+        }
+    }
+
+    /// Emit paste content once the end marker arrives or a chunk fills up.
+    fn frame_paste(&mut self) {
+        if self.buffer.ends_with(PASTE_END) {
+            let content = self.buffer.len() - PASTE_END.len();
+            if content > 0 {
+                let event = self.take_event(content);
+                self.ready.push_back(event);
+            }
+            self.in_paste = false;
+            let event = self.take_event(PASTE_END.len());
+            self.ready.push_back(event);
+        } else if self.buffer.len() >= PASTE_CHUNK {
+            // Hold back enough to still recognize an end marker split across the chunk boundary.
+            let event = self.take_event(self.buffer.len() - (PASTE_END.len() - 1));
+            self.ready.push_back(event);
+        }
+    }
+
+    pub(crate) fn next_pending(&mut self, now: Instant) -> Option<RawEvent> {
+        if let Some(event) = self.ready.pop_front() {
+            return Some(event);
+        }
+
+        if !self.buffer.is_empty() && self.should_flush_pending(now) {
+            return Some(self.take_event(self.buffer.len()));
+        }
+
+        None
+    }
+
+    pub(crate) fn pending(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    pub(crate) fn ready_len(&self) -> usize {
+        self.ready.len()
+    }
+
+    pub(crate) fn drain_closed(&mut self) -> Option<RawEvent> {
+        if let Some(event) = self.ready.pop_front() {
+            return Some(event);
+        }
+        if !self.buffer.is_empty() {
+            return Some(self.take_event(self.buffer.len()));
+        }
+        None
+    }
+
+    /// Split the first `len` buffered bytes off as an event stamped with their arrival times.
+    fn take_event(&mut self, len: usize) -> RawEvent {
+        let last_byte_at = self.last_byte_at.unwrap_or_else(Instant::now);
+        let first_byte_at = self.first_byte_at.unwrap_or(last_byte_at);
+        let bytes = self.buffer.drain(..len).collect();
+        // Anything left over arrived with the byte that completed this event.
+        self.first_byte_at = (!self.buffer.is_empty()).then_some(last_byte_at);
+        RawEvent {
+            bytes,
+            first_byte_at,
+            last_byte_at,
+            oversized: false,
+            paste: self.in_paste,
+        }
+    }
+
+    /// The timeout for what is buffered right now: short for a lone ESC, longer mid-sequence.
+    fn flush_timeout(&self) -> Duration {
+        self.timeouts.for_buffer(&self.buffer)
+    }
+
+    /// When the buffered partial sequence should be flushed, if anything is buffered.
+    pub(crate) fn flush_deadline(&self) -> Option<Instant> {
+        if self.buffer.is_empty() {
+            return None;
+        }
+        self.last_byte_at.map(|last| last + self.flush_timeout())
+    }
+
+    fn should_flush_pending(&self, now: Instant) -> bool {
+        self.last_byte_at
+            .map(|instant| now.saturating_duration_since(instant) >= self.flush_timeout())
+            .unwrap_or(false)
+    }
+
+    pub(crate) fn effective_timeout(&self, requested: Duration, now: Instant) -> Duration {
+        if self.buffer.is_empty() {
+            return requested;
+        }
+
+        if let Some(last) = self.last_byte_at {
+            let elapsed = now.saturating_duration_since(last);
+            let flush_timeout = self.flush_timeout();
+            if elapsed >= flush_timeout {
+                Duration::ZERO
+            } else {
+                requested.min(flush_timeout - elapsed)
+            }
+        } else {
+            requested
+        }
+    }
+}
+
+/// A self-pipe that becomes readable whenever `signal` is delivered.
+#[cfg(unix)]
+struct SignalWatch {
+    signal: libc::c_int,
+    read: UnixStream,
+    id: signal_hook::SigId,
+}
+
+#[cfg(unix)]
+impl SignalWatch {
+    fn new(signal: libc::c_int) -> io::Result<Self> {
+        let (read, write) = UnixStream::pair()?;
+        read.set_nonblocking(true)?;
+        // The handler takes ownership of the write end and closes it once unregistered.
+        let id = signal_hook::low_level::pipe::register(signal, write)?;
+        Ok(Self { signal, read, id })
+    }
+}
+
+#[cfg(unix)]
+impl Drop for SignalWatch {
+    fn drop(&mut self) {
+        signal_hook::low_level::unregister(self.id);
+    }
+}
+
+/// Wakes a [`RawInputReader`] blocked in `poll_next`; see [`RawInputReader::waker`].
+#[cfg(unix)]
+#[derive(Debug)]
+pub struct Waker {
+    write: UnixStream,
+}
+
+#[cfg(unix)]
+impl Waker {
+    /// Interrupt the reader's current (or next) poll.
+    pub fn wake(&self) -> io::Result<()> {
+        match (&self.write).write(&[1]) {
+            Ok(_) => Ok(()),
+            // A full pipe already has a wake-up pending.
+            Err(err) if err.kind() == ErrorKind::WouldBlock => Ok(()),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Discard everything currently readable from a non-blocking notification pipe.
+#[cfg(unix)]
+fn drain(stream: &mut UnixStream) {
+    let mut buf = [0u8; 64];
+    while matches!(stream.read(&mut buf), Ok(n) if n > 0) {}
+}
+
+#[cfg(unix)]
+fn duration_to_poll_timeout(duration: Duration) -> libc::c_int {
+    if duration == Duration::ZERO {
+        return 0;
+    }
+
+    let millis = duration.as_millis().min(i32::MAX as u128);
+    millis as libc::c_int
+}
+
+/// Default cap on buffered bytes of a single incomplete sequence.
+pub const DEFAULT_MAX_PENDING: usize = 4 * 1024;
+
+/// Longest CSI sequence scanned for a final byte; anything longer is treated as garbage.
+pub const MAX_CSI_LENGTH: usize = 256;
+
+/// Whether an incomplete buffered sequence has outgrown `max_pending` or the CSI length cap.
+pub(crate) fn is_oversized(buffer: &[u8], max_pending: usize) -> bool {
+    buffer.len() >= max_pending || csi_overflowed(buffer)
+}
+
+fn csi_overflowed(buffer: &[u8]) -> bool {
+    buffer.len() > MAX_CSI_LENGTH
+        && buffer.starts_with(b"\x1b[")
+        && csi_sequence_length(buffer).is_none()
+}
+
+/// Returns the length of the first complete event in `buffer`, if one is available yet.
+pub fn try_extract_event(buffer: &[u8]) -> Option<usize> {
+    if buffer.is_empty() {
+        return None;
+    }
+    let first = buffer[0];
+
+    if first == 0x1B {
+        if buffer.len() >= 2 {
+            match buffer[1] {
+                b'[' => return csi_sequence_length(buffer),
+                b'O' => {
+                    if buffer.len() >= 3 {
+                        return Some(3);
+                    }
+                }
+                _ => {
+                    let width = utf8_char_width(buffer[1]);
+                    if buffer.len() > width {
+                        return Some(1 + width);
+                    }
+                }
+            }
+        }
+        return None;
+    }
+
+    if first >= 0x80 {
+        let width = utf8_char_width(first);
+        if buffer.len() >= width {
+            return Some(width);
+        }
+        return None;
+    }
+
+    Some(1)
+}
+
+/// Returns the length of a CSI sequence starting at `buffer[0]` once its final byte arrived.
+///
+/// Only the first [`MAX_CSI_LENGTH`] bytes are scanned, so an unterminated sequence never
+/// makes this slower than the cap.
+pub fn csi_sequence_length(buffer: &[u8]) -> Option<usize> {
+    if buffer.len() < 3 {
+        return None;
+    }
+    let scanned = &buffer[..buffer.len().min(MAX_CSI_LENGTH)];
+    for (idx, byte) in scanned[2..].iter().enumerate() {
+        if (0x40..=0x7E).contains(byte) {
+            return Some(idx + 3);
+        }
+    }
+    None
+}
+
+/// Returns the length of an OSC sequence starting at `buffer[0]` once its BEL or ST (`ESC \\`)
+/// terminator arrived.
+///
+/// Terminals answer color queries with OSC, so while a query waits a reply frames as one event
+/// rather than as Alt+] followed by loose characters. Otherwise `ESC ]` is Alt+], since the
+/// keys typed after it would have no terminator to end them.
+pub fn osc_sequence_length(buffer: &[u8]) -> Option<usize> {
+    let body = buffer.get(2..)?;
+    body.iter().enumerate().find_map(|(idx, &byte)| match byte {
+        0x07 => Some(idx + 3),
+        b'\\' if idx > 0 && body[idx - 1] == 0x1B => Some(idx + 3),
+        _ => None,
+    })
+}
+
+/// Number of bytes in the UTF-8 character introduced by `first_byte`.
+pub fn utf8_char_width(first_byte: u8) -> usize {
+    if first_byte < 0x80 {
+        1
+    } else if first_byte >> 5 == 0b110 {
+        2
+    } else if first_byte >> 4 == 0b1110 {
+        3
+    } else if first_byte >> 3 == 0b11110 {
+        4
+    } else {
+        1
+    }
+}
@@ -0,0 +1,93 @@
+use crate::clock::VirtualClock;
+use crate::raw_input::{ByteSource, Readiness};
+use std::collections::VecDeque;
+use std::io;
+use std::time::Duration;
+
+/// A [`ByteSource`] that delivers programmed bursts of bytes at virtual times.
+///
+/// Waiting advances the shared [`VirtualClock`] instead of sleeping, so framing and flush
+/// timeouts can be tested deterministically. Once every burst is read the source reports EOF.
+#[derive(Debug, Clone)]
+pub struct ScriptedSource {
+    clock: VirtualClock,
+    bursts: VecDeque<(Duration, Vec<u8>)>,
+    notices: VecDeque<(Duration, Readiness)>,
+    paused: bool,
+}
+
+impl ScriptedSource {
+    /// An empty script timed against `clock`.
+    pub fn new(clock: VirtualClock) -> Self {
+        Self {
+            clock,
+            bursts: VecDeque::new(),
+            notices: VecDeque::new(),
+            paused: false,
+        }
+    }
+
+    /// Deliver `bytes` once the clock reaches `at`; bursts must be added in time order.
+    pub fn burst(mut self, at: Duration, bytes: impl Into<Vec<u8>>) -> Self {
+        self.bursts.push_back((at, bytes.into()));
+        self
+    }
+
+    /// Report `readiness`, such as a resize or shutdown, once the clock reaches `at`; notices
+    /// must be added in time order and come before a burst due at the same time.
+    pub fn notify(mut self, at: Duration, readiness: Readiness) -> Self {
+        self.notices.push_back((at, readiness));
+        self
+    }
+}
+
+impl ByteSource for ScriptedSource {
+    fn wait_readable(&mut self, timeout: Duration) -> io::Result<Readiness> {
+        let now = self.clock.elapsed();
+        if let Some(&(at, readiness)) = self.notices.front() {
+            let burst_first = self.bursts.front().is_some_and(|(burst, _)| *burst < at);
+            if at <= now + timeout && (self.paused || !burst_first) {
+                self.notices.pop_front();
+                self.clock.advance_to(at);
+                return Ok(readiness);
+            }
+        }
+        match self.bursts.front() {
+            _ if self.paused => {
+                self.clock.advance(timeout);
+                Ok(Readiness::Timeout)
+            }
+            Some((at, _)) if *at > now + timeout => {
+                self.clock.advance(timeout);
+                Ok(Readiness::Timeout)
+            }
+            Some((at, _)) => {
+                self.clock.advance_to(*at);
+                Ok(Readiness::Readable)
+            }
+            None => Ok(Readiness::Readable),
+        }
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let now = self.clock.elapsed();
+        let Some((at, bytes)) = self.bursts.front_mut() else {
+            return Ok(0);
+        };
+        if *at > now {
+            return Err(io::ErrorKind::WouldBlock.into());
+        }
+
+        let n = buf.len().min(bytes.len());
+        buf[..n].copy_from_slice(&bytes[..n]);
+        bytes.drain(..n);
+        if bytes.is_empty() {
+            self.bursts.pop_front();
+        }
+        Ok(n)
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+}
@@ -0,0 +1,242 @@
+use crate::raw_input::{FlushTimeouts, Framer, InputSource, PollResult};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Magic bytes opening every recorded session file.
+pub const SESSION_MAGIC: &[u8; 7] = b"TIDSESS";
+
+/// Format version written after the magic bytes.
+pub const SESSION_VERSION: u8 = 1;
+
+/// A chunk of raw input together with its offset from the start of the recording.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionRecord {
+    pub offset: Duration,
+    pub bytes: Vec<u8>,
+}
+
+/// A recorded input session: a header followed by timestamped byte chunks.
+///
+/// Each record is stored as a little-endian `u64` offset in microseconds, a little-endian
+/// `u32` length, and that many raw bytes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Session {
+    pub records: Vec<SessionRecord>,
+}
+
+impl Session {
+    /// Load a session from a file on disk.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        Self::read_from(BufReader::new(file))
+    }
+
+    /// Parse a session from any reader, validating the header and version.
+    pub fn read_from(mut reader: impl Read) -> io::Result<Self> {
+        let mut magic = [0u8; 7];
+        reader.read_exact(&mut magic)?;
+        if &magic != SESSION_MAGIC {
+            return Err(invalid_data("not a recorded input session"));
+        }
+
+        let mut version = [0u8; 1];
+        reader.read_exact(&mut version)?;
+        if version[0] != SESSION_VERSION {
+            return Err(invalid_data(format!(
+                "unsupported session version {}",
+                version[0]
+            )));
+        }
+
+        let mut records = Vec::new();
+        loop {
+            let mut offset = [0u8; 8];
+            match reader.read_exact(&mut offset) {
+                Ok(()) => {}
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(err) => return Err(err),
+            }
+            let mut len = [0u8; 4];
+            reader.read_exact(&mut len)?;
+            let mut bytes = vec![0u8; u32::from_le_bytes(len) as usize];
+            reader.read_exact(&mut bytes)?;
+
+            records.push(SessionRecord {
+                offset: Duration::from_micros(u64::from_le_bytes(offset)),
+                bytes,
+            });
+        }
+
+        Ok(Self { records })
+    }
+}
+
+/// Writes raw input chunks to a session file as they arrive.
+///
+/// Writes go through a buffer so recording never stalls the input path; the buffer is flushed
+/// by [`SessionRecorder::finish`] or, failing that, when the recorder is dropped.
+pub struct SessionRecorder<W: Write = BufWriter<File>> {
+    writer: Option<W>,
+    started_at: Instant,
+}
+
+impl SessionRecorder {
+    /// Create (or truncate) the session file at `path` and write its header.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::create(path)?;
+        Self::new(BufWriter::with_capacity(64 * 1024, file))
+    }
+}
+
+impl<W: Write> SessionRecorder<W> {
+    /// Start a recording on `writer`, timestamping chunks relative to now.
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(SESSION_MAGIC)?;
+        writer.write_all(&[SESSION_VERSION])?;
+        Ok(Self {
+            writer: Some(writer),
+            started_at: Instant::now(),
+        })
+    }
+
+    /// Append `bytes` stamped with the time elapsed since the recording started.
+    pub fn record(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let offset = self.started_at.elapsed();
+        self.record_at(offset, bytes)
+    }
+
+    /// Append `bytes` with an explicit offset from the start of the recording.
+    pub fn record_at(&mut self, offset: Duration, bytes: &[u8]) -> io::Result<()> {
+        let Some(writer) = self.writer.as_mut() else {
+            return Ok(());
+        };
+        let micros = u64::try_from(offset.as_micros()).unwrap_or(u64::MAX);
+        let len = u32::try_from(bytes.len())
+            .map_err(|_| invalid_data("session record larger than 4 GiB"))?;
+        writer.write_all(&micros.to_le_bytes())?;
+        writer.write_all(&len.to_le_bytes())?;
+        writer.write_all(bytes)
+    }
+
+    /// Flush buffered records and hand back the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        let mut writer = self.writer.take().expect("recorder already finished");
+        writer.flush()?;
+        Ok(writer)
+    }
+}
+
+impl<W: Write> Drop for SessionRecorder<W> {
+    fn drop(&mut self) {
+        if let Some(writer) = self.writer.as_mut() {
+            if let Err(e) = writer.flush() {
+                tracing::error!("Failed to flush session recording: {}", e);
+            }
+        }
+    }
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// Feeds a recorded session through the framer while honoring the recorded delays.
+///
+/// Flush decisions are made against the recorded timeline rather than the wall clock, so a
+/// replay frames bytes identically no matter how late the caller polls. Framing is the same
+/// [`Framer`] the live readers use, so pastes replay as they were captured.
+pub struct SessionReplay {
+    records: VecDeque<SessionRecord>,
+    speed: f64,
+    framer: Framer,
+    started_at: Option<Instant>,
+    /// How far along the recorded timeline the replay has got.
+    now: Option<Instant>,
+}
+
+impl SessionReplay {
+    /// Replay `session`, dividing every recorded delay by `speed`.
+    pub fn new(session: Session, flush_timeout: impl Into<FlushTimeouts>, speed: f64) -> Self {
+        Self {
+            records: session.records.into(),
+            speed,
+            framer: Framer::new(flush_timeout),
+            started_at: None,
+            now: None,
+        }
+    }
+
+    /// Load and replay the session stored at `path`.
+    pub fn open(
+        path: impl AsRef<Path>,
+        flush_timeout: impl Into<FlushTimeouts>,
+        speed: f64,
+    ) -> io::Result<Self> {
+        Ok(Self::new(Session::load(path)?, flush_timeout, speed))
+    }
+
+    /// Whether every recorded byte has been delivered as an event.
+    pub fn is_finished(&self) -> bool {
+        self.records.is_empty() && self.framer.pending().is_empty() && self.framer.ready_len() == 0
+    }
+
+    fn scheduled_at(&self, started_at: Instant, offset: Duration) -> Instant {
+        started_at + offset.div_f64(self.speed)
+    }
+}
+
+impl InputSource for SessionReplay {
+    fn poll_next(&mut self, timeout: Duration) -> io::Result<PollResult> {
+        let started_at = *self.started_at.get_or_insert_with(Instant::now);
+        let wait_until = Instant::now() + timeout;
+
+        loop {
+            let now = *self.now.get_or_insert(started_at);
+            if let Some(event) = self.framer.next_pending(now) {
+                return Ok(PollResult::Event(event));
+            }
+
+            let record_at = self
+                .records
+                .front()
+                .map(|record| self.scheduled_at(started_at, record.offset));
+            // A flush due no later than the next record goes first.
+            let at = match (self.framer.flush_deadline(), record_at) {
+                (Some(flush), Some(record)) => flush.min(record),
+                (Some(at), None) | (None, Some(at)) => at,
+                (None, None) => return Ok(PollResult::Eof),
+            };
+
+            if at > wait_until {
+                sleep_until(wait_until);
+                return Ok(match self.framer.pending().len() {
+                    0 => PollResult::Idle,
+                    buffered => PollResult::Pending { buffered },
+                });
+            }
+
+            sleep_until(at);
+            self.now = Some(at);
+            if let Some(event) = self.framer.next_pending(at) {
+                return Ok(PollResult::Event(event));
+            }
+            if let Some(record) = self.records.pop_front() {
+                self.framer.push_bytes(&record.bytes, at);
+            }
+        }
+    }
+
+    fn pending_bytes(&self) -> &[u8] {
+        self.framer.pending()
+    }
+}
+
+fn sleep_until(deadline: Instant) {
+    let now = Instant::now();
+    if deadline > now {
+        std::thread::sleep(deadline - now);
+    }
+}
@@ -0,0 +1,258 @@
+#[cfg(unix)]
+use crate::clock::Clock;
+#[cfg(unix)]
+use crate::raw_input::{ByteSource, RawInputReader};
+#[cfg(unix)]
+use std::io::{self, Write};
+use std::task::Poll;
+#[cfg(unix)]
+use std::time::Duration;
+use terminal_colorsaurus::{Color, ThemeMode};
+
+/// Asks the terminal to report dark/light theme changes (mode 2031, from contour and kitty).
+pub const ENABLE_THEME_NOTIFICATIONS: &str = "\x1b[?2031h";
+/// Stops the reports turned on by [`ENABLE_THEME_NOTIFICATIONS`].
+pub const DISABLE_THEME_NOTIFICATIONS: &str = "\x1b[?2031l";
+
+/// Asks for the terminal's default foreground color (OSC 10).
+pub const QUERY_FOREGROUND: &[u8] = b"\x1b]10;?\x1b\\";
+/// Asks for the terminal's default background color (OSC 11).
+pub const QUERY_BACKGROUND: &[u8] = b"\x1b]11;?\x1b\\";
+
+/// Primary device attributes (DA1), which every terminal answers; sent after a color query so
+/// a terminal that ignores the query doesn't cost the whole timeout.
+pub const QUERY_DEVICE_ATTRIBUTES: &[u8] = b"\x1b[c";
+
+/// Whether `bytes` are a DA1 reply, `CSI ? … c`.
+pub fn is_device_attributes_reply(bytes: &[u8]) -> bool {
+    bytes.starts_with(b"\x1b[?") && bytes.ends_with(b"c")
+}
+
+/// The color in an OSC `code` reply such as `ESC ] 11 ; rgb:1e1e/1e1e/2e2e BEL`, terminated by
+/// BEL or ST. Channels of one to four hex digits are scaled to 16 bits.
+pub fn parse_color_reply(code: u8, bytes: &[u8]) -> Option<Color> {
+    let prefix = format!("\x1b]{code};");
+    parse_color_spec(bytes.strip_prefix(prefix.as_bytes())?)
+}
+
+/// Asks for palette entry `index` (OSC 4).
+pub fn query_palette_color(index: u8) -> Vec<u8> {
+    format!("\x1b]4;{index};?\x1b\\").into_bytes()
+}
+
+/// The palette index and color in an OSC 4 reply such as `ESC ] 4 ; 1 ; rgb:cdcd/0000/0000 ST`.
+pub fn parse_palette_reply(bytes: &[u8]) -> Option<(u8, Color)> {
+    let body = bytes.strip_prefix(b"\x1b]4;")?;
+    let split = body.iter().position(|&byte| byte == b';')?;
+    let index = std::str::from_utf8(&body[..split]).ok()?.parse().ok()?;
+    Some((index, parse_color_spec(&body[split + 1..])?))
+}
+
+/// An `rgb:r/g/b` color spec followed by BEL or ST.
+fn parse_color_spec(spec: &[u8]) -> Option<Color> {
+    let body = spec.strip_prefix(b"rgb:")?;
+    let body = body
+        .strip_suffix(b"\x07")
+        .or_else(|| body.strip_suffix(b"\x1b\\"))?;
+    let mut channels = body.split(|&byte| byte == b'/').map(parse_channel);
+    let color = Color::rgb(channels.next()??, channels.next()??, channels.next()??);
+    channels.next().is_none().then_some(color)
+}
+
+fn parse_channel(hex: &[u8]) -> Option<u16> {
+    if hex.is_empty() || hex.len() > 4 {
+        return None;
+    }
+    let value = u32::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?;
+    let max = (1u32 << (4 * hex.len())) - 1;
+    Some((value * u32::from(u16::MAX) / max) as u16)
+}
+
+/// Dark or light, judged the way terminal-colorsaurus does: text lighter than its
+/// background means a dark theme.
+pub fn theme_from_colors(foreground: &Color, background: &Color) -> ThemeMode {
+    let fg = foreground.perceived_lightness();
+    let bg = background.perceived_lightness();
+    if bg < fg {
+        ThemeMode::Dark
+    } else if bg > fg || bg > 0.5 {
+        ThemeMode::Light
+    } else {
+        ThemeMode::Dark
+    }
+}
+
+/// A theme query already sent to the terminal, whose replies the app picks out of its own
+/// input as they arrive; for event loops that can't block on the round trip.
+///
+/// Pass every event [`PendingThemeQuery::is_reply`] accepts to [`PendingThemeQuery::feed`]
+/// instead of handling it as input. Give up whenever the app's timeout passes, with
+/// [`PendingThemeQuery::theme`] as the best answer so far. A [`RawInputReader`] only frames
+/// the color replies while [`RawInputReader::set_frames_osc`] is on.
+#[derive(Debug, Clone, Default)]
+pub struct PendingThemeQuery {
+    foreground: Option<Color>,
+    background: Option<Color>,
+    done: bool,
+}
+
+/// Send the background and foreground color queries (OSC 11 and 10), then
+/// [`QUERY_DEVICE_ATTRIBUTES`], whose reply marks the end of the answers.
+#[cfg(unix)]
+pub fn start_theme_query(out: &mut impl Write) -> io::Result<PendingThemeQuery> {
+    out.write_all(&[QUERY_BACKGROUND, QUERY_FOREGROUND, QUERY_DEVICE_ATTRIBUTES].concat())?;
+    out.flush()?;
+    Ok(PendingThemeQuery::default())
+}
+
+impl PendingThemeQuery {
+    /// Whether an event's `bytes` answer the query rather than being input.
+    pub fn is_reply(bytes: &[u8]) -> bool {
+        parse_color_reply(10, bytes).is_some()
+            || parse_color_reply(11, bytes).is_some()
+            || is_device_attributes_reply(bytes)
+    }
+
+    /// Take one reply, which may be the last: ready once the DA1 reply arrives, with the
+    /// theme or `None` if the terminal didn't report its background.
+    pub fn feed(&mut self, bytes: &[u8]) -> Poll<Option<ThemeMode>> {
+        if let Some(color) = parse_color_reply(10, bytes) {
+            self.foreground = Some(color);
+        } else if let Some(color) = parse_color_reply(11, bytes) {
+            self.background = Some(color);
+        } else if is_device_attributes_reply(bytes) {
+            self.done = true;
+        }
+        if self.done {
+            Poll::Ready(self.theme())
+        } else {
+            Poll::Pending
+        }
+    }
+
+    /// The theme the replies so far point to; `None` until the background arrives.
+    pub fn theme(&self) -> Option<ThemeMode> {
+        let background = self.background.as_ref()?;
+        // Without a foreground, judge the background against mid gray.
+        let gray = Color::rgb(0x7fff, 0x7fff, 0x7fff);
+        Some(theme_from_colors(
+            self.foreground.as_ref().unwrap_or(&gray),
+            background,
+        ))
+    }
+}
+
+/// Query the theme through a reader that already owns the terminal's input, so the replies
+/// are taken out of its event stream instead of showing up as keys; see
+/// [`RawInputReader::query_all`].
+///
+/// Blocks for up to `timeout` on a [`PendingThemeQuery`]. `None` if the terminal doesn't
+/// report its background.
+#[cfg(unix)]
+pub fn query_theme<S: ByteSource, C: Clock>(
+    reader: &mut RawInputReader<S, C>,
+    out: &mut impl Write,
+    timeout: Duration,
+) -> io::Result<Option<ThemeMode>> {
+    let mut pending = start_theme_query(out)?;
+    let is_reply = PendingThemeQuery::is_reply;
+    for reply in reader.query_all(out, &[], is_reply, is_device_attributes_reply, timeout)? {
+        if let Poll::Ready(theme) = pending.feed(&reply) {
+            return Ok(theme);
+        }
+    }
+    Ok(pending.theme())
+}
+
+/// Read back the 16 ANSI colors (OSC 4) through `reader`, as [`query_theme`] does for the
+/// theme.
+///
+/// All 16 queries go out at once, followed by [`QUERY_DEVICE_ATTRIBUTES`], and `timeout` covers
+/// the whole batch. Entries the terminal didn't answer are `None`.
+#[cfg(unix)]
+pub fn query_ansi_palette<S: ByteSource, C: Clock>(
+    reader: &mut RawInputReader<S, C>,
+    out: &mut impl Write,
+    timeout: Duration,
+) -> io::Result<[Option<Color>; 16]> {
+    let mut request: Vec<u8> = (0..16).flat_map(query_palette_color).collect();
+    request.extend_from_slice(QUERY_DEVICE_ATTRIBUTES);
+    let matches =
+        |bytes: &[u8]| parse_palette_reply(bytes).is_some() || is_device_attributes_reply(bytes);
+    let replies = reader.query_all(out, &request, matches, is_device_attributes_reply, timeout)?;
+    let mut palette = [const { None }; 16];
+    for (index, color) in replies
+        .iter()
+        .filter_map(|reply| parse_palette_reply(reply))
+    {
+        if let Some(slot) = palette.get_mut(usize::from(index)) {
+            *slot = Some(color);
+        }
+    }
+    Ok(palette)
+}
+
+/// A mode 2031 report, `CSI ? 997 ; Ps n`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ThemeReport {
+    /// `Ps` is 1 for dark or 2 for light.
+    Changed(ThemeMode),
+    /// A report whose parameters aren't a known theme, kept as sent (e.g. `3` or `1;0`).
+    Unrecognized(String),
+}
+
+/// The mode 2031 report in `bytes`, if they are one.
+pub fn parse_theme_report(bytes: &[u8]) -> Option<ThemeReport> {
+    let params = bytes.strip_prefix(b"\x1b[?997;")?.strip_suffix(b"n")?;
+    if !params
+        .iter()
+        .all(|&byte| byte.is_ascii_digit() || byte == b';' || byte == b':')
+    {
+        return None;
+    }
+    Some(match params {
+        b"1" => ThemeReport::Changed(ThemeMode::Dark),
+        b"2" => ThemeReport::Changed(ThemeMode::Light),
+        _ => ThemeReport::Unrecognized(String::from_utf8_lossy(params).into_owned()),
+    })
+}
+
+/// The theme a mode 2031 report says the terminal switched to: `CSI ? 997 ; 1 n` for dark and
+/// `CSI ? 997 ; 2 n` for light. `None` for any other bytes, unrecognized reports included.
+pub fn parse_theme_notification(bytes: &[u8]) -> Option<ThemeMode> {
+    match parse_theme_report(bytes)? {
+        ThemeReport::Changed(mode) => Some(mode),
+        ThemeReport::Unrecognized(_) => None,
+    }
+}
+
+/// Follows the terminal's theme through the mode 2031 reports among the input events fed to it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThemeWatcher {
+    current: Option<ThemeMode>,
+}
+
+impl ThemeWatcher {
+    /// Start from `current`, e.g. the theme queried at startup, or `None` if it is unknown.
+    pub fn new(current: Option<ThemeMode>) -> Self {
+        Self { current }
+    }
+
+    /// Take one input event's bytes, returning the new theme if they report a change.
+    ///
+    /// Terminals report the theme again when notifications are turned on; that is only a
+    /// change if it differs from the current one.
+    pub fn feed(&mut self, bytes: &[u8]) -> Option<ThemeMode> {
+        let reported = parse_theme_notification(bytes)?;
+        if self.current == Some(reported) {
+            return None;
+        }
+        self.current = Some(reported);
+        Some(reported)
+    }
+
+    /// The theme last reported, or the one this watcher started from.
+    pub fn current(&self) -> Option<ThemeMode> {
+        self.current
+    }
+}
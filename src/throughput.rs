@@ -0,0 +1,114 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Span the rates are averaged over.
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Granularity of the sliding window; counts landing in the same slice share one entry.
+const SLICE: Duration = Duration::from_millis(100);
+
+/// Bytes read and events emitted, in total and over the last second.
+///
+/// Counts are added per read or per event, never per byte, and old slices are dropped as new
+/// ones arrive, so the window stays a handful of entries no matter the input rate.
+#[derive(Debug, Clone, Default)]
+pub struct Throughput {
+    total_bytes: u64,
+    total_events: u64,
+    slices: VecDeque<Slice>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Slice {
+    start: Instant,
+    bytes: u64,
+    events: u64,
+}
+
+impl Throughput {
+    /// Count `bytes` read at `at`.
+    pub fn record_bytes(&mut self, at: Instant, bytes: usize) {
+        self.total_bytes += bytes as u64;
+        self.slice_at(at).bytes += bytes as u64;
+    }
+
+    /// Count `events` emitted at `at`.
+    pub fn record_events(&mut self, at: Instant, events: usize) {
+        self.total_events += events as u64;
+        self.slice_at(at).events += events as u64;
+    }
+
+    /// Every byte read so far.
+    pub fn total_bytes(&self) -> u64 {
+        self.total_bytes
+    }
+
+    /// Every event emitted so far.
+    pub fn total_events(&self) -> u64 {
+        self.total_events
+    }
+
+    /// Bytes per second over the second before `now`.
+    pub fn bytes_per_sec(&self, now: Instant) -> u64 {
+        self.recent(now).map(|slice| slice.bytes).sum()
+    }
+
+    /// Events per second over the second before `now`.
+    pub fn events_per_sec(&self, now: Instant) -> u64 {
+        self.recent(now).map(|slice| slice.events).sum()
+    }
+
+    /// The current rates, formatted like `142 B/s, 18 ev/s`.
+    pub fn rates(&self, now: Instant) -> Rates {
+        Rates {
+            bytes_per_sec: self.bytes_per_sec(now),
+            events_per_sec: self.events_per_sec(now),
+        }
+    }
+
+    fn recent(&self, now: Instant) -> impl Iterator<Item = &Slice> {
+        self.slices
+            .iter()
+            .filter(move |slice| now.saturating_duration_since(slice.start) < RATE_WINDOW)
+    }
+
+    fn slice_at(&mut self, at: Instant) -> &mut Slice {
+        while self
+            .slices
+            .front()
+            .is_some_and(|slice| at.saturating_duration_since(slice.start) >= RATE_WINDOW)
+        {
+            self.slices.pop_front();
+        }
+        let fits = self
+            .slices
+            .back()
+            .is_some_and(|slice| at.saturating_duration_since(slice.start) < SLICE);
+        if !fits {
+            self.slices.push_back(Slice {
+                start: at,
+                bytes: 0,
+                events: 0,
+            });
+        }
+        self.slices.back_mut().expect("a slice was just ensured")
+    }
+}
+
+/// A snapshot of [`Throughput`] rates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rates {
+    pub bytes_per_sec: u64,
+    pub events_per_sec: u64,
+}
+
+impl fmt::Display for Rates {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} B/s, {} ev/s",
+            self.bytes_per_sec, self.events_per_sec
+        )
+    }
+}
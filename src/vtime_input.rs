@@ -0,0 +1,114 @@
+use crate::clock::SystemClock;
+use crate::raw_input::{ByteSource, FlushTimeouts, RawInputReader, Readiness};
+use nix::sys::termios::{self, SetArg, SpecialCharacterIndices};
+use std::io::{self, Read};
+use std::os::fd::AsFd;
+use std::time::Duration;
+
+/// Inter-byte gap, in tenths of a second, after which the kernel ends a read.
+const VTIME_DECISECONDS: u8 = 1;
+
+/// Bytes after which the kernel ends a read without waiting for a gap. With VMIN=1 a read
+/// returns on the first byte and VTIME never gets to time anything, so use the largest count.
+const VMIN_BYTES: u8 = u8::MAX;
+
+/// A [`ByteSource`] that frames bursts with the tty's VMIN/VTIME timer instead of `poll()`.
+///
+/// Reads block until a byte arrives and then keep going until the line goes quiet for
+/// [`VTIME_DECISECONDS`], so each read holds whole sequences at the cost of that much latency.
+/// Because nothing is polled, read timeouts, signal watches and wakers have no effect: a blocked
+/// read only returns on input.
+///
+/// Only the VMIN and VTIME slots are touched, on creation and again on drop, so this composes
+/// with raw mode set up (or torn down) by crossterm in either order. Create it after raw mode is
+/// enabled, since enabling raw mode resets VTIME.
+pub struct VtimeSource<F: AsFd> {
+    source: F,
+    original: (u8, u8),
+    paused: bool,
+}
+
+impl<F: AsFd + Read> VtimeSource<F> {
+    /// Switch `source`'s tty to inter-byte timed reads.
+    pub fn new(source: F) -> io::Result<Self> {
+        let original = set_vmin_vtime(&source, VMIN_BYTES, VTIME_DECISECONDS)?;
+        Ok(Self {
+            source,
+            original,
+            paused: false,
+        })
+    }
+}
+
+impl<F: AsFd> Drop for VtimeSource<F> {
+    fn drop(&mut self) {
+        let (vmin, vtime) = self.original;
+        if let Err(e) = set_vmin_vtime(&self.source, vmin, vtime) {
+            tracing::warn!(target: "raw_input_reader", "Failed to restore VMIN/VTIME: {}", e);
+        }
+    }
+}
+
+impl<F: AsFd + Read> ByteSource for VtimeSource<F> {
+    fn wait_readable(&mut self, timeout: Duration) -> io::Result<Readiness> {
+        if self.paused {
+            std::thread::sleep(timeout);
+            return Ok(Readiness::Timeout);
+        }
+        // The blocking read is the wait.
+        Ok(Readiness::Readable)
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut filled = 0;
+        // A read that stops at VMIN ended on the byte count rather than a quiet line, so more of
+        // the burst may follow; keep reading until the timer ends one.
+        loop {
+            let n = match self.source.read(&mut buf[filled..]) {
+                // A PTY whose other side closed reports EIO rather than EOF.
+                Err(err) if err.raw_os_error() == Some(libc::EIO) => 0,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) if filled > 0 => {
+                    tracing::debug!(target: "raw_input_reader", "read ended early: {}", err);
+                    0
+                }
+                result => result?,
+            };
+            filled += n;
+            if n < usize::from(VMIN_BYTES) || filled == buf.len() {
+                return Ok(filled);
+            }
+        }
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+}
+
+impl<F: AsFd + Read> RawInputReader<VtimeSource<F>> {
+    /// Read `source` with kernel inter-byte timing; partial sequences left at the end of a read
+    /// are flushed at once, since the kernel already waited for the rest.
+    pub fn with_vtime(source: F) -> io::Result<Self> {
+        Ok(Self::with_byte_source(
+            VtimeSource::new(source)?,
+            SystemClock,
+            FlushTimeouts::from(Duration::ZERO),
+        ))
+    }
+}
+
+/// Set VMIN and VTIME on `fd`'s tty, returning the previous values.
+fn set_vmin_vtime(fd: &impl AsFd, vmin: u8, vtime: u8) -> io::Result<(u8, u8)> {
+    let mut attrs = termios::tcgetattr(fd)?;
+    let vmin_index = SpecialCharacterIndices::VMIN as usize;
+    let vtime_index = SpecialCharacterIndices::VTIME as usize;
+    let original = (
+        attrs.control_chars[vmin_index],
+        attrs.control_chars[vtime_index],
+    );
+    attrs.control_chars[vmin_index] = vmin;
+    attrs.control_chars[vtime_index] = vtime;
+    termios::tcsetattr(fd, SetArg::TCSANOW, &attrs)?;
+    Ok(original)
+}
@@ -0,0 +1,36 @@
+#![cfg(all(unix, feature = "tokio"))]
+
+use _tuicore::async_input::AsyncRawInputReader;
+use std::io::Write;
+use std::os::fd::OwnedFd;
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(future)
+}
+
+async fn next_bytes(reader: &mut AsyncRawInputReader) -> Option<Vec<u8>> {
+    reader.next_event().await.unwrap().map(|event| event.bytes)
+}
+
+#[test]
+fn frames_events_and_flushes_partial_sequences_asynchronously() {
+    block_on(async {
+        let (mut writer, source) = UnixStream::pair().unwrap();
+        let mut reader =
+            AsyncRawInputReader::with_fd(OwnedFd::from(source), Duration::from_millis(20)).unwrap();
+
+        writer.write_all(b"\x1b[1;5Ca\x1b").unwrap();
+        assert_eq!(next_bytes(&mut reader).await, Some(b"\x1b[1;5C".to_vec()));
+        assert_eq!(next_bytes(&mut reader).await, Some(b"a".to_vec()));
+        assert_eq!(next_bytes(&mut reader).await, Some(b"\x1b".to_vec()));
+
+        drop(writer);
+        assert_eq!(next_bytes(&mut reader).await, None);
+    });
+}
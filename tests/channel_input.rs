@@ -0,0 +1,121 @@
+#![cfg(unix)]
+
+use _tuicore::channel_input::{ChannelInputReader, InputMessage};
+use _tuicore::raw_input::RawInputReader;
+use std::io::Write;
+use std::os::unix::net::UnixStream;
+use std::time::{Duration, Instant};
+
+const FLUSH_TIMEOUT: Duration = Duration::from_millis(20);
+const RECV_TIMEOUT: Duration = Duration::from_secs(1);
+
+fn spawn(tick_interval: Duration) -> (UnixStream, ChannelInputReader) {
+    let (writer, source) = UnixStream::pair().unwrap();
+    let reader = RawInputReader::with_source(source, FLUSH_TIMEOUT);
+    (
+        writer,
+        ChannelInputReader::spawn(reader, tick_interval).unwrap(),
+    )
+}
+
+fn next_bytes(input: &ChannelInputReader) -> Vec<u8> {
+    loop {
+        match input.recv_timeout(RECV_TIMEOUT).unwrap() {
+            InputMessage::Event(event) => return event.bytes,
+            InputMessage::Tick => continue,
+            other => panic!("expected an event, got {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn delivers_events_then_eof() {
+    let (mut writer, input) = spawn(Duration::from_secs(60));
+
+    writer.write_all(b"a\x1b[A").unwrap();
+    assert_eq!(next_bytes(&input), b"a");
+    assert_eq!(next_bytes(&input), b"\x1b[A");
+
+    drop(writer);
+    assert!(matches!(
+        input.recv_timeout(RECV_TIMEOUT).unwrap(),
+        InputMessage::Eof
+    ));
+}
+
+#[test]
+fn flushes_partial_sequences_without_the_consumer_polling() {
+    let (mut writer, input) = spawn(Duration::from_secs(60));
+
+    writer.write_all(b"\x1b[").unwrap();
+    std::thread::sleep(FLUSH_TIMEOUT * 3);
+    match input.try_recv().unwrap() {
+        InputMessage::Event(event) => assert_eq!(event.bytes, b"\x1b["),
+        other => panic!("expected a flushed event, got {:?}", other),
+    }
+}
+
+#[test]
+fn sends_ticks_while_idle() {
+    let (_writer, input) = spawn(Duration::from_millis(10));
+
+    assert!(matches!(
+        input.recv_timeout(RECV_TIMEOUT).unwrap(),
+        InputMessage::Tick
+    ));
+}
+
+#[test]
+fn drop_wakes_and_joins_the_thread() {
+    let (_writer, input) = spawn(Duration::from_secs(60));
+
+    let started = Instant::now();
+    drop(input);
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn pause_holds_input_until_resume() {
+    let (mut writer, input) = spawn(Duration::from_secs(60));
+
+    input.pause();
+    assert!(input.is_paused());
+    std::thread::sleep(Duration::from_millis(20));
+    writer.write_all(b"x").unwrap();
+    assert!(input.recv_timeout(Duration::from_millis(50)).is_err());
+
+    input.resume();
+    assert_eq!(next_bytes(&input), b"x");
+}
+
+#[test]
+fn publishes_pending_bytes_from_the_thread() {
+    let (mut writer, source) = UnixStream::pair().unwrap();
+    let reader = RawInputReader::with_source(source, Duration::from_secs(60));
+    let input = ChannelInputReader::spawn(reader, Duration::from_secs(60)).unwrap();
+
+    writer.write_all(b"\x1b[1;").unwrap();
+    let started = Instant::now();
+    while input.pending_bytes().is_empty() {
+        assert!(started.elapsed() < RECV_TIMEOUT);
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(input.pending_bytes(), b"\x1b[1;");
+
+    writer.write_all(b"5C").unwrap();
+    assert_eq!(next_bytes(&input), b"\x1b[1;5C");
+    assert!(input.pending_bytes().is_empty());
+}
+
+#[test]
+fn consumer_can_stop_mid_batch_and_keep_the_rest_in_order() {
+    let (mut writer, input) = spawn(Duration::from_secs(60));
+
+    writer.write_all(b"abcde").unwrap();
+    let max_inputs = 2;
+    let taken: Vec<Vec<u8>> = (0..max_inputs).map(|_| next_bytes(&input)).collect();
+    assert_eq!(taken, vec![b"a".to_vec(), b"b".to_vec()]);
+
+    let rest: Vec<Vec<u8>> = (0..3).map(|_| next_bytes(&input)).collect();
+    assert_eq!(rest, vec![b"c".to_vec(), b"d".to_vec(), b"e".to_vec()]);
+}
@@ -0,0 +1,97 @@
+use _tuicore::coalesce::{Coalesced, RepeatCoalescer};
+use _tuicore::raw_input::RawEvent;
+use std::time::{Duration, Instant};
+
+const WINDOW: Duration = Duration::from_millis(100);
+
+fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+fn event(start: Instant, at: Duration, bytes: &[u8]) -> RawEvent {
+    RawEvent {
+        bytes: bytes.to_vec(),
+        first_byte_at: start + at,
+        last_byte_at: start + at,
+        oversized: false,
+        paste: false,
+    }
+}
+
+#[test]
+fn folds_identical_events_inside_the_window() {
+    let start = Instant::now();
+    let mut coalescer = RepeatCoalescer::new(WINDOW);
+
+    assert_eq!(
+        coalescer.push(&event(start, ms(0), b"\x1b[A")),
+        Coalesced::New
+    );
+    assert_eq!(
+        coalescer.push(&event(start, ms(30), b"\x1b[A")),
+        Coalesced::Repeat(2)
+    );
+    assert_eq!(
+        coalescer.push(&event(start, ms(60), b"\x1b[A")),
+        Coalesced::Repeat(3)
+    );
+    assert_eq!(
+        coalescer.push(&event(start, ms(90), b"\x1b[B")),
+        Coalesced::New
+    );
+}
+
+#[test]
+fn window_is_measured_from_the_previous_repeat() {
+    let start = Instant::now();
+    let mut coalescer = RepeatCoalescer::new(WINDOW);
+
+    for step in 0..10 {
+        coalescer.push(&event(start, ms(step * 80), b"a"));
+    }
+    assert_eq!(coalescer.entries(), 1);
+
+    assert_eq!(
+        coalescer.push(&event(start, ms(2000), b"a")),
+        Coalesced::New
+    );
+}
+
+#[test]
+fn repeats_do_not_count_toward_entries() {
+    let start = Instant::now();
+    let mut coalescer = RepeatCoalescer::new(WINDOW);
+
+    for step in 0..37 {
+        coalescer.push(&event(start, ms(step * 30), b"\x1b[A"));
+    }
+    coalescer.push(&event(start, ms(2000), b"q"));
+
+    assert_eq!(coalescer.entries(), 2);
+    assert_eq!(coalescer.events(), 38);
+}
+
+#[test]
+fn disabled_coalescer_counts_every_event() {
+    let start = Instant::now();
+    let mut coalescer = RepeatCoalescer::disabled();
+
+    for step in 0..5 {
+        assert_eq!(
+            coalescer.push(&event(start, ms(step), b"\x1b[A")),
+            Coalesced::New
+        );
+    }
+    assert_eq!(coalescer.entries(), 5);
+    assert_eq!(coalescer.events(), 5);
+}
+
+#[test]
+fn reset_ends_the_run() {
+    let start = Instant::now();
+    let mut coalescer = RepeatCoalescer::new(WINDOW);
+
+    coalescer.push(&event(start, ms(0), b"a"));
+    coalescer.reset();
+    assert_eq!(coalescer.push(&event(start, ms(10), b"a")), Coalesced::New);
+}
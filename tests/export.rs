@@ -0,0 +1,134 @@
+use _tuicore::export::{write_csv, write_json_lines, ExportedEvent, SCHEMA_VERSION};
+use _tuicore::interpret::parse_event_stream;
+use std::time::Duration;
+
+#[test]
+fn json_lines_parse_back_into_the_exported_events() {
+    let events: Vec<ExportedEvent> = parse_event_stream(b"q\x1b[1;5A\x1b\x03\xe2\x82\xac")
+        .iter()
+        .enumerate()
+        .map(|(idx, info)| ExportedEvent::new(info, Some(Duration::from_millis(idx as u64 * 7))))
+        .collect();
+    let mut out = Vec::new();
+
+    write_json_lines(&mut out, &events).unwrap();
+
+    let text = String::from_utf8(out).unwrap();
+    let parsed: Vec<ExportedEvent> = text
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(parsed, events);
+    assert_eq!(text.lines().count(), events.len());
+}
+
+#[test]
+fn exports_every_field_of_the_guess() {
+    let info = &parse_event_stream(b"\x1b[1;5A")[0];
+
+    let exported = ExportedEvent::new(info, Some(Duration::from_micros(1500)));
+
+    let json: serde_json::Value = serde_json::to_value(&exported).unwrap();
+    assert_eq!(json["schema"], SCHEMA_VERSION);
+    assert_eq!(json["hex"], "1B 5B 31 3B 35 41");
+    assert_eq!(json["escaped"], "\\x1B[1;5A");
+    assert_eq!(json["timestamp_ms"], 1.5);
+    assert_eq!(json["key"], info.guess.key.as_str());
+    assert_eq!(json["code"], "Up");
+    assert_eq!(json["modifiers"], info.guess.modifiers.as_str());
+    assert_eq!(json["kind"], "Press");
+    assert_eq!(json["description"], info.guess.description.as_str());
+    assert_eq!(json["repeats"], 1);
+}
+
+#[test]
+fn offline_events_have_no_timestamp() {
+    let exported = ExportedEvent::new(&parse_event_stream(b"x")[0], None);
+
+    let line = serde_json::to_string(&exported).unwrap();
+    assert!(line.contains("\"timestamp_ms\":null"), "{line}");
+}
+
+fn exported(bytes: &[u8]) -> Vec<ExportedEvent> {
+    parse_event_stream(bytes)
+        .iter()
+        .enumerate()
+        .map(|(idx, info)| ExportedEvent::new(info, Some(Duration::from_millis(idx as u64 * 3))))
+        .collect()
+}
+
+#[test]
+fn csv_parses_back_into_the_exported_events() {
+    // Commas, semicolons, quotes and multi-byte UTF-8 all land in the escaped column.
+    let events = exported("a,b;\"é€\x1b[1;5A".as_bytes());
+    assert!(events.iter().any(|event| event.escaped.contains(',')));
+    assert!(events.iter().any(|event| event.escaped.contains(';')));
+    assert!(events.iter().any(|event| event.escaped.contains('"')));
+    let mut out = Vec::new();
+
+    write_csv(&mut out, &events).unwrap();
+
+    let parsed: Vec<ExportedEvent> = csv::Reader::from_reader(out.as_slice())
+        .deserialize()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(parsed, events);
+}
+
+#[test]
+fn csv_starts_with_a_header_row_naming_the_json_fields() {
+    let events = exported(b"x");
+    let mut out = Vec::new();
+
+    write_csv(&mut out, &events).unwrap();
+
+    let text = String::from_utf8(out).unwrap();
+    let json: serde_json::Value = serde_json::to_value(&events[0]).unwrap();
+    let json_fields: Vec<&str> = json
+        .as_object()
+        .unwrap()
+        .keys()
+        .map(String::as_str)
+        .collect();
+    let header: Vec<&str> = text.lines().next().unwrap().split(',').collect();
+    let mut sorted = header.clone();
+    sorted.sort_unstable();
+    assert_eq!(sorted, json_fields);
+    assert_eq!(header[0], "schema");
+    assert_eq!(text.lines().count(), 2);
+}
+
+#[test]
+fn csv_leaves_a_missing_timestamp_empty() {
+    let offline = vec![ExportedEvent::new(&parse_event_stream(b"x")[0], None)];
+    let mut out = Vec::new();
+
+    write_csv(&mut out, &offline).unwrap();
+
+    let parsed: Vec<ExportedEvent> = csv::Reader::from_reader(out.as_slice())
+        .deserialize()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(parsed, offline);
+    assert!(String::from_utf8(out).unwrap().contains(",,"));
+}
+
+#[test]
+fn json_without_a_gap_still_parses() {
+    let line = r#"{"schema":1,"hex":"78","escaped":"x","timestamp_ms":null,"key":"x","code":"x",
+        "modifiers":"None","kind":"Press","description":"","repeats":1}"#;
+
+    let parsed: ExportedEvent = serde_json::from_str(line).unwrap();
+
+    assert_eq!(parsed.gap_ms, None);
+}
+
+#[test]
+fn json_without_totals_still_parses() {
+    let line = r#"{"schema":1,"hex":"78","escaped":"x","timestamp_ms":1.0,"gap_ms":null,"key":"x",
+        "code":"x","modifiers":"None","kind":"Press","description":"","repeats":1}"#;
+
+    let parsed: ExportedEvent = serde_json::from_str(line).unwrap();
+
+    assert_eq!((parsed.total_bytes, parsed.total_events), (None, None));
+}
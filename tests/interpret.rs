@@ -0,0 +1,171 @@
+use _tuicore::interpret::{parse_event_stream, InputEventInfo};
+use std::fs;
+use std::path::Path;
+
+fn fixture(name: &str) -> Vec<u8> {
+    let path = format!(
+        "{}/tests/fixtures/parse/{}",
+        env!("CARGO_MANIFEST_DIR"),
+        name
+    );
+    fs::read(path).unwrap()
+}
+
+fn keys(events: &[InputEventInfo]) -> Vec<(&str, &str)> {
+    events
+        .iter()
+        .map(|info| (info.guess.key.as_str(), info.guess.code.as_str()))
+        .collect()
+}
+
+#[test]
+fn every_fixture_frames_back_into_its_input() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/parse");
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        let bytes = fs::read(&path).unwrap();
+        let events = parse_event_stream(&bytes);
+        let rejoined: Vec<u8> = events.iter().flat_map(|info| info.bytes.clone()).collect();
+        assert_eq!(rejoined, bytes, "{}", path.display());
+    }
+}
+
+#[test]
+fn frames_and_interprets_a_mixed_stream() {
+    let events = parse_event_stream(b"a\x1b[1;5A\x1bOP\xc3\xa9\x1b");
+    assert_eq!(
+        keys(&events),
+        vec![
+            ("'a'", "Char('a')"),
+            ("Up", "Up"),
+            ("F1", "F(1)"),
+            ("'é'", "Char('é')"),
+            ("Esc", "Esc"),
+        ]
+    );
+    assert_eq!(events[1].hex_string, "1B 5B 31 3B 35 41");
+    assert_eq!(events[1].escaped_string, "\\x1B[1;5A");
+}
+
+#[test]
+fn oversized_csi_parameters_saturate() {
+    let events = parse_event_stream(&fixture("csi_param_overflow.bin"));
+    assert_eq!(keys(&events), vec![("Up", "Up"), ("Down", "Down")]);
+}
+
+#[test]
+fn escape_before_a_control_key_adds_alt() {
+    let events = parse_event_stream(&fixture("alt_control_key.bin"));
+    assert_eq!(
+        keys(&events),
+        vec![
+            ("'a'", "Char('a')"),
+            ("Esc", "Esc"),
+            ("Backspace", "Backspace"),
+        ]
+    );
+    assert!(events
+        .iter()
+        .all(|info| info.guess.modifiers.contains("ALT")));
+    assert!(events[0].guess.modifiers.contains("CONTROL"));
+}
+
+#[test]
+fn c1_controls_are_never_displayed_raw() {
+    let events = parse_event_stream(&fixture("c1_control_char.bin"));
+    assert_eq!(events.len(), 2);
+    for info in &events {
+        let shown = format!("{}{}", info.guess.key, info.escaped_string);
+        assert!(!shown.chars().any(char::is_control), "{:?}", shown);
+    }
+}
+
+#[test]
+fn unterminated_paste_is_flushed_as_paste_content() {
+    let events = parse_event_stream(&fixture("unterminated_paste.bin"));
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].guess.description, "Paste start");
+    assert_eq!(events[1].bytes, b"abc\x1b[201");
+    assert_eq!(events[1].guess.key, "Paste");
+}
+
+#[test]
+fn trailing_partial_sequences_are_flushed_at_the_end() {
+    let events = parse_event_stream(&fixture("truncated_utf8.bin"));
+    assert_eq!(
+        keys(&events),
+        vec![("'a'", "Char('a')"), ("Unknown", "Unknown")]
+    );
+
+    let events = parse_event_stream(&fixture("lone_csi_introducer.bin"));
+    assert_eq!(keys(&events), vec![("'['", "Char('[')")]);
+    assert!(events[0].guess.modifiers.contains("ALT"));
+}
+
+#[test]
+fn survives_arbitrary_short_streams() {
+    const ALPHABET: [u8; 16] = [
+        0x00, 0x01, 0x1B, b'[', b'O', b'~', b';', b'2', b'0', b'9', b'A', 0x7F, 0x9B, 0xC2, 0xE2,
+        0xFF,
+    ];
+    for len in 1..=4u32 {
+        for mut index in 0..ALPHABET.len().pow(len) {
+            let bytes: Vec<u8> = (0..len)
+                .map(|_| {
+                    let byte = ALPHABET[index % ALPHABET.len()];
+                    index /= ALPHABET.len();
+                    byte
+                })
+                .collect();
+            let rejoined: Vec<u8> = parse_event_stream(&bytes)
+                .into_iter()
+                .flat_map(|info| info.bytes)
+                .collect();
+            assert_eq!(rejoined, bytes);
+        }
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn matches_the_live_reader_on_one_burst() {
+    use _tuicore::clock::VirtualClock;
+    use _tuicore::raw_input::{InputSource, RawInputReader};
+    use _tuicore::scripted_input::ScriptedSource;
+    use std::time::Duration;
+
+    let bytes = b"q\x1b[A\x1b\x1bx\x1b[200~hi\x1b[201~\xe2\x82\xac\x1b[1;";
+    let clock = VirtualClock::new();
+    let source = ScriptedSource::new(clock.clone()).burst(Duration::ZERO, &bytes[..]);
+    let mut reader = RawInputReader::with_byte_source(source, clock, Duration::from_millis(35));
+    let live: Vec<Vec<u8>> = reader
+        .events(None)
+        .map(|event| event.unwrap().bytes)
+        .collect();
+
+    let parsed: Vec<Vec<u8>> = parse_event_stream(bytes)
+        .into_iter()
+        .map(|info| info.bytes)
+        .collect();
+    assert_eq!(parsed, live);
+}
+
+#[test]
+fn theme_reports_are_labelled_with_the_new_theme() {
+    let events = parse_event_stream(&fixture("theme_reports.bin"));
+    let described: Vec<(&str, &str)> = events
+        .iter()
+        .map(|info| (info.guess.key.as_str(), info.guess.description.as_str()))
+        .collect();
+    assert_eq!(
+        described,
+        vec![
+            ("'a'", "Printable character"),
+            ("Theme", "Theme changed → Dark"),
+            ("Theme", "Theme changed → Light"),
+            ("'b'", "Printable character"),
+            ("Theme", "Theme notification (3)"),
+            ("Theme", "Theme notification (1;0)"),
+        ]
+    );
+}
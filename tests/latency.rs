@@ -0,0 +1,44 @@
+use _tuicore::latency::LatencyStats;
+use std::time::Duration;
+
+fn us(micros: u64) -> Duration {
+    Duration::from_micros(micros)
+}
+
+#[test]
+fn empty_stats_report_nothing() {
+    let stats = LatencyStats::default();
+    assert_eq!(stats.count(), 0);
+    assert_eq!(stats.min(), None);
+    assert_eq!(stats.avg(), None);
+    assert_eq!(stats.p95(), None);
+    assert_eq!(stats.to_string(), "no events");
+}
+
+#[test]
+fn tracks_min_avg_p95_and_max() {
+    let mut stats = LatencyStats::default();
+    for micros in 1..=100 {
+        stats.record(us(micros));
+    }
+
+    assert_eq!(stats.count(), 100);
+    assert_eq!(stats.min(), Some(us(1)));
+    assert_eq!(stats.max(), Some(us(100)));
+    assert_eq!(stats.avg(), Some(Duration::from_nanos(50_500)));
+    assert_eq!(stats.p95(), Some(us(95)));
+}
+
+#[test]
+fn p95_follows_recent_samples() {
+    let mut stats = LatencyStats::default();
+    for _ in 0..1000 {
+        stats.record(us(500));
+    }
+    for _ in 0..1000 {
+        stats.record(us(10));
+    }
+
+    assert_eq!(stats.p95(), Some(us(10)));
+    assert_eq!(stats.max(), Some(us(500)));
+}
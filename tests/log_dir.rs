@@ -0,0 +1,168 @@
+use _tuicore::{log_dir_env_var, log_directory, LogEnvironment, LogLocation, TuiApp};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+const APP: &str = "myapp";
+
+#[derive(Default)]
+struct FakeEnvironment {
+    vars: HashMap<String, String>,
+    home: Option<PathBuf>,
+    state: Option<PathBuf>,
+    data_local: Option<PathBuf>,
+}
+
+impl FakeEnvironment {
+    /// A user with a home and the usual Linux directories under it.
+    fn user() -> Self {
+        Self {
+            home: Some(PathBuf::from("/home/user")),
+            state: Some(PathBuf::from("/home/user/.local/state")),
+            data_local: Some(PathBuf::from("/home/user/.local/share")),
+            ..Self::default()
+        }
+    }
+
+    fn var(mut self, key: &str, value: &str) -> Self {
+        self.vars.insert(key.to_string(), value.to_string());
+        self
+    }
+}
+
+impl LogEnvironment for FakeEnvironment {
+    fn var(&self, key: &str) -> Option<String> {
+        self.vars.get(key).cloned()
+    }
+
+    fn home_dir(&self) -> Option<PathBuf> {
+        self.home.clone()
+    }
+
+    fn state_dir(&self) -> Option<PathBuf> {
+        self.state.clone()
+    }
+
+    fn data_local_dir(&self) -> Option<PathBuf> {
+        self.data_local.clone()
+    }
+}
+
+fn default_dir(app_name: &str, location: LogLocation, env: &FakeEnvironment) -> PathBuf {
+    log_directory(app_name, None, location, env)
+}
+
+#[test]
+fn env_override_wins_over_everything() {
+    let env = FakeEnvironment::user().var("MYAPP_LOG_DIR", "/srv/logs");
+    for location in [LogLocation::Platform, LogLocation::Dotfile] {
+        let configured = Some(Path::new("/etc/myapp/logs"));
+        assert_eq!(
+            log_directory(APP, configured, location, &env),
+            Path::new("/srv/logs")
+        );
+    }
+}
+
+#[test]
+fn builder_directory_wins_over_the_default_location() {
+    let env = FakeEnvironment::user();
+    let configured = Some(Path::new("/etc/myapp/logs"));
+    let dir = log_directory(APP, configured, LogLocation::Platform, &env);
+    assert_eq!(dir, Path::new("/etc/myapp/logs"));
+}
+
+#[test]
+fn dotfile_location_keeps_the_legacy_layout() {
+    let dir = default_dir(APP, LogLocation::Dotfile, &FakeEnvironment::user());
+    assert_eq!(dir, Path::new("/home/user/.myapp/logs"));
+}
+
+#[cfg(not(target_os = "macos"))]
+#[test]
+fn platform_location_uses_the_state_directory() {
+    let dir = default_dir(APP, LogLocation::Platform, &FakeEnvironment::user());
+    assert_eq!(dir, Path::new("/home/user/.local/state/myapp/logs"));
+}
+
+#[cfg(not(target_os = "macos"))]
+#[test]
+fn platform_location_falls_back_to_the_data_directory() {
+    let env = FakeEnvironment {
+        state: None,
+        ..FakeEnvironment::user()
+    };
+    let dir = default_dir(APP, LogLocation::Platform, &env);
+    assert_eq!(dir, Path::new("/home/user/.local/share/myapp/logs"));
+}
+
+#[cfg(target_os = "macos")]
+#[test]
+fn platform_location_uses_library_logs() {
+    let dir = default_dir(APP, LogLocation::Platform, &FakeEnvironment::user());
+    assert_eq!(dir, Path::new("/home/user/Library/Logs/myapp"));
+}
+
+#[test]
+fn without_any_user_directory_logs_go_to_the_temp_directory() {
+    let env = FakeEnvironment::default();
+    for location in [LogLocation::Platform, LogLocation::Dotfile] {
+        assert_eq!(
+            default_dir(APP, location, &env),
+            std::env::temp_dir().join(APP)
+        );
+    }
+}
+
+#[test]
+fn app_name_is_made_safe_to_name_one_directory() {
+    let env = FakeEnvironment::user();
+    for (name, safe) in [
+        ("my/app", "my_app"),
+        ("my\\app", "my_app"),
+        ("my:app?", "my_app_"),
+        ("my\napp", "my_app"),
+        ("..", "__"),
+        ("my app", "my app"),
+    ] {
+        let dir = default_dir(name, LogLocation::Dotfile, &env);
+        assert_eq!(
+            dir,
+            Path::new("/home/user")
+                .join(format!(".{safe}"))
+                .join("logs")
+        );
+    }
+}
+
+#[test]
+fn env_var_keeps_simple_names_as_they_were() {
+    assert_eq!(log_dir_env_var("myapp"), "MYAPP_LOG_DIR");
+    assert_eq!(log_dir_env_var("my_app2"), "MY_APP2_LOG_DIR");
+}
+
+#[test]
+fn env_var_replaces_everything_but_ascii_letters_and_digits() {
+    for (name, var) in [
+        ("my-cool tool", "MY_COOL_TOOL_LOG_DIR"),
+        ("my.app", "MY_APP_LOG_DIR"),
+        ("café", "CAF__LOG_DIR"),
+        ("日本", "___LOG_DIR"),
+    ] {
+        assert_eq!(log_dir_env_var(name), var);
+    }
+}
+
+#[test]
+fn env_var_for_a_dashed_name_overrides_its_log_directory() {
+    let env = FakeEnvironment::user().var("MY_COOL_TOOL_LOG_DIR", "/srv/logs");
+    assert_eq!(
+        default_dir("my-cool tool", LogLocation::Platform, &env),
+        Path::new("/srv/logs")
+    );
+}
+
+#[test]
+fn app_reports_its_env_var() {
+    let app = TuiApp::builder("my-cool tool").build();
+    assert_eq!(app.log_dir_env_var(), "MY_COOL_TOOL_LOG_DIR");
+}
@@ -0,0 +1,117 @@
+use _tuicore::probe::{color_depth_from, ColorDepth, ProbeReplies, TerminalCapabilities};
+
+const DA1: &[u8] = b"\x1b[?62;22c";
+
+fn replies(bytes: &[u8]) -> ProbeReplies {
+    let mut replies = ProbeReplies::new();
+    for &byte in bytes {
+        replies.push(byte);
+    }
+    replies
+}
+
+#[test]
+fn reads_every_reply_up_to_device_attributes() {
+    let replies = replies(&[b"\x1b[?1u\x1b[?2026;2$y\x1b[?2004;1$y".as_slice(), DA1].concat());
+
+    assert!(replies.is_complete());
+    assert_eq!(
+        replies.capabilities(),
+        TerminalCapabilities {
+            kitty_keyboard: true,
+            synchronized_output: true,
+            bracketed_paste: true,
+            truecolor: false,
+        }
+    );
+}
+
+#[test]
+fn modes_reported_unknown_or_permanently_reset_are_unsupported() {
+    let replies = replies(&[b"\x1b[?2026;0$y\x1b[?2004;4$y".as_slice(), DA1].concat());
+
+    assert_eq!(replies.capabilities(), TerminalCapabilities::default());
+}
+
+#[test]
+fn a_terminal_that_only_answers_device_attributes_supports_nothing() {
+    let replies = replies(DA1);
+
+    assert!(replies.is_complete());
+    assert_eq!(replies.capabilities(), TerminalCapabilities::default());
+}
+
+#[test]
+fn is_incomplete_until_device_attributes_arrive() {
+    let mut replies = replies(b"\x1b[?1u\x1b[?2026;1$y");
+    assert!(!replies.is_complete());
+
+    let (last, rest) = DA1.split_last().unwrap();
+    for &byte in rest {
+        assert!(!replies.push(byte));
+    }
+    assert!(replies.push(*last));
+}
+
+#[test]
+fn secondary_device_attributes_do_not_end_the_probe() {
+    assert!(!replies(b"\x1b[>1;10;0c").is_complete());
+}
+
+#[test]
+fn keys_typed_during_the_probe_are_dropped() {
+    let replies = replies(&[b"x\x1b[A\x1b\x1b[?2026;1$yq\x1bO".as_slice(), DA1].concat());
+
+    assert!(replies.is_complete());
+    assert_eq!(
+        replies.capabilities(),
+        TerminalCapabilities {
+            synchronized_output: true,
+            ..TerminalCapabilities::default()
+        }
+    );
+}
+
+#[test]
+fn bytes_after_the_probe_ends_are_ignored() {
+    let replies = replies(&[DA1, b"\x1b[?1u".as_slice()].concat());
+
+    assert!(!replies.capabilities().kitty_keyboard);
+}
+
+fn depth(vars: &[(&str, &str)]) -> ColorDepth {
+    color_depth_from(|name| {
+        vars.iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value.to_string())
+    })
+}
+
+#[test]
+fn colorterm_advertises_truecolor_whatever_term_says() {
+    assert_eq!(
+        depth(&[("COLORTERM", "truecolor"), ("TERM", "xterm")]),
+        ColorDepth::TrueColor
+    );
+    assert_eq!(depth(&[("COLORTERM", "24bit")]), ColorDepth::TrueColor);
+}
+
+#[test]
+fn term_suffixes_set_the_color_depth() {
+    assert_eq!(depth(&[("TERM", "xterm-direct")]), ColorDepth::TrueColor);
+    assert_eq!(depth(&[("TERM", "xterm-256color")]), ColorDepth::Ansi256);
+    assert_eq!(
+        depth(&[("TERM", "screen.xterm-256color")]),
+        ColorDepth::Ansi256
+    );
+    assert_eq!(
+        depth(&[("TERM", "xterm"), ("COLORTERM", "yes")]),
+        ColorDepth::Ansi16
+    );
+}
+
+#[test]
+fn dumb_or_missing_term_has_no_color() {
+    assert_eq!(depth(&[("TERM", "dumb")]), ColorDepth::Monochrome);
+    assert_eq!(depth(&[]), ColorDepth::Monochrome);
+}
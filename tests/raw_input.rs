@@ -0,0 +1,321 @@
+#![cfg(unix)]
+
+use _tuicore::raw_input::{
+    csi_sequence_length, try_extract_event, InputSource, PollResult, RawInputReader, MAX_CSI_LENGTH,
+};
+use std::io::Write;
+use std::os::unix::net::UnixStream;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const FLUSH_TIMEOUT: Duration = Duration::from_millis(20);
+
+fn reader() -> RawInputReader {
+    RawInputReader::new(FLUSH_TIMEOUT).unwrap()
+}
+
+fn next_bytes(reader: &mut RawInputReader) -> Option<Vec<u8>> {
+    reader.next_pending().map(|event| event.bytes)
+}
+
+#[test]
+fn frames_complete_sequences() {
+    assert_eq!(try_extract_event(b"a"), Some(1));
+    assert_eq!(try_extract_event(b"\x1b[A"), Some(3));
+    assert_eq!(try_extract_event(b"\x1bOP"), Some(3));
+    assert_eq!(try_extract_event(b"\x1bx"), Some(2));
+    assert_eq!(try_extract_event("é".as_bytes()), Some(2));
+    assert_eq!(csi_sequence_length(b"\x1b[13;2u"), Some(7));
+}
+
+#[test]
+fn waits_for_incomplete_sequences() {
+    assert_eq!(try_extract_event(b""), None);
+    assert_eq!(try_extract_event(b"\x1b"), None);
+    assert_eq!(try_extract_event(b"\x1b[1;5"), None);
+    assert_eq!(try_extract_event(b"\xc3"), None);
+}
+
+#[test]
+fn reassembles_sequences_split_across_reads() {
+    let mut reader = reader();
+
+    reader.push_bytes(b"\x1b[1;");
+    assert_eq!(next_bytes(&mut reader), None);
+
+    reader.push_bytes(b"5C");
+    assert_eq!(next_bytes(&mut reader), Some(b"\x1b[1;5C".to_vec()));
+    assert_eq!(next_bytes(&mut reader), None);
+}
+
+#[test]
+fn flushes_partial_sequence_after_timeout() {
+    let mut reader = reader();
+
+    reader.push_bytes(b"\x1b");
+    assert_eq!(next_bytes(&mut reader), None);
+
+    thread::sleep(FLUSH_TIMEOUT * 2);
+    assert_eq!(next_bytes(&mut reader), Some(b"\x1b".to_vec()));
+    assert_eq!(next_bytes(&mut reader), None);
+}
+
+#[test]
+fn flush_timeout_can_be_changed_at_runtime() {
+    let mut reader = reader();
+    reader.set_flush_timeout(Duration::from_secs(60));
+    assert_eq!(reader.esc_timeout(), Duration::from_secs(60));
+    assert_eq!(reader.sequence_timeout(), Duration::from_secs(60));
+
+    reader.push_bytes(b"\x1b[");
+    thread::sleep(FLUSH_TIMEOUT * 2);
+    assert_eq!(next_bytes(&mut reader), None);
+
+    reader.set_flush_timeout(Duration::ZERO);
+    assert_eq!(next_bytes(&mut reader), Some(b"\x1b[".to_vec()));
+}
+
+#[test]
+fn ready_queue_preserves_arrival_order() {
+    let mut reader = reader();
+
+    reader.push_bytes(b"ab\x1b[A\x1bOP\xc3\xa9");
+
+    let events: Vec<Vec<u8>> = std::iter::from_fn(|| next_bytes(&mut reader)).collect();
+    assert_eq!(
+        events,
+        vec![
+            b"a".to_vec(),
+            b"b".to_vec(),
+            b"\x1b[A".to_vec(),
+            b"\x1bOP".to_vec(),
+            "é".as_bytes().to_vec(),
+        ]
+    );
+}
+
+#[test]
+fn reports_eof_after_flushing_trailing_partial_sequence() {
+    let (mut writer, source) = UnixStream::pair().unwrap();
+    let mut reader = RawInputReader::with_source(source, Duration::from_secs(60));
+
+    writer.write_all(b"a\x1b[1;").unwrap();
+    drop(writer);
+
+    let timeout = Duration::from_secs(1);
+    assert_eq!(
+        reader.poll_next(timeout).unwrap().event_bytes(),
+        Some(&b"a"[..])
+    );
+    assert_eq!(
+        reader.poll_next(timeout).unwrap().event_bytes(),
+        Some(&b"\x1b[1;"[..])
+    );
+    assert_eq!(reader.poll_next(timeout).unwrap(), PollResult::Eof);
+    assert_eq!(reader.poll_next(timeout).unwrap(), PollResult::Eof);
+}
+
+#[test]
+fn events_iterator_stops_at_eof() {
+    let (mut writer, source) = UnixStream::pair().unwrap();
+    let mut reader = RawInputReader::with_source(source, Duration::from_secs(60));
+
+    writer.write_all(b"a\x1b[Ab\x1b[1;").unwrap();
+    drop(writer);
+
+    let events: Vec<Vec<u8>> = reader
+        .events(None)
+        .map(|event| event.unwrap().bytes)
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            b"a".to_vec(),
+            b"\x1b[A".to_vec(),
+            b"b".to_vec(),
+            b"\x1b[1;".to_vec(),
+        ]
+    );
+}
+
+#[test]
+fn events_iterator_stops_at_deadline() {
+    let (mut writer, source) = UnixStream::pair().unwrap();
+    let mut reader = RawInputReader::with_source(source, FLUSH_TIMEOUT);
+
+    writer.write_all(b"x").unwrap();
+
+    let deadline = Instant::now() + Duration::from_millis(50);
+    let mut events = reader.events(Some(deadline));
+    assert_eq!(events.next().unwrap().unwrap().bytes, b"x");
+    assert!(events.next().is_none());
+    assert!(Instant::now() >= deadline);
+}
+
+#[test]
+fn reports_sigwinch_as_resize() {
+    let (_writer, source) = UnixStream::pair().unwrap();
+    let mut reader = RawInputReader::with_source(source, FLUSH_TIMEOUT);
+    reader.watch_resize().unwrap();
+
+    unsafe { libc::raise(libc::SIGWINCH) };
+
+    assert_eq!(
+        reader.poll_next(Duration::from_secs(1)).unwrap(),
+        PollResult::Resize
+    );
+    assert_eq!(
+        reader.poll_next(Duration::from_millis(20)).unwrap(),
+        PollResult::Idle
+    );
+}
+
+#[test]
+fn stamps_first_and_last_byte_of_split_sequences() {
+    let mut reader = reader();
+
+    reader.push_bytes(b"\x1b[1;");
+    thread::sleep(Duration::from_millis(5));
+    reader.push_bytes(b"5C");
+
+    let event = reader.next_pending().unwrap();
+    assert_eq!(event.bytes, b"\x1b[1;5C");
+    assert!(event.last_byte_at > event.first_byte_at);
+    assert!(event.spread() >= Duration::from_millis(5));
+
+    reader.push_bytes(b"a");
+    let event = reader.next_pending().unwrap();
+    assert_eq!(event.first_byte_at, event.last_byte_at);
+}
+
+#[test]
+fn caps_unterminated_csi_and_keeps_framing() {
+    let mut reader = reader();
+
+    reader.push_bytes(b"\x1b[");
+    let chunk = [b';'; 8 * 1024];
+    let mut oversized = 0;
+    let mut max_pending = 0;
+    for _ in 0..(1024 * 1024 / chunk.len()) {
+        reader.push_bytes(&chunk);
+        max_pending = max_pending.max(reader.pending_len());
+        while let Some(event) = reader.next_pending() {
+            if event.oversized {
+                assert!(event.bytes.len() <= MAX_CSI_LENGTH + 1);
+                oversized += 1;
+            }
+        }
+    }
+    assert_eq!(oversized, 1);
+    assert!(max_pending <= MAX_CSI_LENGTH);
+
+    reader.push_bytes(b"\x1b[A");
+    let event = reader.next_pending().unwrap();
+    assert_eq!(event.bytes, b"\x1b[A");
+    assert!(!event.oversized);
+}
+
+#[test]
+fn max_pending_caps_the_buffer_below_the_csi_limit() {
+    let mut reader = reader();
+    reader.set_max_pending(16);
+
+    reader.push_bytes(b"\x1b[");
+    reader.push_bytes(&[b'1'; 64]);
+    let event = reader.next_pending().unwrap();
+    assert!(event.oversized);
+    assert_eq!(event.bytes.len(), 16);
+    assert!(reader.pending_len() < 16);
+}
+
+#[test]
+fn csi_scan_stops_at_the_length_cap() {
+    let mut sequence = b"\x1b[".to_vec();
+    sequence.extend(std::iter::repeat_n(b';', MAX_CSI_LENGTH));
+    sequence.push(b'm');
+    assert_eq!(csi_sequence_length(&sequence), None);
+}
+
+#[test]
+fn paused_reader_leaves_bytes_unread_until_resumed() {
+    let (mut writer, source) = UnixStream::pair().unwrap();
+    let mut reader = RawInputReader::with_source(source, FLUSH_TIMEOUT);
+
+    reader.pause();
+    assert!(reader.is_paused());
+    writer.write_all(b"ab").unwrap();
+    assert_eq!(
+        reader.poll_next(Duration::from_millis(20)).unwrap(),
+        PollResult::Idle
+    );
+
+    reader.resume();
+    let events: Vec<Vec<u8>> = std::iter::from_fn(|| {
+        reader
+            .poll_next(Duration::from_millis(100))
+            .unwrap()
+            .into_event()
+            .map(|event| event.bytes)
+    })
+    .collect();
+    assert_eq!(events, vec![b"a".to_vec(), b"b".to_vec()]);
+}
+
+#[test]
+fn exposes_pending_bytes_without_consuming_them() {
+    let mut reader = RawInputReader::new(Duration::from_secs(60)).unwrap();
+
+    reader.push_bytes(b"a\x1b[1;");
+    assert_eq!(reader.pending_bytes(), b"\x1b[1;");
+    assert_eq!(reader.pending_bytes(), b"\x1b[1;");
+    assert_eq!(next_bytes(&mut reader), Some(b"a".to_vec()));
+
+    reader.push_bytes(b"5C");
+    assert_eq!(reader.pending_bytes(), b"");
+}
+
+#[test]
+fn poll_batch_returns_every_ready_event_in_order() {
+    let (mut writer, source) = UnixStream::pair().unwrap();
+    let mut reader = RawInputReader::with_source(source, FLUSH_TIMEOUT);
+
+    writer.write_all(b"ab\x1b[Ac\x1b[1;").unwrap();
+
+    let mut batch = Vec::new();
+    assert_eq!(
+        reader
+            .poll_batch(Duration::from_secs(1), &mut batch)
+            .unwrap(),
+        PollResult::Pending { buffered: 4 }
+    );
+    let bytes: Vec<Vec<u8>> = batch.into_iter().map(|event| event.bytes).collect();
+    assert_eq!(
+        bytes,
+        vec![
+            b"a".to_vec(),
+            b"b".to_vec(),
+            b"\x1b[A".to_vec(),
+            b"c".to_vec()
+        ]
+    );
+    assert_eq!(reader.pending_bytes(), b"\x1b[1;");
+}
+
+#[test]
+fn poll_batch_flushes_and_reports_eof_when_the_source_closes() {
+    let (mut writer, source) = UnixStream::pair().unwrap();
+    let mut reader = RawInputReader::with_source(source, Duration::from_secs(60));
+
+    writer.write_all(b"a\x1b[1;").unwrap();
+    drop(writer);
+
+    let mut batch = Vec::new();
+    let mut result = PollResult::Idle;
+    while result != PollResult::Eof {
+        result = reader
+            .poll_batch(Duration::from_secs(1), &mut batch)
+            .unwrap();
+    }
+    let bytes: Vec<Vec<u8>> = batch.into_iter().map(|event| event.bytes).collect();
+    assert_eq!(bytes, vec![b"a".to_vec(), b"\x1b[1;".to_vec()]);
+}
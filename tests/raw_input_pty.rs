@@ -0,0 +1,170 @@
+#![cfg(unix)]
+
+mod support;
+
+use _tuicore::raw_input::{ByteSource, FdSource, InputSource, PollResult, RawInputReader};
+use nix::sys::termios::{self, LocalFlags, SetArg};
+use std::io::{self, Write};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const FLUSH_TIMEOUT: Duration = Duration::from_millis(20);
+
+#[test]
+fn reads_framed_events_from_a_pty_instead_of_stdin() {
+    let (mut master, slave) = support::raw_pty();
+    let mut reader = RawInputReader::with_source(slave, FLUSH_TIMEOUT);
+
+    master.write_all(b"\x1b[13;2ux").unwrap();
+
+    assert_eq!(
+        reader
+            .poll_next(Duration::from_secs(1))
+            .unwrap()
+            .event_bytes(),
+        Some(&b"\x1b[13;2u"[..])
+    );
+    assert_eq!(
+        reader
+            .poll_next(Duration::from_secs(1))
+            .unwrap()
+            .event_bytes(),
+        Some(&b"x"[..])
+    );
+    assert_eq!(
+        reader.poll_next(Duration::from_millis(50)).unwrap(),
+        PollResult::Idle
+    );
+}
+
+#[test]
+fn reports_eof_once_the_pty_master_closes() {
+    let (mut master, slave) = support::raw_pty();
+    let mut reader = RawInputReader::with_source(slave, FLUSH_TIMEOUT);
+
+    master.write_all(b"a").unwrap();
+    assert_eq!(
+        reader
+            .poll_next(Duration::from_secs(1))
+            .unwrap()
+            .event_bytes(),
+        Some(&b"a"[..])
+    );
+
+    drop(master);
+    assert_eq!(
+        reader.poll_next(Duration::from_secs(1)).unwrap(),
+        PollResult::Eof
+    );
+    assert!(reader.is_closed());
+}
+
+#[test]
+fn decodes_arrow_keys_in_both_cursor_modes() {
+    let (mut master, slave) = support::raw_pty();
+    let mut reader = RawInputReader::with_source(slave, FLUSH_TIMEOUT);
+
+    // Normal and application cursor mode, then a modified arrow.
+    master.write_all(b"\x1b[A\x1bOB\x1b[1;5C").unwrap();
+
+    let events: Vec<Vec<u8>> = reader
+        .events(Some(Instant::now() + Duration::from_millis(200)))
+        .map(|event| event.unwrap().bytes)
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            b"\x1b[A".to_vec(),
+            b"\x1bOB".to_vec(),
+            b"\x1b[1;5C".to_vec()
+        ]
+    );
+}
+
+#[test]
+fn reading_an_empty_tty_does_not_block() {
+    let (_master, slave) = support::raw_pty();
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut source = FdSource::new(slave);
+        let _ = sender.send(source.read(&mut [0u8; 16]).map_err(|err| err.kind()));
+    });
+
+    assert_eq!(
+        receiver.recv_timeout(Duration::from_secs(1)).unwrap(),
+        Err(io::ErrorKind::WouldBlock)
+    );
+}
+
+#[test]
+fn single_byte_on_a_slow_line_does_not_block_the_next_poll() {
+    let (mut master, slave) = support::raw_pty();
+    let mut reader = RawInputReader::with_source(slave, Duration::from_secs(60));
+
+    master.write_all(b"\x1b").unwrap();
+    assert_eq!(
+        reader.poll_next(Duration::from_secs(1)).unwrap(),
+        PollResult::Pending { buffered: 1 }
+    );
+    assert_eq!(reader.pending_bytes(), b"\x1b");
+
+    let started = Instant::now();
+    assert_eq!(
+        reader.poll_next(Duration::from_millis(50)).unwrap(),
+        PollResult::Pending { buffered: 1 }
+    );
+    assert!(started.elapsed() < Duration::from_secs(1));
+
+    master.write_all(b"[A").unwrap();
+    assert_eq!(
+        reader
+            .poll_next(Duration::from_secs(1))
+            .unwrap()
+            .event_bytes(),
+        Some(&b"\x1b[A"[..])
+    );
+}
+
+#[test]
+fn shutdown_child() {
+    if !support::is_child("shutdown_child") {
+        return;
+    }
+
+    let stdin = io::stdin();
+    let original = termios::tcgetattr(&stdin).unwrap();
+    let mut raw = original.clone();
+    termios::cfmakeraw(&mut raw);
+    termios::tcsetattr(&stdin, SetArg::TCSANOW, &raw).unwrap();
+
+    let mut reader = RawInputReader::new(FLUSH_TIMEOUT).unwrap();
+    reader.watch_shutdown().unwrap();
+    io::stdout().write_all(b"ready").unwrap();
+    io::stdout().flush().unwrap();
+
+    loop {
+        if let PollResult::Shutdown(signal) = reader.poll_next(Duration::from_secs(1)).unwrap() {
+            termios::tcsetattr(&stdin, SetArg::TCSANOW, &original).unwrap();
+            std::process::exit(128 + signal);
+        }
+    }
+}
+
+#[test]
+fn sigterm_restores_the_tty() {
+    let mut child = support::PtyChild::spawn("shutdown_child");
+    child.wait_for(b"ready");
+
+    let raw = child.termios();
+    assert!(!raw.local_flags.contains(LocalFlags::ICANON));
+
+    assert_eq!(unsafe { libc::kill(child.pid(), libc::SIGTERM) }, 0);
+    let status = child.wait();
+    assert_eq!(status.code(), Some(128 + libc::SIGTERM));
+
+    let restored = child.termios();
+    assert!(restored
+        .local_flags
+        .contains(LocalFlags::ICANON | LocalFlags::ECHO));
+}
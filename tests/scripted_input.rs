@@ -0,0 +1,575 @@
+#![cfg(unix)]
+
+use _tuicore::clock::{Clock, VirtualClock};
+use _tuicore::raw_input::{
+    FlushTimeouts, InputSource, PollResult, RawEvent, RawInputReader, Readiness, PASTE_CHUNK,
+    PASTE_END, PASTE_START,
+};
+use _tuicore::scripted_input::ScriptedSource;
+use std::time::Duration;
+
+const FLUSH_TIMEOUT: Duration = Duration::from_millis(35);
+const POLL_TIMEOUT: Duration = Duration::from_secs(1);
+
+fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+/// Poll until EOF, collecting each event's bytes and virtual arrival offsets.
+fn run(clock: &VirtualClock, source: ScriptedSource) -> Vec<(Vec<u8>, Duration, Duration)> {
+    let start = clock.now() - clock.elapsed();
+    let mut reader = RawInputReader::with_byte_source(source, clock.clone(), FLUSH_TIMEOUT);
+    let mut events = Vec::new();
+    loop {
+        match reader.poll_next(POLL_TIMEOUT).unwrap() {
+            PollResult::Event(event) => events.push((
+                event.bytes,
+                event.first_byte_at - start,
+                event.last_byte_at - start,
+            )),
+            PollResult::Eof => return events,
+            PollResult::Idle
+            | PollResult::Pending { .. }
+            | PollResult::Resize
+            | PollResult::Shutdown(_) => {}
+        }
+    }
+}
+
+#[test]
+fn reassembles_csi_split_across_reads() {
+    let clock = VirtualClock::new();
+    let source = ScriptedSource::new(clock.clone())
+        .burst(ms(0), &b"\x1b[1;"[..])
+        .burst(ms(10), &b"5C"[..]);
+
+    assert_eq!(
+        run(&clock, source),
+        vec![(b"\x1b[1;5C".to_vec(), ms(0), ms(10))]
+    );
+}
+
+#[test]
+fn flushes_lone_escape_after_timeout() {
+    let clock = VirtualClock::new();
+    let source = ScriptedSource::new(clock.clone())
+        .burst(ms(0), &b"\x1b"[..])
+        .burst(ms(100), &b"a"[..]);
+
+    let events = run(&clock, source);
+    assert_eq!(
+        events,
+        vec![
+            (b"\x1b".to_vec(), ms(0), ms(0)),
+            (b"a".to_vec(), ms(100), ms(100)),
+        ]
+    );
+}
+
+#[test]
+fn flush_happens_at_the_timeout_not_at_the_next_burst() {
+    let clock = VirtualClock::new();
+    let source = ScriptedSource::new(clock.clone())
+        .burst(ms(0), &b"\x1b["[..])
+        .burst(ms(500), &b"x"[..]);
+    let mut reader = RawInputReader::with_byte_source(source, clock.clone(), FLUSH_TIMEOUT);
+
+    let mut flushed = None;
+    while flushed.is_none() {
+        flushed = reader.poll_next(POLL_TIMEOUT).unwrap().into_event();
+    }
+    assert_eq!(flushed.unwrap().bytes, b"\x1b[");
+    assert_eq!(clock.elapsed(), FLUSH_TIMEOUT);
+}
+
+#[test]
+fn preserves_order_of_events_from_one_burst() {
+    let clock = VirtualClock::new();
+    let source = ScriptedSource::new(clock.clone()).burst(ms(5), &b"a\x1b[Ab\x1bOPc"[..]);
+
+    let bytes: Vec<Vec<u8>> = run(&clock, source)
+        .into_iter()
+        .map(|(bytes, _, _)| bytes)
+        .collect();
+    assert_eq!(
+        bytes,
+        vec![
+            b"a".to_vec(),
+            b"\x1b[A".to_vec(),
+            b"b".to_vec(),
+            b"\x1bOP".to_vec(),
+            b"c".to_vec(),
+        ]
+    );
+}
+
+fn split_timeouts() -> FlushTimeouts {
+    FlushTimeouts {
+        esc_timeout: ms(10),
+        sequence_timeout: ms(150),
+    }
+}
+
+/// Poll until the first event, returning its bytes and the virtual time it was delivered.
+fn first_event(clock: &VirtualClock, source: ScriptedSource) -> (Vec<u8>, Duration) {
+    let mut reader = RawInputReader::with_byte_source(source, clock.clone(), split_timeouts());
+    loop {
+        if let Some(event) = reader.poll_next(POLL_TIMEOUT).unwrap().into_event() {
+            return (event.bytes, clock.elapsed());
+        }
+    }
+}
+
+#[test]
+fn lone_escape_uses_the_esc_timeout() {
+    let clock = VirtualClock::new();
+    let source = ScriptedSource::new(clock.clone())
+        .burst(ms(0), &b"\x1b"[..])
+        .burst(ms(100), &b"a"[..]);
+
+    assert_eq!(first_event(&clock, source), (b"\x1b".to_vec(), ms(10)));
+}
+
+#[test]
+fn partial_sequence_uses_the_sequence_timeout() {
+    let clock = VirtualClock::new();
+    let source = ScriptedSource::new(clock.clone())
+        .burst(ms(0), &b"\x1b["[..])
+        .burst(ms(200), &b"A"[..]);
+
+    assert_eq!(first_event(&clock, source), (b"\x1b[".to_vec(), ms(150)));
+}
+
+#[test]
+fn sequence_timeout_reassembles_slow_csi() {
+    let clock = VirtualClock::new();
+    let source = ScriptedSource::new(clock.clone())
+        .burst(ms(0), &b"\x1b"[..])
+        .burst(ms(5), &b"["[..])
+        .burst(ms(100), &b"A"[..]);
+
+    assert_eq!(first_event(&clock, source), (b"\x1b[A".to_vec(), ms(100)));
+}
+
+#[test]
+fn flush_fires_exactly_at_the_threshold() {
+    let clock = VirtualClock::new();
+    let source = ScriptedSource::new(clock.clone())
+        .burst(ms(0), &b"\x1b["[..])
+        .burst(ms(500), &b"x"[..]);
+    let mut reader = RawInputReader::with_byte_source(source, clock.clone(), FLUSH_TIMEOUT);
+
+    assert_eq!(
+        reader.poll_next(Duration::ZERO).unwrap(),
+        PollResult::Pending { buffered: 2 }
+    );
+    assert_eq!(
+        reader.poll_next(FLUSH_TIMEOUT - ms(1)).unwrap(),
+        PollResult::Pending { buffered: 2 }
+    );
+    assert_eq!(clock.elapsed(), FLUSH_TIMEOUT - ms(1));
+
+    clock.advance(ms(1));
+    assert_eq!(
+        reader.poll_next(Duration::ZERO).unwrap().event_bytes(),
+        Some(&b"\x1b["[..])
+    );
+    assert_eq!(clock.elapsed(), FLUSH_TIMEOUT);
+}
+
+#[test]
+fn shorter_requested_timeout_wins_over_the_flush_window() {
+    let clock = VirtualClock::new();
+    let source = ScriptedSource::new(clock.clone())
+        .burst(ms(0), &b"\x1b["[..])
+        .burst(ms(500), &b"x"[..]);
+    let mut reader = RawInputReader::with_byte_source(source, clock.clone(), FLUSH_TIMEOUT);
+
+    assert_eq!(
+        reader.poll_next(Duration::ZERO).unwrap(),
+        PollResult::Pending { buffered: 2 }
+    );
+    assert_eq!(
+        reader.poll_next(ms(10)).unwrap(),
+        PollResult::Pending { buffered: 2 }
+    );
+    assert_eq!(clock.elapsed(), ms(10));
+
+    // The next wait only covers what is left of the window, not the full requested timeout.
+    assert!(reader
+        .poll_next(POLL_TIMEOUT)
+        .unwrap()
+        .into_event()
+        .is_some());
+    assert_eq!(clock.elapsed(), FLUSH_TIMEOUT);
+}
+
+#[test]
+fn new_bytes_restart_the_flush_window() {
+    let clock = VirtualClock::new();
+    let source = ScriptedSource::new(clock.clone())
+        .burst(ms(0), &b"\x1b"[..])
+        .burst(ms(20), &b"["[..])
+        .burst(ms(45), &b"1"[..])
+        .burst(ms(500), &b"x"[..]);
+    let mut reader = RawInputReader::with_byte_source(source, clock.clone(), FLUSH_TIMEOUT);
+
+    let mut flushed = None;
+    while flushed.is_none() {
+        flushed = reader.poll_next(POLL_TIMEOUT).unwrap().into_event();
+    }
+    let flushed = flushed.unwrap();
+    assert_eq!(flushed.bytes, b"\x1b[1");
+    assert_eq!(clock.elapsed(), ms(45) + FLUSH_TIMEOUT);
+    assert_eq!(flushed.first_byte_at + ms(45), flushed.last_byte_at);
+}
+
+#[test]
+fn measures_first_byte_to_emit_latency() {
+    let clock = VirtualClock::new();
+    let source = ScriptedSource::new(clock.clone())
+        .burst(ms(0), &b"\x1b[1;"[..])
+        .burst(ms(10), &b"5Ca"[..])
+        .burst(ms(20), &b"\x1b"[..]);
+    let mut reader = RawInputReader::with_byte_source(source, clock.clone(), FLUSH_TIMEOUT);
+    while reader.poll_next(POLL_TIMEOUT).unwrap() != PollResult::Eof {}
+
+    let latency = reader.latency();
+    assert_eq!(latency.first_byte_to_emit.count(), 3);
+    assert_eq!(latency.first_byte_to_emit.min(), Some(Duration::ZERO));
+    assert_eq!(latency.first_byte_to_emit.max(), Some(ms(10)));
+    assert_eq!(latency.wakeup_to_frame.count(), 2);
+    assert_eq!(latency.wakeup_to_frame.max(), Some(Duration::ZERO));
+}
+
+#[test]
+fn counts_bytes_read_and_events_returned() {
+    let clock = VirtualClock::new();
+    let source = ScriptedSource::new(clock.clone())
+        .burst(ms(0), &b"\x1b[1;"[..])
+        .burst(ms(10), &b"5Ca"[..])
+        .burst(ms(1500), &b"b"[..]);
+    let mut reader = RawInputReader::with_byte_source(source, clock.clone(), FLUSH_TIMEOUT);
+
+    while reader
+        .poll_next(POLL_TIMEOUT)
+        .unwrap()
+        .into_event()
+        .is_none()
+    {}
+    let throughput = reader.throughput();
+    assert_eq!(throughput.total_bytes(), 7);
+    assert_eq!(throughput.bytes_per_sec(clock.now()), 7);
+
+    while reader.poll_next(POLL_TIMEOUT).unwrap() != PollResult::Eof {}
+    let throughput = reader.throughput();
+    assert_eq!(throughput.total_bytes(), 8);
+    assert_eq!(throughput.total_events(), 3);
+    assert_eq!(throughput.bytes_per_sec(clock.now()), 1);
+    assert_eq!(throughput.events_per_sec(clock.now()), 1);
+}
+
+#[test]
+fn distinguishes_timeouts_from_pending_sequences() {
+    let clock = VirtualClock::new();
+    let source = ScriptedSource::new(clock.clone())
+        .burst(ms(10), &b"\x1b[1;"[..])
+        .burst(ms(20), &b"5C"[..])
+        .burst(ms(500), &b"x"[..]);
+    let mut reader = RawInputReader::with_byte_source(source, clock.clone(), FLUSH_TIMEOUT);
+
+    assert_eq!(reader.poll_next(ms(5)).unwrap(), PollResult::Idle);
+    assert_eq!(
+        reader.poll_next(POLL_TIMEOUT).unwrap(),
+        PollResult::Pending { buffered: 4 }
+    );
+    assert_eq!(
+        reader.poll_next(POLL_TIMEOUT).unwrap().event_bytes(),
+        Some(&b"\x1b[1;5C"[..])
+    );
+    assert_eq!(reader.poll_next(ms(5)).unwrap(), PollResult::Idle);
+}
+
+#[test]
+fn streams_a_huge_paste_in_bounded_chunks() {
+    const PASTE_SIZE: usize = 10 * 1024 * 1024;
+    const BURST: usize = 256 * 1024;
+
+    let clock = VirtualClock::new();
+    let mut source = ScriptedSource::new(clock.clone()).burst(ms(0), PASTE_START);
+    for i in 0..PASTE_SIZE / BURST {
+        source = source.burst(ms(1 + i as u64), vec![b'x'; BURST]);
+    }
+    source = source.burst(ms(1000), PASTE_END).burst(ms(1001), &b"a"[..]);
+    let mut reader = RawInputReader::with_byte_source(source, clock.clone(), FLUSH_TIMEOUT);
+
+    let mut markers = Vec::new();
+    let mut pasted = 0;
+    let mut max_pending = 0;
+    loop {
+        max_pending = max_pending.max(reader.pending_len());
+        match reader.poll_next(POLL_TIMEOUT).unwrap() {
+            PollResult::Event(event) if event.paste => {
+                assert!(event.bytes.len() <= PASTE_CHUNK);
+                assert!(event.bytes.iter().all(|&byte| byte == b'x'));
+                pasted += event.bytes.len();
+            }
+            PollResult::Event(event) => markers.push(event.bytes),
+            PollResult::Eof => break,
+            _ => {}
+        }
+    }
+
+    assert_eq!(pasted, PASTE_SIZE);
+    assert!(max_pending <= PASTE_CHUNK);
+    assert_eq!(
+        markers,
+        vec![PASTE_START.to_vec(), PASTE_END.to_vec(), b"a".to_vec()]
+    );
+}
+
+#[test]
+fn finds_a_paste_end_marker_split_across_reads() {
+    let clock = VirtualClock::new();
+    let source = ScriptedSource::new(clock.clone())
+        .burst(ms(0), &b"\x1b[200~hi\x1b[2"[..])
+        .burst(ms(5), &b"01~"[..]);
+
+    let mut reader = RawInputReader::with_byte_source(source, clock.clone(), FLUSH_TIMEOUT);
+    let events: Vec<(Vec<u8>, bool)> = reader
+        .events(None)
+        .map(|event| event.unwrap())
+        .map(|event| (event.bytes, event.paste))
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            (PASTE_START.to_vec(), false),
+            (b"hi".to_vec(), true),
+            (PASTE_END.to_vec(), false),
+        ]
+    );
+}
+
+#[test]
+fn poll_until_collects_events_and_flushes_up_to_the_deadline() {
+    let clock = VirtualClock::new();
+    let start = clock.now();
+    let source = ScriptedSource::new(clock.clone())
+        .burst(ms(0), &b"a"[..])
+        .burst(ms(10), &b"\x1b["[..])
+        .burst(ms(20), &b"A"[..])
+        .burst(ms(60), &b"\x1b"[..])
+        .burst(ms(200), &b"b"[..]);
+    let mut reader = RawInputReader::with_byte_source(source, clock.clone(), FLUSH_TIMEOUT);
+
+    let bytes = |events: Vec<RawEvent>| -> Vec<Vec<u8>> {
+        events.into_iter().map(|event| event.bytes).collect()
+    };
+    assert_eq!(
+        bytes(reader.poll_until(start + ms(100)).unwrap()),
+        vec![b"a".to_vec(), b"\x1b[A".to_vec(), b"\x1b".to_vec()]
+    );
+    assert_eq!(clock.elapsed(), ms(100));
+
+    assert_eq!(
+        bytes(reader.poll_until(start + ms(150)).unwrap()),
+        Vec::<Vec<u8>>::new()
+    );
+    assert_eq!(clock.elapsed(), ms(150));
+
+    // EOF ends the wait early and stays visible to the next poll.
+    assert_eq!(
+        bytes(reader.poll_until(start + ms(1000)).unwrap()),
+        vec![b"b".to_vec()]
+    );
+    assert!(clock.elapsed() < ms(1000));
+    assert_eq!(reader.poll_next(POLL_TIMEOUT).unwrap(), PollResult::Eof);
+}
+
+const BACKGROUND_REPLY: &[u8] = b"\x1b]11;rgb:1e1e/1e1e/2e2e\x1b\\";
+
+fn is_background_reply(bytes: &[u8]) -> bool {
+    bytes.starts_with(b"\x1b]11;")
+}
+
+#[test]
+fn queries_frame_osc_replies_terminated_by_bel_or_st() {
+    let clock = VirtualClock::new();
+    let source = ScriptedSource::new(clock.clone())
+        .burst(ms(0), &b"\x1b]10;rgb:ffff/ffff/ffff\x07"[..])
+        .burst(ms(5), &b"\x1b]11;rgb:1e1e"[..])
+        .burst(ms(10), &b"/1e1e/2e2e\x1b\\q"[..]);
+    let mut reader = RawInputReader::with_byte_source(source, clock.clone(), FLUSH_TIMEOUT);
+
+    let replies = reader
+        .query_all(
+            &mut Vec::new(),
+            &[],
+            |bytes| bytes.starts_with(b"\x1b]"),
+            is_background_reply,
+            ms(100),
+        )
+        .unwrap();
+
+    assert_eq!(
+        replies,
+        vec![
+            b"\x1b]10;rgb:ffff/ffff/ffff\x07".to_vec(),
+            BACKGROUND_REPLY.to_vec()
+        ]
+    );
+    assert_eq!(
+        reader.poll_next(POLL_TIMEOUT).unwrap().event_bytes(),
+        Some(&b"q"[..])
+    );
+}
+
+#[test]
+fn queries_keep_osc_framing_the_caller_turned_on() {
+    let clock = VirtualClock::new();
+    let source = ScriptedSource::new(clock.clone())
+        .burst(ms(0), BACKGROUND_REPLY)
+        .burst(ms(5), &b"\x1b]10;rgb:0/0/0\x07"[..]);
+    let mut reader = RawInputReader::with_byte_source(source, clock.clone(), FLUSH_TIMEOUT);
+    reader.set_frames_osc(true);
+
+    let reply = reader
+        .query(
+            &mut Vec::new(),
+            b"\x1b]11;?\x1b\\",
+            is_background_reply,
+            ms(100),
+        )
+        .unwrap();
+
+    assert_eq!(reply.as_deref(), Some(BACKGROUND_REPLY));
+    assert!(reader.frames_osc());
+    // A late reply to a query still pending elsewhere frames as one event.
+    assert_eq!(
+        reader.poll_next(POLL_TIMEOUT).unwrap().event_bytes(),
+        Some(&b"\x1b]10;rgb:0/0/0\x07"[..])
+    );
+}
+
+#[test]
+fn alt_bracket_outside_a_query_does_not_swallow_later_keys() {
+    let clock = VirtualClock::new();
+    let source = ScriptedSource::new(clock.clone())
+        .burst(ms(0), &b"\x1b]"[..])
+        .burst(ms(5), &b"a"[..]);
+
+    let events: Vec<Vec<u8>> = run(&clock, source)
+        .into_iter()
+        .map(|event| event.0)
+        .collect();
+    assert_eq!(events, vec![b"\x1b]".to_vec(), b"a".to_vec()]);
+}
+
+#[test]
+fn query_takes_the_reply_out_of_band_and_keeps_other_input_in_order() {
+    let clock = VirtualClock::new();
+    let source = ScriptedSource::new(clock.clone())
+        .burst(ms(0), &b"a"[..])
+        .burst(ms(5), BACKGROUND_REPLY)
+        .burst(ms(6), &b"b"[..]);
+    let mut reader = RawInputReader::with_byte_source(source, clock.clone(), FLUSH_TIMEOUT);
+    let mut written = Vec::new();
+
+    let reply = reader
+        .query(
+            &mut written,
+            b"\x1b]11;?\x1b\\",
+            is_background_reply,
+            ms(100),
+        )
+        .unwrap();
+
+    assert_eq!(written, b"\x1b]11;?\x1b\\");
+    assert_eq!(reply.as_deref(), Some(BACKGROUND_REPLY));
+    assert_eq!(clock.elapsed(), ms(5));
+    let events: Vec<Vec<u8>> = reader
+        .events(None)
+        .map(|event| event.unwrap().bytes)
+        .collect();
+    assert_eq!(events, vec![b"a".to_vec(), b"b".to_vec()]);
+}
+
+#[test]
+fn query_gives_up_at_the_timeout_and_leaves_late_input_alone() {
+    let clock = VirtualClock::new();
+    let source = ScriptedSource::new(clock.clone())
+        .burst(ms(0), &b"a"[..])
+        .burst(ms(500), BACKGROUND_REPLY);
+    let mut reader = RawInputReader::with_byte_source(source, clock.clone(), FLUSH_TIMEOUT);
+
+    let reply = reader
+        .query(
+            &mut Vec::new(),
+            b"\x1b]11;?\x1b\\",
+            is_background_reply,
+            ms(100),
+        )
+        .unwrap();
+
+    assert_eq!(reply, None);
+    assert_eq!(clock.elapsed(), ms(100));
+    assert_eq!(
+        reader.poll_next(POLL_TIMEOUT).unwrap().event_bytes(),
+        Some(&b"a"[..])
+    );
+}
+
+#[test]
+fn query_reads_while_paused_and_stays_paused() {
+    let clock = VirtualClock::new();
+    let source = ScriptedSource::new(clock.clone())
+        .burst(ms(0), &b"a"[..])
+        .burst(ms(5), BACKGROUND_REPLY);
+    let mut reader = RawInputReader::with_byte_source(source, clock.clone(), FLUSH_TIMEOUT);
+    reader.pause();
+
+    let reply = reader
+        .query(
+            &mut Vec::new(),
+            b"\x1b]11;?\x1b\\",
+            is_background_reply,
+            ms(100),
+        )
+        .unwrap();
+
+    assert_eq!(reply.as_deref(), Some(BACKGROUND_REPLY));
+    assert!(reader.is_paused());
+    assert_eq!(reader.poll_next(ms(10)).unwrap(), PollResult::Idle);
+    reader.resume();
+    assert_eq!(
+        reader.poll_next(POLL_TIMEOUT).unwrap().event_bytes(),
+        Some(&b"a"[..])
+    );
+}
+
+#[test]
+fn query_stops_at_a_shutdown_and_reports_it_over_an_earlier_resize() {
+    let clock = VirtualClock::new();
+    let source = ScriptedSource::new(clock.clone())
+        .notify(ms(5), Readiness::Resize)
+        .notify(ms(10), Readiness::Shutdown(libc::SIGTERM))
+        .burst(ms(20), BACKGROUND_REPLY);
+    let mut reader = RawInputReader::with_byte_source(source, clock.clone(), FLUSH_TIMEOUT);
+
+    let reply = reader
+        .query(
+            &mut Vec::new(),
+            b"\x1b]11;?\x1b\\",
+            is_background_reply,
+            ms(100),
+        )
+        .unwrap();
+
+    assert_eq!(reply, None);
+    assert_eq!(clock.elapsed(), ms(10));
+    assert_eq!(
+        reader.poll_next(POLL_TIMEOUT).unwrap(),
+        PollResult::Shutdown(libc::SIGTERM)
+    );
+}
@@ -0,0 +1,40 @@
+use _tuicore::session::{Session, SessionRecord, SessionRecorder, SESSION_MAGIC, SESSION_VERSION};
+use std::time::Duration;
+
+#[test]
+fn recorded_sessions_round_trip_through_the_reader() {
+    let mut recorder = SessionRecorder::new(Vec::new()).unwrap();
+    recorder
+        .record_at(Duration::from_micros(0), b"\x1b[1;")
+        .unwrap();
+    recorder
+        .record_at(Duration::from_micros(12_345), b"5C")
+        .unwrap();
+    let bytes = recorder.finish().unwrap();
+
+    assert!(bytes.starts_with(SESSION_MAGIC));
+    assert_eq!(bytes[SESSION_MAGIC.len()], SESSION_VERSION);
+
+    let session = Session::read_from(bytes.as_slice()).unwrap();
+    assert_eq!(
+        session.records,
+        vec![
+            SessionRecord {
+                offset: Duration::ZERO,
+                bytes: b"\x1b[1;".to_vec(),
+            },
+            SessionRecord {
+                offset: Duration::from_micros(12_345),
+                bytes: b"5C".to_vec(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn rejects_unknown_session_versions() {
+    let mut bytes = SESSION_MAGIC.to_vec();
+    bytes.push(SESSION_VERSION + 1);
+    let err = Session::read_from(bytes.as_slice()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
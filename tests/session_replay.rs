@@ -0,0 +1,86 @@
+use _tuicore::raw_input::{InputSource, PollResult};
+use _tuicore::session::{Session, SessionRecord, SessionReplay};
+use std::time::Duration;
+
+const FLUSH_TIMEOUT: Duration = Duration::from_millis(35);
+
+fn fixture_path(name: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+fn replay_all(replay: &mut SessionReplay) -> Vec<Vec<u8>> {
+    let mut events = Vec::new();
+    loop {
+        match replay.poll_next(Duration::from_millis(100)).unwrap() {
+            PollResult::Event(event) => events.push(event.bytes),
+            PollResult::Idle
+            | PollResult::Pending { .. }
+            | PollResult::Resize
+            | PollResult::Shutdown(_) => {}
+            PollResult::Eof => return events,
+        }
+    }
+}
+
+#[test]
+fn replays_bundled_fixture_into_framed_events() {
+    let mut replay =
+        SessionReplay::open(fixture_path("split_sequences.bin"), FLUSH_TIMEOUT, 1.0).unwrap();
+
+    let events = replay_all(&mut replay);
+
+    assert_eq!(
+        events,
+        vec![
+            b"a".to_vec(),
+            b"\x1b[1;5C".to_vec(),
+            b"\x1b".to_vec(),
+            b"x".to_vec(),
+            "é".as_bytes().to_vec(),
+        ]
+    );
+}
+
+#[test]
+fn replays_a_paste_as_one_event_between_its_markers() {
+    let record = |millis, bytes: &[u8]| SessionRecord {
+        offset: Duration::from_millis(millis),
+        bytes: bytes.to_vec(),
+    };
+    let session = Session {
+        records: vec![
+            record(0, b"a"),
+            record(1, b"\x1b[200~hi\x1b[A"),
+            record(2, b"\x1bx\x1b[201~"),
+            record(3, b"b"),
+        ],
+    };
+    let mut replay = SessionReplay::new(session, FLUSH_TIMEOUT, 1.0);
+
+    let mut events = Vec::new();
+    loop {
+        match replay.poll_next(Duration::from_millis(100)).unwrap() {
+            PollResult::Event(event) => events.push((event.bytes, event.paste)),
+            PollResult::Eof => break,
+            _ => {}
+        }
+    }
+
+    assert_eq!(
+        events,
+        vec![
+            (b"a".to_vec(), false),
+            (b"\x1b[200~".to_vec(), false),
+            (b"hi\x1b[A\x1bx".to_vec(), true),
+            (b"\x1b[201~".to_vec(), false),
+            (b"b".to_vec(), false),
+        ]
+    );
+    assert!(replay.is_finished());
+}
+
+#[test]
+fn rejects_files_without_session_header() {
+    let err = Session::read_from(&b"not a session"[..]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,281 @@
+//! PTY plumbing shared by the integration tests.
+#![allow(dead_code)]
+
+use nix::poll::{poll, PollFd, PollFlags, PollTimeout};
+use nix::pty::{openpty, Winsize};
+use nix::sys::termios::{self, SetArg, Termios};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::os::fd::{AsFd, AsRawFd};
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+
+/// Names the child test to run when a test binary re-executes itself on a PTY.
+const CHILD_ENV: &str = "TUICORE_PTY_CHILD";
+
+/// Names the case a child test shared by a table of cases should run.
+const CASE_ENV: &str = "TUICORE_PTY_CASE";
+
+/// Rows and columns of the PTY children run on.
+pub const PTY_ROWS: u16 = 24;
+pub const PTY_COLS: u16 = 80;
+
+/// How long to wait for a child to produce expected output or exit.
+const CHILD_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A PTY pair whose slave is already in raw mode, as `(master, slave)`.
+pub fn raw_pty() -> (File, File) {
+    let pty = openpty(None, None).unwrap();
+    let mut raw = termios::tcgetattr(&pty.slave).unwrap();
+    termios::cfmakeraw(&mut raw);
+    termios::tcsetattr(&pty.slave, SetArg::TCSANOW, &raw).unwrap();
+    (File::from(pty.master), File::from(pty.slave))
+}
+
+/// Whether this process was started by [`PtyChild::spawn`] to run `test_name`.
+pub fn is_child(test_name: &str) -> bool {
+    std::env::var(CHILD_ENV).is_ok_and(|name| name == test_name)
+}
+
+/// The case [`PtyChild::spawn_case`] started this process to run `test_name` for, if it did.
+pub fn child_case(test_name: &str) -> Option<String> {
+    is_child(test_name)
+        .then(|| std::env::var(CASE_ENV).ok())
+        .flatten()
+}
+
+/// This test binary re-run as a single test with its stdio on a fresh PTY slave, which is also
+/// its controlling terminal so `/dev/tty` reaches the PTY too.
+///
+/// The child test should return early unless [`is_child`] says it was spawned for it.
+pub struct PtyChild {
+    child: Child,
+    master: File,
+    slave: File,
+    output: Vec<u8>,
+    cursor_queries: usize,
+}
+
+impl PtyChild {
+    /// Run `test_name` from the current test binary on a cooked PTY of [`PTY_ROWS`] by
+    /// [`PTY_COLS`].
+    pub fn spawn(test_name: &str) -> Self {
+        Self::spawn_with_stderr(test_name, Stdio::null())
+    }
+
+    /// Like [`PtyChild::spawn`], for a child test that runs whichever `case` it is given; see
+    /// [`child_case`].
+    pub fn spawn_case(test_name: &str, case: &str) -> Self {
+        Self::spawn_with_stdio(test_name, Some(case), None, Stdio::null())
+    }
+
+    /// Like [`PtyChild::spawn`], with the child's stderr sent to `stderr` instead of discarded.
+    pub fn spawn_with_stderr(test_name: &str, stderr: impl Into<Stdio>) -> Self {
+        Self::spawn_with_stdio(test_name, None, None, stderr.into())
+    }
+
+    /// Like [`PtyChild::spawn`], with the child's stdout a pipe, read by
+    /// [`PtyChild::read_stdout`], and its stderr on the PTY.
+    pub fn spawn_with_piped_stdout(test_name: &str) -> Self {
+        Self::spawn_with_stdio(test_name, None, Some(Stdio::piped()), Stdio::null())
+    }
+
+    /// Spawn with stdout on the PTY unless `stdout` is given, and stderr on the PTY if stdout
+    /// isn't.
+    fn spawn_with_stdio(
+        test_name: &str,
+        case: Option<&str>,
+        stdout: Option<Stdio>,
+        stderr: Stdio,
+    ) -> Self {
+        let size = Winsize {
+            ws_row: PTY_ROWS,
+            ws_col: PTY_COLS,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        };
+        let pty = openpty(Some(&size), None).unwrap();
+        let slave = File::from(pty.slave);
+        let (stdout, stderr) = match stdout {
+            Some(stdout) => (stdout, Stdio::from(slave.try_clone().unwrap())),
+            None => (Stdio::from(slave.try_clone().unwrap()), stderr),
+        };
+        let mut command = Command::new(std::env::current_exe().unwrap());
+        command
+            .args(["--exact", test_name, "--nocapture", "--test-threads=1"])
+            .env(CHILD_ENV, test_name)
+            .stdin(slave.try_clone().unwrap())
+            .stdout(stdout)
+            .stderr(stderr);
+        if let Some(case) = case {
+            command.env(CASE_ENV, case);
+        }
+        // SAFETY: only async-signal-safe calls between fork and exec.
+        unsafe {
+            command.pre_exec(|| {
+                if libc::setsid() < 0 || libc::ioctl(0, libc::TIOCSCTTY, 0) < 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        let child = command.spawn().unwrap();
+        Self {
+            child,
+            master: File::from(pty.master),
+            slave,
+            output: Vec::new(),
+            cursor_queries: 0,
+        }
+    }
+
+    pub fn pid(&self) -> libc::pid_t {
+        self.child.id() as libc::pid_t
+    }
+
+    /// Resize the PTY, which also sends the child SIGWINCH.
+    pub fn resize(&self, cols: u16, rows: u16) {
+        let size = Winsize {
+            ws_row: rows,
+            ws_col: cols,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        };
+        // SAFETY: TIOCSWINSZ only reads the winsize it is given.
+        assert_eq!(
+            unsafe { libc::ioctl(self.master.as_raw_fd(), libc::TIOCSWINSZ, &size) },
+            0
+        );
+    }
+
+    /// Send `signal` to the child.
+    pub fn signal(&self, signal: libc::c_int) {
+        // SAFETY: kill has no memory-safety preconditions.
+        assert_eq!(unsafe { libc::kill(self.pid(), signal) }, 0);
+    }
+
+    /// Block until the child stops on a signal, returning `false` if it exits instead.
+    pub fn wait_until_stopped(&mut self) -> bool {
+        let mut status = 0;
+        // SAFETY: waitpid only writes the status; WUNTRACED leaves a stopped child unreaped.
+        let pid = unsafe { libc::waitpid(self.pid(), &mut status, libc::WUNTRACED) };
+        pid == self.pid() && libc::WIFSTOPPED(status)
+    }
+
+    /// Send `bytes` to the child as terminal input.
+    pub fn write(&mut self, bytes: &[u8]) {
+        self.master.write_all(bytes).unwrap();
+    }
+
+    /// Collect output until `needle` shows up, panicking if it doesn't within the timeout.
+    pub fn wait_for(&mut self, needle: &[u8]) {
+        self.wait_for_count(needle, 1);
+    }
+
+    /// Like [`PtyChild::wait_for`], until `needle` has shown up `times` times.
+    pub fn wait_for_count(&mut self, needle: &[u8], times: usize) {
+        let deadline = Instant::now() + CHILD_TIMEOUT;
+        while count(&self.output, needle) < times {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            assert!(
+                self.read_some(remaining),
+                "never saw {:?} in {:?}",
+                String::from_utf8_lossy(needle),
+                String::from_utf8_lossy(&self.output)
+            );
+        }
+    }
+
+    /// Wait for the child to exit, then collect whatever output it left behind.
+    pub fn wait(&mut self) -> ExitStatus {
+        let deadline = Instant::now() + CHILD_TIMEOUT;
+        let status = loop {
+            if let Some(status) = self.child.try_wait().unwrap() {
+                break status;
+            }
+            assert!(Instant::now() < deadline, "child did not exit");
+            self.read_some(Duration::from_millis(10));
+        };
+        while self.read_some(Duration::from_millis(50)) {}
+        status
+    }
+
+    /// Answer the next cursor position query, like the one an inline viewport sends on init.
+    pub fn answer_cursor_query(&mut self) {
+        self.cursor_queries += 1;
+        self.wait_for_count(b"\x1b[6n", self.cursor_queries);
+        self.write(b"\x1b[1;1R");
+    }
+
+    /// Everything the child wrote to its piped stdout, once it has exited.
+    pub fn read_stdout(&mut self) -> Vec<u8> {
+        let mut stdout = Vec::new();
+        self.child
+            .stdout
+            .take()
+            .unwrap()
+            .read_to_end(&mut stdout)
+            .unwrap();
+        stdout
+    }
+
+    /// Everything the child has written to the terminal so far.
+    pub fn output(&self) -> &[u8] {
+        &self.output
+    }
+
+    /// Current terminal settings of the child's PTY.
+    pub fn termios(&self) -> Termios {
+        termios::tcgetattr(&self.slave).unwrap()
+    }
+
+    /// Read once from the master if output arrives within `timeout`.
+    fn read_some(&mut self, timeout: Duration) -> bool {
+        let mut fds = [PollFd::new(self.master.as_fd(), PollFlags::POLLIN)];
+        let millis = timeout.as_millis().min(u16::MAX.into()) as u16;
+        if poll(&mut fds, PollTimeout::from(millis)).unwrap() == 0 {
+            return false;
+        }
+        let mut buf = [0u8; 4096];
+        match self.master.read(&mut buf) {
+            Ok(n) if n > 0 => {
+                self.output.extend_from_slice(&buf[..n]);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Drop for PtyChild {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Whether `needle` occurs anywhere in `haystack`.
+pub fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    find(haystack, needle).is_some()
+}
+
+/// Offset of the first `needle` in `haystack`.
+pub fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// What follows the first `marker` in `haystack`, or nothing if it never occurs.
+pub fn after<'a>(haystack: &'a [u8], marker: &[u8]) -> &'a [u8] {
+    find(haystack, marker).map_or(&[], |at| &haystack[at + marker.len()..])
+}
+
+/// How many times `needle` occurs in `haystack`.
+pub fn count(haystack: &[u8], needle: &[u8]) -> usize {
+    haystack
+        .windows(needle.len())
+        .filter(|window| *window == needle)
+        .count()
+}
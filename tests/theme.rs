@@ -0,0 +1,346 @@
+#[cfg(unix)]
+use _tuicore::clock::VirtualClock;
+#[cfg(unix)]
+use _tuicore::raw_input::PollResult;
+#[cfg(unix)]
+use _tuicore::raw_input::{InputSource, RawInputReader};
+#[cfg(unix)]
+use _tuicore::scripted_input::ScriptedSource;
+use _tuicore::theme::PendingThemeQuery;
+use _tuicore::theme::{
+    parse_color_reply, parse_palette_reply, parse_theme_notification, parse_theme_report,
+    query_palette_color, theme_from_colors, ThemeReport, ThemeWatcher,
+};
+#[cfg(unix)]
+use _tuicore::theme::{
+    query_ansi_palette, query_theme, start_theme_query, QUERY_BACKGROUND, QUERY_DEVICE_ATTRIBUTES,
+    QUERY_FOREGROUND,
+};
+use std::task::Poll;
+#[cfg(unix)]
+use std::time::Duration;
+use terminal_colorsaurus::{Color, ThemeMode};
+
+const DARK: &[u8] = b"\x1b[?997;1n";
+const LIGHT: &[u8] = b"\x1b[?997;2n";
+
+#[test]
+fn parses_dark_and_light_reports() {
+    assert_eq!(parse_theme_notification(DARK), Some(ThemeMode::Dark));
+    assert_eq!(parse_theme_notification(LIGHT), Some(ThemeMode::Light));
+}
+
+#[test]
+fn other_sequences_are_not_reports() {
+    for bytes in [
+        b"\x1b[?997;3n".as_slice(),
+        b"\x1b[?996n",
+        b"\x1b[A",
+        b"",
+        b"\x1b[?997;1",
+    ] {
+        assert_eq!(parse_theme_notification(bytes), None);
+    }
+}
+
+#[test]
+fn unknown_reports_keep_their_parameters() {
+    assert_eq!(
+        parse_theme_report(b"\x1b[?997;3n"),
+        Some(ThemeReport::Unrecognized("3".to_string()))
+    );
+    assert_eq!(
+        parse_theme_report(b"\x1b[?997;1:2n"),
+        Some(ThemeReport::Unrecognized("1:2".to_string()))
+    );
+    assert_eq!(
+        parse_theme_report(DARK),
+        Some(ThemeReport::Changed(ThemeMode::Dark))
+    );
+    assert_eq!(parse_theme_notification(b"\x1b[?997;3n"), None);
+    assert_eq!(parse_theme_report(b"\x1b[?997;xn"), None);
+}
+
+#[test]
+fn watcher_yields_only_changes() {
+    let mut watcher = ThemeWatcher::new(Some(ThemeMode::Dark));
+
+    assert_eq!(watcher.feed(DARK), None);
+    assert_eq!(watcher.feed(b"q"), None);
+    assert_eq!(watcher.feed(LIGHT), Some(ThemeMode::Light));
+    assert_eq!(watcher.feed(LIGHT), None);
+    assert_eq!(watcher.current(), Some(ThemeMode::Light));
+}
+
+#[test]
+fn watcher_without_a_known_theme_takes_the_first_report() {
+    let mut watcher = ThemeWatcher::default();
+
+    assert_eq!(watcher.feed(DARK), Some(ThemeMode::Dark));
+    assert_eq!(watcher.current(), Some(ThemeMode::Dark));
+}
+
+#[test]
+fn parses_color_replies_with_either_terminator() {
+    let expected = Some(Color::rgb(0x1e1e, 0x1e1e, 0x2e2e));
+    assert_eq!(
+        parse_color_reply(11, b"\x1b]11;rgb:1e1e/1e1e/2e2e\x07"),
+        expected
+    );
+    assert_eq!(
+        parse_color_reply(11, b"\x1b]11;rgb:1e1e/1e1e/2e2e\x1b\\"),
+        expected
+    );
+}
+
+#[test]
+fn scales_short_color_channels_to_16_bits() {
+    assert_eq!(
+        parse_color_reply(10, b"\x1b]10;rgb:ff/0/80\x07"),
+        Some(Color::rgb(0xffff, 0, 0x8080))
+    );
+}
+
+#[test]
+fn rejects_other_color_replies() {
+    for bytes in [
+        b"\x1b]10;rgb:ffff/ffff/ffff\x07".as_slice(),
+        b"\x1b]11;rgb:ffff/ffff\x07",
+        b"\x1b]11;rgb:ffff/ffff/ffff/ffff\x07",
+        b"\x1b]11;rgb:fffff/0/0\x07",
+        b"\x1b]11;rgb:ffff/ffff/ffff",
+        b"\x1b]11;?\x07",
+    ] {
+        assert_eq!(parse_color_reply(11, bytes), None);
+    }
+}
+
+#[test]
+fn light_text_on_a_dark_background_is_a_dark_theme() {
+    let white = Color::rgb(0xffff, 0xffff, 0xffff);
+    let black = Color::rgb(0, 0, 0);
+    assert_eq!(theme_from_colors(&white, &black), ThemeMode::Dark);
+    assert_eq!(theme_from_colors(&black, &white), ThemeMode::Light);
+}
+
+#[cfg(unix)]
+fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+#[cfg(unix)]
+#[test]
+fn queries_the_theme_through_the_reader_without_leaking_replies() {
+    let clock = VirtualClock::new();
+    let source = ScriptedSource::new(clock.clone())
+        .burst(ms(0), &b"a"[..])
+        .burst(ms(5), &b"\x1b]11;rgb:ffff/ffff/ffff\x1b\\"[..])
+        .burst(ms(10), &b"\x1b]10;rgb:0000/0000/0000\x1b\\\x1b[?62;22c"[..])
+        .burst(ms(15), &b"b"[..]);
+    let mut reader = RawInputReader::with_byte_source(source, clock.clone(), ms(35));
+    let mut written = Vec::new();
+
+    let theme = query_theme(&mut reader, &mut written, ms(100)).unwrap();
+
+    assert_eq!(theme, Some(ThemeMode::Light));
+    assert_eq!(
+        written,
+        [QUERY_BACKGROUND, QUERY_FOREGROUND, QUERY_DEVICE_ATTRIBUTES].concat()
+    );
+    let events: Vec<Vec<u8>> = reader
+        .events(None)
+        .map(|event| event.unwrap().bytes)
+        .collect();
+    assert_eq!(events, vec![b"a".to_vec(), b"b".to_vec()]);
+}
+
+#[cfg(unix)]
+#[test]
+fn a_terminal_without_color_reports_answers_through_device_attributes() {
+    let clock = VirtualClock::new();
+    let source = ScriptedSource::new(clock.clone())
+        .burst(ms(5), &b"\x1b[?1;2c"[..])
+        .burst(ms(500), &b"q"[..]);
+    let mut reader = RawInputReader::with_byte_source(source, clock.clone(), ms(35));
+
+    let theme = query_theme(&mut reader, &mut Vec::new(), ms(1000)).unwrap();
+
+    assert_eq!(theme, None);
+    assert_eq!(clock.elapsed(), ms(5));
+    let events: Vec<Vec<u8>> = reader
+        .events(None)
+        .map(|event| event.unwrap().bytes)
+        .collect();
+    assert_eq!(events, vec![b"q".to_vec()]);
+}
+
+#[test]
+fn parses_palette_replies() {
+    assert_eq!(query_palette_color(12), b"\x1b]4;12;?\x1b\\");
+    assert_eq!(
+        parse_palette_reply(b"\x1b]4;12;rgb:5c5c/5c5c/ffff\x07"),
+        Some((12, Color::rgb(0x5c5c, 0x5c5c, 0xffff)))
+    );
+    for bytes in [
+        b"\x1b]4;x;rgb:0/0/0\x07".as_slice(),
+        b"\x1b]4;1;?\x07",
+        b"\x1b]4;256;rgb:0/0/0\x07",
+        b"\x1b]11;rgb:0/0/0\x07",
+    ] {
+        assert_eq!(parse_palette_reply(bytes), None);
+    }
+}
+
+#[cfg(unix)]
+fn palette_reply(index: u8, channel: &str) -> Vec<u8> {
+    format!("\x1b]4;{index};rgb:{channel}/{channel}/{channel}\x1b\\").into_bytes()
+}
+
+#[cfg(unix)]
+#[test]
+fn reads_back_the_ansi_palette_from_concatenated_replies() {
+    let clock = VirtualClock::new();
+    let replies: Vec<u8> = (0..16)
+        .flat_map(|index| palette_reply(index, &format!("{:02x}", index * 16)))
+        .chain(b"\x1b[?62;22c".iter().copied())
+        .collect();
+    let source = ScriptedSource::new(clock.clone())
+        .burst(ms(0), &b"a"[..])
+        .burst(ms(5), replies);
+    let mut reader = RawInputReader::with_byte_source(source, clock.clone(), ms(35));
+    let mut written = Vec::new();
+
+    let palette = query_ansi_palette(&mut reader, &mut written, ms(100)).unwrap();
+
+    for (index, color) in palette.iter().enumerate() {
+        let channel = (index * 16 * 0xffff / 0xff) as u16;
+        assert_eq!(color, &Some(Color::rgb(channel, channel, channel)));
+    }
+    assert!(written.starts_with(&query_palette_color(0)));
+    assert!(written.ends_with(QUERY_DEVICE_ATTRIBUTES));
+    assert_eq!(clock.elapsed(), ms(5));
+    let events: Vec<Vec<u8>> = reader
+        .events(None)
+        .map(|event| event.unwrap().bytes)
+        .collect();
+    assert_eq!(events, vec![b"a".to_vec()]);
+}
+
+#[cfg(unix)]
+#[test]
+fn leaves_unanswered_palette_entries_empty() {
+    let clock = VirtualClock::new();
+    let source = ScriptedSource::new(clock.clone())
+        .burst(ms(5), palette_reply(1, "cdcd"))
+        .burst(ms(6), palette_reply(15, "ffff"))
+        .burst(ms(7), &b"\x1b[?1;2c"[..]);
+    let mut reader = RawInputReader::with_byte_source(source, clock.clone(), ms(35));
+
+    let palette = query_ansi_palette(&mut reader, &mut Vec::new(), ms(100)).unwrap();
+
+    let answered: Vec<usize> = (0..16).filter(|&index| palette[index].is_some()).collect();
+    assert_eq!(answered, vec![1, 15]);
+    assert_eq!(palette[1], Some(Color::rgb(0xcdcd, 0xcdcd, 0xcdcd)));
+}
+
+#[cfg(unix)]
+#[test]
+fn the_timeout_covers_the_whole_palette_batch() {
+    let clock = VirtualClock::new();
+    let source = ScriptedSource::new(clock.clone())
+        .burst(ms(40), palette_reply(0, "00"))
+        .burst(ms(80), palette_reply(1, "80"))
+        .burst(ms(120), palette_reply(2, "ff"));
+    let mut reader = RawInputReader::with_byte_source(source, clock.clone(), ms(35));
+
+    let palette = query_ansi_palette(&mut reader, &mut Vec::new(), ms(100)).unwrap();
+
+    assert_eq!(clock.elapsed(), ms(100));
+    assert!(palette[0].is_some() && palette[1].is_some() && palette[2].is_none());
+    // The late reply is input now, starting with Alt+], as nothing is waiting for it.
+    assert_eq!(
+        reader.poll_next(ms(50)).unwrap().event_bytes(),
+        Some(&b"\x1b]"[..])
+    );
+}
+
+#[test]
+fn pending_query_is_ready_once_device_attributes_arrive() {
+    let mut pending = PendingThemeQuery::default();
+
+    // Darker than mid gray on its own, but lighter than the text that arrives next.
+    assert_eq!(
+        pending.feed(b"\x1b]11;rgb:6060/6060/6060\x07"),
+        Poll::Pending
+    );
+    assert_eq!(pending.theme(), Some(ThemeMode::Dark));
+    assert_eq!(
+        pending.feed(b"\x1b]10;rgb:0000/0000/0000\x07"),
+        Poll::Pending
+    );
+    assert_eq!(
+        pending.feed(b"\x1b[?62;22c"),
+        Poll::Ready(Some(ThemeMode::Light))
+    );
+}
+
+#[test]
+fn pending_query_tells_replies_from_input() {
+    assert!(PendingThemeQuery::is_reply(b"\x1b]10;rgb:0/0/0\x07"));
+    assert!(PendingThemeQuery::is_reply(b"\x1b]11;rgb:0/0/0\x07"));
+    assert!(PendingThemeQuery::is_reply(b"\x1b[?1;2c"));
+    assert!(!PendingThemeQuery::is_reply(b"c"));
+    assert!(!PendingThemeQuery::is_reply(b"\x1b[A"));
+    assert!(!PendingThemeQuery::is_reply(b"\x1b]4;1;rgb:0/0/0\x07"));
+}
+
+#[cfg(unix)]
+#[test]
+fn pending_query_rides_along_the_poll_loop_with_split_replies() {
+    let clock = VirtualClock::new();
+    let source = ScriptedSource::new(clock.clone())
+        .burst(ms(0), &b"a\x1b]11;rgb:ff"[..])
+        .burst(ms(5), &b"ff/ffff/ffff\x1b\\b\x1b]10;rgb:0/0/0"[..])
+        .burst(ms(10), &b"\x07\x1b[?62;"[..])
+        .burst(ms(15), &b"22c"[..]);
+    let mut reader = RawInputReader::with_byte_source(source, clock.clone(), ms(35));
+    let mut written = Vec::new();
+
+    let mut pending = start_theme_query(&mut written).unwrap();
+    reader.set_frames_osc(true);
+    let mut input = Vec::new();
+    let mut theme = Poll::Pending;
+    while theme.is_pending() {
+        let PollResult::Event(event) = reader.poll_next(ms(100)).unwrap() else {
+            continue;
+        };
+        if PendingThemeQuery::is_reply(&event.bytes) {
+            theme = pending.feed(&event.bytes);
+        } else {
+            input.push(event.bytes);
+        }
+    }
+
+    assert_eq!(
+        written,
+        [QUERY_BACKGROUND, QUERY_FOREGROUND, QUERY_DEVICE_ATTRIBUTES].concat()
+    );
+    reader.set_frames_osc(false);
+    assert_eq!(theme, Poll::Ready(Some(ThemeMode::Light)));
+    assert_eq!(input, vec![b"a".to_vec(), b"b".to_vec()]);
+}
+
+#[cfg(unix)]
+#[test]
+fn blocking_query_settles_for_the_background_at_the_timeout() {
+    let clock = VirtualClock::new();
+    let source = ScriptedSource::new(clock.clone())
+        .burst(ms(5), &b"\x1b]11;rgb:f0/f0/f0\x07"[..])
+        .burst(ms(500), &b"q"[..]);
+    let mut reader = RawInputReader::with_byte_source(source, clock.clone(), ms(35));
+
+    let theme = query_theme(&mut reader, &mut Vec::new(), ms(100)).unwrap();
+
+    assert_eq!(theme, Some(ThemeMode::Light));
+    assert_eq!(clock.elapsed(), ms(100));
+}
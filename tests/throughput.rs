@@ -0,0 +1,46 @@
+use _tuicore::throughput::{Rates, Throughput};
+use std::time::{Duration, Instant};
+
+fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+#[test]
+fn rates_cover_the_last_second() {
+    let start = Instant::now();
+    let mut throughput = Throughput::default();
+
+    throughput.record_bytes(start, 100);
+    throughput.record_events(start, 10);
+    throughput.record_bytes(start + ms(600), 42);
+    throughput.record_events(start + ms(600), 8);
+
+    assert_eq!(throughput.bytes_per_sec(start + ms(900)), 142);
+    assert_eq!(throughput.events_per_sec(start + ms(900)), 18);
+    assert_eq!(throughput.bytes_per_sec(start + ms(1200)), 42);
+    assert_eq!(throughput.events_per_sec(start + ms(1700)), 0);
+}
+
+#[test]
+fn totals_outlive_the_window() {
+    let start = Instant::now();
+    let mut throughput = Throughput::default();
+
+    for step in 0..50 {
+        throughput.record_bytes(start + ms(step * 100), 3);
+        throughput.record_events(start + ms(step * 100), 1);
+    }
+
+    assert_eq!(throughput.total_bytes(), 150);
+    assert_eq!(throughput.total_events(), 50);
+    assert_eq!(throughput.events_per_sec(start + ms(4950)), 10);
+}
+
+#[test]
+fn formats_rates_for_the_title_line() {
+    let rates = Rates {
+        bytes_per_sec: 142,
+        events_per_sec: 18,
+    };
+    assert_eq!(rates.to_string(), "142 B/s, 18 ev/s");
+}
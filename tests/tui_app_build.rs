@@ -0,0 +1,111 @@
+use _tuicore::{AlternateScreenBackend, BuildError, TuiApp, TuiAppBuilder, MAX_INLINE_HEIGHT};
+
+fn build_error(builder: TuiAppBuilder) -> BuildError {
+    builder
+        .try_build()
+        .err()
+        .expect("the configuration should be rejected")
+}
+
+#[test]
+fn zero_inline_height_is_rejected() {
+    let builder = TuiApp::builder("tuicore-build-test").inline(0);
+    assert_eq!(build_error(builder), BuildError::ZeroInlineHeight);
+}
+
+#[test]
+fn inline_height_above_the_maximum_is_rejected() {
+    let builder = TuiApp::builder("tuicore-build-test").inline(MAX_INLINE_HEIGHT + 1);
+    assert_eq!(
+        build_error(builder),
+        BuildError::InlineHeightTooLarge(MAX_INLINE_HEIGHT + 1)
+    );
+    let builder = TuiApp::builder("tuicore-build-test").inline(u16::MAX);
+    assert_eq!(
+        build_error(builder),
+        BuildError::InlineHeightTooLarge(u16::MAX)
+    );
+    assert!(TuiApp::builder("tuicore-build-test")
+        .inline(MAX_INLINE_HEIGHT)
+        .try_build()
+        .is_ok());
+}
+
+#[test]
+fn keeping_no_log_files_is_rejected() {
+    let builder = TuiApp::builder("tuicore-build-test").log_keep(0);
+    assert_eq!(build_error(builder), BuildError::ZeroLogKeep);
+}
+
+#[test]
+fn stderr_logging_with_a_stderr_tui_is_rejected() {
+    let builder = TuiApp::builder("tuicore-build-test")
+        .alternate_screen_backend(AlternateScreenBackend::Stderr)
+        .log_to_stderr(true);
+    assert_eq!(build_error(builder), BuildError::StderrConflict);
+}
+
+#[test]
+fn app_names_that_cannot_name_the_log_directory_are_rejected() {
+    assert_eq!(
+        build_error(TuiApp::builder("")),
+        BuildError::InvalidAppName(String::new())
+    );
+}
+
+#[test]
+fn app_names_the_log_directory_sanitizes_are_accepted() {
+    for name in [".", "..", "my/app", "my\\app", "my\0app", "a:b"] {
+        assert!(TuiApp::builder(name).try_build().is_ok(), "{name:?}");
+    }
+}
+
+#[test]
+fn app_name_is_not_checked_when_it_does_not_name_the_log_directory() {
+    assert!(TuiApp::builder("").use_disk_logs(false).try_build().is_ok());
+    assert!(TuiApp::builder("my/app")
+        .log_dir("/tmp/logs")
+        .try_build()
+        .is_ok());
+}
+
+#[test]
+fn valid_configuration_builds() {
+    let app = TuiApp::builder("tuicore-build-test")
+        .inline(5)
+        .try_build()
+        .unwrap();
+    assert_eq!(app.app_name(), "tuicore-build-test");
+}
+
+#[test]
+fn init_fails_with_the_build_error_for_an_unchecked_build() {
+    let mut app = TuiApp::builder("tuicore-build-test").inline(0).build();
+    let error = app
+        .init()
+        .err()
+        .expect("init should reject a zero-row viewport");
+    assert_eq!(
+        error.downcast_ref::<BuildError>(),
+        Some(&BuildError::ZeroInlineHeight)
+    );
+}
+
+#[cfg(feature = "test-backend")]
+#[test]
+fn init_test_draws_in_memory_without_starting_the_terminal_lifecycle() {
+    let mut app = TuiApp::builder("tuicore-build-test")
+        .use_disk_logs(false)
+        .build();
+    let mut terminal = app.init_test(20, 3).unwrap();
+    terminal
+        .draw(|f| f.render_widget(ratatui::text::Text::raw("drawn"), f.area()))
+        .unwrap();
+
+    assert_eq!(
+        terminal.backend().buffer().area,
+        ratatui::layout::Rect::new(0, 0, 20, 3)
+    );
+    assert_eq!(terminal.backend().buffer()[(0, 0)].symbol(), "d");
+    assert_eq!(app.lifecycle_state(), _tuicore::LifecycleState::NotStarted);
+}
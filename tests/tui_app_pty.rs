@@ -0,0 +1,2331 @@
+#![cfg(unix)]
+
+mod support;
+
+use _tuicore::{
+    AltScreenStyle, AlternateScreenBackend, ClearOnInit, CursorRestore, CursorStyle,
+    InlineRestorePolicy, LifecycleState, MinSizeBehavior, NonTtyBehavior, Signal, TuiApp,
+    TuiAppBuilder,
+};
+use crossterm::event::KeyboardEnhancementFlags;
+use crossterm::style::Print;
+use nix::sys::termios::LocalFlags;
+use ratatui::text::Text;
+use std::io::{self, Read, Write};
+use std::os::unix::process::ExitStatusExt;
+
+/// Each crossterm mouse mode that `capture_mouse` turns on.
+const MOUSE_MODES: [&str; 5] = ["1000", "1002", "1003", "1015", "1006"];
+
+/// A builder for the test app with color-eyre, disk logs and mouse capture off.
+fn builder() -> TuiAppBuilder {
+    TuiApp::builder("tuicore-pty-test")
+        .use_color_eyre(false)
+        .use_disk_logs(false)
+        .capture_mouse(false)
+}
+
+fn app() -> TuiApp {
+    builder().capture_mouse(true).build()
+}
+
+#[test]
+fn two_cycles_child() {
+    if !support::is_child("two_cycles_child") {
+        return;
+    }
+
+    let mut app = app();
+    for cycle in ["first", "second"] {
+        let _terminal = app.init().unwrap();
+        io::stdout().write_all(cycle.as_bytes()).unwrap();
+        io::stdout().flush().unwrap();
+        app.restore().unwrap();
+    }
+    ready();
+}
+
+#[test]
+fn each_init_restore_cycle_sets_up_and_restores_once() {
+    let mut child = support::PtyChild::spawn("two_cycles_child");
+    child.wait_for(b"ready");
+    assert!(child.wait().success());
+    let output = child.output();
+
+    let (setup, rest) = output.split_at(position(output, b"first"));
+    let (between, last) = rest.split_at(position(rest, b"second"));
+    for (part, on, off) in [(setup, 1, 0), (between, 1, 1), (last, 0, 1)] {
+        for mode in ["1049", "1000"] {
+            let (enter, leave) = (format!("\x1b[?{mode}h"), format!("\x1b[?{mode}l"));
+            assert_eq!(support::count(part, enter.as_bytes()), on, "{mode}h");
+            assert_eq!(support::count(part, leave.as_bytes()), off, "{mode}l");
+        }
+    }
+}
+
+#[test]
+fn second_init_child() {
+    if !support::is_child("second_init_child") {
+        return;
+    }
+
+    let mut app = app();
+    let _terminal = app.init().unwrap();
+    let error = app.init().err().expect("a second init should fail");
+    let state = app.lifecycle_state();
+    app.restore().unwrap();
+    io::stdout()
+        .write_all(format!("error <{error}> state <{state:?}>").as_bytes())
+        .unwrap();
+    io::stdout().flush().unwrap();
+}
+
+#[test]
+fn init_while_active_fails_and_leaves_the_session_alone() {
+    let mut child = support::PtyChild::spawn("second_init_child");
+    child.wait_for(b"state <");
+    assert!(child.wait().success());
+    let output = child.output();
+
+    assert!(support::contains(output, b"already active"));
+    assert!(support::contains(output, b"state <Active>"));
+    assert_eq!(support::count(output, b"\x1b[?1049h"), 1);
+    assert_eq!(support::count(output, b"\x1b[?1049l"), 1);
+    assert!(child
+        .termios()
+        .local_flags
+        .contains(LocalFlags::ICANON | LocalFlags::ECHO));
+}
+
+/// Run an inline app of `INLINE_HEIGHT` rows through init and restore under `policy`.
+fn inline_lifecycle(policy: InlineRestorePolicy, panic: bool) {
+    let mut app = builder()
+        .inline(INLINE_HEIGHT)
+        .inline_restore(policy)
+        .build();
+    let _terminal = app.init().unwrap();
+    ready();
+    if panic {
+        panic!("boom");
+    }
+    app.restore().unwrap();
+}
+
+const INLINE_HEIGHT: u16 = 5;
+
+/// Output an inline child wrote while restoring, i.e. after it reported ready.
+fn restore_output(name: &str) -> Vec<u8> {
+    let mut child = support::PtyChild::spawn(name);
+    child.answer_cursor_query();
+    child.wait_for(b"ready");
+    child.wait();
+    support::after(child.output(), b"ready").to_vec()
+}
+
+fn ready() {
+    io::stdout().write_all(b"ready").unwrap();
+    io::stdout().flush().unwrap();
+}
+
+#[test]
+fn lifecycle_child() {
+    if !support::is_child("lifecycle_child") {
+        return;
+    }
+
+    let mut app = app();
+    let _terminal = app.init().unwrap();
+    ready();
+    // Hold raw mode until the parent has looked at it.
+    io::stdin().read_exact(&mut [0u8]).unwrap();
+    app.restore().unwrap();
+}
+
+#[test]
+fn panic_child() {
+    if !support::is_child("panic_child") {
+        return;
+    }
+
+    let mut app = app();
+    let _terminal = app.init().unwrap();
+    ready();
+    panic!("boom");
+}
+
+#[test]
+fn mouse_capture_is_enabled_and_disabled_symmetrically() {
+    let mut child = support::PtyChild::spawn("lifecycle_child");
+    child.wait_for(b"ready");
+    child.write(b"q");
+    assert!(child.wait().success());
+
+    let output = child.output();
+    for mode in MOUSE_MODES {
+        let enable = format!("\x1b[?{mode}h");
+        let disable = format!("\x1b[?{mode}l");
+        assert_eq!(
+            support::count(output, enable.as_bytes()),
+            1,
+            "enable {mode}"
+        );
+        assert_eq!(
+            support::count(output, disable.as_bytes()),
+            1,
+            "disable {mode}"
+        );
+        assert!(
+            support::find(output, enable.as_bytes()) < support::find(output, disable.as_bytes()),
+            "mode {mode} disabled before it was enabled"
+        );
+    }
+}
+
+#[test]
+fn init_enters_raw_mode_and_restore_leaves_it() {
+    let mut child = support::PtyChild::spawn("lifecycle_child");
+    child.wait_for(b"ready");
+    assert!(!child.termios().local_flags.contains(LocalFlags::ICANON));
+
+    child.write(b"q");
+    assert!(child.wait().success());
+    assert!(child
+        .termios()
+        .local_flags
+        .contains(LocalFlags::ICANON | LocalFlags::ECHO));
+    assert!(support::contains(child.output(), b"\x1b[?1049l"));
+}
+
+#[test]
+fn panic_restores_a_sane_tty() {
+    let mut child = support::PtyChild::spawn("panic_child");
+    child.wait_for(b"ready");
+
+    assert!(!child.wait().success());
+    assert!(child
+        .termios()
+        .local_flags
+        .contains(LocalFlags::ICANON | LocalFlags::ECHO));
+    let output = child.output();
+    assert!(support::contains(output, b"\x1b[?1000l"));
+    assert!(support::contains(output, b"\x1b[?1049l"));
+}
+
+#[test]
+fn clear_viewport_child() {
+    if support::is_child("clear_viewport_child") {
+        inline_lifecycle(InlineRestorePolicy::ClearViewport, false);
+    }
+}
+
+#[test]
+fn leave_untouched_child() {
+    if support::is_child("leave_untouched_child") {
+        inline_lifecycle(InlineRestorePolicy::LeaveUntouched, false);
+    }
+}
+
+#[test]
+fn clear_bottom_child() {
+    if support::is_child("clear_bottom_child") {
+        inline_lifecycle(InlineRestorePolicy::ClearBottom(3), false);
+    }
+}
+
+#[test]
+fn clear_bottom_zero_child() {
+    if support::is_child("clear_bottom_zero_child") {
+        inline_lifecycle(InlineRestorePolicy::ClearBottom(0), false);
+    }
+}
+
+#[test]
+fn clear_all_child() {
+    if support::is_child("clear_all_child") {
+        inline_lifecycle(InlineRestorePolicy::ClearAll, false);
+    }
+}
+
+#[test]
+fn clear_bottom_panic_child() {
+    if support::is_child("clear_bottom_panic_child") {
+        inline_lifecycle(InlineRestorePolicy::ClearBottom(3), true);
+    }
+}
+
+/// `CSI row ; col H` for a zero-based row at column zero.
+fn move_to_row(row: u16) -> Vec<u8> {
+    format!("\x1b[{};1H", row + 1).into_bytes()
+}
+
+#[test]
+fn clear_viewport_clears_the_inline_rows() {
+    let output = restore_output("clear_viewport_child");
+    let mut expected = move_to_row(support::PTY_ROWS - INLINE_HEIGHT);
+    expected.extend_from_slice(b"\x1b[J");
+    assert!(
+        support::contains(&output, &expected),
+        "{:?}",
+        String::from_utf8_lossy(&output)
+    );
+}
+
+#[test]
+fn leave_untouched_moves_below_without_clearing() {
+    let output = restore_output("leave_untouched_child");
+    // The cooked tty expands the newline to CRLF, hence no exact trailing match.
+    let mut expected = move_to_row(support::PTY_ROWS - 1);
+    expected.push(b'\r');
+    assert!(
+        support::contains(&output, &expected),
+        "{:?}",
+        String::from_utf8_lossy(&output)
+    );
+    assert!(!support::contains(&output, b"\x1b[J"));
+    assert!(!support::contains(&output, b"\x1b[2J"));
+}
+
+#[test]
+fn clear_bottom_clears_only_the_requested_rows() {
+    let output = restore_output("clear_bottom_child");
+    let mut expected = move_to_row(support::PTY_ROWS - 3);
+    expected.extend_from_slice(b"\x1b[J");
+    assert!(
+        support::contains(&output, &expected),
+        "{:?}",
+        String::from_utf8_lossy(&output)
+    );
+}
+
+#[test]
+fn clear_bottom_zero_leaves_every_row_like_leave_untouched() {
+    let output = restore_output("clear_bottom_zero_child");
+    let mut expected = move_to_row(support::PTY_ROWS - 1);
+    expected.push(b'\r');
+    assert!(
+        support::contains(&output, &expected),
+        "{:?}",
+        String::from_utf8_lossy(&output)
+    );
+    assert!(!support::contains(&output, b"\x1b[J"));
+}
+
+#[test]
+fn clear_all_clears_the_screen_and_homes_the_cursor() {
+    let output = restore_output("clear_all_child");
+    assert!(
+        support::contains(&output, b"\x1b[2J\x1b[1;1H"),
+        "{:?}",
+        String::from_utf8_lossy(&output)
+    );
+}
+
+#[test]
+fn panic_restore_uses_the_same_inline_policy() {
+    let output = restore_output("clear_bottom_panic_child");
+    let mut expected = move_to_row(support::PTY_ROWS - 3);
+    expected.extend_from_slice(b"\x1b[J");
+    assert!(
+        support::contains(&output, &expected),
+        "{:?}",
+        String::from_utf8_lossy(&output)
+    );
+}
+
+/// Run an app through init and restore, parking the cursor per `cursor`.
+fn cursor_restore_lifecycle(inline: Option<InlineRestorePolicy>, cursor: CursorRestore) {
+    let builder = builder().cursor_on_restore(cursor);
+    let builder = match inline {
+        Some(policy) => builder.inline(INLINE_HEIGHT).inline_restore(policy),
+        None => builder,
+    };
+    let mut app = builder.build();
+    let _terminal = app.init().unwrap();
+    ready();
+    app.restore().unwrap();
+}
+
+#[test]
+fn last_row_cursor_child() {
+    if support::is_child("last_row_cursor_child") {
+        let policy = Some(InlineRestorePolicy::ClearViewport);
+        cursor_restore_lifecycle(policy, CursorRestore::Column0LastRow);
+    }
+}
+
+#[test]
+fn saved_cursor_child() {
+    if support::is_child("saved_cursor_child") {
+        let policy = Some(InlineRestorePolicy::ClearViewport);
+        cursor_restore_lifecycle(policy, CursorRestore::SavedPosition);
+    }
+}
+
+#[test]
+fn saved_cursor_leave_untouched_child() {
+    if support::is_child("saved_cursor_leave_untouched_child") {
+        let policy = Some(InlineRestorePolicy::LeaveUntouched);
+        cursor_restore_lifecycle(policy, CursorRestore::SavedPosition);
+    }
+}
+
+#[test]
+fn saved_cursor_alternate_screen_child() {
+    if support::is_child("saved_cursor_alternate_screen_child") {
+        cursor_restore_lifecycle(None, CursorRestore::SavedPosition);
+    }
+}
+
+const SAVE_CURSOR: &[u8] = b"\x1b7";
+const RESTORE_CURSOR: &[u8] = b"\x1b8";
+
+#[test]
+fn last_row_cursor_lands_after_the_inline_policy() {
+    let output = restore_output("last_row_cursor_child");
+    let cleared = position(&output, b"\x1b[J");
+    let parked = position(&output[cleared..], &move_to_row(support::PTY_ROWS - 1)) + cleared;
+    assert!(parked < position(&output, b"\x1b[?25h"));
+}
+
+#[test]
+fn saved_cursor_is_restored_after_clearing_the_viewport() {
+    let mut child = support::PtyChild::spawn("saved_cursor_child");
+    child.answer_cursor_query();
+    child.wait_for(b"ready");
+    assert!(child.wait().success());
+    let output = child.output();
+
+    assert!(position(output, SAVE_CURSOR) < position(output, b"\x1b[6n"));
+    let restored = support::after(output, b"ready");
+    assert!(position(restored, b"\x1b[J") < position(restored, RESTORE_CURSOR));
+    assert!(position(restored, RESTORE_CURSOR) < position(restored, b"\x1b[?25h"));
+}
+
+#[test]
+fn saved_cursor_does_not_return_into_a_kept_frame() {
+    let output = restore_output("saved_cursor_leave_untouched_child");
+    let mut below = move_to_row(support::PTY_ROWS - 1);
+    below.push(b'\r');
+    assert!(support::contains(&output, &below));
+    assert!(!support::contains(&output, RESTORE_CURSOR));
+}
+
+#[test]
+fn saved_cursor_is_saved_before_the_alternate_screen_and_restored_after() {
+    let mut child = support::PtyChild::spawn("saved_cursor_alternate_screen_child");
+    child.wait_for(b"ready");
+    assert!(child.wait().success());
+    let output = child.output();
+
+    assert!(position(output, SAVE_CURSOR) < position(output, b"\x1b[?1049h"));
+    let restored = support::after(output, b"ready");
+    assert!(position(restored, b"\x1b[?1049l") < position(restored, RESTORE_CURSOR));
+}
+
+#[test]
+fn cursor_is_not_moved_again_by_default() {
+    let output = restore_output("clear_viewport_child");
+    assert!(!support::contains(&output, RESTORE_CURSOR));
+    assert!(!support::contains(
+        &output,
+        &move_to_row(support::PTY_ROWS - 1)
+    ));
+}
+
+fn banner_app() -> TuiApp {
+    builder()
+        .welcome_banner("welcome")
+        .goodbye_banner("goodbye")
+        .error_banner(|error| format!("failed: {error}"))
+        .build()
+}
+
+#[test]
+fn banner_child() {
+    if support::is_child("banner_child") {
+        let mut app = banner_app();
+        let _terminal = app.init().unwrap();
+        ready();
+        app.restore().unwrap();
+    }
+}
+
+#[test]
+fn error_banner_child() {
+    if support::is_child("error_banner_child") {
+        let mut app = banner_app();
+        let _terminal = app.init().unwrap();
+        ready();
+        app.restore_with_error(&"bad input").unwrap();
+    }
+}
+
+#[test]
+fn panic_banner_child() {
+    if support::is_child("panic_banner_child") {
+        let mut app = banner_app();
+        let _terminal = app.init().unwrap();
+        ready();
+        panic!("boom");
+    }
+}
+
+/// Offset of `needle` in the child's output, panicking with the output if it is missing.
+fn position(output: &[u8], needle: &[u8]) -> usize {
+    support::find(output, needle).unwrap_or_else(|| {
+        panic!(
+            "{:?} missing from {:?}",
+            String::from_utf8_lossy(needle),
+            String::from_utf8_lossy(output)
+        )
+    })
+}
+
+#[test]
+fn welcome_and_goodbye_banners_bracket_the_tui() {
+    let mut child = support::PtyChild::spawn("banner_child");
+    child.wait_for(b"ready");
+    assert!(child.wait().success());
+    let output = child.output();
+
+    // Printed in cooked mode, so the tty expands each newline to CRLF.
+    let welcome = position(output, b"welcome\r\n");
+    let goodbye = position(output, b"goodbye\r\n");
+    assert!(welcome < position(output, b"\x1b[?1049h"));
+    assert!(position(output, b"\x1b[?1049l") < goodbye);
+    assert!(!support::contains(output, b"failed:"));
+}
+
+#[test]
+fn error_banner_replaces_goodbye_after_restore() {
+    let mut child = support::PtyChild::spawn("error_banner_child");
+    child.wait_for(b"ready");
+    assert!(child.wait().success());
+    let output = child.output();
+
+    assert!(position(output, b"\x1b[?1049l") < position(output, b"failed: bad input\r\n"));
+    assert!(!support::contains(output, b"goodbye"));
+}
+
+#[test]
+fn panic_hook_prints_the_error_banner_after_restoring() {
+    let mut child = support::PtyChild::spawn("panic_banner_child");
+    child.wait_for(b"ready");
+    assert!(!child.wait().success());
+    let output = child.output();
+
+    assert!(position(output, b"\x1b[?1049l") < position(output, b"failed: boom\r\n"));
+}
+
+/// Run an app asking for two keyboard enhancement flags, reporting which ones it got.
+fn keyboard_lifecycle(panic: bool) {
+    let flags = KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+        | KeyboardEnhancementFlags::REPORT_EVENT_TYPES;
+    let mut app = builder().keyboard_enhancement(flags).build();
+    let _terminal = app.init().unwrap();
+    let pushed = app
+        .keyboard_enhancement()
+        .map_or("none".to_string(), |flags| flags.bits().to_string());
+    io::stdout()
+        .write_all(format!("flags <{pushed}>").as_bytes())
+        .unwrap();
+    ready();
+    if panic {
+        panic!("boom");
+    }
+    app.restore().unwrap();
+}
+
+#[test]
+fn keyboard_child() {
+    if support::is_child("keyboard_child") {
+        keyboard_lifecycle(false);
+    }
+}
+
+#[test]
+fn keyboard_panic_child() {
+    if support::is_child("keyboard_panic_child") {
+        keyboard_lifecycle(true);
+    }
+}
+
+/// Run a keyboard child, answering its support probe with `reply`.
+fn keyboard_output(name: &str, reply: &[u8]) -> (bool, Vec<u8>) {
+    let mut child = support::PtyChild::spawn(name);
+    child.wait_for(b"\x1b[?u\x1b[c");
+    child.write(reply);
+    child.wait_for(b"ready");
+    let success = child.wait().success();
+    (success, child.output().to_vec())
+}
+
+/// A kitty-protocol terminal's answer: current flags, then primary device attributes.
+const KITTY_REPLY: &[u8] = b"\x1b[?0u\x1b[?62c";
+
+#[test]
+fn keyboard_flags_are_pushed_inside_the_alternate_screen_and_popped() {
+    let (success, output) = keyboard_output("keyboard_child", KITTY_REPLY);
+    assert!(success);
+
+    assert!(support::contains(&output, b"flags <3>"));
+    assert_eq!(support::count(&output, b"\x1b[>3u"), 1);
+    assert_eq!(support::count(&output, b"\x1b[<1u"), 1);
+    assert!(position(&output, b"\x1b[?1049h") < position(&output, b"\x1b[>3u"));
+    assert!(position(&output, b"\x1b[<1u") < position(&output, b"\x1b[?1049l"));
+}
+
+#[test]
+fn keyboard_flags_are_popped_when_the_app_panics() {
+    let (success, output) = keyboard_output("keyboard_panic_child", KITTY_REPLY);
+    assert!(!success);
+
+    assert!(position(&output, b"\x1b[>3u") < position(&output, b"\x1b[<1u"));
+    assert!(position(&output, b"\x1b[<1u") < position(&output, b"\x1b[?1049l"));
+}
+
+#[test]
+fn keyboard_enhancement_is_skipped_without_terminal_support() {
+    let (success, output) = keyboard_output("keyboard_child", b"\x1b[?62c");
+    assert!(success);
+
+    assert!(support::contains(&output, b"flags <none>"));
+    assert!(!support::contains(&output, b"\x1b[>"));
+    assert!(!support::contains(&output, b"\x1b[<1u"));
+}
+
+#[test]
+fn every_mode_panic_child() {
+    if !support::is_child("every_mode_panic_child") {
+        return;
+    }
+
+    let mut app = TuiApp::builder("tuicore-pty-test")
+        .use_color_eyre(false)
+        .use_disk_logs(false)
+        .capture_mouse(true)
+        .keyboard_enhancement(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES)
+        .bracketed_paste(true)
+        .focus_events(true)
+        .build();
+    let _terminal = app.init().unwrap();
+    ready();
+    panic!("boom");
+}
+
+#[test]
+fn panic_undoes_every_mode_in_reverse_order() {
+    let (success, output) = keyboard_output("every_mode_panic_child", KITTY_REPLY);
+    assert!(!success);
+
+    let restore = support::after(&output, b"ready");
+    let undone = [
+        b"\x1b[<1u".as_slice(),
+        b"\x1b[?1049l",
+        b"\x1b[?1004l",
+        b"\x1b[?2004l",
+        b"\x1b[?1000l",
+    ];
+    for pair in undone.windows(2) {
+        assert!(position(restore, pair[0]) < position(restore, pair[1]));
+    }
+}
+
+/// A mode the builder can turn on, with the sequences that turn it on and off.
+struct ModeCase {
+    name: &'static str,
+    configure: fn(TuiAppBuilder) -> TuiAppBuilder,
+    enable: &'static [u8],
+    disable: &'static [u8],
+}
+
+const PUSH_TITLE: &[u8] = b"\x1b[22;0t";
+const POP_TITLE: &[u8] = b"\x1b[23;0t";
+const SET_TITLE: &[u8] = b"\x1b]0;my tool\x07";
+const CLEAR_TITLE: &[u8] = b"\x1b]0;\x07";
+const STEADY_BAR: &[u8] = b"\x1b[6 q";
+const DEFAULT_CURSOR: &[u8] = b"\x1b[0 q";
+const BEGIN_SYNC: &[u8] = b"\x1b[?2026h";
+const END_SYNC: &[u8] = b"\x1b[?2026l";
+const EXTRA_INIT: &[u8] = b"\x1b[?2027h\x1b]1337;custom\x07";
+const EXTRA_RESTORE: &[u8] = b"\x1b[?2027l";
+
+const MODE_CASES: &[ModeCase] = &[
+    ModeCase {
+        name: "paste",
+        configure: |builder| builder.bracketed_paste(true),
+        enable: b"\x1b[?2004h",
+        disable: b"\x1b[?2004l",
+    },
+    ModeCase {
+        name: "focus",
+        configure: |builder| builder.focus_events(true),
+        enable: b"\x1b[?1004h",
+        disable: b"\x1b[?1004l",
+    },
+    ModeCase {
+        name: "title",
+        configure: |builder| builder.title("my tool"),
+        enable: SET_TITLE,
+        disable: POP_TITLE,
+    },
+    ModeCase {
+        // Hidden as by default.
+        name: "cursor_style",
+        configure: |builder| builder.cursor_style(CursorStyle::SteadyBar),
+        enable: STEADY_BAR,
+        disable: DEFAULT_CURSOR,
+    },
+    ModeCase {
+        name: "synchronized_output",
+        configure: |builder| builder.synchronized_output(true),
+        enable: BEGIN_SYNC,
+        disable: END_SYNC,
+    },
+    ModeCase {
+        name: "extra_commands",
+        configure: |builder| {
+            builder
+                .bracketed_paste(true)
+                .extra_init_command(Print("\x1b[?2027h"))
+                .extra_init_command(Print("\x1b]1337;custom\x07"))
+                .extra_restore_command(Print("\x1b[?2027l"))
+        },
+        enable: EXTRA_INIT,
+        disable: EXTRA_RESTORE,
+    },
+    ModeCase {
+        name: "alt_screen_47",
+        configure: |builder| builder.alt_screen_style(AltScreenStyle::Mode47),
+        enable: b"\x1b[?47h",
+        disable: b"\x1b[?47l",
+    },
+    ModeCase {
+        name: "alt_screen_1047",
+        configure: |builder| builder.alt_screen_style(AltScreenStyle::Mode1047),
+        enable: b"\x1b[?1047h",
+        disable: b"\x1b[?1047l",
+    },
+];
+
+/// Run an app from `configure` through init, one synced frame and restore, panicking inside
+/// the frame if `panic`.
+fn mode_lifecycle(configure: impl FnOnce(TuiAppBuilder) -> TuiAppBuilder, panic: bool) {
+    let mut app = configure(builder()).build();
+    let mut terminal = app.init().unwrap();
+    ready();
+    app.draw_synced(&mut terminal, |f| {
+        if panic {
+            panic!("boom");
+        }
+        f.render_widget(Text::raw("synced"), f.area());
+    })
+    .unwrap();
+    app.restore().unwrap();
+}
+
+/// Run the [`MODE_CASES`] entry named by the parent, or the defaults for `"default"`.
+fn mode_case_lifecycle(case: &str, panic: bool) {
+    let configure: fn(TuiAppBuilder) -> TuiAppBuilder = match case {
+        "default" => |builder| builder,
+        name => {
+            let mode = MODE_CASES.iter().find(|mode| mode.name == name);
+            mode.expect("unknown mode case").configure
+        }
+    };
+    mode_lifecycle(configure, panic);
+}
+
+#[test]
+fn mode_child() {
+    if let Some(case) = support::child_case("mode_child") {
+        mode_case_lifecycle(&case, false);
+    }
+}
+
+#[test]
+fn mode_panic_child() {
+    if let Some(case) = support::child_case("mode_panic_child") {
+        mode_case_lifecycle(&case, true);
+    }
+}
+
+/// Run `case` to completion and return its output.
+fn mode_output(case: &str) -> Vec<u8> {
+    let mut child = support::PtyChild::spawn_case("mode_child", case);
+    child.wait_for(b"ready");
+    assert!(child.wait().success(), "{case}");
+    child.output().to_vec()
+}
+
+#[test]
+fn modes_are_turned_on_for_the_session_and_off_after_it() {
+    for mode in MODE_CASES {
+        let output = mode_output(mode.name);
+
+        assert!(
+            position(&output, mode.enable) < position(&output, b"synced"),
+            "{}",
+            mode.name
+        );
+        assert!(
+            support::contains(support::after(&output, b"synced"), mode.disable),
+            "{}",
+            mode.name
+        );
+    }
+}
+
+#[test]
+fn modes_are_turned_off_when_the_app_panics() {
+    for mode in MODE_CASES {
+        let mut child = support::PtyChild::spawn_case("mode_panic_child", mode.name);
+        child.wait_for(b"ready");
+        assert!(!child.wait().success(), "{}", mode.name);
+
+        assert!(
+            support::contains(support::after(child.output(), b"ready"), mode.disable),
+            "{}",
+            mode.name
+        );
+    }
+}
+
+#[test]
+fn modes_are_left_alone_by_default() {
+    let output = mode_output("default");
+
+    assert!(support::contains(&output, b"synced"));
+    for mode in MODE_CASES {
+        assert!(!support::contains(&output, mode.enable), "{}", mode.name);
+        assert!(!support::contains(&output, mode.disable), "{}", mode.name);
+    }
+}
+
+#[test]
+fn bracketed_paste_brackets_the_session() {
+    let output = mode_output("paste");
+
+    assert_eq!(support::count(&output, b"\x1b[?2004h"), 1);
+    assert_eq!(support::count(&output, b"\x1b[?2004l"), 1);
+    assert!(position(&output, b"\x1b[?2004h") < position(&output, b"ready"));
+}
+
+#[test]
+fn stderr_paste_child() {
+    if !support::is_child("stderr_paste_child") {
+        return;
+    }
+
+    stderr_to_pty();
+    let mut app = builder()
+        .alternate_screen_backend(AlternateScreenBackend::Stderr)
+        .bracketed_paste(true)
+        .build();
+    let _terminal = app.init().unwrap();
+    ready();
+    app.restore().unwrap();
+}
+
+#[test]
+fn bracketed_paste_composes_with_the_stderr_backend() {
+    let mut child = support::PtyChild::spawn("stderr_paste_child");
+    child.wait_for(b"ready");
+    assert!(child.wait().success());
+    let output = child.output();
+
+    assert!(position(output, b"\x1b[?2004h") < position(output, b"ready"));
+    assert!(position(output, b"ready") < position(output, b"\x1b[?1049l"));
+    // Turned off in reverse: paste was on before the alternate screen was entered.
+    assert!(position(output, b"\x1b[?1049l") < position(output, b"\x1b[?2004l"));
+}
+
+#[test]
+fn focus_restore_without_init_child() {
+    if !support::is_child("focus_restore_without_init_child") {
+        return;
+    }
+
+    let app = builder().focus_events(true).build();
+    assert!(!app.focus_events());
+    app.restore().unwrap();
+    ready();
+}
+
+#[test]
+fn focus_events_are_enabled_and_disabled_symmetrically() {
+    let output = mode_output("focus");
+
+    assert_eq!(support::count(&output, b"\x1b[?1004h"), 1);
+    assert_eq!(support::count(&output, b"\x1b[?1004l"), 1);
+}
+
+#[test]
+fn restore_only_disables_focus_events_that_were_enabled() {
+    let mut child = support::PtyChild::spawn("focus_restore_without_init_child");
+    child.wait_for(b"ready");
+    assert!(child.wait().success());
+
+    assert!(!support::contains(child.output(), b"\x1b[?1004"));
+}
+
+#[test]
+fn title_is_pushed_set_and_popped() {
+    let output = mode_output("title");
+
+    assert!(position(&output, PUSH_TITLE) < position(&output, SET_TITLE));
+    let restored = support::after(&output, b"ready");
+    assert!(position(restored, CLEAR_TITLE) < position(restored, POP_TITLE));
+}
+
+#[test]
+fn cursor_style_is_set_under_the_hidden_cursor_and_reset() {
+    let output = mode_output("cursor_style");
+
+    assert!(position(&output, STEADY_BAR) < position(&output, b"\x1b[?25l"));
+    assert!(position(&output, b"\x1b[?25l") < position(&output, b"ready"));
+    let restored = support::after(&output, b"ready");
+    assert!(position(restored, DEFAULT_CURSOR) < position(restored, b"\x1b[?25h"));
+}
+
+#[test]
+fn synchronized_output_brackets_each_frame() {
+    let output = mode_output("synchronized_output");
+    let output = support::after(&output, b"ready");
+
+    assert_eq!(support::count(output, BEGIN_SYNC), 1);
+    assert!(position(output, BEGIN_SYNC) < position(output, b"synced"));
+    assert!(position(output, b"synced") < position(output, END_SYNC));
+    // Once after the frame, and once more on restore.
+    assert_eq!(support::count(output, END_SYNC), 2);
+}
+
+#[test]
+fn extra_commands_follow_init_and_precede_restore() {
+    let output = mode_output("extra_commands");
+
+    assert!(position(&output, b"\x1b[?2004h") < position(&output, b"\x1b[?1049h"));
+    assert!(position(&output, b"\x1b[?1049h") < position(&output, EXTRA_INIT));
+    assert!(position(&output, EXTRA_INIT) < position(&output, b"\x1b[?25l"));
+    let restored = support::after(&output, b"ready");
+    assert!(position(restored, EXTRA_RESTORE) < position(restored, b"\x1b[?2004l"));
+    assert!(position(restored, EXTRA_RESTORE) < position(restored, b"\x1b[?1049l"));
+}
+
+#[test]
+fn extra_restore_commands_run_once_when_the_app_panics() {
+    let mut child = support::PtyChild::spawn_case("mode_panic_child", "extra_commands");
+    child.wait_for(b"ready");
+    assert!(!child.wait().success());
+
+    let restored = support::after(child.output(), b"ready");
+    assert_eq!(support::count(restored, EXTRA_RESTORE), 1);
+    assert!(position(restored, EXTRA_RESTORE) < position(restored, b"\x1b[?1049l"));
+}
+
+fn probing_app() -> TuiApp {
+    builder().probe_capabilities(true).build()
+}
+
+#[test]
+fn probe_child() {
+    if !support::is_child("probe_child") {
+        return;
+    }
+
+    std::env::set_var("COLORTERM", "truecolor");
+    let mut app = probing_app();
+    let _terminal = app.init().unwrap();
+    let key = match crossterm::event::read().unwrap() {
+        crossterm::event::Event::Key(key) => format!("{:?}", key.code),
+        other => format!("{:?}", other),
+    };
+    print!("{:?} then {}", app.capabilities(), key);
+    ready();
+    app.restore().unwrap();
+}
+
+#[test]
+fn unanswered_probe_child() {
+    if !support::is_child("unanswered_probe_child") {
+        return;
+    }
+
+    let mut app = probing_app();
+    let _terminal = app.init().unwrap();
+    print!("{:?}", app.capabilities());
+    ready();
+    app.restore().unwrap();
+}
+
+#[test]
+fn probe_records_the_replies_and_leaves_later_input_alone() {
+    let mut child = support::PtyChild::spawn("probe_child");
+    child.wait_for(b"\x1b[c");
+    child.write(b"\x1b[?0u\x1b[?2026;2$y\x1b[?2004;2$y\x1b[?62;22cx");
+    child.wait_for(b"ready");
+    assert!(child.wait().success());
+
+    let expected = "Some(TerminalCapabilities { kitty_keyboard: true, synchronized_output: true, \
+                    bracketed_paste: true, truecolor: true }) then Char('x')";
+    assert!(
+        support::contains(child.output(), expected.as_bytes()),
+        "{:?}",
+        String::from_utf8_lossy(child.output())
+    );
+}
+
+#[test]
+fn probe_without_replies_leaves_capabilities_unknown() {
+    let mut child = support::PtyChild::spawn("unanswered_probe_child");
+    child.wait_for(b"ready");
+    assert!(child.wait().success());
+
+    assert!(support::contains(child.output(), b"Noneready"));
+}
+
+/// An app with a goodbye banner whose terminal comes from `init_guarded`.
+fn guarded_app() -> TuiApp {
+    TuiApp::builder("tuicore-pty-test")
+        .use_color_eyre(false)
+        .use_disk_logs(false)
+        .goodbye_banner("goodbye")
+        .build()
+}
+
+fn guarded_step(fail: bool) -> Result<(), String> {
+    let mut app = guarded_app();
+    let mut terminal = app.init_guarded().map_err(|e| e.to_string())?;
+    terminal.draw(|_| {}).map_err(|e| e.to_string())?;
+    ready();
+    if fail {
+        return Err("early return".to_string());
+    }
+    Ok(())
+}
+
+#[test]
+fn guard_drop_child() {
+    if support::is_child("guard_drop_child") {
+        assert!(guarded_step(true).is_err());
+    }
+}
+
+#[test]
+fn guard_after_restore_child() {
+    if !support::is_child("guard_after_restore_child") {
+        return;
+    }
+
+    let mut app = guarded_app();
+    let terminal = app.init_guarded().unwrap();
+    ready();
+    terminal.app().restore().unwrap();
+    drop(terminal);
+}
+
+#[test]
+fn guard_panic_child() {
+    if !support::is_child("guard_panic_child") {
+        return;
+    }
+
+    let mut app = guarded_app();
+    let _terminal = app.init_guarded().unwrap();
+    ready();
+    panic!("boom");
+}
+
+/// Restores exactly once: leaves the alternate screen, then says goodbye.
+fn assert_restored_once(output: &[u8]) {
+    assert_eq!(support::count(output, b"\x1b[?1049l"), 1);
+    assert_eq!(support::count(output, b"\x1b[?1000l"), 1);
+    assert!(position(output, b"ready") < position(output, b"\x1b[?1049l"));
+}
+
+#[test]
+fn dropping_the_guard_restores_the_terminal() {
+    let mut child = support::PtyChild::spawn("guard_drop_child");
+    child.wait_for(b"ready");
+    assert!(child.wait().success());
+    let output = child.output();
+
+    assert_restored_once(output);
+    assert!(position(output, b"\x1b[?1049l") < position(output, b"goodbye"));
+    assert!(child
+        .termios()
+        .local_flags
+        .contains(LocalFlags::ICANON | LocalFlags::ECHO));
+}
+
+#[test]
+fn guard_drop_after_an_explicit_restore_does_nothing() {
+    let mut child = support::PtyChild::spawn("guard_after_restore_child");
+    child.wait_for(b"ready");
+    assert!(child.wait().success());
+    let output = child.output();
+
+    assert_restored_once(output);
+    assert_eq!(support::count(output, b"goodbye"), 1);
+}
+
+#[test]
+fn guard_drop_after_the_panic_hook_does_nothing() {
+    let mut child = support::PtyChild::spawn("guard_panic_child");
+    child.wait_for(b"ready");
+    assert!(!child.wait().success());
+    let output = child.output();
+
+    assert_restored_once(output);
+    assert!(!support::contains(output, b"goodbye"));
+}
+
+/// An app with both banners, run through [`TuiApp::run`] with `outcome` from the closure.
+fn run_lifecycle(outcome: &str) {
+    let mut app = TuiApp::builder("tuicore-pty-test")
+        .use_color_eyre(false)
+        .use_disk_logs(false)
+        .goodbye_banner("goodbye")
+        .error_banner(|message| format!("failed: {message}"))
+        .build();
+    let result = app.run(|terminal| {
+        terminal.draw(|_| {})?;
+        ready();
+        match outcome {
+            "ok" => Ok(42),
+            "err" => Err(eyre::eyre!("bad input")),
+            _ => panic!("boom"),
+        }
+    });
+    let reported = match result {
+        Ok(value) => format!("returned <{value}>"),
+        Err(e) => format!("returned <{e}>"),
+    };
+    io::stdout().write_all(reported.as_bytes()).unwrap();
+}
+
+#[test]
+fn run_ok_child() {
+    if support::is_child("run_ok_child") {
+        run_lifecycle("ok");
+    }
+}
+
+#[test]
+fn run_err_child() {
+    if support::is_child("run_err_child") {
+        run_lifecycle("err");
+    }
+}
+
+#[test]
+fn run_panic_child() {
+    if support::is_child("run_panic_child") {
+        run_lifecycle("panic");
+    }
+}
+
+/// Output of a `run` child once it has exited, and whether it succeeded.
+fn run_output(name: &str) -> (bool, Vec<u8>) {
+    let mut child = support::PtyChild::spawn(name);
+    child.wait_for(b"ready");
+    let success = child.wait().success();
+    (success, child.output().to_vec())
+}
+
+#[test]
+fn run_restores_and_returns_the_closure_value() {
+    let (success, output) = run_output("run_ok_child");
+    assert!(success);
+
+    assert_restored_once(&output);
+    assert!(position(&output, b"\x1b[?1049l") < position(&output, b"goodbye"));
+    assert!(position(&output, b"goodbye") < position(&output, b"returned <42>"));
+}
+
+#[test]
+fn run_restores_and_returns_the_closure_error() {
+    let (success, output) = run_output("run_err_child");
+    assert!(success);
+
+    assert_restored_once(&output);
+    assert!(position(&output, b"\x1b[?1049l") < position(&output, b"failed: bad input"));
+    assert!(support::contains(&output, b"returned <bad input>"));
+    assert!(!support::contains(&output, b"goodbye"));
+}
+
+#[test]
+fn run_restores_when_the_closure_panics() {
+    let (success, output) = run_output("run_panic_child");
+    assert!(!success);
+
+    assert_restored_once(&output);
+    assert!(position(&output, b"\x1b[?1049l") < position(&output, b"failed: boom"));
+    assert!(!support::contains(&output, b"goodbye"));
+    assert!(!support::contains(&output, b"returned"));
+}
+
+#[test]
+fn suspend_child() {
+    if !support::is_child("suspend_child") {
+        return;
+    }
+
+    let mut app = builder().bracketed_paste(true).build();
+    let mut terminal = app.init().unwrap();
+    ready();
+    app.suspend(&mut terminal).unwrap();
+    io::stdout().write_all(b"resumed").unwrap();
+    io::stdout().flush().unwrap();
+    // Hold raw mode until the parent has looked at it.
+    io::stdin().read_exact(&mut [0u8]).unwrap();
+    app.restore().unwrap();
+}
+
+#[test]
+fn suspend_stops_with_a_restored_tty_and_resumes_into_the_tui() {
+    let mut child = support::PtyChild::spawn("suspend_child");
+    child.wait_for(b"ready");
+    assert!(child.wait_until_stopped());
+    child.wait_for(b"\x1b[?2004l");
+
+    let stopped = support::after(child.output(), b"ready").to_vec();
+    assert!(position(&stopped, b"\x1b[?1049l") < position(&stopped, b"\x1b[?2004l"));
+    assert!(child
+        .termios()
+        .local_flags
+        .contains(LocalFlags::ICANON | LocalFlags::ECHO));
+
+    child.signal(libc::SIGCONT);
+    child.wait_for(b"resumed");
+    assert!(!child.termios().local_flags.contains(LocalFlags::ICANON));
+    let resumed = support::after(child.output(), b"\x1b[?1049l").to_vec();
+    assert!(position(&resumed, b"\x1b[?2004h") < position(&resumed, b"resumed"));
+    assert!(position(&resumed, b"\x1b[?1049h") < position(&resumed, b"resumed"));
+    assert!(position(&resumed, b"\x1b[2J") < position(&resumed, b"resumed"));
+
+    child.write(b"q");
+    assert!(child.wait().success());
+    assert_eq!(support::count(child.output(), b"\x1b[?1049l"), 2);
+    assert!(child
+        .termios()
+        .local_flags
+        .contains(LocalFlags::ICANON | LocalFlags::ECHO));
+}
+
+#[test]
+fn sigtstp_request_child() {
+    if !support::is_child("sigtstp_request_child") {
+        return;
+    }
+
+    let mut app = builder().handle_suspend(true).build();
+    let _terminal = app.init().unwrap();
+    assert!(!app.take_suspend_request());
+    // SAFETY: raising a signal with a handler installed just runs the handler.
+    unsafe { libc::raise(libc::SIGTSTP) };
+    let requested = app.take_suspend_request();
+    let cleared = !app.take_suspend_request();
+    app.restore().unwrap();
+    io::stdout()
+        .write_all(format!("requested <{requested} {cleared}>").as_bytes())
+        .unwrap();
+    io::stdout().flush().unwrap();
+}
+
+#[test]
+fn handle_suspend_turns_sigtstp_into_a_request() {
+    let mut child = support::PtyChild::spawn("sigtstp_request_child");
+    child.wait_for(b">");
+    assert!(child.wait().success());
+    assert!(support::contains(child.output(), b"requested <true true>"));
+}
+
+#[test]
+fn sigtstp_after_restore_child() {
+    if !support::is_child("sigtstp_after_restore_child") {
+        return;
+    }
+
+    let mut app = builder().handle_suspend(true).build();
+    for _ in 0..2 {
+        let _terminal = app.init().unwrap();
+        app.restore().unwrap();
+    }
+    ready();
+    // SAFETY: raising a signal has no memory-safety preconditions.
+    unsafe { libc::raise(libc::SIGTSTP) };
+    io::stdout().write_all(b"continued").unwrap();
+    io::stdout().flush().unwrap();
+}
+
+#[test]
+fn sigtstp_stops_the_process_again_after_restore() {
+    let mut child = support::PtyChild::spawn("sigtstp_after_restore_child");
+    child.wait_for(b"ready");
+    assert!(child.wait_until_stopped());
+
+    child.signal(libc::SIGCONT);
+    child.wait_for(b"continued");
+    assert!(child.wait().success());
+}
+
+/// An inline app that leaves raw mode alone, asking for mouse capture anyway.
+fn cooked_app() -> TuiApp {
+    TuiApp::builder("tuicore-pty-test")
+        .use_color_eyre(false)
+        .use_disk_logs(false)
+        .raw_mode(false)
+        .capture_mouse(true)
+        .inline(INLINE_HEIGHT)
+        .build()
+}
+
+#[test]
+fn cooked_child() {
+    if !support::is_child("cooked_child") {
+        return;
+    }
+
+    let mut app = cooked_app();
+    let _terminal = app.init().unwrap();
+    ready();
+    // Read a whole line, which only arrives once the tty has seen the newline.
+    let mut line = String::new();
+    io::stdin().read_line(&mut line).unwrap();
+    app.restore().unwrap();
+    io::stdout()
+        .write_all(format!("line <{}>", line.trim_end()).as_bytes())
+        .unwrap();
+    io::stdout().flush().unwrap();
+}
+
+#[test]
+fn cooked_panic_child() {
+    if !support::is_child("cooked_panic_child") {
+        return;
+    }
+
+    // The host's own raw mode, which the TUI must not undo.
+    crossterm::terminal::enable_raw_mode().unwrap();
+    let mut app = cooked_app();
+    let _terminal = app.init().unwrap();
+    ready();
+    panic!("boom");
+}
+
+#[test]
+fn cooked_mode_keeps_line_buffered_input_and_skips_mouse_capture() {
+    let mut child = support::PtyChild::spawn("cooked_child");
+    child.answer_cursor_query();
+    child.wait_for(b"ready");
+    assert!(child.termios().local_flags.contains(LocalFlags::ICANON));
+
+    child.write(b"hello\n");
+    child.wait_for(b">");
+    assert!(child.wait().success());
+    let output = child.output();
+
+    assert!(support::contains(output, b"line <hello>"));
+    assert!(!support::contains(output, b"\x1b[?1000h"));
+    assert!(!support::contains(output, b"\x1b[?1000l"));
+}
+
+#[test]
+fn cooked_mode_panic_leaves_the_host_termios_alone() {
+    let mut child = support::PtyChild::spawn("cooked_panic_child");
+    child.answer_cursor_query();
+    child.wait_for(b"ready");
+    assert!(!child.wait().success());
+
+    assert!(!child.termios().local_flags.contains(LocalFlags::ICANON));
+}
+
+#[test]
+fn restore_before_init_does_nothing() {
+    let app = app();
+    assert_eq!(app.lifecycle_state(), LifecycleState::NotStarted);
+    app.restore().unwrap();
+    assert_eq!(app.lifecycle_state(), LifecycleState::NotStarted);
+}
+
+#[test]
+fn double_restore_child() {
+    if !support::is_child("double_restore_child") {
+        return;
+    }
+
+    let mut app = app();
+    let terminal = app.init().unwrap();
+    let active = app.lifecycle_state();
+    ready();
+    app.restore().unwrap();
+    let restored = app.lifecycle_state();
+    // Ratatui shows the cursor again when its terminal drops; keep that out of the check.
+    drop(terminal);
+    io::stdout().write_all(b"between").unwrap();
+    io::stdout().flush().unwrap();
+    app.restore().unwrap();
+    assert_eq!(app.lifecycle_state(), LifecycleState::Restored);
+    io::stdout()
+        .write_all(format!("states <{active:?} {restored:?}>").as_bytes())
+        .unwrap();
+    io::stdout().flush().unwrap();
+    panic!("after restore");
+}
+
+#[test]
+fn drop_child() {
+    if !support::is_child("drop_child") {
+        return;
+    }
+
+    let mut app = app();
+    let _terminal = app.init().unwrap();
+    ready();
+    drop(app);
+}
+
+#[test]
+fn second_restore_and_later_panic_emit_nothing() {
+    let mut child = support::PtyChild::spawn("double_restore_child");
+    child.wait_for(b"ready");
+    assert!(!child.wait().success());
+    let output = child.output();
+
+    assert!(support::contains(output, b"states <Active Restored>"));
+    assert_eq!(support::count(output, b"\x1b[?1049l"), 1);
+    let after_first = support::after(output, b"between");
+    assert!(!support::contains(after_first, b"\x1b["), "{after_first:?}");
+}
+
+#[test]
+fn dropping_an_active_app_restores_the_terminal() {
+    let mut child = support::PtyChild::spawn("drop_child");
+    child.wait_for(b"ready");
+    assert!(child.wait().success());
+
+    assert_restored_once(child.output());
+    assert!(child
+        .termios()
+        .local_flags
+        .contains(LocalFlags::ICANON | LocalFlags::ECHO));
+}
+
+/// Put stderr on the PTY too, so the parent sees what the app writes there.
+fn stderr_to_pty() {
+    // SAFETY: both descriptors are open for the life of the process.
+    unsafe { libc::dup2(libc::STDOUT_FILENO, libc::STDERR_FILENO) };
+}
+
+fn report_log_dir() -> std::path::PathBuf {
+    std::env::temp_dir().join("tuicore-pty-test-panic-report")
+}
+
+#[test]
+fn panic_report_child() {
+    if !support::is_child("panic_report_child") {
+        return;
+    }
+
+    stderr_to_pty();
+    let mut app = TuiApp::builder("tuicore-pty-test")
+        .use_color_eyre(false)
+        .log_dir(report_log_dir())
+        .build();
+    let _terminal = app.init().unwrap();
+    ready();
+    panic!("boom");
+}
+
+#[test]
+fn custom_panic_report_child() {
+    if !support::is_child("custom_panic_report_child") {
+        return;
+    }
+
+    stderr_to_pty();
+    let mut app = TuiApp::builder("tuicore-pty-test")
+        .use_color_eyre(false)
+        .use_disk_logs(false)
+        .inline(INLINE_HEIGHT)
+        .panic_report(|info| format!("custom report, line {}", info.location().unwrap().line()))
+        .build();
+    let _terminal = app.init().unwrap();
+    ready();
+    panic!("boom");
+}
+
+#[test]
+fn panic_report_is_printed_after_restoring() {
+    let mut child = support::PtyChild::spawn("panic_report_child");
+    child.wait_for(b"ready");
+    assert!(!child.wait().success());
+    let output = child.output();
+
+    let report = position(
+        output,
+        b"tuicore-pty-test panicked at tests/tui_app_pty.rs:",
+    );
+    assert!(position(output, b"\x1b[?1049l") < report);
+    let report = &output[report..];
+    assert!(position(report, b"boom") < position(report, b"Logs are in"));
+    let dir = report_log_dir().display().to_string();
+    assert!(support::contains(report, dir.as_bytes()));
+}
+
+#[test]
+fn custom_panic_report_replaces_the_default_one() {
+    let mut child = support::PtyChild::spawn("custom_panic_report_child");
+    child.answer_cursor_query();
+    child.wait_for(b"ready");
+    assert!(!child.wait().success());
+    let output = support::after(child.output(), b"ready");
+
+    assert!(position(output, b"\x1b[J") < position(output, b"custom report, line "));
+    assert!(!support::contains(output, b"tuicore-pty-test panicked"));
+}
+
+#[test]
+fn two_apps_child() {
+    if !support::is_child("two_apps_child") {
+        return;
+    }
+
+    // The host installs color-eyre first, then runs two apps that each want it too.
+    color_eyre::install().unwrap();
+    for _ in 0..2 {
+        let mut app = TuiApp::builder("tuicore-pty-test")
+            .use_disk_logs(false)
+            .capture_mouse(false)
+            .color_eyre_theme(color_eyre::config::Theme::new())
+            .build();
+        let _terminal = app.init().unwrap();
+        app.restore().unwrap();
+    }
+    ready();
+}
+
+#[test]
+fn init_tolerates_color_eyre_already_installed() {
+    let mut child = support::PtyChild::spawn("two_apps_child");
+    child.wait_for(b"ready");
+    assert!(child.wait().success());
+    assert_eq!(support::count(child.output(), b"\x1b[?1049l"), 2);
+}
+
+fn min_size_app(cols: u16, rows: u16, behavior: MinSizeBehavior) -> TuiApp {
+    builder()
+        .min_size(cols, rows)
+        .min_size_behavior(behavior)
+        .build()
+}
+
+#[test]
+fn min_size_fail_child() {
+    if !support::is_child("min_size_fail_child") {
+        return;
+    }
+
+    let mut app = min_size_app(100, 30, MinSizeBehavior::Fail);
+    let error = app.init().err().unwrap();
+    io::stdout()
+        .write_all(format!("error <{error}>").as_bytes())
+        .unwrap();
+    io::stdout().flush().unwrap();
+}
+
+#[test]
+fn min_size_wait_child() {
+    if !support::is_child("min_size_wait_child") {
+        return;
+    }
+
+    let mut app = min_size_app(100, 30, MinSizeBehavior::Wait);
+    let _terminal = app.init().unwrap();
+    ready();
+    app.restore().unwrap();
+}
+
+#[test]
+fn inline_clamp_child() {
+    if !support::is_child("inline_clamp_child") {
+        return;
+    }
+
+    let mut app = builder().inline(support::PTY_ROWS + 10).build();
+    let mut terminal = app.init().unwrap();
+    let area = terminal.draw(|_| {}).unwrap().area;
+    io::stdout()
+        .write_all(format!("height <{}>", area.height).as_bytes())
+        .unwrap();
+    ready();
+    app.restore().unwrap();
+}
+
+#[test]
+fn too_small_a_terminal_fails_init_before_touching_it() {
+    let mut child = support::PtyChild::spawn("min_size_fail_child");
+    child.wait_for(b">");
+    assert!(child.wait().success());
+    let output = child.output();
+
+    let error = b"error <Terminal is 80x24, but needs to be at least 100x30>";
+    assert!(support::contains(output, error));
+    assert!(!support::contains(output, b"\x1b[?1049h"));
+    assert!(child.termios().local_flags.contains(LocalFlags::ICANON));
+}
+
+#[test]
+fn min_size_wait_asks_to_enlarge_then_starts() {
+    let mut child = support::PtyChild::spawn("min_size_wait_child");
+    child.wait_for(b"Please enlarge your terminal to at least 100x30 (now 80x24)");
+    assert!(!support::contains(child.output(), b"\x1b[?1049h"));
+
+    child.resize(100, 30);
+    child.wait_for(b"ready");
+    assert!(child.wait().success());
+    let output = child.output();
+    assert!(position(output, b"enlarge") < position(output, b"\x1b[?1049h"));
+}
+
+#[test]
+fn inline_height_is_clamped_to_the_terminal() {
+    let mut child = support::PtyChild::spawn("inline_clamp_child");
+    child.answer_cursor_query();
+    child.wait_for(b"ready");
+    assert!(child.wait().success());
+    let expected = format!("height <{}>", support::PTY_ROWS);
+    assert!(support::contains(child.output(), expected.as_bytes()));
+}
+
+/// Wait for the `Event::Resize` to `rows` an event loop would handle, returning the new size.
+fn wait_for_resize(rows: u16) -> ratatui::layout::Size {
+    loop {
+        if let crossterm::event::Event::Resize(cols, current) = crossterm::event::read().unwrap() {
+            if current == rows {
+                return ratatui::layout::Size::new(cols, current);
+            }
+        }
+    }
+}
+
+#[test]
+fn inline_resize_child() {
+    if !support::is_child("inline_resize_child") {
+        return;
+    }
+
+    let mut app = builder().inline(10).build();
+    let mut terminal = app.init().unwrap();
+    terminal.draw(|_| {}).unwrap();
+    ready();
+
+    let new_size = wait_for_resize(6);
+    io::stdout().write_all(b"resizing").unwrap();
+    io::stdout().flush().unwrap();
+    app.handle_resize(&mut terminal, new_size).unwrap();
+    let area = terminal.draw(|_| {}).unwrap().area;
+    io::stdout()
+        .write_all(format!("height <{}>", area.height).as_bytes())
+        .unwrap();
+    app.restore().unwrap();
+}
+
+#[test]
+fn alternate_resize_child() {
+    if !support::is_child("alternate_resize_child") {
+        return;
+    }
+
+    let mut app = builder().build();
+    let mut terminal = app.init().unwrap();
+    // Start crossterm's event reader, which catches SIGWINCH, before the parent resizes.
+    crossterm::event::poll(std::time::Duration::ZERO).unwrap();
+    ready();
+
+    let new_size = wait_for_resize(30);
+    app.handle_resize(&mut terminal, new_size).unwrap();
+    let area = terminal.draw(|_| {}).unwrap().area;
+    io::stdout()
+        .write_all(format!("area <{}x{}>", area.width, area.height).as_bytes())
+        .unwrap();
+    app.restore().unwrap();
+}
+
+#[test]
+fn inline_resize_clamps_and_reanchors_the_viewport() {
+    let mut child = support::PtyChild::spawn("inline_resize_child");
+    child.answer_cursor_query();
+    child.wait_for(b"ready");
+    child.resize(support::PTY_COLS, 6);
+    child.answer_cursor_query();
+    assert!(child.wait().success());
+
+    let output = support::after(child.output(), b"resizing");
+    // The stale rows are cleared from the top of the shrunk screen, which the viewport now fills.
+    assert!(support::contains(output, b"\x1b[1;1H\x1b[J"));
+    assert!(support::contains(output, b"height <6>"));
+    let restored = support::after(output, b"height <6>");
+    assert!(support::contains(restored, b"\x1b[1;1H\x1b[J"));
+}
+
+#[test]
+fn alternate_resize_repaints_at_the_new_size() {
+    let mut child = support::PtyChild::spawn("alternate_resize_child");
+    child.wait_for(b"ready");
+    child.resize(100, 30);
+    child.wait_for(b">");
+    assert!(child.wait().success());
+
+    let output = support::after(child.output(), b"ready");
+    assert!(support::contains(output, b"\x1b[2J"));
+    assert!(support::contains(output, b"area <100x30>"));
+}
+
+/// Init with `clear`, then mark where init ends and draw "hi" on an otherwise blank frame.
+fn clear_on_init_lifecycle(clear: ClearOnInit, inline: bool) {
+    let builder = builder().clear_on_init(clear);
+    let mut app = if inline { builder.inline(5) } else { builder }.build();
+    let mut terminal = app.init().unwrap();
+    io::stdout().write_all(b"initialized").unwrap();
+    io::stdout().flush().unwrap();
+    terminal
+        .draw(|frame| frame.render_widget("hi", frame.area()))
+        .unwrap();
+    ready();
+    app.restore().unwrap();
+}
+
+#[test]
+fn clear_always_inline_child() {
+    if support::is_child("clear_always_inline_child") {
+        clear_on_init_lifecycle(ClearOnInit::Always, true);
+    }
+}
+
+#[test]
+fn clear_never_inline_child() {
+    if support::is_child("clear_never_inline_child") {
+        clear_on_init_lifecycle(ClearOnInit::Never, true);
+    }
+}
+
+#[test]
+fn clear_viewport_only_inline_child() {
+    if support::is_child("clear_viewport_only_inline_child") {
+        clear_on_init_lifecycle(ClearOnInit::ViewportOnly, true);
+    }
+}
+
+#[test]
+fn clear_never_alternate_child() {
+    if support::is_child("clear_never_alternate_child") {
+        clear_on_init_lifecycle(ClearOnInit::Never, false);
+    }
+}
+
+/// What `test_name` wrote during init, after the cursor query, and during its first draw.
+fn clear_on_init_output(test_name: &str, inline: bool) -> (Vec<u8>, Vec<u8>) {
+    let mut child = support::PtyChild::spawn(test_name);
+    if inline {
+        child.answer_cursor_query();
+    }
+    child.wait_for(b"ready");
+    assert!(child.wait().success());
+    let output = child.output();
+    let start = if inline {
+        b"\x1b[6n".as_slice()
+    } else {
+        b"\x1b[?1049h".as_slice()
+    };
+    let init = support::after(output, start);
+    let init = &init[..position(init, b"initialized")];
+    let draw = support::after(output, b"initialized");
+    let draw = &draw[..position(draw, b"ready")];
+    (init.to_vec(), draw.to_vec())
+}
+
+const CLEAR_DOWN: &[u8] = b"\x1b[J";
+const CLEAR_LINE: &[u8] = b"\x1b[K";
+const CLEAR_ALL: &[u8] = b"\x1b[2J";
+
+#[test]
+fn clear_on_init_always_clears_from_the_inline_viewport_down() {
+    let (init, _draw) = clear_on_init_output("clear_always_inline_child", true);
+    assert!(support::contains(&init, CLEAR_DOWN));
+    assert!(!support::contains(&init, CLEAR_LINE));
+}
+
+#[test]
+fn clear_on_init_viewport_only_clears_each_viewport_row() {
+    let (init, _draw) = clear_on_init_output("clear_viewport_only_inline_child", true);
+    assert_eq!(support::count(&init, CLEAR_LINE), 5);
+    assert!(!support::contains(&init, CLEAR_DOWN));
+}
+
+#[test]
+fn clear_on_init_never_paints_the_whole_inline_viewport_on_first_draw() {
+    let (init, draw) = clear_on_init_output("clear_never_inline_child", true);
+    for clear in [CLEAR_DOWN, CLEAR_LINE, CLEAR_ALL] {
+        assert!(!support::contains(&init, clear));
+        assert!(!support::contains(&draw, clear));
+    }
+    assert!(support::contains(&draw, b"hi"));
+    let cells = usize::from(support::PTY_COLS) * 5;
+    assert_eq!(support::count(&draw, b" "), cells - 2);
+}
+
+#[test]
+fn clear_on_init_never_leaves_the_alternate_screen_uncleared() {
+    let (init, draw) = clear_on_init_output("clear_never_alternate_child", false);
+    assert!(!support::contains(&init, CLEAR_ALL));
+    let cells = usize::from(support::PTY_COLS) * usize::from(support::PTY_ROWS);
+    assert_eq!(support::count(&draw, b" "), cells - 2);
+}
+
+fn inline_app(height: u16) -> TuiApp {
+    builder().inline(height).build()
+}
+
+#[test]
+fn insert_lines_above_child() {
+    if !support::is_child("insert_lines_above_child") {
+        return;
+    }
+
+    let mut app = inline_app(3);
+    let mut terminal = app.init().unwrap();
+    app.insert_lines_above(&mut terminal, 2, |buf| {
+        ratatui::widgets::Widget::render("scrolled above", buf.area, buf);
+    })
+    .unwrap();
+    ready();
+    app.restore().unwrap();
+}
+
+#[test]
+fn passthrough_child() {
+    if !support::is_child("passthrough_child") {
+        return;
+    }
+
+    let mut app = inline_app(3);
+    let _terminal = app.init().unwrap();
+    app.println_passthrough("one\ntwo\n").unwrap();
+    app.restore().unwrap();
+    app.println_passthrough("three").unwrap();
+    ready();
+}
+
+#[test]
+fn insert_lines_above_draws_into_the_scrollback() {
+    let mut child = support::PtyChild::spawn("insert_lines_above_child");
+    child.answer_cursor_query();
+    child.wait_for(b"ready");
+    assert!(child.wait().success());
+
+    assert!(support::contains(child.output(), b"scrolled above"));
+}
+
+#[test]
+fn println_passthrough_writes_crlf_lines_to_the_other_stream_in_raw_mode() {
+    let stderr_path = std::env::temp_dir().join("tuicore-pty-test-passthrough.txt");
+    let stderr = std::fs::File::create(&stderr_path).unwrap();
+    let mut child = support::PtyChild::spawn_with_stderr("passthrough_child", stderr);
+    child.answer_cursor_query();
+    child.wait_for(b"ready");
+    assert!(child.wait().success());
+
+    assert!(!support::contains(child.output(), b"one"));
+    let stderr = std::fs::read_to_string(&stderr_path).unwrap();
+    assert_eq!(stderr, "one\r\ntwo\r\nthree\n");
+}
+
+#[test]
+fn theme_notifications_child() {
+    if !support::is_child("theme_notifications_child") {
+        return;
+    }
+
+    let mut app = builder()
+        .focus_events(true)
+        .theme_notifications(true)
+        .build();
+    let _terminal = app.init().unwrap();
+    ready();
+    panic!("boom");
+}
+
+#[test]
+fn theme_notifications_are_enabled_and_disabled_after_a_panic() {
+    let mut child = support::PtyChild::spawn("theme_notifications_child");
+    child.wait_for(b"ready");
+    assert!(!child.wait().success());
+    let output = child.output();
+
+    assert!(position(output, b"\x1b[?1004h") < position(output, b"\x1b[?2031h"));
+    assert!(position(output, b"\x1b[?2031h") < position(output, b"ready"));
+    assert!(position(output, b"ready") < position(output, b"\x1b[?2031l"));
+    assert!(position(output, b"\x1b[?2031l") < position(output, b"\x1b[?1004l"));
+}
+
+/// Run `case` of `test_name` and check it switched screens with exactly `mode`.
+fn assert_alt_screen_mode(test_name: &str, case: &str, mode: &str, succeeds: bool) {
+    let mut child = support::PtyChild::spawn_case(test_name, case);
+    child.wait_for(b"ready");
+    assert_eq!(child.wait().success(), succeeds);
+    let output = child.output();
+
+    for other in ["47", "1047", "1049"]
+        .into_iter()
+        .filter(|other| *other != mode)
+    {
+        assert!(!support::contains(
+            output,
+            format!("\x1b[?{other}h").as_bytes()
+        ));
+    }
+    let (enter, leave) = (format!("\x1b[?{mode}h"), format!("\x1b[?{mode}l"));
+    assert!(position(output, enter.as_bytes()) < position(output, b"ready"));
+    assert!(support::contains(
+        support::after(output, b"ready"),
+        leave.as_bytes()
+    ));
+}
+
+#[test]
+fn alt_screen_style_standard_uses_1049() {
+    assert_alt_screen_mode("mode_child", "default", "1049", true);
+}
+
+#[test]
+fn alt_screen_style_47_uses_47() {
+    assert_alt_screen_mode("mode_child", "alt_screen_47", "47", true);
+}
+
+#[test]
+fn alt_screen_style_1047_uses_1047() {
+    assert_alt_screen_mode("mode_child", "alt_screen_1047", "1047", true);
+}
+
+#[test]
+fn alt_screen_style_is_left_by_the_panic_hook() {
+    assert_alt_screen_mode("mode_panic_child", "alt_screen_47", "47", false);
+}
+
+/// Run an app that restores on SIGTERM until it is killed, after `cycles` full init and restore
+/// cycles; with none, it is killed while still active.
+fn signal_restore_lifecycle(cycles: usize) {
+    let mut app = TuiApp::builder("tuicore-pty-test")
+        .use_color_eyre(false)
+        .use_disk_logs(false)
+        .restore_on_signals(&[Signal::Terminate])
+        .build();
+    for _ in 0..cycles {
+        let _terminal = app.init().unwrap();
+        app.restore().unwrap();
+    }
+    let _terminal = (cycles == 0).then(|| app.init().unwrap());
+    ready();
+    loop {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn sigterm_child() {
+    if support::is_child("sigterm_child") {
+        signal_restore_lifecycle(0);
+    }
+}
+
+#[test]
+fn sigterm_after_restore_child() {
+    if support::is_child("sigterm_after_restore_child") {
+        signal_restore_lifecycle(1);
+    }
+}
+
+#[test]
+fn sigterm_after_second_restore_child() {
+    if support::is_child("sigterm_after_second_restore_child") {
+        signal_restore_lifecycle(2);
+    }
+}
+
+#[test]
+fn sigterm_restores_the_terminal_then_kills_the_app() {
+    let mut child = support::PtyChild::spawn("sigterm_child");
+    child.wait_for(b"ready");
+    child.signal(libc::SIGTERM);
+    let status = child.wait();
+
+    assert_eq!(status.signal(), Some(libc::SIGTERM));
+    assert!(child.termios().local_flags.contains(LocalFlags::ICANON));
+    let restored = support::after(child.output(), b"ready");
+    assert!(support::contains(restored, b"\x1b[?1049l"));
+    for mode in MOUSE_MODES {
+        assert!(support::contains(
+            restored,
+            format!("\x1b[?{mode}l").as_bytes()
+        ));
+    }
+}
+
+#[test]
+fn sigterm_after_restore_takes_the_default_action() {
+    // The second init arms the handler again, so its restore has to disarm it again.
+    for case in [
+        "sigterm_after_restore_child",
+        "sigterm_after_second_restore_child",
+    ] {
+        let mut child = support::PtyChild::spawn(case);
+        child.wait_for(b"ready");
+        child.signal(libc::SIGTERM);
+        let status = child.wait();
+
+        assert_eq!(status.signal(), Some(libc::SIGTERM), "{case}");
+        assert!(
+            !support::contains(support::after(child.output(), b"ready"), b"\x1b[?1049l"),
+            "{case}"
+        );
+    }
+}
+
+/// Point stdout at /dev/null, as if piped, keeping stderr on the PTY for reporting.
+fn stdout_to_null() {
+    stderr_to_pty();
+    let null = std::fs::File::create("/dev/null").unwrap();
+    // SAFETY: both descriptors are open for the life of the process.
+    unsafe { libc::dup2(std::os::fd::AsRawFd::as_raw_fd(&null), libc::STDOUT_FILENO) };
+}
+
+fn report(text: &str) {
+    io::stderr().write_all(text.as_bytes()).unwrap();
+}
+
+/// Init a fullscreen app on a piped stdout with `behavior`, reporting what happened on stderr.
+fn non_tty_lifecycle(behavior: NonTtyBehavior) {
+    stdout_to_null();
+    let mut app = builder().non_tty_behavior(behavior).build();
+    match app.init() {
+        Ok(_terminal) => {
+            report(&format!("stream <{:?}>", app.output_stream()));
+            app.restore().unwrap();
+        }
+        Err(e) => report(&format!("error <{e}>")),
+    }
+}
+
+#[test]
+fn non_tty_error_child() {
+    if support::is_child("non_tty_error_child") {
+        non_tty_lifecycle(NonTtyBehavior::Error);
+    }
+}
+
+#[test]
+fn non_tty_fallback_child() {
+    if support::is_child("non_tty_fallback_child") {
+        non_tty_lifecycle(NonTtyBehavior::FallbackToStderr);
+    }
+}
+
+#[test]
+fn non_tty_proceed_child() {
+    if support::is_child("non_tty_proceed_child") {
+        non_tty_lifecycle(NonTtyBehavior::Proceed);
+    }
+}
+
+#[test]
+fn piped_stdout_is_an_error_before_touching_the_terminal() {
+    let mut child = support::PtyChild::spawn("non_tty_error_child");
+    child.wait_for(b">");
+    assert!(child.wait().success());
+
+    let output = child.output();
+    let error = b"error <Cannot draw the TUI on stdout, which is not a terminal>";
+    assert!(support::contains(output, error));
+    assert!(!support::contains(output, b"\x1b[?1049h"));
+    assert!(child.termios().local_flags.contains(LocalFlags::ICANON));
+}
+
+#[test]
+fn piped_stdout_falls_back_to_stderr() {
+    let mut child = support::PtyChild::spawn("non_tty_fallback_child");
+    child.wait_for(b">");
+    assert!(child.wait().success());
+
+    let output = child.output();
+    assert!(support::contains(output, b"stream <Stderr>"));
+    assert!(position(output, b"\x1b[?1049h") < position(output, b"stream"));
+    assert!(support::contains(
+        support::after(output, b"stream"),
+        b"\x1b[?1049l"
+    ));
+}
+
+#[test]
+fn piped_stdout_is_drawn_on_anyway_by_default() {
+    let mut child = support::PtyChild::spawn("non_tty_proceed_child");
+    child.wait_for(b">");
+    assert!(child.wait().success());
+
+    let output = child.output();
+    assert!(support::contains(output, b"stream <Stdout>"));
+    assert!(!support::contains(output, b"\x1b[?1049h"));
+}
+
+/// Init an app from `builder`, reporting the error and lifecycle state init leaves behind.
+fn failed_init_lifecycle(builder: TuiAppBuilder) {
+    let mut app = builder.use_color_eyre(false).build();
+    let error = app.init().err().expect("init should fail");
+    let state = app.lifecycle_state();
+    io::stdout()
+        .write_all(format!("error <{error}> state <{state:?}>").as_bytes())
+        .unwrap();
+    io::stdout().flush().unwrap();
+}
+
+#[test]
+fn unusable_log_dir_child() {
+    if support::is_child("unusable_log_dir_child") {
+        let blocker = std::env::temp_dir().join("tuicore-pty-test-not-a-dir");
+        std::fs::write(&blocker, b"").unwrap();
+        failed_init_lifecycle(TuiApp::builder("tuicore-pty-test").log_dir(blocker.join("logs")));
+    }
+}
+
+#[test]
+fn unanswered_cursor_query_child() {
+    if support::is_child("unanswered_cursor_query_child") {
+        let builder = TuiApp::builder("tuicore-pty-test")
+            .use_disk_logs(false)
+            .bracketed_paste(true)
+            .inline(5);
+        failed_init_lifecycle(builder);
+    }
+}
+
+/// Run `test_name`, whose init fails, and return its output.
+fn failed_init_output(test_name: &str) -> Vec<u8> {
+    let mut child = support::PtyChild::spawn(test_name);
+    child.wait_for(b"state <");
+    assert!(child.wait().success());
+    assert!(child.termios().local_flags.contains(LocalFlags::ICANON));
+    child.output().to_vec()
+}
+
+#[test]
+fn a_logging_failure_leaves_the_terminal_untouched() {
+    let output = failed_init_output("unusable_log_dir_child");
+    assert!(support::contains(&output, b"Failed to initialize logging"));
+    assert!(!support::contains(&output, b"\x1b["));
+}
+
+#[test]
+fn a_failure_after_entering_modes_turns_them_off_again() {
+    let output = failed_init_output("unanswered_cursor_query_child");
+    assert!(support::contains(&output, b"Failed to create terminal"));
+    assert!(support::contains(&output, b"state <Restored>"));
+
+    let failed = support::after(&output, b"\x1b[6n");
+    for mode in MOUSE_MODES {
+        assert!(support::contains(
+            failed,
+            format!("\x1b[?{mode}l").as_bytes()
+        ));
+    }
+    assert!(support::contains(failed, b"\x1b[?2004l"));
+    // Nothing was drawn, so none of the inline restore policy's clearing applies.
+    assert!(!support::contains(failed, b"\x1b[J"));
+}
+
+fn cycles_log_dir() -> std::path::PathBuf {
+    std::env::temp_dir().join("tuicore-pty-test-cycles")
+}
+
+#[test]
+fn repeated_cycles_child() {
+    if !support::is_child("repeated_cycles_child") {
+        return;
+    }
+
+    stderr_to_pty();
+    let mut app = TuiApp::builder("tuicore-pty-test")
+        .capture_mouse(false)
+        .log_dir(cycles_log_dir())
+        .restore_on_signals(&[Signal::Terminate])
+        .build();
+    for cycle in 0..2 {
+        app.run(|_terminal| {
+            tracing::info!("marker from cycle {cycle}");
+            Ok(())
+        })
+        .unwrap();
+        io::stdout().write_all(b"shell prompt\r\n").unwrap();
+    }
+    let _terminal = app.init().unwrap();
+    ready();
+    panic!("boom");
+}
+
+#[test]
+fn repeated_cycles_share_diagnostics_without_stacking_hooks() {
+    let _ = std::fs::remove_dir_all(cycles_log_dir());
+    let mut child = support::PtyChild::spawn("repeated_cycles_child");
+    child.wait_for(b"ready");
+    assert!(!child.wait().success());
+    let output = child.output();
+
+    assert_eq!(support::count(output, b"\x1b[?1049h"), 3);
+    assert_eq!(support::count(output, b"\x1b[?1049l"), 3);
+    assert_eq!(support::count(output, b"shell prompt"), 2);
+    // One restore and one report for the panic, not one per session.
+    let panicked = support::after(output, b"ready");
+    assert_eq!(support::count(panicked, b"\x1b[?1049l"), 1);
+    assert_eq!(support::count(panicked, b"tuicore-pty-test panicked at"), 1);
+
+    let logs: String = std::fs::read_dir(cycles_log_dir())
+        .unwrap()
+        .map(|entry| std::fs::read_to_string(entry.unwrap().path()).unwrap())
+        .collect();
+    assert!(logs.contains("marker from cycle 0"));
+    assert!(logs.contains("marker from cycle 1"));
+}
+
+/// An in-memory writer whose bytes can be read back after the app is done with it.
+#[derive(Clone, Default)]
+struct Capture(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+impl Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn custom_writer_child() {
+    if !support::is_child("custom_writer_child") {
+        return;
+    }
+
+    let capture = Capture::default();
+    let mut app = TuiApp::builder("tuicore-pty-test")
+        .use_color_eyre(false)
+        .use_disk_logs(false)
+        .custom_writer(Box::new(capture.clone()))
+        .build();
+    let mut terminal = app.init().unwrap();
+    terminal
+        .draw(|frame| frame.render_widget(Text::raw("frame"), frame.area()))
+        .unwrap();
+    let termios = nix::sys::termios::tcgetattr(io::stdin()).unwrap();
+    let raw = !termios.local_flags.contains(LocalFlags::ICANON);
+    ready();
+    app.restore().unwrap();
+
+    let mut out = io::stdout();
+    write!(out, "raw <{raw}> captured:").unwrap();
+    out.write_all(&capture.0.lock().unwrap()).unwrap();
+    out.flush().unwrap();
+}
+
+#[test]
+fn custom_writer_gets_the_frames_and_restore_while_the_tty_goes_raw() {
+    let mut child = support::PtyChild::spawn("custom_writer_child");
+    child.wait_for(b"captured:");
+    assert!(child.wait().success());
+    assert!(child
+        .termios()
+        .local_flags
+        .contains(LocalFlags::ICANON | LocalFlags::ECHO));
+
+    let output = child.output();
+    let (tty, captured) = output.split_at(position(output, b"captured:"));
+    assert!(support::contains(tty, b"raw <true>"));
+    for needle in [
+        b"\x1b[?1049h".as_slice(),
+        b"\x1b[?1000h",
+        b"frame",
+        b"\x1b[?1049l",
+    ] {
+        assert!(!support::contains(tty, needle));
+        assert!(support::contains(captured, needle));
+    }
+    assert!(position(captured, b"frame") < position(captured, b"\x1b[?1049l"));
+    assert!(support::contains(captured, b"\x1b[?1000l"));
+}
+
+#[test]
+fn custom_writer_panic_child() {
+    if !support::is_child("custom_writer_panic_child") {
+        return;
+    }
+
+    let mut app = builder()
+        .bracketed_paste(true)
+        .custom_writer(Box::new(io::stderr()))
+        .build();
+    let _terminal = app.init().unwrap();
+    ready();
+    panic!("boom");
+}
+
+#[test]
+fn custom_writer_gets_the_restore_after_a_panic() {
+    let stderr_path = std::env::temp_dir().join("tuicore-pty-test-custom-writer.txt");
+    let stderr = std::fs::File::create(&stderr_path).unwrap();
+    let mut child = support::PtyChild::spawn_with_stderr("custom_writer_panic_child", stderr);
+    child.wait_for(b"ready");
+    assert!(!child.wait().success());
+
+    assert!(!support::contains(child.output(), b"\x1b[?1049"));
+    let written = std::fs::read(&stderr_path).unwrap();
+    assert!(position(&written, b"\x1b[?1049h") < position(&written, b"\x1b[?1049l"));
+    assert!(position(&written, b"\x1b[?2004l") < position(&written, b"panicked"));
+}
+
+#[test]
+fn timings_child() {
+    if !support::is_child("timings_child") {
+        return;
+    }
+
+    let mut app = app();
+    let _terminal = app.init().unwrap();
+    let init = app.timings();
+    ready();
+    app.restore().unwrap();
+    let phases: Vec<_> = app.timings().iter().map(|(name, _)| name).collect();
+    write!(
+        io::stdout(),
+        "init <{}> phases <{}>",
+        init.iter().count(),
+        phases.join(" ")
+    )
+    .unwrap();
+    io::stdout().flush().unwrap();
+}
+
+#[test]
+fn init_and_restore_phases_are_timed_in_order() {
+    let mut child = support::PtyChild::spawn("timings_child");
+    child.wait_for(b"phases <");
+    child.wait_for(b">");
+    assert!(child.wait().success());
+
+    let phases = "subscriber raw_mode modes alternate_screen clear \
+                  restore_screen restore_modes restore_raw_mode";
+    assert!(support::contains(
+        child.output(),
+        format!("init <5> phases <{phases}>").as_bytes()
+    ));
+}
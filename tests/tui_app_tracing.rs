@@ -0,0 +1,443 @@
+#![cfg(unix)]
+
+mod support;
+
+use _tuicore::{
+    AlternateScreenBackend, BoxedLayer, LogRotation, StderrLog, TracingConfig, TuiApp,
+    TuiAppBuilder,
+};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+const APP_NAME: &str = "tuicore_tracing_test";
+
+/// A log directory private to `test_name`, emptied for each run.
+fn log_dir(test_name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("{APP_NAME}-{test_name}"))
+}
+
+fn fresh_log_dir(test_name: &str) -> PathBuf {
+    let dir = log_dir(test_name);
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+/// Run an app with `tracing` through init and restore, logging to `test_name`'s directory.
+fn run_app(test_name: &str, tracing: TracingConfig) {
+    run_built_app(test_name, TuiApp::builder(APP_NAME).tracing(tracing));
+}
+
+fn run_built_app(test_name: &str, builder: TuiAppBuilder) {
+    std::env::set_var(_tuicore::log_dir_env_var(APP_NAME), log_dir(test_name));
+    let mut app = builder.use_color_eyre(false).capture_mouse(false).build();
+    let _terminal = app.init().unwrap();
+    tracing::info!("marker from {test_name}");
+    tracing::warn!("warning from {test_name}");
+    io::stdout().write_all(b"ready").unwrap();
+    io::stdout().flush().unwrap();
+    app.restore().unwrap();
+}
+
+/// File names in `dir`, sorted.
+fn file_names(dir: &PathBuf) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+/// Every log file's contents in `dir`, concatenated.
+fn logs(dir: &PathBuf) -> String {
+    std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| std::fs::read_to_string(entry.unwrap().path()).unwrap())
+        .collect()
+}
+
+fn run_child(test_name: &str) {
+    let mut child = support::PtyChild::spawn(test_name);
+    child.wait_for(b"ready");
+    assert!(child.wait().success());
+}
+
+#[test]
+fn existing_subscriber_child() {
+    if support::is_child("existing_subscriber_child") {
+        tracing_subscriber::fmt()
+            .with_writer(io::stdout)
+            .with_ansi(false)
+            .init();
+        run_app("existing_subscriber_child", TracingConfig::Default);
+    }
+}
+
+#[test]
+fn custom_subscriber_child() {
+    if support::is_child("custom_subscriber_child") {
+        let install = Arc::new(|file_layer| {
+            tracing_subscriber::registry().with(file_layer).try_init()?;
+            Ok(())
+        });
+        run_app("custom_subscriber_child", TracingConfig::Custom(install));
+    }
+}
+
+#[test]
+fn disabled_child() {
+    if support::is_child("disabled_child") {
+        run_app("disabled_child", TracingConfig::Disabled);
+    }
+}
+
+#[test]
+fn stderr_log_child() {
+    if support::is_child("stderr_log_child") {
+        let stderr_log = StderrLog {
+            ansi: false,
+            ..StderrLog::default()
+        };
+        let builder = TuiApp::builder(APP_NAME).stderr_log(stderr_log);
+        run_built_app("stderr_log_child", builder);
+    }
+}
+
+#[test]
+fn builder_log_dir_child() {
+    if support::is_child("builder_log_dir_child") {
+        let mut app = TuiApp::builder(APP_NAME)
+            .use_color_eyre(false)
+            .capture_mouse(false)
+            .log_dir(log_dir("builder_log_dir_child"))
+            .log_file_prefix("session")
+            .build();
+        let _terminal = app.init().unwrap();
+        tracing::info!("marker from builder_log_dir_child");
+        app.restore().unwrap();
+        let dir = app.log_dir().unwrap().display().to_string();
+        io::stdout()
+            .write_all(format!("logs at <{dir}>").as_bytes())
+            .unwrap();
+        io::stdout().flush().unwrap();
+    }
+}
+
+/// Run an app logging to `test_name`'s directory through the builder rather than the env var.
+fn run_rotation_child(test_name: &str, rotation: LogRotation, keep: usize) {
+    let mut app = TuiApp::builder(APP_NAME)
+        .use_color_eyre(false)
+        .capture_mouse(false)
+        .log_dir(log_dir(test_name))
+        .log_file_prefix("run")
+        .log_rotation(rotation)
+        .log_keep(keep)
+        .build();
+    let _terminal = app.init().unwrap();
+    tracing::info!("marker from {test_name}");
+    io::stdout().write_all(b"ready").unwrap();
+    io::stdout().flush().unwrap();
+    app.restore().unwrap();
+}
+
+#[test]
+fn hourly_keep_two_child() {
+    if support::is_child("hourly_keep_two_child") {
+        run_rotation_child("hourly_keep_two_child", LogRotation::Hourly, 2);
+    }
+}
+
+#[test]
+fn never_keep_one_child() {
+    if support::is_child("never_keep_one_child") {
+        run_rotation_child("never_keep_one_child", LogRotation::Never, 1);
+    }
+}
+
+#[test]
+fn single_file_child() {
+    if support::is_child("single_file_child") {
+        run_rotation_child("single_file_child", LogRotation::Never, 1);
+    }
+}
+
+/// Run an app with a `warn` default filter and report the filter it ended up with.
+fn run_filter_child(test_name: &str) {
+    std::env::set_var(_tuicore::log_dir_env_var(APP_NAME), log_dir(test_name));
+    let mut app = TuiApp::builder(APP_NAME)
+        .use_color_eyre(false)
+        .capture_mouse(false)
+        .log_filter("warn")
+        .build();
+    let _terminal = app.init().unwrap();
+    tracing::info!("marker from {test_name}");
+    tracing::warn!("warning from {test_name}");
+    app.restore().unwrap();
+    let filter = app.log_filter().unwrap();
+    io::stdout()
+        .write_all(format!("filter <{filter}>").as_bytes())
+        .unwrap();
+    io::stdout().flush().unwrap();
+}
+
+#[test]
+fn builder_filter_child() {
+    if support::is_child("builder_filter_child") {
+        std::env::remove_var("RUST_LOG");
+        run_filter_child("builder_filter_child");
+    }
+}
+
+#[test]
+fn env_filter_child() {
+    if support::is_child("env_filter_child") {
+        std::env::set_var("RUST_LOG", "debug");
+        run_filter_child("env_filter_child");
+    }
+}
+
+#[test]
+fn init_logs_through_an_existing_global_subscriber() {
+    let dir = fresh_log_dir("existing_subscriber_child");
+    let mut child = support::PtyChild::spawn("existing_subscriber_child");
+    child.wait_for(b"ready");
+    assert!(child.wait().success());
+
+    let output = child.output();
+    assert!(support::contains(
+        output,
+        b"through the existing tracing subscriber"
+    ));
+    assert!(support::contains(
+        output,
+        b"marker from existing_subscriber_child"
+    ));
+    assert!(!dir.exists());
+}
+
+#[test]
+fn custom_subscriber_receives_the_file_layer() {
+    let dir = fresh_log_dir("custom_subscriber_child");
+    run_child("custom_subscriber_child");
+    assert!(logs(&dir).contains("marker from custom_subscriber_child"));
+}
+
+#[test]
+fn custom_install_error_fails_init() {
+    let install =
+        Arc::new(|_: Option<BoxedLayer>| -> eyre::Result<()> { eyre::bail!("subscriber refused") });
+    let mut app = TuiApp::builder(APP_NAME)
+        .use_color_eyre(false)
+        .log_dir(fresh_log_dir("custom_install_error"))
+        .tracing(TracingConfig::Custom(install))
+        .build();
+    let err = app
+        .init()
+        .err()
+        .expect("init should fail when the custom subscriber does");
+    assert!(format!("{err:#}").contains("subscriber refused"), "{err:#}");
+}
+
+#[test]
+fn disabled_tracing_creates_no_log_file() {
+    let dir = fresh_log_dir("disabled_child");
+    run_child("disabled_child");
+    assert!(!dir.exists());
+}
+
+#[test]
+fn stderr_layer_logs_at_its_own_level_next_to_the_file() {
+    let dir = fresh_log_dir("stderr_log_child");
+    let stderr_path = std::env::temp_dir().join(format!("{APP_NAME}-stderr_log_child.txt"));
+    let stderr = std::fs::File::create(&stderr_path).unwrap();
+    let mut child = support::PtyChild::spawn_with_stderr("stderr_log_child", stderr);
+    child.wait_for(b"ready");
+    assert!(child.wait().success());
+
+    let stderr = std::fs::read_to_string(&stderr_path).unwrap();
+    assert!(
+        stderr.contains("warning from stderr_log_child"),
+        "{stderr:?}"
+    );
+    assert!(
+        !stderr.contains("marker from stderr_log_child"),
+        "{stderr:?}"
+    );
+    assert!(!stderr.contains('\x1b'), "{stderr:?}");
+    assert!(logs(&dir).contains("marker from stderr_log_child"));
+}
+
+#[test]
+fn stderr_logging_is_rejected_for_a_tui_on_stderr() {
+    let mut app = TuiApp::builder(APP_NAME)
+        .use_color_eyre(false)
+        .alternate_screen_backend(AlternateScreenBackend::Stderr)
+        .log_to_stderr(true)
+        .build();
+    let err = app
+        .init()
+        .err()
+        .expect("init should refuse to share stderr");
+    assert!(err.to_string().contains("stderr"));
+}
+
+#[test]
+fn builder_log_dir_and_prefix_name_the_log_file() {
+    let dir = fresh_log_dir("builder_log_dir_child");
+    let mut child = support::PtyChild::spawn("builder_log_dir_child");
+    child.wait_for(b">");
+    assert!(child.wait().success());
+
+    let reported = format!("logs at <{}>", dir.display());
+    assert!(support::contains(child.output(), reported.as_bytes()));
+    let names = file_names(&dir);
+    assert!(
+        names.iter().all(|name| name.starts_with("session.")),
+        "{names:?}"
+    );
+    assert!(logs(&dir).contains("marker from builder_log_dir_child"));
+}
+
+#[test]
+fn unusable_log_dir_is_an_init_error() {
+    let blocker = std::env::temp_dir().join(format!("{APP_NAME}-not-a-dir"));
+    std::fs::write(&blocker, b"").unwrap();
+    let mut app = TuiApp::builder(APP_NAME)
+        .use_color_eyre(false)
+        .log_dir(blocker.join("logs"))
+        .build();
+    let err = app
+        .init()
+        .err()
+        .expect("init should fail to create the log directory");
+    assert!(format!("{err:#}").contains("log directory"));
+    assert!(app.log_dir().is_none());
+}
+
+#[test]
+fn log_keep_prunes_the_oldest_rotated_files_at_init() {
+    let dir = fresh_log_dir("hourly_keep_two_child");
+    std::fs::create_dir_all(&dir).unwrap();
+    for name in [
+        "run.2000-01-01-00",
+        "run.2000-01-01-01",
+        "run.2000-01-01-02",
+        "run.2000-01-01",
+        "notes.txt",
+    ] {
+        std::fs::write(dir.join(name), b"").unwrap();
+    }
+    run_child("hourly_keep_two_child");
+
+    // The daily file is another rotation's, so it doesn't count towards the two kept.
+    let names = file_names(&dir);
+    assert_eq!(names.len(), 4, "{names:?}");
+    assert_eq!(names[0], "notes.txt");
+    assert_eq!(names[1], "run.2000-01-01");
+    assert_eq!(names[2], "run.2000-01-01-02");
+    assert!(names[3].starts_with("run.20") && names[3].len() == "run.YYYY-MM-DD-HH".len());
+    assert!(logs(&dir).contains("marker from hourly_keep_two_child"));
+}
+
+#[test]
+fn never_rotating_leaves_rotated_files_alone() {
+    let dir = fresh_log_dir("never_keep_one_child");
+    std::fs::create_dir_all(&dir).unwrap();
+    for name in ["run.2000-01-01", "run.2000-01-01-00"] {
+        std::fs::write(dir.join(name), b"").unwrap();
+    }
+    run_child("never_keep_one_child");
+
+    assert_eq!(
+        file_names(&dir),
+        vec!["run", "run.2000-01-01", "run.2000-01-01-00"]
+    );
+    assert!(logs(&dir).contains("marker from never_keep_one_child"));
+}
+
+#[test]
+fn never_rotating_logs_to_a_single_file() {
+    let dir = fresh_log_dir("single_file_child");
+    run_child("single_file_child");
+    run_child("single_file_child");
+
+    assert_eq!(file_names(&dir), vec!["run".to_string()]);
+    let log = std::fs::read_to_string(dir.join("run")).unwrap();
+    assert_eq!(log.matches("marker from single_file_child").count(), 2);
+}
+
+#[test]
+fn builder_log_filter_sets_the_default_level() {
+    let dir = fresh_log_dir("builder_filter_child");
+    let mut child = support::PtyChild::spawn("builder_filter_child");
+    child.wait_for(b">");
+    assert!(child.wait().success());
+
+    assert!(support::contains(child.output(), b"filter <warn>"));
+    let logs = logs(&dir);
+    assert!(logs.contains("warning from builder_filter_child"));
+    assert!(!logs.contains("marker from builder_filter_child"));
+}
+
+#[test]
+fn rust_log_overrides_the_builder_filter() {
+    let dir = fresh_log_dir("env_filter_child");
+    let mut child = support::PtyChild::spawn("env_filter_child");
+    child.wait_for(b">");
+    assert!(child.wait().success());
+
+    assert!(support::contains(child.output(), b"filter <debug>"));
+    let logs = logs(&dir);
+    assert!(logs.contains("marker from env_filter_child"));
+    assert!(logs.contains("Log filter: debug"));
+    // Each init and restore phase is a span, closed with its duration.
+    assert!(logs.contains("lifecycle{phase=\"alternate_screen\"}"));
+    assert!(logs.contains("Phase restore_raw_mode took"));
+}
+
+#[test]
+fn shutdown_child() {
+    if support::is_child("shutdown_child") {
+        let mut app = TuiApp::builder(APP_NAME)
+            .use_color_eyre(false)
+            .capture_mouse(false)
+            .log_dir(log_dir("shutdown_child"))
+            .goodbye_banner("goodbye")
+            .build();
+        let _terminal = app.init().unwrap();
+        io::stdout().write_all(b"ready").unwrap();
+        io::stdout().flush().unwrap();
+        tracing::info!("last words from shutdown_child");
+        app.shutdown().unwrap();
+        // Skips every destructor, as exiting from deep inside an app would.
+        std::process::exit(0);
+    }
+}
+
+#[test]
+fn shutdown_flushes_the_logs_before_exit_and_prints_the_banner_after_restore() {
+    let dir = fresh_log_dir("shutdown_child");
+    let mut child = support::PtyChild::spawn("shutdown_child");
+    child.wait_for(b"ready");
+    assert!(child.wait().success());
+
+    let restored = support::after(child.output(), b"ready");
+    let left_alternate_screen = support::find(restored, b"\x1b[?1049l").unwrap();
+    assert!(support::contains(
+        &restored[left_alternate_screen..],
+        b"goodbye"
+    ));
+    assert!(logs(&dir).contains("last words from shutdown_child"));
+}
+
+#[test]
+fn shutdown_before_init_is_a_no_op() {
+    let app = TuiApp::builder(APP_NAME)
+        .use_disk_logs(false)
+        .goodbye_banner("goodbye")
+        .build();
+    assert!(app.shutdown().is_ok());
+}
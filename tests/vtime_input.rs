@@ -0,0 +1,82 @@
+#![cfg(unix)]
+
+mod support;
+
+use _tuicore::raw_input::{InputSource, PollResult, RawInputReader};
+use nix::sys::termios::{self, SpecialCharacterIndices};
+use std::fs::File;
+use std::io::Write;
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn vmin_vtime(tty: &File) -> (u8, u8) {
+    let attrs = termios::tcgetattr(tty).unwrap();
+    (
+        attrs.control_chars[SpecialCharacterIndices::VMIN as usize],
+        attrs.control_chars[SpecialCharacterIndices::VTIME as usize],
+    )
+}
+
+#[test]
+fn kernel_timer_reassembles_a_sequence_split_across_writes() {
+    let (mut master, slave) = support::raw_pty();
+    let mut reader = RawInputReader::with_vtime(slave).unwrap();
+
+    master.write_all(b"\x1b[").unwrap();
+    thread::sleep(Duration::from_millis(20));
+    master.write_all(b"Ax").unwrap();
+
+    let events: Vec<Vec<u8>> = (0..2)
+        .map(|_| {
+            reader
+                .poll_next(Duration::ZERO)
+                .unwrap()
+                .into_event()
+                .unwrap()
+                .bytes
+        })
+        .collect();
+    assert_eq!(events, vec![b"\x1b[A".to_vec(), b"x".to_vec()]);
+}
+
+#[test]
+fn lone_escape_is_flushed_once_the_line_goes_quiet() {
+    let (mut master, slave) = support::raw_pty();
+    let mut reader = RawInputReader::with_vtime(slave).unwrap();
+
+    let started = Instant::now();
+    master.write_all(b"\x1b").unwrap();
+    assert_eq!(
+        reader.poll_next(Duration::ZERO).unwrap().event_bytes(),
+        Some(&b"\x1b"[..])
+    );
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn restores_vmin_and_vtime_on_drop() {
+    let (_master, slave) = support::raw_pty();
+    let probe = slave.try_clone().unwrap();
+    let original = vmin_vtime(&probe);
+
+    let reader = RawInputReader::with_vtime(slave).unwrap();
+    assert_eq!(vmin_vtime(&probe), (u8::MAX, 1));
+
+    drop(reader);
+    assert_eq!(vmin_vtime(&probe), original);
+}
+
+#[test]
+fn reports_eof_once_the_pty_master_closes() {
+    let (mut master, slave) = support::raw_pty();
+    let mut reader = RawInputReader::with_vtime(slave).unwrap();
+
+    master.write_all(b"a").unwrap();
+    assert_eq!(
+        reader.poll_next(Duration::ZERO).unwrap().event_bytes(),
+        Some(&b"a"[..])
+    );
+
+    drop(master);
+    assert_eq!(reader.poll_next(Duration::ZERO).unwrap(), PollResult::Eof);
+}